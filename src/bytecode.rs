@@ -0,0 +1,226 @@
+//! Bytecode of the Mini-PL programming language.
+//!
+//! The [BytecodeGenerator](crate::visitors::BytecodeGenerator) compiles a program into a [Chunk] of instructions for a stack machine.
+//! Each instruction keeps the span of the source code it originates from, and the [Disassembler] prints the chunk as a readable listing.
+//! The [VirtualMachine] executes the chunk: the instructions perform the same runtime checks as the [Interpreter](crate::visitors::Interpreter), in the same order.
+
+use std::fmt::Display;
+
+use crate::parsing::VarType;
+use crate::span::StartEndSpan;
+
+mod disassembler;
+pub use disassembler::Disassembler;
+mod vm;
+pub use vm::VirtualMachine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An instruction of the stack machine. Operands refer to the constants and variables of the [Chunk], and jump targets to offsets of its code
+pub enum Instruction {
+    /// Pushes the constant with the given index
+    Constant(usize),
+    /// Pushes the boolean `true`
+    True,
+    /// Pushes the boolean `false`
+    False,
+    /// Pushes the value of the variable
+    Get(usize),
+    /// Declares the variable with the default value of the type constant
+    Define(usize, usize),
+    /// Pops the initial value, and declares the variable of the type constant with it
+    DefineInit(usize, usize),
+    /// Pops a value and assigns it to the variable. Pushes the value back, as the value of the assignment expression
+    Assign(usize),
+    /// Pops a value and an index, and assigns the value to the element of the array variable. Pushes the value back
+    AssignIndex(usize),
+    /// Pops an array and an index, and pushes the element. The index is evaluated before the array
+    Index,
+    /// Pops two operands and pushes their sum, or the concatenation of two texts
+    Add,
+    /// Pops two operands and pushes their difference
+    Subtract,
+    /// Pops two operands and pushes their product
+    Multiply,
+    /// Pops two operands and pushes their quotient
+    Divide,
+    /// Pops two operands and pushes whether they are equal
+    Equal,
+    /// Pops two operands and pushes whether they are not equal
+    NotEqual,
+    /// Pops two operands and pushes whether the first is less than the second
+    Less,
+    /// Pops two operands and pushes whether the first is less than or equal to the second
+    LessEqual,
+    /// Pops two operands and pushes whether the first is greater than the second
+    Greater,
+    /// Pops two operands and pushes whether the first is greater than or equal to the second
+    GreaterEqual,
+    /// Pops a number or a real and pushes its negation
+    Negate,
+    /// Pops a value and pushes its logical negation
+    Not,
+    /// Pops a value and pushes it back as the value of the instruction's expression, failing if it is not a boolean
+    AsBoolean,
+    /// Pops a value and pushes its text representation, as printed
+    ToText,
+    /// Pops two texts and pushes their concatenation
+    Concat,
+    /// Pops the given number of arguments, and pushes the result of the builtin function named by the text constant
+    Call(usize, usize),
+    /// Continues at the target offset
+    Jump(usize),
+    /// Pops a boolean, and continues at the target offset if it is false
+    JumpIfFalse(usize),
+    /// Pops a boolean, and continues at the target offset if it is true
+    JumpIfTrue(usize),
+    /// Pops and discards a value
+    Pop,
+    /// Pops a value and prints it
+    Print,
+    /// Reads a line of input into the variable
+    Read(usize),
+    /// Pops a value, and fails if it is not `true`
+    Assert,
+    /// Pops the end and the start of a range, and begins a `for` loop over it with the control variable
+    RangeLoop(usize),
+    /// Pops an array, and begins a `for` loop over its elements with the control variable
+    ArrayLoop(usize),
+    /// Assigns the next value of the innermost loop to its control variable, or continues at the target offset if the loop is done
+    LoopNext(usize),
+    /// Ends the innermost loop, releasing its control variable
+    LoopEnd,
+}
+
+impl Instruction {
+    /// The mnemonic of the instruction, as shown in listings
+    #[must_use]
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Constant(_) => "CONSTANT",
+            Instruction::True => "TRUE",
+            Instruction::False => "FALSE",
+            Instruction::Get(_) => "GET",
+            Instruction::Define(_, _) => "DEFINE",
+            Instruction::DefineInit(_, _) => "DEFINE_INIT",
+            Instruction::Assign(_) => "ASSIGN",
+            Instruction::AssignIndex(_) => "ASSIGN_INDEX",
+            Instruction::Index => "INDEX",
+            Instruction::Add => "ADD",
+            Instruction::Subtract => "SUBTRACT",
+            Instruction::Multiply => "MULTIPLY",
+            Instruction::Divide => "DIVIDE",
+            Instruction::Equal => "EQUAL",
+            Instruction::NotEqual => "NOT_EQUAL",
+            Instruction::Less => "LESS",
+            Instruction::LessEqual => "LESS_EQUAL",
+            Instruction::Greater => "GREATER",
+            Instruction::GreaterEqual => "GREATER_EQUAL",
+            Instruction::Negate => "NEGATE",
+            Instruction::Not => "NOT",
+            Instruction::AsBoolean => "AS_BOOLEAN",
+            Instruction::ToText => "TO_TEXT",
+            Instruction::Concat => "CONCAT",
+            Instruction::Call(_, _) => "CALL",
+            Instruction::Jump(_) => "JUMP",
+            Instruction::JumpIfFalse(_) => "JUMP_IF_FALSE",
+            Instruction::JumpIfTrue(_) => "JUMP_IF_TRUE",
+            Instruction::Pop => "POP",
+            Instruction::Print => "PRINT",
+            Instruction::Read(_) => "READ",
+            Instruction::Assert => "ASSERT",
+            Instruction::RangeLoop(_) => "RANGE_LOOP",
+            Instruction::ArrayLoop(_) => "ARRAY_LOOP",
+            Instruction::LoopNext(_) => "LOOP_NEXT",
+            Instruction::LoopEnd => "LOOP_END",
+        }
+    }
+
+    /// The offset the instruction may continue at, other than the next one
+    #[must_use]
+    pub fn jump_target(&self) -> Option<usize> {
+        match self {
+            Instruction::Jump(target)
+            | Instruction::JumpIfFalse(target)
+            | Instruction::JumpIfTrue(target)
+            | Instruction::LoopNext(target) => Some(*target),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A value in the constant pool of a [Chunk]
+pub enum Constant {
+    /// Number literal
+    Number(i64),
+    /// Real number literal
+    Real(f64),
+    /// Text literal, or the name of a builtin function
+    Text(String),
+    /// Declared type of a variable
+    Type(VarType),
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Number(number) => write!(f, "{number}"),
+            Constant::Real(real) => write!(f, "{real:?}"),
+            Constant::Text(text) => write!(f, "{text:?}"),
+            Constant::Type(kind) => write!(f, "{kind}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// A compiled program: the instructions with their source spans, the constant pool and the names of the variables
+pub struct Chunk {
+    /// The instructions, indexed by offset
+    pub code: Vec<Instruction>,
+    /// The source span each instruction originates from, indexed by offset
+    pub spans: Vec<StartEndSpan>,
+    /// The constant pool. Each constant is stored only once
+    pub constants: Vec<Constant>,
+    /// The names of the variables, indexed by the variable operands of the instructions
+    pub variables: Vec<String>,
+}
+
+impl Chunk {
+    /// Appends the instruction, returning its offset
+    pub fn push(&mut self, instruction: Instruction, span: StartEndSpan) -> usize {
+        self.code.push(instruction);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    /// Returns the index of the constant in the pool, adding it if needed
+    pub fn constant(&mut self, constant: Constant) -> usize {
+        if let Some(index) = self.constants.iter().position(|c| *c == constant) {
+            return index;
+        }
+        self.constants.push(constant);
+        self.constants.len() - 1
+    }
+
+    /// Returns the index of the variable with the given name, adding it if needed
+    pub fn variable(&mut self, name: &str) -> usize {
+        if let Some(index) = self.variables.iter().position(|v| v == name) {
+            return index;
+        }
+        self.variables.push(name.to_owned());
+        self.variables.len() - 1
+    }
+
+    /// The offsets that instructions may jump to, in order
+    #[must_use]
+    pub fn jump_targets(&self) -> Vec<usize> {
+        let mut targets: Vec<usize> = self
+            .code
+            .iter()
+            .filter_map(Instruction::jump_target)
+            .collect();
+        targets.sort_unstable();
+        targets.dedup();
+        targets
+    }
+}
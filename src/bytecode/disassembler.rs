@@ -0,0 +1,177 @@
+use std::fmt::Write;
+
+use crate::visitors::codegen::SourceLines;
+
+use super::{Chunk, Instruction};
+
+const INDENT: &str = "    ";
+
+#[derive(Debug)]
+/// [Disassembler] prints a [Chunk] as a readable listing: the constant pool, the variables, and the instructions with their source locations.
+///
+/// Jump targets are labeled `L0`, `L1` and so on, in the order of their offsets. In the interleaved mode,
+/// each source line is printed as a comment before the first instruction originating from it.
+pub struct Disassembler<'a> {
+    chunk: &'a Chunk,
+    source: &'a str,
+    lines: SourceLines,
+    interleave: bool,
+}
+
+impl<'a> Disassembler<'a> {
+    /// Creates a new [Disassembler] for the chunk compiled from the given source code
+    #[must_use]
+    pub fn new(chunk: &'a Chunk, source: &'a str, interleave: bool) -> Self {
+        Self {
+            chunk,
+            source,
+            lines: SourceLines::new(source),
+            interleave,
+        }
+    }
+
+    /// The primary function of the [Disassembler]: returns the listing of the whole chunk
+    #[must_use]
+    pub fn disassemble(&self) -> String {
+        let mut listing = String::from("== constants ==\n");
+        for (index, constant) in self.chunk.constants.iter().enumerate() {
+            let _ = writeln!(listing, "{INDENT}#{index:<4} {constant}");
+        }
+        listing.push_str("== variables ==\n");
+        for (index, name) in self.chunk.variables.iter().enumerate() {
+            let _ = writeln!(listing, "{INDENT}${index:<4} {name}");
+        }
+        listing.push_str("== code ==\n");
+        let targets = self.chunk.jump_targets();
+        let source_lines: Vec<&str> = self.source.lines().collect();
+        let mut previous_line = None;
+        for (offset, (instruction, span)) in
+            self.chunk.code.iter().zip(&self.chunk.spans).enumerate()
+        {
+            let (line, column) = self.lines.line_column(span.start);
+            if self.interleave && previous_line != Some(line) {
+                let text = source_lines.get(line - 1).copied().unwrap_or_default();
+                let _ = writeln!(listing, "; {line:>4} | {}", text.trim_end());
+            }
+            previous_line = Some(line);
+            if let Ok(label) = targets.binary_search(&offset) {
+                let _ = writeln!(listing, "L{label}:");
+            }
+            let location = format!("{line}:{column}");
+            let range = format!("{}..{}", span.start, span.end);
+            let _ = writeln!(
+                listing,
+                "{INDENT}{offset:04}  {location:<8} {range:<12} {:<14} {}",
+                instruction.mnemonic(),
+                self.operands(instruction, &targets)
+            );
+        }
+        // A jump past the last instruction ends the program
+        if let Ok(label) = targets.binary_search(&self.chunk.code.len()) {
+            let _ = writeln!(listing, "L{label}:");
+        }
+        listing
+            .lines()
+            .map(str::trim_end)
+            .fold(String::new(), |listing, line| listing + line + "\n")
+    }
+
+    /// Internal helper: returns the operands of the instruction, with the constants and variables they refer to
+    fn operands(&self, instruction: &Instruction, targets: &[usize]) -> String {
+        let constant = |index: usize| match self.chunk.constants.get(index) {
+            Some(constant) => format!("#{index} {constant}"),
+            None => format!("#{index} <invalid constant>"),
+        };
+        let variable = |index: usize| match self.chunk.variables.get(index) {
+            Some(name) => format!("${index} {name}"),
+            None => format!("${index} <invalid variable>"),
+        };
+        match *instruction {
+            Instruction::Constant(index) => constant(index),
+            Instruction::Get(index)
+            | Instruction::Assign(index)
+            | Instruction::AssignIndex(index)
+            | Instruction::Read(index)
+            | Instruction::RangeLoop(index)
+            | Instruction::ArrayLoop(index) => variable(index),
+            Instruction::Define(index, kind) | Instruction::DefineInit(index, kind) => {
+                format!("{}, {}", variable(index), constant(kind))
+            }
+            Instruction::Call(name, arguments) => {
+                let plural = if arguments == 1 { "" } else { "s" };
+                format!("{}, {arguments} argument{plural}", constant(name))
+            }
+            Instruction::Jump(target)
+            | Instruction::JumpIfFalse(target)
+            | Instruction::JumpIfTrue(target)
+            | Instruction::LoopNext(target) => match targets.binary_search(&target) {
+                Ok(label) => format!("-> L{label} ({target:04})"),
+                Err(_) => format!("-> {target:04}"),
+            },
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::bytecode::*;
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::visitors::BytecodeGenerator;
+
+    fn disassemble(source: &str, interleave: bool) -> String {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let chunk = BytecodeGenerator::default().generate(&statements).unwrap();
+        Disassembler::new(&chunk, source, interleave).disassemble()
+    }
+
+    #[test]
+    fn listing() {
+        let listing = disassemble("var x : int := 1;\nprint x + 1;", false);
+        let expected = "\
+== constants ==
+    #0    1
+    #1    Number
+== variables ==
+    $0    x
+== code ==
+    0000  1:16     15..16       CONSTANT       #0 1
+    0001  1:1      0..16        DEFINE_INIT    $0 x, #1 Number
+    0002  2:7      24..25       GET            $0 x
+    0003  2:11     28..29       CONSTANT       #0 1
+    0004  2:7      24..29       ADD
+    0005  2:1      18..29       PRINT
+";
+        assert_eq!(listing, expected);
+    }
+
+    #[test]
+    fn jump_targets_are_labeled() {
+        let listing = disassemble("for i in 1..2 do\n  print i;\nend for;", true);
+        let expected = "\
+== constants ==
+    #0    1
+    #1    2
+== variables ==
+    $0    i
+== code ==
+;    1 | for i in 1..2 do
+    0000  1:10     9..10        CONSTANT       #0 1
+    0001  1:13     12..13       CONSTANT       #1 2
+    0002  1:1      0..16        RANGE_LOOP     $0 i
+L0:
+    0003  1:1      0..35        LOOP_NEXT      -> L1 (0007)
+;    2 |   print i;
+    0004  2:9      25..26       GET            $0 i
+    0005  2:3      19..26       PRINT
+;    1 | for i in 1..2 do
+    0006  1:1      0..35        JUMP           -> L0 (0003)
+L1:
+    0007  1:1      0..35        LOOP_END
+";
+        assert_eq!(listing, expected);
+    }
+}
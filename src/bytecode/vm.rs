@@ -0,0 +1,445 @@
+use crate::parsing::VarType;
+use crate::runtime::{builtins, operators, Console, Environment, Object, RuntimeError, Stdio};
+use crate::snapshot::LoopValues;
+use crate::span::StartEndSpan;
+use crate::tokens::{RawToken, Token};
+
+use super::{Chunk, Constant, Instruction};
+
+#[derive(Debug)]
+/// [VirtualMachine] executes a [Chunk] of bytecode on a stack machine.
+///
+/// The program behaves exactly as with the [Interpreter](crate::visitors::Interpreter): the instructions perform the same runtime
+/// checks in the same order, and report the same errors. Each value on the stack keeps the span of the instruction that pushed it,
+/// i.e. of the expression it originates from, for the error reports.
+pub struct VirtualMachine {
+    /// Environment for storing variables
+    pub environment: Environment,
+    stack: Vec<(Object, StartEndSpan)>,
+    /// The currently executing `for` loops: the control variable, the span of the loop header, and the values yet to assign
+    loops: Vec<(String, StartEndSpan, LoopValues)>,
+    console: Box<dyn Console>,
+}
+
+impl VirtualMachine {
+    /// Creates a new [VirtualMachine] object
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            environment: Environment::default(),
+            stack: Vec::new(),
+            loops: Vec::new(),
+            console: Box::new(Stdio),
+        }
+    }
+
+    /// Replaces the [Console] the program reads its input from and prints its output to
+    pub fn set_console(&mut self, console: Box<dyn Console>) {
+        self.console = console;
+    }
+
+    /// The primary function of the [VirtualMachine]: executes the whole chunk
+    ///
+    /// # Errors
+    /// Returns the same [RuntimeError] as the [Interpreter](crate::visitors::Interpreter) for the program the chunk was compiled from,
+    /// or [RuntimeError::InvalidBytecode] for a chunk the [BytecodeGenerator](crate::visitors::BytecodeGenerator) never produces
+    pub fn run(&mut self, chunk: &Chunk) -> Result<(), RuntimeError> {
+        self.environment = Environment::default();
+        self.stack.clear();
+        self.loops.clear();
+        let mut offset = 0;
+        while let Some(&instruction) = chunk.code.get(offset) {
+            let span = chunk
+                .spans
+                .get(offset)
+                .copied()
+                .unwrap_or(StartEndSpan::new(0, 0));
+            offset = match self.execute(chunk, instruction, span)? {
+                Some(target) => target,
+                None => offset + 1,
+            };
+        }
+        Ok(())
+    }
+
+    /// Internal helper: executes a single instruction, returning the offset to continue at if it jumps
+    fn execute(
+        &mut self,
+        chunk: &Chunk,
+        instruction: Instruction,
+        span: StartEndSpan,
+    ) -> Result<Option<usize>, RuntimeError> {
+        match instruction {
+            Instruction::Constant(index) => {
+                let value = match constant(chunk, index, span)? {
+                    Constant::Number(n) => Object::Number(*n),
+                    Constant::Real(n) => Object::Real(*n),
+                    Constant::Text(t) => Object::Text(t.clone()),
+                    Constant::Type(kind) => return Err(invalid(format!("type {kind}"), span)),
+                };
+                self.stack.push((value, span));
+            }
+            Instruction::True => self.stack.push((Object::Boolean(true), span)),
+            Instruction::False => self.stack.push((Object::Boolean(false), span)),
+            Instruction::Get(variable) => {
+                let value = self.environment.get(name(chunk, variable, span)?, span)?;
+                self.stack.push((value, span));
+            }
+            Instruction::Define(variable, kind) => {
+                let name = name(chunk, variable, span)?;
+                self.check_for_variable(name, span)?;
+                let value = Object::default_value(kind_of(chunk, kind, span)?);
+                self.environment.define(name, value, span)?;
+            }
+            Instruction::DefineInit(variable, kind) => {
+                let (value, value_span) = self.pop(span)?;
+                let name = name(chunk, variable, span)?;
+                let kind = kind_of(chunk, kind, span)?;
+                self.check_for_variable(name, span)?;
+                if value.kind_to_string() != kind.to_string() {
+                    return Err(RuntimeError::VariableAssignTypeMismatch(
+                        kind.to_string(),
+                        value.kind_to_string(),
+                        span.into(),
+                        value_span.into(),
+                    ));
+                }
+                self.environment.define(name, value, span)?;
+            }
+            Instruction::Assign(variable) => {
+                let (value, _) = self.pop(span)?;
+                let name = name(chunk, variable, span)?;
+                self.check_for_variable(name, span)?;
+                self.environment.assign(name, value.clone(), span)?;
+                self.stack.push((value, span));
+            }
+            Instruction::AssignIndex(variable) => {
+                let (value, value_span) = self.pop(span)?;
+                let (index, index_span) = self.pop(span)?;
+                let name = name(chunk, variable, span)?;
+                self.check_for_variable(name, span)?;
+                let index = as_index(&index, index_span)?;
+                self.environment.assign_index(
+                    name,
+                    index,
+                    value.clone(),
+                    index_span,
+                    value_span,
+                )?;
+                self.stack.push((value, span));
+            }
+            Instruction::Index => {
+                let (array, array_span) = self.pop(span)?;
+                let (index, index_span) = self.pop(span)?;
+                let index = as_index(&index, index_span)?;
+                let element = match array {
+                    Object::Array(elements) => usize::try_from(index)
+                        .ok()
+                        .and_then(|i| elements.get(i))
+                        .cloned()
+                        .ok_or_else(|| {
+                            RuntimeError::IndexOutOfBounds(index, elements.len(), index_span.into())
+                        })?,
+                    other => {
+                        return Err(RuntimeError::IndexNonArray(
+                            other.kind_to_string(),
+                            array_span.into(),
+                        ))
+                    }
+                };
+                self.stack.push((element, span));
+            }
+            Instruction::Add
+            | Instruction::Subtract
+            | Instruction::Multiply
+            | Instruction::Divide
+            | Instruction::Equal
+            | Instruction::NotEqual
+            | Instruction::Less
+            | Instruction::LessEqual
+            | Instruction::Greater
+            | Instruction::GreaterEqual => {
+                let (right, right_span) = self.pop(span)?;
+                let (left, left_span) = self.pop(span)?;
+                let operator = operator(instruction, span)?;
+                let result =
+                    operators::binary(&operator, &left, &right, left_span, right_span, span)?;
+                self.stack.push((result, span));
+            }
+            Instruction::Negate | Instruction::Not => {
+                let (right, right_span) = self.pop(span)?;
+                let operator = operator(instruction, span)?;
+                let result = operators::unary(&operator, &right, right_span, span)?;
+                self.stack.push((result, span));
+            }
+            Instruction::AsBoolean => {
+                let (value, value_span) = self.pop(span)?;
+                value.as_bool(value_span)?;
+                // The value of a logical expression is that of the instruction, rather than of its right operand
+                self.stack.push((value, span));
+            }
+            Instruction::ToText => {
+                let (value, value_span) = self.pop(span)?;
+                self.stack
+                    .push((Object::Text(value.to_string()), value_span));
+            }
+            Instruction::Concat => {
+                let (right, right_span) = self.pop(span)?;
+                let (left, left_span) = self.pop(span)?;
+                let text = left.as_text(left_span)? + &right.as_text(right_span)?;
+                self.stack.push((Object::Text(text), span));
+            }
+            Instruction::Call(function, count) => {
+                let function = match constant(chunk, function, span)? {
+                    Constant::Text(function) => function,
+                    other => return Err(invalid(format!("function {other}"), span)),
+                };
+                let start = self
+                    .stack
+                    .len()
+                    .checked_sub(count)
+                    .ok_or_else(|| invalid("missing arguments".to_owned(), span))?;
+                let arguments = self.stack.split_off(start);
+                // The call expression starts with the name of the function
+                let name_span =
+                    StartEndSpan::new(span.start, span.start + function.chars().count());
+                let result = builtins::call(function, &arguments, span, name_span)?;
+                self.stack.push((result, span));
+            }
+            Instruction::Jump(target) => return Ok(Some(target)),
+            Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => {
+                let (value, value_span) = self.pop(span)?;
+                let jump = matches!(instruction, Instruction::JumpIfTrue(_));
+                if value.as_bool(value_span)? == jump {
+                    return Ok(Some(target));
+                }
+            }
+            Instruction::Pop => {
+                self.pop(span)?;
+            }
+            Instruction::Print => {
+                let (value, _) = self.pop(span)?;
+                self.console
+                    .print(&value.to_string())
+                    .map_err(|_| RuntimeError::PrintCouldNotFlush(span.into()))?;
+            }
+            Instruction::Read(variable) => {
+                let name = name(chunk, variable, span)?;
+                self.check_for_variable(name, span)?;
+                let mut buffer = String::new();
+                self.console
+                    .read_line(&mut buffer)
+                    .map_err(|_| RuntimeError::ReadLineFailed(span.into()))?;
+                let old = self.environment.get(name, span)?;
+                let new = old.parse_input(buffer, span)?;
+                self.environment.assign(name, new, span)?;
+            }
+            Instruction::Assert => {
+                let (value, value_span) = self.pop(span)?;
+                match value.as_bool(value_span) {
+                    Ok(true) => (),
+                    Ok(false) => return Err(RuntimeError::AssertionFailed(value_span.into())),
+                    Err(_) => return Err(RuntimeError::AssertExprNotTruthy(value_span.into())),
+                }
+            }
+            Instruction::RangeLoop(variable) => {
+                let (end, end_span) = self.pop(span)?;
+                let (start, start_span) = self.pop(span)?;
+                let start = start.as_numeric(start_span).map_err(|_| {
+                    RuntimeError::ForStartNonNumeric(start.to_string(), start_span.into())
+                })?;
+                let end = end.as_numeric(end_span).map_err(|_| {
+                    RuntimeError::ForEndNonNumeric(end.to_string(), end_span.into())
+                })?;
+                if start > end {
+                    return Err(RuntimeError::ForEndLarger(
+                        start_span.into(),
+                        end_span.into(),
+                    ));
+                }
+                self.begin_loop(
+                    name(chunk, variable, span)?,
+                    span,
+                    LoopValues::Range { next: start, end },
+                )?;
+            }
+            Instruction::ArrayLoop(variable) => {
+                let (array, array_span) = self.pop(span)?;
+                let elements = match array {
+                    Object::Array(elements) => elements,
+                    other => {
+                        return Err(RuntimeError::ForIterateNonArray(
+                            other.kind_to_string(),
+                            array_span.into(),
+                        ))
+                    }
+                };
+                let values = LoopValues::Elements { elements, next: 0 };
+                self.begin_loop(name(chunk, variable, span)?, span, values)?;
+            }
+            Instruction::LoopNext(target) => {
+                let (name, header, values) = self
+                    .loops
+                    .last_mut()
+                    .ok_or_else(|| invalid("no loop".to_owned(), span))?;
+                let value = match values.next_value() {
+                    Some(value) => value,
+                    None => return Ok(Some(target)),
+                };
+                if self.environment.assign(name, value, *header).is_err() {
+                    return Err(RuntimeError::ForBadAssignment(name.clone(), span.into()));
+                }
+            }
+            Instruction::LoopEnd => {
+                self.loops
+                    .pop()
+                    .ok_or_else(|| invalid("no loop".to_owned(), span))?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Internal helper: pops a value with the span of its expression
+    fn pop(&mut self, span: StartEndSpan) -> Result<(Object, StartEndSpan), RuntimeError> {
+        self.stack
+            .pop()
+            .ok_or_else(|| invalid("empty stack".to_owned(), span))
+    }
+
+    /// Internal helper: returns a ForVariableModified error if the given name is the control variable of an active `for` loop
+    fn check_for_variable(&self, name: &str, span: StartEndSpan) -> Result<(), RuntimeError> {
+        match self.loops.iter().find(|(n, _, _)| n == name) {
+            Some((_, header, _)) => Err(RuntimeError::ForVariableModified(
+                name.to_owned(),
+                (*header).into(),
+                span.into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Internal helper: begins a `for` loop over the values with the control variable. The span is that of the loop header
+    fn begin_loop(
+        &mut self,
+        name: &str,
+        header: StartEndSpan,
+        values: LoopValues,
+    ) -> Result<(), RuntimeError> {
+        // Nested loops must not reuse the control variable of an enclosing loop
+        self.check_for_variable(name, header)?;
+        self.loops.push((name.to_owned(), header, values));
+        Ok(())
+    }
+}
+
+impl Default for VirtualMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Internal helper: returns an InvalidBytecode error
+fn invalid(reason: String, span: StartEndSpan) -> RuntimeError {
+    RuntimeError::InvalidBytecode(reason, span.into())
+}
+
+/// Internal helper: returns the constant with the given index
+fn constant(chunk: &Chunk, index: usize, span: StartEndSpan) -> Result<&Constant, RuntimeError> {
+    chunk
+        .constants
+        .get(index)
+        .ok_or_else(|| invalid(format!("no constant #{index}"), span))
+}
+
+/// Internal helper: returns the type constant with the given index
+fn kind_of(chunk: &Chunk, index: usize, span: StartEndSpan) -> Result<&VarType, RuntimeError> {
+    match constant(chunk, index, span)? {
+        Constant::Type(kind) => Ok(kind),
+        other => Err(invalid(format!("type {other}"), span)),
+    }
+}
+
+/// Internal helper: returns the name of the variable with the given index
+fn name(chunk: &Chunk, index: usize, span: StartEndSpan) -> Result<&str, RuntimeError> {
+    chunk
+        .variables
+        .get(index)
+        .map(String::as_str)
+        .ok_or_else(|| invalid(format!("no variable ${index}"), span))
+}
+
+/// Internal helper: converts an array index into a number
+fn as_index(index: &Object, span: StartEndSpan) -> Result<i64, RuntimeError> {
+    index
+        .as_numeric(span)
+        .map_err(|_| RuntimeError::IndexNonNumeric(index.kind_to_string(), span.into()))
+}
+
+/// Internal helper: the operator token of an arithmetic, comparison or negation instruction
+fn operator(instruction: Instruction, span: StartEndSpan) -> Result<Token, RuntimeError> {
+    let operator = match instruction {
+        Instruction::Add => RawToken::Plus,
+        Instruction::Subtract | Instruction::Negate => RawToken::Minus,
+        Instruction::Multiply => RawToken::Star,
+        Instruction::Divide => RawToken::Slash,
+        Instruction::Equal => RawToken::Equal,
+        Instruction::NotEqual => RawToken::NotEqual,
+        Instruction::Less => RawToken::Less,
+        Instruction::LessEqual => RawToken::LessEqual,
+        Instruction::Greater => RawToken::Greater,
+        Instruction::GreaterEqual => RawToken::GreaterEqual,
+        Instruction::Not => RawToken::Bang,
+        _ => return Err(invalid(instruction.mnemonic().to_owned(), span)),
+    };
+    Ok(Token::new(operator, span))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::bytecode::{Chunk, Instruction, VirtualMachine};
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::runtime::{Object, RuntimeError};
+    use crate::span::StartEndSpan;
+    use crate::visitors::BytecodeGenerator;
+
+    fn run(source: &str) -> (VirtualMachine, Result<(), RuntimeError>) {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let chunk = BytecodeGenerator::default().generate(&statements).unwrap();
+        let mut vm = VirtualMachine::new();
+        let result = vm.run(&chunk);
+        (vm, result)
+    }
+
+    #[test]
+    fn loops_and_arrays() {
+        let (vm, result) = run("var xs : array[3] of int; var i : int; var sum : int;
+            for i in 0..2 do xs[i] := i + 1; end for;
+            for i in xs do sum := sum + i; end for;");
+        result.unwrap();
+        let span = StartEndSpan::new(0, 0);
+        assert!(matches!(
+            vm.environment.get("sum", span),
+            Ok(Object::Number(6))
+        ));
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        // The right operands would fail if they were evaluated
+        let (_, result) = run("assert (false & 1 < \"a\");");
+        assert!(matches!(result, Err(RuntimeError::AssertionFailed(_))));
+        let (_, result) = run("assert (true | 1 < \"a\");");
+        result.unwrap();
+    }
+
+    #[test]
+    fn invalid_bytecode_is_an_error() {
+        let mut chunk = Chunk::default();
+        chunk.push(Instruction::Pop, StartEndSpan::new(0, 0));
+        let result = VirtualMachine::new().run(&chunk);
+        assert!(matches!(result, Err(RuntimeError::InvalidBytecode(_, _))));
+    }
+}
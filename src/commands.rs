@@ -1,8 +1,35 @@
 mod ast;
 pub use ast::ast;
 
+mod build;
+pub use build::{build, Target};
+
+mod check;
+pub use check::check;
+
+mod dap;
+pub use dap::dap;
+
+mod debug;
+pub use debug::debug;
+
+mod disasm;
+pub use disasm::disasm;
+
+mod explain;
+pub use explain::explain;
+
 mod lex;
 pub use lex::lex;
 
+mod lint;
+pub use lint::LintOptions;
+
+mod report;
+pub use report::MessageFormat;
+
+mod resume;
+pub use resume::{resume, PauseOptions};
+
 mod run;
-pub use run::run;
+pub use run::{run, ToolOptions};
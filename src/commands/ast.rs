@@ -1,43 +1,28 @@
 use std::fs;
 
+use minipl::optimizer::Optimizer;
 use minipl::tokens::RawToken;
 use minipl::visitors::ASTPrinter;
 use minipl::{lexing::Lexer, parsing::Parser};
 
-use ariadne::{ColorGenerator, Label, Report, ReportKind, Source};
 use camino::Utf8PathBuf;
 use miette::{IntoDiagnostic, Result};
 use tracing::info;
 
-pub fn ast(path: Utf8PathBuf) -> Result<()> {
+use super::report::{self, MessageFormat};
+
+pub fn ast(path: Utf8PathBuf, format: MessageFormat, optimize: bool) -> Result<()> {
     // 1. Lexing
     let source: String = fs::read_to_string(&path).into_diagnostic()?;
-    let mut lexer = Lexer::new(&source);
+    let lexer = Lexer::new(&source);
     let mut tokens = lexer.scan()?;
-    let mut colors = ColorGenerator::new();
 
     // 2. Error reporting for lexing
     if tokens
         .iter()
         .any(|token| matches!(token.token, RawToken::Error(_)))
     {
-        let mut report =
-            Report::build(ReportKind::Error, &path, 0).with_message("Lexing errors found");
-
-        for token in &tokens {
-            if let RawToken::Error(message) = token.token.clone() {
-                report = report.with_label(
-                    Label::new((&path, token.span.start..token.span.end))
-                        .with_message(message)
-                        .with_color(colors.next()),
-                );
-            }
-        }
-
-        report
-            .finish()
-            .print((&path, Source::from(&source)))
-            .unwrap();
+        report::print_lexing_errors(&path, &tokens, format, &source)?;
     }
 
     // 3. Parsing
@@ -59,12 +44,23 @@ pub fn ast(path: Utf8PathBuf) -> Result<()> {
     let statements = match parser.parse() {
         Ok(statements) => statements,
         Err(err) => {
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
             let report: miette::Report = err.into();
             return Err(report.with_source_code(source));
         }
     };
 
-    // 4. AST prettyprinting
+    // 4. Optimization
+    let statements = if optimize {
+        Optimizer::default().optimize(&statements)
+    } else {
+        statements
+    };
+
+    // 5. AST prettyprinting
     let mut astprinter = ASTPrinter::default();
     for statement in statements {
         let prettyprint = astprinter.print(&statement)?;
@@ -0,0 +1,109 @@
+use std::fs;
+
+use minipl::tokens::RawToken;
+use minipl::visitors::{CGenerator, WatGenerator};
+use minipl::{lexing::Lexer, parsing::Parser};
+
+use camino::Utf8PathBuf;
+use clap::ArgEnum;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use tracing::info;
+
+use super::report::{self, MessageFormat};
+
+/// The target language of the compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Target {
+    /// Standalone C99 source file, compiled with e.g. `cc -std=c99 program.c -lm`
+    C,
+    /// WebAssembly module in the text format. Imports its input and output from the host, see the generated runtime
+    Wat,
+    /// WebAssembly module in the binary format, assembled from the text format
+    Wasm,
+}
+
+impl Target {
+    /// The file extension of the compiled output
+    fn extension(self) -> &'static str {
+        match self {
+            Target::C => "c",
+            Target::Wat => "wat",
+            Target::Wasm => "wasm",
+        }
+    }
+}
+
+pub fn build(
+    path: Utf8PathBuf,
+    format: MessageFormat,
+    target: Target,
+    output: Option<Utf8PathBuf>,
+) -> Result<()> {
+    // 1. Lexing
+    let source: String = fs::read_to_string(&path).into_diagnostic()?;
+    let lexer = Lexer::new(&source);
+    let mut tokens = lexer.scan()?;
+
+    // 2. Error reporting for lexing. Unlike when running, any lexing error aborts the build, before anything is written
+    let lexing_failed = tokens
+        .iter()
+        .any(|token| matches!(token.token, RawToken::Error(_)));
+    if lexing_failed {
+        report::print_lexing_errors(&path, &tokens, format, &source)?;
+        return Err(report::aborting());
+    }
+
+    // 3. Parsing
+    // remove ignorables
+    tokens.retain(|token| {
+        !matches!(
+            token.token,
+            RawToken::Comment | RawToken::Error(_) | RawToken::Whitespace | RawToken::EOF
+        )
+    });
+
+    let statements = if tokens.is_empty() {
+        info!("Nothing to parse. Source contained ignorable tokens only.");
+        Vec::new()
+    } else {
+        let mut parser = Parser::new(tokens);
+        match parser.parse() {
+            Ok(statements) => statements,
+            Err(err) => {
+                if format == MessageFormat::Json {
+                    report::print_json(&err, err.suggestion(), &source);
+                    return Err(report::aborting());
+                }
+                let report: miette::Report = err.into();
+                return Err(report.with_source_code(source));
+            }
+        }
+    };
+
+    // 4. Code generation
+    let code = match target {
+        Target::C => CGenerator::new(&source, path.as_str())
+            .generate(&statements)
+            .map_err(|err| report::generation_failed(err, format, &source))?
+            .into_bytes(),
+        Target::Wat => WatGenerator::new(&source)
+            .generate(&statements)
+            .map_err(|err| report::generation_failed(err, format, &source))?
+            .into_bytes(),
+        Target::Wasm => {
+            let module = WatGenerator::new(&source)
+                .generate(&statements)
+                .map_err(|err| report::generation_failed(err, format, &source))?;
+            wat::parse_str(module)
+                .into_diagnostic()
+                .wrap_err("Failed to assemble the generated WebAssembly module")?
+        }
+    };
+    let output = output.unwrap_or_else(|| path.with_extension(target.extension()));
+    fs::write(&output, code)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write the output file {output}"))?;
+    info!("Wrote {}", output);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::fs::File;
+
+use minipl::{lexing::Lexer, parsing::Parser};
+
+use camino::Utf8PathBuf;
+use miette::{IntoDiagnostic, Result};
+
+use super::lint::{self, LintOptions};
+use super::report::{self, MessageFormat};
+
+pub fn check(path: Utf8PathBuf, format: MessageFormat, options: &LintOptions) -> Result<()> {
+    // 1. Lexing and parsing straight from the file, which is read again only for the diagnostics
+    let file = File::open(&path).into_diagnostic()?;
+    let mut parser = Parser::from_lexer(Lexer::from_reader(file));
+    let result = parser.parse();
+
+    // 2. Error reporting for lexing
+    let lexing_failed = !parser.lexing_errors().is_empty();
+    if lexing_failed {
+        let source = report::read_source(&path)?;
+        report::print_lexing_errors(&path, parser.lexing_errors(), format, &source)?;
+    }
+
+    // 3. Error reporting for parsing
+    let statements = match result {
+        Ok(statements) => statements,
+        Err(err) => {
+            let source = report::read_source(&path)?;
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
+            let report: miette::Report = err.into();
+            return Err(report.with_source_code(source));
+        }
+    };
+    // The parser skips the invalid tokens, but the program is not valid with them
+    if lexing_failed {
+        return Err(report::aborting());
+    }
+
+    // 4. Linting
+    lint::lint(&path, &statements, format, options)
+}
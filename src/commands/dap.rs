@@ -0,0 +1,11 @@
+use std::io;
+
+use minipl::dap::DapServer;
+
+use miette::{IntoDiagnostic, Result};
+
+pub fn dap() -> Result<()> {
+    // The messages are exchanged on the standard input and output, so the program prints in output events and reads the input given at launch
+    let mut server = DapServer::new(io::stdin().lock(), io::stdout());
+    server.run().into_diagnostic()
+}
@@ -0,0 +1,93 @@
+use std::{fs, io};
+
+use minipl::debugger::Debugger;
+use minipl::runtime::RuntimeError;
+use minipl::tokens::RawToken;
+use minipl::visitors::Interpreter;
+use minipl::{lexing::Lexer, parsing::Parser};
+
+use camino::Utf8PathBuf;
+use miette::{IntoDiagnostic, Result};
+use tracing::info;
+
+use super::report::{self, MessageFormat};
+
+pub fn debug(path: Utf8PathBuf, format: MessageFormat) -> Result<()> {
+    // 1. Lexing
+    let source: String = fs::read_to_string(&path).into_diagnostic()?;
+    let lexer = Lexer::new(&source);
+    let scan_results = lexer.scan();
+    // 2. Error reporting for UnrecoverableLexingError
+    let tokens = match scan_results {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            if format == MessageFormat::Json {
+                report::print_json(err.as_ref(), None, &source);
+                return Err(report::aborting());
+            }
+            // Print an additional newline to clear possible outputs
+            println!();
+            let report: miette::Report = err;
+            return Err(report.with_source_code(source));
+        }
+    };
+
+    // 3. Error reporting for RecoverableLexingError. The program is not debugged without the invalid tokens
+    if tokens
+        .iter()
+        .any(|token| matches!(token.token, RawToken::Error(_)))
+    {
+        report::print_lexing_errors(&path, &tokens, format, &source)?;
+        return Err(report::aborting());
+    }
+
+    // 4. Parsing
+    if tokens.is_empty() {
+        info!("Nothing to execute. Source contained ignorable tokens only.");
+        return Ok(());
+    }
+    let mut parser = Parser::new(tokens);
+
+    let statements = match parser.parse() {
+        Ok(statements) => statements,
+        Err(err) => {
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
+            let report: miette::Report = err.into();
+            return Err(report.with_source_code(source));
+        }
+    };
+
+    // 5. Debugging. The commands and the input of the program are read from the same standard input, line by line
+    println!("Debugging {path}, paused before the first statement. Type `help` for the commands");
+    let commands = std::iter::from_fn(|| {
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    });
+    let mut interpreter = Interpreter::with_hook(Debugger::new(&source, commands, io::stdout()));
+    match interpreter.eval(&statements) {
+        Ok(()) => {
+            println!("\nProgram finished");
+            Ok(())
+        }
+        Err(RuntimeError::Stopped(_)) => {
+            println!("\nProgram stopped");
+            Ok(())
+        }
+        Err(err) => {
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
+            // Print an additional newline to clear the output line
+            println!();
+            let report: miette::Report = err.into();
+            Err(report.with_source_code(source))
+        }
+    }
+}
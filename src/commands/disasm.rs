@@ -0,0 +1,66 @@
+use std::fs;
+
+use minipl::bytecode::Disassembler;
+use minipl::tokens::RawToken;
+use minipl::visitors::BytecodeGenerator;
+use minipl::{lexing::Lexer, parsing::Parser};
+
+use camino::Utf8PathBuf;
+use miette::{IntoDiagnostic, Result};
+use tracing::info;
+
+use super::report::{self, MessageFormat};
+
+pub fn disasm(path: Utf8PathBuf, format: MessageFormat, interleave: bool) -> Result<()> {
+    // 1. Lexing
+    let source: String = fs::read_to_string(&path).into_diagnostic()?;
+    let lexer = Lexer::new(&source);
+    let mut tokens = lexer.scan()?;
+
+    // 2. Error reporting for lexing. As when building, any lexing error aborts
+    let lexing_failed = tokens
+        .iter()
+        .any(|token| matches!(token.token, RawToken::Error(_)));
+    if lexing_failed {
+        report::print_lexing_errors(&path, &tokens, format, &source)?;
+        return Err(report::aborting());
+    }
+
+    // 3. Parsing
+    // remove ignorables
+    tokens.retain(|token| {
+        !matches!(
+            token.token,
+            RawToken::Comment | RawToken::Error(_) | RawToken::Whitespace | RawToken::EOF
+        )
+    });
+
+    let statements = if tokens.is_empty() {
+        info!("Nothing to parse. Source contained ignorable tokens only.");
+        Vec::new()
+    } else {
+        let mut parser = Parser::new(tokens);
+        match parser.parse() {
+            Ok(statements) => statements,
+            Err(err) => {
+                if format == MessageFormat::Json {
+                    report::print_json(&err, err.suggestion(), &source);
+                    return Err(report::aborting());
+                }
+                let report: miette::Report = err.into();
+                return Err(report.with_source_code(source));
+            }
+        }
+    };
+
+    // 4. Compilation and disassembly
+    let chunk = BytecodeGenerator::default()
+        .generate(&statements)
+        .map_err(|err| report::generation_failed(err, format, &source))?;
+    print!(
+        "{}",
+        Disassembler::new(&chunk, &source, interleave).disassemble()
+    );
+
+    Ok(())
+}
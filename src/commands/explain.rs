@@ -0,0 +1,16 @@
+use minipl::explanations;
+
+use miette::{miette, Result};
+
+pub fn explain(code: &str) -> Result<()> {
+    match explanations::explain(code) {
+        Some(explanation) => {
+            println!("{explanation}");
+            Ok(())
+        }
+        None => Err(miette!(
+            "Unknown error code: {}. Error codes look like E0101, see the `code` of the reported error",
+            code
+        )),
+    }
+}
@@ -10,7 +10,7 @@ use tracing::info;
 
 pub fn lex(path: Utf8PathBuf, verbose: bool) -> Result<()> {
     let source: String = fs::read_to_string(&path).into_diagnostic()?;
-    let mut lexer = Lexer::new(&source);
+    let lexer = Lexer::new(&source);
     let scan_results = if verbose {
         lexer.scan_verbose()
     } else {
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use minipl::lints::{Level, LintConfig};
+use minipl::parsing::statement::Statement;
+use minipl::visitors::Linter;
+
+use camino::Utf8Path;
+use clap::{value_parser, Arg, ArgMatches, Args, FromArgMatches};
+use miette::{miette, IntoDiagnostic, Result, WrapErr};
+use tracing::debug;
+
+use super::report::{self, MessageFormat};
+
+/// The name of the project config file, searched for from the directory of the source file upwards
+const CONFIG_FILE: &str = "minipl.toml";
+
+/// Command line flags for setting the levels of the lint rules. Applied after the project config file, in the order given
+/// on the command line, so that a later flag overrides an earlier one as with rustc. The group `warnings` refers to all rules
+#[derive(Debug, Default)]
+pub struct LintOptions {
+    /// The rules with their levels, in the order of the flags
+    levels: Vec<(Level, String)>,
+}
+
+/// The long and short names of the flags, the levels they set, and their help texts
+const FLAGS: [(&str, char, Level, &str); 3] = [
+    (
+        "allow",
+        'A',
+        Level::Allow,
+        "Allow the given lint rule, i.e. do not report it",
+    ),
+    (
+        "warn",
+        'W',
+        Level::Warn,
+        "Report the given lint rule as a warning",
+    ),
+    (
+        "deny",
+        'D',
+        Level::Deny,
+        "Report the given lint rule as an error, and do not run the program",
+    ),
+];
+
+// The flags are declared by hand, as the derived parser would collect each flag separately and lose their relative order
+impl Args for LintOptions {
+    fn augment_args(command: clap::Command<'_>) -> clap::Command<'_> {
+        FLAGS
+            .into_iter()
+            .fold(command, |command, (name, short, _, help)| {
+                command.arg(
+                    Arg::new(name)
+                        .long(name)
+                        .short(short)
+                        .value_name("RULE")
+                        .help(help)
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .value_parser(value_parser!(String)),
+                )
+            })
+    }
+
+    fn augment_args_for_update(command: clap::Command<'_>) -> clap::Command<'_> {
+        Self::augment_args(command)
+    }
+}
+
+impl FromArgMatches for LintOptions {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let mut flags = Vec::new();
+        for (name, _, level, _) in FLAGS {
+            if let (Some(indices), Some(rules)) =
+                (matches.indices_of(name), matches.get_many::<String>(name))
+            {
+                flags.extend(
+                    indices
+                        .zip(rules)
+                        .map(|(index, rule)| (index, level, rule.clone())),
+                );
+            }
+        }
+        flags.sort_by_key(|(index, _, _)| *index);
+        let levels = flags
+            .into_iter()
+            .map(|(_, level, rule)| (level, rule))
+            .collect();
+        Ok(Self { levels })
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+/// Lints the program and reports the found lints, reading the source file again to show them. Returns an error if any lint was denied
+pub fn lint(
+    path: &Utf8Path,
+    statements: &[Statement],
+    format: MessageFormat,
+    options: &LintOptions,
+) -> Result<()> {
+    let config = config(path, options)?;
+    let lints = Linter::new(config).lint(statements);
+    if lints.is_empty() {
+        return Ok(());
+    }
+    let source = report::read_source(path)?;
+    for lint in &lints {
+        if format == MessageFormat::Json {
+            report::print_json(lint, None, &source);
+        } else {
+            let report: miette::Report = lint.clone().into();
+            eprintln!("{:?}", report.with_source_code(source.clone()));
+        }
+    }
+
+    let denied = lints
+        .iter()
+        .filter(|lint| lint.level == Level::Deny)
+        .count();
+    match denied {
+        0 => Ok(()),
+        _ if format == MessageFormat::Json => Err(report::aborting()),
+        1 => Err(miette!("Aborting due to the previous denied lint")),
+        _ => Err(miette!("Aborting due to {} denied lints", denied)),
+    }
+}
+
+/// Builds the lint configuration from the project config file, if any, and the command line flags
+fn config(path: &Utf8Path, options: &LintOptions) -> Result<LintConfig> {
+    let mut config = match find_config(path.as_std_path()) {
+        Some(config_path) => {
+            debug!("Lint config file: {}", config_path.display());
+            let contents = fs::read_to_string(&config_path).into_diagnostic()?;
+            LintConfig::from_toml(&contents).wrap_err_with(|| {
+                format!("Failed to load the config file {}", config_path.display())
+            })?
+        }
+        None => LintConfig::default(),
+    };
+    for (level, name) in &options.levels {
+        config.set(name, *level)?;
+    }
+    Ok(config)
+}
+
+/// Returns the closest `minipl.toml` in the directory of the source file or its ancestors
+fn find_config(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .skip(1)
+        .map(|directory| directory.join(CONFIG_FILE))
+        .find(|candidate| candidate.is_file())
+}
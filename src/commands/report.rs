@@ -0,0 +1,167 @@
+use std::fs;
+
+use ariadne::{ColorGenerator, Label, ReportKind, Source};
+use camino::Utf8Path;
+use clap::ArgEnum;
+use minipl::lexing::RecoverableLexingError;
+use minipl::runtime::RuntimeError;
+use minipl::suggestions::Suggestion;
+use minipl::tokens::{RawToken, Token};
+use minipl::StartEndSpan;
+
+use miette::{miette, Diagnostic, IntoDiagnostic, Report, Result, Severity};
+use serde_json::{json, Value};
+
+/// The output format of the diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum MessageFormat {
+    /// Human-readable, graphical reports
+    Human,
+    /// One JSON object per line on stdout, with LSP-style ranges and quick fixes
+    Json,
+}
+
+/// Prints the diagnostic as a single line of JSON to stdout
+pub fn print_json(diagnostic: &dyn Diagnostic, suggestion: Option<&Suggestion>, source: &str) {
+    let labels: Vec<Value> = diagnostic
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|label| {
+            let span = StartEndSpan::new(label.offset(), label.offset() + label.len());
+            json!({
+                "message": label.label(),
+                "span": span_json(span),
+                "range": range_json(span, source),
+            })
+        })
+        .collect();
+    let severity = match diagnostic.severity() {
+        Some(Severity::Advice) => "advice",
+        Some(Severity::Warning) => "warning",
+        Some(Severity::Error) | None => "error",
+    };
+    let json = json!({
+        "severity": severity,
+        "code": diagnostic.code().map(|code| code.to_string()),
+        "message": diagnostic.to_string(),
+        "help": diagnostic.help().map(|help| help.to_string()),
+        "labels": labels,
+        "fixes": fixes_json(suggestion, source),
+    });
+    println!("{json}");
+}
+
+/// Prints the recoverable lexing error as a single line of JSON to stdout
+pub fn print_lexing_error_json(error: &RecoverableLexingError, span: StartEndSpan, source: &str) {
+    let json = json!({
+        "severity": "error",
+        "code": error.code(),
+        "message": error.to_string(),
+        "help": null,
+        "labels": [{
+            "message": null,
+            "span": span_json(span),
+            "range": range_json(span, source),
+        }],
+        "fixes": [],
+    });
+    println!("{json}");
+}
+
+/// Reads the source code of the program for showing it in diagnostics. Commands parsing the program straight from its file
+/// read it again only when there is a diagnostic to show. Invalid UTF-8 is replaced, as the lexer stops there anyway
+pub fn read_source(path: &Utf8Path) -> Result<String> {
+    let bytes = fs::read(path).into_diagnostic()?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Prints the recoverable lexing errors among the tokens, as one line of JSON each or as a single graphical report on the standard error
+pub fn print_lexing_errors(
+    path: &Utf8Path,
+    errors: &[Token],
+    format: MessageFormat,
+    source: &str,
+) -> Result<()> {
+    if format == MessageFormat::Json {
+        for token in errors {
+            if let RawToken::Error(error) = &token.token {
+                print_lexing_error_json(error, token.span, source);
+            }
+        }
+        return Ok(());
+    }
+    let mut colors = ColorGenerator::new();
+    let mut report =
+        ariadne::Report::build(ReportKind::Error, path, 0).with_message("Lexing errors found");
+    for token in errors {
+        if let RawToken::Error(message) = &token.token {
+            report = report.with_label(
+                Label::new((path, token.span.start..token.span.end))
+                    .with_message(format!("[{}] {message}", message.code()))
+                    .with_color(colors.next()),
+            );
+        }
+    }
+    report
+        .finish()
+        .print((path, Source::from(source)))
+        .into_diagnostic()
+}
+
+/// Returns the error to exit with after the diagnostics have been printed as JSON
+pub fn aborting() -> Report {
+    miette!("Aborting due to the previous error")
+}
+
+/// Reports an error in generating code for a program, in the same way as the interpreter reports it
+pub fn generation_failed(err: RuntimeError, format: MessageFormat, source: &str) -> Report {
+    if format == MessageFormat::Json {
+        print_json(&err, err.suggestion(), source);
+        return aborting();
+    }
+    let report: Report = err.into();
+    report.with_source_code(source.to_owned())
+}
+
+/// Quick fixes in the style of the Language Server Protocol code actions
+fn fixes_json(suggestion: Option<&Suggestion>, source: &str) -> Value {
+    match suggestion {
+        Some(suggestion) => json!([{
+            "title": format!("Replace with `{}`", suggestion.replacement),
+            "kind": "quickfix",
+            "edits": [{
+                "span": span_json(suggestion.span),
+                "range": range_json(suggestion.span, source),
+                "newText": suggestion.replacement,
+            }],
+        }]),
+        None => json!([]),
+    }
+}
+
+fn span_json(span: StartEndSpan) -> Value {
+    json!({ "start": span.start, "end": span.end })
+}
+
+/// LSP-style range with zero-based lines and characters
+fn range_json(span: StartEndSpan, source: &str) -> Value {
+    json!({
+        "start": position_json(span.start, source),
+        "end": position_json(span.end, source),
+    })
+}
+
+fn position_json(offset: usize, source: &str) -> Value {
+    let mut line = 0;
+    let mut character = 0;
+    for char in source.chars().take(offset) {
+        if char == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += 1;
+        }
+    }
+    json!({ "line": line, "character": character })
+}
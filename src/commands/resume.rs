@@ -0,0 +1,127 @@
+use std::fs;
+
+use minipl::parsing::statement::Statement;
+use minipl::runtime::Stdio;
+use minipl::snapshot::{Capture, ExecutionState, Pause, PauseReason, Snapshot, SnapshotError};
+use minipl::visitors::Interpreter;
+
+use camino::Utf8PathBuf;
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+
+use super::report::{self, MessageFormat};
+use super::run::report_result;
+
+/// Pausing the program and saving its state into a snapshot, to be continued with `minipl resume`
+#[derive(Debug, Args)]
+pub struct PauseOptions {
+    /// Save the state of the program into the given snapshot file when it pauses, see the `--pause` options
+    #[clap(long, value_name = "PATH")]
+    snapshot: Option<Utf8PathBuf>,
+    /// Pause after executing the given number of statements
+    #[clap(long, value_name = "STEPS", requires = "snapshot")]
+    pause_after: Option<u64>,
+    /// Pause before the statements on the given line. Can be given several times
+    #[clap(
+        long,
+        value_name = "LINE",
+        requires = "snapshot",
+        multiple_occurrences = true
+    )]
+    pause_at: Vec<usize>,
+    /// Pause before each `read` statement, i.e. whenever the program is about to await input
+    #[clap(long, requires = "snapshot")]
+    pause_on_read: bool,
+}
+
+impl PauseOptions {
+    /// Returns whether a snapshot was requested
+    pub(super) fn requested(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Returns the hook pausing the program with the given source code, if a snapshot was requested
+    pub(super) fn hook(&self, source: &str) -> Option<Pause> {
+        self.snapshot.as_ref()?;
+        let mut pause = Pause::new(source).at(self.pause_at.clone());
+        if let Some(steps) = self.pause_after {
+            pause = pause.after(steps);
+        }
+        if self.pause_on_read {
+            pause = pause.on_read();
+        }
+        Some(pause)
+    }
+
+    /// Saves the snapshot if the program was paused, returning whether it was. The steps are those executed before this run
+    pub(super) fn save(
+        &self,
+        pause: &Pause,
+        state: ExecutionState,
+        source: &str,
+        output: String,
+        steps: u64,
+    ) -> Result<bool> {
+        let (path, (reason, line, column)) = match (&self.snapshot, pause.paused()) {
+            (Some(path), Some(paused)) => (path, paused),
+            _ => return Ok(false),
+        };
+        let snapshot = Snapshot {
+            source: source.to_owned(),
+            state,
+            reason,
+            line,
+            column,
+            steps: steps + pause.steps(),
+            output,
+        };
+        fs::write(path, snapshot.to_json()).into_diagnostic()?;
+        let why = match reason {
+            PauseReason::Steps => format!("after {} steps", pause.steps()),
+            PauseReason::Line => "at a breakpoint".to_owned(),
+            PauseReason::Read => "awaiting input".to_owned(),
+        };
+        eprintln!("\nPaused {why}, before the statement at line {line}, column {column}");
+        eprintln!("Wrote the snapshot to {path}, continue with `minipl resume {path}`");
+        Ok(true)
+    }
+}
+
+pub fn resume(path: Utf8PathBuf, format: MessageFormat, options: &PauseOptions) -> Result<()> {
+    let json = fs::read_to_string(&path).into_diagnostic()?;
+    let (snapshot, statements) = match load(&json) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            if format == MessageFormat::Json {
+                report::print_json(&err, None, "");
+                return Err(report::aborting());
+            }
+            return Err(err.into());
+        }
+    };
+    let source = snapshot.source;
+
+    // The program continues with the statement it was paused before, which does not pause again
+    let pause = options
+        .hook(&source)
+        .unwrap_or_else(|| Pause::new(&source))
+        .resumed();
+    let capture = Capture::new(Box::new(Stdio));
+    let mut interpreter = Interpreter::with_hook(pause);
+    interpreter.set_console(Box::new(capture.clone()));
+    interpreter.restore(snapshot.state);
+    let result = interpreter.resume(&statements);
+    let state = interpreter.state();
+    let pause = interpreter.into_hook();
+    if options.save(&pause, state, &source, capture.output(), snapshot.steps)? {
+        return Ok(());
+    }
+    report_result(result, format, || Ok(source))
+}
+
+/// Internal helper: parses the snapshot, and the program in it
+fn load(json: &str) -> Result<(Snapshot, Vec<Statement>), SnapshotError> {
+    let snapshot = Snapshot::from_json(json)?;
+    let statements = snapshot.statements()?;
+    Ok((snapshot, statements))
+}
@@ -1,73 +1,263 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 
-use minipl::tokens::RawToken;
-use minipl::visitors::Interpreter;
+use minipl::bytecode::VirtualMachine;
+use minipl::coverage::Coverage;
+use minipl::profiler::Profiler;
+use minipl::replay::{Recorder, ReplayError, Session};
+use minipl::runtime::{Console, RuntimeError, Stdio};
+use minipl::snapshot::Capture;
+use minipl::tracer::{self, Tracer};
+use minipl::visitors::{BytecodeGenerator, Interpreter};
 use minipl::{lexing::Lexer, parsing::Parser};
 
-use ariadne::{ColorGenerator, Label, Report, ReportKind, Source};
 use camino::Utf8PathBuf;
+use clap::{ArgEnum, Args};
 use miette::{IntoDiagnostic, Result};
-use tracing::info;
 
-pub fn run(path: Utf8PathBuf) -> Result<()> {
-    // 1. Lexing
-    let source: String = fs::read_to_string(&path).into_diagnostic()?;
-    let mut lexer = Lexer::new(&source);
-    let scan_results = lexer.scan();
-    // 2. Error reporting for UnrecoverableLexingError
-    let tokens = match scan_results {
-        Ok(tokens) => tokens,
+use super::lint::{self, LintOptions};
+use super::report::{self, MessageFormat};
+use super::resume::PauseOptions;
+
+/// The tools observing the execution of the program
+#[derive(Debug, Args)]
+pub struct ToolOptions {
+    /// Log each executed statement, evaluated expression and variable write to the standard error, as text or with `--trace=json` as JSON Lines
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "FORMAT",
+        min_values = 0,
+        max_values = 1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    trace: Option<TraceFormat>,
+    /// Write the trace to the given file instead of the standard error
+    #[clap(long, value_name = "PATH", requires = "trace")]
+    trace_output: Option<Utf8PathBuf>,
+    /// Count the executions and measure the time of each statement and `for` loop, and print the hot spots to the standard error
+    #[clap(long)]
+    profile: bool,
+    /// Write the collapsed stacks of the profile for flamegraph tools to the given file. Defaults to the source path with the extension `folded`
+    #[clap(long, value_name = "PATH", requires = "profile")]
+    profile_output: Option<Utf8PathBuf>,
+    /// Record the executions of each statement, expression and branch, print a summary to the standard error, and write an LCOV tracefile to the given path
+    #[clap(long, value_name = "PATH")]
+    coverage: Option<Utf8PathBuf>,
+    /// Write the annotated-source HTML report of the coverage to the given file. Defaults to the LCOV path with the extension `html`
+    #[clap(long, value_name = "PATH", requires = "coverage")]
+    coverage_html: Option<Utf8PathBuf>,
+    /// Record the lines read by the program and the text it prints into the given session file, to be replayed with `--replay`
+    #[clap(long, value_name = "PATH", conflicts_with_all = &["replay", "snapshot"])]
+    record: Option<Utf8PathBuf>,
+    /// Feed the lines recorded in the given session file to the program instead of the standard input, and verify that it prints the recorded text
+    #[clap(long, value_name = "PATH", conflicts_with = "snapshot")]
+    replay: Option<Utf8PathBuf>,
+    #[clap(flatten)]
+    pause: PauseOptions,
+}
+
+impl ToolOptions {
+    /// Returns whether any tool was requested
+    fn requested(&self) -> bool {
+        self.trace.is_some()
+            || self.profile
+            || self.coverage.is_some()
+            || self.record.is_some()
+            || self.replay.is_some()
+            || self.pause.requested()
+    }
+}
+
+/// The output format of the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum TraceFormat {
+    /// One readable line per event
+    Text,
+    /// JSON Lines: one object per event
+    Json,
+}
+
+pub fn run(
+    path: Utf8PathBuf,
+    format: MessageFormat,
+    options: &LintOptions,
+    tools: &ToolOptions,
+    bytecode: bool,
+) -> Result<()> {
+    // 1. Lexing and parsing straight from the file, which is read again only for the diagnostics and the tools
+    let file = File::open(&path).into_diagnostic()?;
+    let mut parser = Parser::from_lexer(Lexer::from_reader(file));
+    let result = parser.parse();
+
+    // 2. Error reporting for lexing
+    let lexing_failed = !parser.lexing_errors().is_empty();
+    if lexing_failed {
+        let source = report::read_source(&path)?;
+        report::print_lexing_errors(&path, parser.lexing_errors(), format, &source)?;
+    }
+
+    // 3. Error reporting for parsing
+    let statements = match result {
+        Ok(statements) => statements,
         Err(err) => {
-            // Print an additional newline to clear possible outputs
-            println!();
-            let report: miette::Report = err;
+            let source = report::read_source(&path)?;
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
+            let report: miette::Report = err.into();
             return Err(report.with_source_code(source));
         }
     };
+    // The parser skips the invalid tokens, but the program is not run with them
+    if lexing_failed {
+        return Err(report::aborting());
+    }
 
-    // 3. Error reporting for RecoverableLexingError
-    let mut colors = ColorGenerator::new();
-    if tokens
-        .iter()
-        .any(|token| matches!(token.token, RawToken::Error(_)))
-    {
-        let mut report =
-            Report::build(ReportKind::Error, &path, 0).with_message("Lexing errors found");
+    // 4. Linting
+    lint::lint(&path, &statements, format, options)?;
 
-        for token in &tokens {
-            if let RawToken::Error(message) = token.token.clone() {
-                report = report.with_label(
-                    Label::new((&path, token.span.start..token.span.end))
-                        .with_message(message)
-                        .with_color(colors.next()),
-                );
+    // 5. Execution on the virtual machine, which no tools observe
+    if bytecode {
+        let chunk = match BytecodeGenerator::default().generate(&statements) {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                let source = report::read_source(&path)?;
+                return Err(report::generation_failed(err, format, &source));
             }
-        }
-
-        report
-            .finish()
-            .print((&path, Source::from(&source)))
-            .unwrap();
+        };
+        let result = VirtualMachine::new().run(&chunk);
+        return report_result(result, format, || report::read_source(&path));
     }
 
-    // 4. Parsing
-    if tokens.is_empty() {
-        info!("Nothing to execute. Source contained ignorable tokens only.");
-        return Ok(());
+    // 6. Evaluation. Without any tools, the interpreter is not instrumented at all
+    if !tools.requested() {
+        let result = Interpreter::new().eval(&statements);
+        return report_result(result, format, || report::read_source(&path));
     }
-    let mut parser = Parser::new(tokens);
 
-    let statements = match parser.parse() {
-        Ok(statements) => statements,
-        Err(err) => {
-            let report: miette::Report = err.into();
-            return Err(report.with_source_code(source));
+    // 7. Evaluation, observed by the requested tools
+    let source = report::read_source(&path)?;
+    let tracer = match tools.trace {
+        Some(trace) => {
+            let output: Box<dyn Write> = match &tools.trace_output {
+                Some(path) => Box::new(BufWriter::new(File::create(path).into_diagnostic()?)),
+                None => Box::new(io::stderr()),
+            };
+            let trace = match trace {
+                TraceFormat::Text => tracer::TraceFormat::Text,
+                TraceFormat::Json => tracer::TraceFormat::Json,
+            };
+            Some(Tracer::new(&source, trace, output))
+        }
+        None => None,
+    };
+    let profiler = tools.profile.then(|| Profiler::new(&source));
+    let coverage = tools
+        .coverage
+        .is_some()
+        .then(|| Coverage::new(&source, &statements));
+    let recorder = match (&tools.record, &tools.replay) {
+        (Some(_), _) => Some(Recorder::record(&source, path.as_str(), Box::new(Stdio))),
+        (None, Some(replay)) => {
+            let json = fs::read_to_string(replay).into_diagnostic()?;
+            let session = match Session::from_json(&json) {
+                Ok(session) => session,
+                Err(err) => {
+                    if format == MessageFormat::Json {
+                        report::print_json(&err, None, &source);
+                        return Err(report::aborting());
+                    }
+                    return Err(err.into());
+                }
+            };
+            Some(Recorder::replay(&source, session, Box::new(Stdio)))
         }
+        (None, None) => None,
     };
+    let pause = tools.pause.hook(&source);
+    let mut interpreter =
+        Interpreter::with_hook(((tracer, profiler), (coverage, (recorder.clone(), pause))));
+    let console: Box<dyn Console> = match &recorder {
+        Some(recorder) => Box::new(recorder.clone()),
+        None => Box::new(Stdio),
+    };
+    let capture = Capture::new(console);
+    interpreter.set_console(Box::new(capture.clone()));
+    let result = interpreter.eval(&statements);
+    let state = interpreter.state();
+    let ((tracer, profiler), (coverage, (_, pause))) = interpreter.into_hook();
+    if let Some(pause) = pause {
+        if tools
+            .pause
+            .save(&pause, state, &source, capture.output(), 0)?
+        {
+            return Ok(());
+        }
+    }
+    if let Some(tracer) = tracer {
+        tracer.into_output().flush().into_diagnostic()?;
+    }
+    if let Some(mut profiler) = profiler {
+        profiler.finish();
+        eprint!("\n{}", profiler.report());
+        let output = match &tools.profile_output {
+            Some(output) => output.clone(),
+            None => path.with_extension("folded"),
+        };
+        fs::write(&output, profiler.collapsed_stacks()).into_diagnostic()?;
+        eprintln!("\nWrote the collapsed stacks for flamegraph tools to {output}");
+    }
+    if let (Some(coverage), Some(lcov)) = (coverage, &tools.coverage) {
+        eprint!("\n{}", coverage.report());
+        fs::write(lcov, coverage.lcov(path.as_str())).into_diagnostic()?;
+        let html = match &tools.coverage_html {
+            Some(html) => html.clone(),
+            None => lcov.with_extension("html"),
+        };
+        fs::write(&html, coverage.html(path.as_str())).into_diagnostic()?;
+        eprintln!("\nWrote the LCOV tracefile to {lcov} and the HTML report to {html}");
+    }
+    if let (Some(recorder), Some(record)) = (&recorder, &tools.record) {
+        let session = recorder.session();
+        fs::write(record, session.to_json()).into_diagnostic()?;
+        eprintln!(
+            "\nRecorded {} events of the run to {record}",
+            session.events.len()
+        );
+    }
+    if let (Some(recorder), Some(replay)) = (&recorder, &tools.replay) {
+        match recorder.finish() {
+            // The errors of the program itself are more telling than the recording going on after them
+            Err(ReplayError::EndDiverged(..)) if result.is_err() => (),
+            Err(err) => {
+                if format == MessageFormat::Json {
+                    report::print_json(&err, None, &source);
+                    return Err(report::aborting());
+                }
+                // Print an additional newline to clear the output line
+                println!();
+                let report: miette::Report = err.into();
+                return Err(report.with_source_code(source));
+            }
+            Ok(()) if result.is_ok() => {
+                eprintln!("\nReplayed {replay}: the output matches the recording");
+            }
+            Ok(()) => (),
+        }
+    }
+    report_result(result, format, || Ok(source))
+}
 
-    // 5. Evaluation
-    let mut interpreter = Interpreter::default();
-    match interpreter.eval(&statements) {
+/// Reports the result of the program: prints a newline to clear the output line, and the runtime error if any
+pub(super) fn report_result(
+    result: Result<(), RuntimeError>,
+    format: MessageFormat,
+    source: impl FnOnce() -> Result<String>,
+) -> Result<()> {
+    match result {
         // NOTE: we discard any returned values
         Ok(_result) => {
             // Print an additional newline to clear the output line
@@ -76,6 +266,11 @@ pub fn run(path: Utf8PathBuf) -> Result<()> {
             Ok(())
         }
         Err(err) => {
+            let source = source()?;
+            if format == MessageFormat::Json {
+                report::print_json(&err, err.suggestion(), &source);
+                return Err(report::aborting());
+            }
             // Print an additional newline to clear the output line
             println!();
             let report: miette::Report = err.into();
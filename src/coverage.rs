@@ -0,0 +1,548 @@
+//! Code coverage of Mini-PL programs, see `minipl run --coverage`.
+//!
+//! The [Coverage] is a [Hook] of the [Interpreter](crate::visitors::Interpreter) that counts the executions of each statement
+//! and the evaluations of each expression, keyed by their spans. All the statements and expressions of the program are known
+//! up front, so the ones that were never reached are reported as well.
+//!
+//! Mini-PL has no conditional statements, and the body of a `for` loop is always executed as neither ranges nor arrays can be
+//! empty, so its branches are the short-circuiting logical operators: the right operand is either evaluated or not.
+//!
+//! The results are shown as a summary table, see [Coverage::report], an LCOV tracefile, see [Coverage::lcov], and an
+//! annotated-source HTML report, see [Coverage::html].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::parsing::{Expr, Expression, InterpolationSegment, Statement, Stmt};
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The number of covered items out of all the items of a kind
+pub struct Ratio {
+    /// The number of items executed at least once
+    pub covered: usize,
+    /// The number of items in the program
+    pub total: usize,
+}
+
+impl Ratio {
+    /// Returns the covered items as a percentage of all the items, or [None] if there are no items
+    #[must_use]
+    pub fn percent(self) -> Option<f64> {
+        // The counts of a program are far below the precision of a float
+        #[allow(clippy::cast_precision_loss)]
+        (self.total > 0).then(|| 100.0 * self.covered as f64 / self.total as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The coverage of a program by kind of item
+pub struct Summary {
+    /// The lines on which statements start
+    pub lines: Ratio,
+    /// The statements
+    pub statements: Ratio,
+    /// The expressions, including the nested ones
+    pub expressions: Ratio,
+    /// The two ways of each branch
+    pub branches: Ratio,
+}
+
+impl Summary {
+    /// Internal helper: returns the rows of the summary table, by kind of item
+    fn rows(self) -> [(&'static str, Ratio); 4] {
+        [
+            ("Lines", self.lines),
+            ("Statements", self.statements),
+            ("Expressions", self.expressions),
+            ("Branches", self.branches),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A branch of the program i.e. a logical expression, AND `&` or OR `|`, with the number of times each of its two ways was taken
+pub struct Branch {
+    /// The span of the logical expression
+    pub span: StartEndSpan,
+    /// The line the branch starts on
+    pub line: usize,
+    /// The number of times the right operand was evaluated, and the number of times the left operand determined the result.
+    /// [None] if the logical expression was never reached
+    pub taken: Option<[u64; 2]>,
+}
+
+/// The descriptions of the two ways of a [Branch]
+const WAYS: [&str; 2] = ["right operand evaluated", "short-circuited"];
+
+#[derive(Debug)]
+/// The code coverage recorder
+pub struct Coverage {
+    /// The characters of the source code, as the spans are character offsets
+    source: Vec<char>,
+    lines: SourceLines,
+    /// The spans of all the statements, in source order
+    statements: Vec<StartEndSpan>,
+    /// The spans of all the expressions, in source order
+    expressions: Vec<StartEndSpan>,
+    /// The spans of the logical expressions and of their right operands, in source order
+    branches: Vec<(StartEndSpan, StartEndSpan)>,
+    statement_counts: HashMap<StartEndSpan, u64>,
+    expression_counts: HashMap<StartEndSpan, u64>,
+}
+
+impl Coverage {
+    /// Creates a new [Coverage] for the program with the given source code and statements
+    #[must_use]
+    pub fn new(source: &str, statements: &[Statement]) -> Self {
+        let mut coverage = Self {
+            source: source.chars().collect(),
+            lines: SourceLines::new(source),
+            statements: Vec::new(),
+            expressions: Vec::new(),
+            branches: Vec::new(),
+            statement_counts: HashMap::new(),
+            expression_counts: HashMap::new(),
+        };
+        for statement in statements {
+            coverage.register_statement(statement);
+        }
+        coverage
+    }
+
+    /// Returns the number of times the statement with the given span was executed
+    #[must_use]
+    pub fn statement_count(&self, span: StartEndSpan) -> u64 {
+        self.statement_counts
+            .get(&span)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of times the expression with the given span was evaluated
+    #[must_use]
+    pub fn expression_count(&self, span: StartEndSpan) -> u64 {
+        self.expression_counts
+            .get(&span)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the execution counts of the lines on which statements start, by line. A line on which several statements start
+    /// has the largest count of them
+    #[must_use]
+    pub fn lines(&self) -> BTreeMap<usize, u64> {
+        let mut lines = BTreeMap::new();
+        for span in &self.statements {
+            let (line, _) = self.lines.line_column(span.start);
+            let count = lines.entry(line).or_default();
+            *count = self.statement_count(*span).max(*count);
+        }
+        lines
+    }
+
+    /// Returns the branches of the program, in source order
+    #[must_use]
+    pub fn branches(&self) -> Vec<Branch> {
+        self.branches
+            .iter()
+            .map(|&(span, right)| {
+                let evaluated = self.expression_count(right);
+                // A logical expression whose right operand is not a boolean fails without being counted
+                let total = self.expression_count(span).max(evaluated);
+                Branch {
+                    span,
+                    line: self.lines.line_column(span.start).0,
+                    taken: (total > 0).then(|| [evaluated, total - evaluated]),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the coverage of the program by kind of item
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let ratio = |counts: &mut dyn Iterator<Item = u64>| {
+            counts.fold(Ratio::default(), |ratio, count| Ratio {
+                covered: ratio.covered + usize::from(count > 0),
+                total: ratio.total + 1,
+            })
+        };
+        Summary {
+            lines: ratio(&mut self.lines().into_values()),
+            statements: ratio(&mut self.statements.iter().map(|s| self.statement_count(*s))),
+            expressions: ratio(&mut self.expressions.iter().map(|e| self.expression_count(*e))),
+            branches: ratio(
+                &mut self
+                    .branches()
+                    .into_iter()
+                    .flat_map(|branch| branch.taken.unwrap_or_default()),
+            ),
+        }
+    }
+
+    /// Returns the summary table of the coverage, followed by the lines that were never executed
+    #[must_use]
+    pub fn report(&self) -> String {
+        let summary = self.summary();
+        let mut report = String::from("Coverage:\n");
+        let _ = writeln!(
+            report,
+            "  {:<12} {:>8} {:>8} {:>8}",
+            "Kind", "Covered", "Total", "Percent"
+        );
+        for (kind, ratio) in summary.rows() {
+            let percent = ratio
+                .percent()
+                .map_or_else(|| "-".to_owned(), |percent| format!("{percent:.1}%"));
+            let _ = writeln!(
+                report,
+                "  {:<12} {:>8} {:>8} {:>8}",
+                kind, ratio.covered, ratio.total, percent
+            );
+        }
+        let missed: Vec<String> = self
+            .lines()
+            .into_iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(line, _)| line.to_string())
+            .collect();
+        if !missed.is_empty() {
+            let _ = writeln!(report, "\nLines never executed: {}", missed.join(", "));
+        }
+        report
+    }
+
+    /// Returns the coverage as an LCOV tracefile, for the source file with the given path
+    #[must_use]
+    pub fn lcov(&self, path: &str) -> String {
+        let mut lcov = format!("TN:\nSF:{path}\n");
+        let branches = self.branches();
+        for (block, branch) in branches.iter().enumerate() {
+            for way in 0..2 {
+                // A branch that was never reached has no counts
+                let taken = match branch.taken {
+                    Some(taken) => taken[way].to_string(),
+                    None => "-".to_owned(),
+                };
+                let _ = writeln!(lcov, "BRDA:{},{block},{way},{taken}", branch.line);
+            }
+        }
+        let summary = self.summary();
+        let _ = writeln!(lcov, "BRF:{}", summary.branches.total);
+        let _ = writeln!(lcov, "BRH:{}", summary.branches.covered);
+        for (line, count) in self.lines() {
+            let _ = writeln!(lcov, "DA:{line},{count}");
+        }
+        let _ = writeln!(lcov, "LF:{}", summary.lines.total);
+        let _ = writeln!(lcov, "LH:{}", summary.lines.covered);
+        lcov.push_str("end_of_record\n");
+        lcov
+    }
+
+    /// Returns the coverage as a standalone HTML page, with the source code of the file with the given name annotated with the
+    /// execution counts of its lines. Lines that were never executed are red, lines with branches not taken both ways are
+    /// yellow, and expressions that were never evaluated are highlighted within the executed lines
+    #[must_use]
+    pub fn html(&self, name: &str) -> String {
+        let summary = self.summary();
+        let lines = self.lines();
+        let mut branches: HashMap<usize, Vec<Branch>> = HashMap::new();
+        for branch in self.branches() {
+            branches.entry(branch.line).or_default().push(branch);
+        }
+        // The characters inside expressions that were never evaluated
+        let mut missed = vec![false; self.source.len()];
+        for span in &self.expressions {
+            if self.expression_count(*span) == 0 {
+                let end = span.end.min(missed.len());
+                missed[span.start.min(end)..end].fill(true);
+            }
+        }
+
+        let name = escape(name);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Coverage of {name}</title>\n{STYLE}</head>\n<body>\n<h1>Coverage of {name}</h1>\n"
+        );
+        html.push_str("<table class=\"summary\">\n<tr><th>Kind</th><th>Covered</th><th>Total</th><th>Percent</th></tr>\n");
+        for (kind, ratio) in summary.rows() {
+            let percent = ratio
+                .percent()
+                .map_or_else(|| "-".to_owned(), |percent| format!("{percent:.1}%"));
+            let _ = writeln!(
+                html,
+                "<tr><td>{kind}</td><td>{}</td><td>{}</td><td>{percent}</td></tr>",
+                ratio.covered, ratio.total
+            );
+        }
+        html.push_str("</table>\n<table class=\"source\">\n");
+
+        let mut offset = 0;
+        let text: String = self.source.iter().collect();
+        for (index, segment) in text.split_inclusive('\n').enumerate() {
+            let line = segment.trim_end_matches('\n');
+            let number = index + 1;
+            let line_branches = branches.get(&number).map(Vec::as_slice).unwrap_or_default();
+            let partial = line_branches
+                .iter()
+                .any(|branch| branch.taken.is_none_or(|taken| taken.contains(&0)));
+            let (class, count) = match lines.get(&number) {
+                Some(0) => (" class=\"missed\"", "0".to_owned()),
+                Some(count) if partial => (" class=\"partial\"", count.to_string()),
+                Some(count) => (" class=\"hit\"", count.to_string()),
+                None => ("", String::new()),
+            };
+            let title: Vec<String> = line_branches
+                .iter()
+                .map(|branch| match branch.taken {
+                    Some([first, second]) => format!("{}: {first}, {}: {second}", WAYS[0], WAYS[1]),
+                    None => format!("{} and {}: never reached", WAYS[0], WAYS[1]),
+                })
+                .collect();
+            let title = if title.is_empty() {
+                String::new()
+            } else {
+                format!(" title=\"{}\"", escape(&title.join("; ")))
+            };
+
+            let _ = write!(
+                html,
+                "<tr{class}><td class=\"line\">{number}</td><td class=\"count\"{title}>{count}</td><td class=\"code\">"
+            );
+            // Within lines that were never executed, the expressions need no highlighting
+            let highlight = lines.get(&number) != Some(&0);
+            let mut open = false;
+            for (column, character) in line.chars().enumerate() {
+                let never = highlight && missed.get(offset + column).copied().unwrap_or_default();
+                if never != open {
+                    html.push_str(if never {
+                        "<span class=\"never\" title=\"Never evaluated\">"
+                    } else {
+                        "</span>"
+                    });
+                    open = never;
+                }
+                html.push_str(&escape(&character.to_string()));
+            }
+            if open {
+                html.push_str("</span>");
+            }
+            html.push_str("</td></tr>\n");
+            offset += segment.chars().count();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Internal helper: registers the statement and the statements and expressions in it
+    fn register_statement(&mut self, statement: &Statement) {
+        self.statements.push(statement.span);
+        match &statement.stmt {
+            Stmt::Assert(expression) | Stmt::Expression(expression) | Stmt::Print(expression) => {
+                self.register_expression(expression);
+            }
+            Stmt::Forloop(forloop) => {
+                self.register_expression(&forloop.left);
+                if let Some(right) = &forloop.right {
+                    self.register_expression(right);
+                }
+                for statement in &forloop.body {
+                    self.register_statement(statement);
+                }
+            }
+            Stmt::Read(_) => (),
+            Stmt::VariableDefinition(variable) => {
+                if let Some(initializer) = &variable.initializer {
+                    self.register_expression(initializer);
+                }
+            }
+        }
+    }
+
+    /// Internal helper: registers the expression and the expressions in it
+    fn register_expression(&mut self, expression: &Expression) {
+        // A grouping has the span of the expression inside it, so only the latter is counted
+        if !matches!(expression.expr, Expr::Grouping(_)) {
+            self.expressions.push(expression.span);
+        }
+        match &expression.expr {
+            Expr::Assign(a) => self.register_expression(&a.value),
+            Expr::AssignIndex(a) => {
+                self.register_expression(&a.index);
+                self.register_expression(&a.value);
+            }
+            Expr::Binary(b) => {
+                self.register_expression(&b.left);
+                self.register_expression(&b.right);
+            }
+            Expr::Call(c) => {
+                for argument in &c.arguments {
+                    self.register_expression(argument);
+                }
+            }
+            Expr::Grouping(g) => self.register_expression(&g.expression),
+            Expr::Index(i) => {
+                self.register_expression(&i.array);
+                self.register_expression(&i.index);
+            }
+            Expr::Interpolation(i) => {
+                for segment in &i.segments {
+                    if let InterpolationSegment::Expression(expression) = segment {
+                        self.register_expression(expression);
+                    }
+                }
+            }
+            Expr::Logical(l) => {
+                self.branches.push((expression.span, l.right.span));
+                self.register_expression(&l.left);
+                self.register_expression(&l.right);
+            }
+            Expr::Unary(u) => self.register_expression(&u.right),
+            Expr::Literal(_) | Expr::VariableUsage(_) => (),
+        }
+    }
+}
+
+impl Hook for Coverage {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        _state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        *self.statement_counts.entry(statement.span).or_default() += 1;
+        Ok(())
+    }
+
+    fn after_expression(&mut self, expression: &Expression, _value: &Object) {
+        if matches!(expression.expr, Expr::Grouping(_)) {
+            return;
+        }
+        *self.expression_counts.entry(expression.span).or_default() += 1;
+    }
+}
+
+/// Internal helper: escapes the text for HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The style sheet of the HTML report
+const STYLE: &str = "<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+.summary td, .summary th { padding: 0.2em 1em; text-align: right; }
+.summary td:first-child, .summary th:first-child { text-align: left; }
+.source { font-family: monospace; margin-top: 2em; }
+.source td { padding: 0 0.5em; white-space: pre; }
+.line, .count { color: #777; text-align: right; }
+.hit .count { background: #cfc; }
+.partial .count { background: #ffc; }
+.missed .count, .missed .code { background: #fcc; }
+.never { background: #fcc; }
+</style>
+";
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::{Coverage, Ratio};
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::runtime::RuntimeError;
+    use crate::visitors::Interpreter;
+
+    /// Runs the program, returning the result and its coverage
+    fn cover(source: &str) -> (Result<(), RuntimeError>, Coverage) {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::with_hook(Coverage::new(source, &statements));
+        let result = interpreter.eval(&statements);
+        (result, interpreter.into_hook())
+    }
+
+    #[test]
+    fn counts_lines_statements_and_expressions() {
+        let source =
+            "var i : int;\nvar x : int;\nfor i in 1..3 do\n  x := x + i;\nend for;\nassert x = 6;";
+        let (result, coverage) = cover(source);
+        assert!(result.is_ok());
+        let lines: Vec<(usize, u64)> = coverage.lines().into_iter().collect();
+        assert_eq!(lines, [(1, 1), (2, 1), (3, 1), (4, 3), (6, 1)]);
+        let summary = coverage.summary();
+        assert_eq!(
+            summary.statements,
+            Ratio {
+                covered: 5,
+                total: 5
+            }
+        );
+        assert_eq!(summary.expressions.covered, summary.expressions.total);
+        assert_eq!(summary.branches.total, 0);
+        assert_eq!(summary.branches.percent(), None);
+    }
+
+    #[test]
+    fn statements_after_an_error_are_not_covered() {
+        let (result, coverage) = cover("var x : int;\nassert x = 1;\nprint x;");
+        assert!(result.is_err());
+        let summary = coverage.summary();
+        assert_eq!(
+            summary.lines,
+            Ratio {
+                covered: 2,
+                total: 3
+            }
+        );
+        assert!(coverage.report().ends_with("\nLines never executed: 3\n"));
+        assert!(coverage.lcov("a.minipl").contains("\nDA:3,0\n"));
+    }
+
+    #[test]
+    fn branches() {
+        let source = "var b : bool;\nvar x : int;\nb := b & !b;\nfor x in 1..2 do\n  b := b | x = 1;\nend for;\nb := b & (b | b);";
+        let (result, coverage) = cover(source);
+        assert!(result.is_ok());
+        let taken: Vec<(usize, Option<[u64; 2]>)> = coverage
+            .branches()
+            .iter()
+            .map(|branch| (branch.line, branch.taken))
+            .collect();
+        assert_eq!(
+            taken,
+            [
+                (3, Some([0, 1])),
+                (5, Some([1, 1])),
+                (7, Some([1, 0])),
+                (7, Some([0, 1])),
+            ]
+        );
+        assert_eq!(
+            coverage.summary().branches,
+            Ratio {
+                covered: 5,
+                total: 8
+            }
+        );
+        let lcov = coverage.lcov("branches.minipl");
+        assert!(lcov.starts_with("TN:\nSF:branches.minipl\nBRDA:3,0,0,0\nBRDA:3,0,1,1\n"));
+        assert!(lcov.contains("\nBRDA:7,3,0,0\nBRDA:7,3,1,1\nBRF:8\nBRH:5\n"));
+        assert!(lcov.contains("\nDA:7,1\nLF:6\nLH:6\nend_of_record\n"));
+    }
+
+    #[test]
+    fn html() {
+        let (_, coverage) = cover("var b : bool := false & 1 < 2;\nvar s : string := \"<done>\";");
+        let html = coverage.html("a&b.minipl");
+        assert!(html.contains("<title>Coverage of a&amp;b.minipl</title>"));
+        assert!(html.contains(
+            "<tr class=\"partial\"><td class=\"line\">1</td><td class=\"count\" title=\"right operand evaluated: 0, short-circuited: 1\">1</td><td class=\"code\">var b : bool := false &amp; <span class=\"never\" title=\"Never evaluated\">1 &lt; 2</span>;</td></tr>"
+        ));
+        assert!(html.contains("&quot;&lt;done&gt;&quot;;"));
+    }
+}
@@ -0,0 +1,722 @@
+//! Debug Adapter Protocol server for Mini-PL programs, see `minipl dap`.
+//!
+//! The [DapServer] reads requests from its input and writes responses and events to its output, each message framed with a
+//! `Content-Length` header as in the [specification](https://microsoft.github.io/debug-adapter-protocol/specification).
+//! It debugs a single program in a single thread: while the program runs, the server is the [Hook] of the [Interpreter],
+//! and while the program is paused before a statement, the server handles requests until one of them resumes the program.
+//!
+//! The call stack has a frame for the program and one for each `for` loop being executed, innermost first. The text printed
+//! by the program is sent in `output` events, and the input of its `read` statements is given in the `input` argument of the `launch` request.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::rc::Rc;
+
+use miette::Diagnostic;
+use serde_json::{json, Value};
+
+use crate::debugger::{self, Mode};
+use crate::parsing::{Statement, Stmt};
+use crate::runtime::{Console, Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, Interpreter, State};
+
+/// The only thread of the program
+const THREAD_ID: usize = 1;
+/// The variables reference of the global variables. The elements of arrays are referenced by the numbers after it
+const GLOBALS: usize = 1;
+
+/// The output of the [DapServer], shared with the [Console] of the program
+struct Connection<W> {
+    output: W,
+    /// The sequence number of the previous message
+    seq: usize,
+}
+
+impl<W: Write> Connection<W> {
+    /// Sends the message with the next sequence number
+    fn send(&mut self, mut message: Value) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = message.to_string();
+        let _ = write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len());
+        let _ = self.output.flush();
+    }
+
+    /// Sends the event with the body
+    fn event(&mut self, event: &str, body: Value) {
+        let mut message = json!({ "type": "event", "event": event });
+        message["body"] = body;
+        self.send(message);
+    }
+}
+
+/// The [Console] of a program being debugged: the input is given at launch, and the output is sent in `output` events
+struct DapConsole<W> {
+    connection: Rc<RefCell<Connection<W>>>,
+    input: VecDeque<String>,
+}
+
+impl<W> Debug for DapConsole<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DapConsole")
+            .field("input", &self.input)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> Console for DapConsole<W> {
+    fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+        let line = self.input.pop_front().unwrap_or_default();
+        buffer.push_str(&line);
+        Ok(line.len())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.connection
+            .borrow_mut()
+            .event("output", json!({ "category": "stdout", "output": text }));
+        Ok(())
+    }
+}
+
+/// The launched program
+struct Program {
+    path: String,
+    lines: SourceLines,
+    statements: Vec<Statement>,
+    /// The lines that statements start on, i.e. where breakpoints can be set
+    statement_lines: BTreeSet<usize>,
+    /// The input of the `read` statements
+    input: String,
+}
+
+/// The program being paused before a statement
+#[derive(Clone, Copy)]
+struct Paused<'a> {
+    statement: &'a Statement,
+    state: State<'a>,
+}
+
+/// What to do after a request
+enum Action {
+    /// Handle the next request
+    Wait,
+    /// Run the launched program
+    Run,
+    /// Resume the program, pausing next as in the mode
+    Resume(Mode),
+    /// Stop the program and the server
+    Disconnect,
+}
+
+/// The Debug Adapter Protocol server. Reads requests from its input, and writes responses and events to its output.
+pub struct DapServer<R, W> {
+    input: R,
+    connection: Rc<RefCell<Connection<W>>>,
+    program: Option<Program>,
+    /// The lines of the breakpoints, with their ids
+    breakpoints: BTreeMap<usize, usize>,
+    /// The id of the previous breakpoint
+    breakpoint_id: usize,
+    mode: Mode,
+    /// Whether the next pause is the one requested with `stopOnEntry`
+    entry: bool,
+    configured: bool,
+    disconnected: bool,
+    lines_start_at_1: bool,
+    columns_start_at_1: bool,
+    /// The arrays shown since the program paused, referenced by the numbers after [GLOBALS]
+    arrays: Vec<Vec<Object>>,
+}
+
+impl<R, W> Debug for DapServer<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DapServer")
+            .field("breakpoints", &self.breakpoints)
+            .field("mode", &self.mode)
+            .field("configured", &self.configured)
+            .field("disconnected", &self.disconnected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: BufRead, W: Write + 'static> DapServer<R, W> {
+    /// Creates a new [DapServer] reading requests from the input and writing to the output
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            connection: Rc::new(RefCell::new(Connection { output, seq: 0 })),
+            program: None,
+            breakpoints: BTreeMap::new(),
+            breakpoint_id: 0,
+            mode: Mode::Continue,
+            entry: false,
+            configured: false,
+            disconnected: false,
+            lines_start_at_1: true,
+            columns_start_at_1: true,
+            arrays: Vec::new(),
+        }
+    }
+
+    /// The primary function of the [DapServer]: handles requests until the client disconnects or the input ends.
+    ///
+    /// # Errors
+    /// Returns an error if a message could not be read
+    pub fn run(&mut self) -> io::Result<()> {
+        while !self.disconnected {
+            let message = match self.receive()? {
+                Some(message) => message,
+                None => break,
+            };
+            if let Action::Run = self.handle(&message, None) {
+                self.execute();
+            }
+        }
+        Ok(())
+    }
+
+    /// Internal helper: reads the next message, or returns `None` at the end of the input. A message that is not valid JSON
+    /// is reported to the client in an `output` event and skipped
+    fn receive(&mut self) -> io::Result<Option<Value>> {
+        loop {
+            let body = match self.receive_body()? {
+                Some(body) => body,
+                None => return Ok(None),
+            };
+            match serde_json::from_slice(&body) {
+                Ok(message) => return Ok(Some(message)),
+                Err(error) => {
+                    let output = format!("Ignored a message that is not valid JSON: {error}\n");
+                    self.event("output", json!({ "category": "console", "output": output }));
+                }
+            }
+        }
+    }
+
+    /// Internal helper: reads the body of the next message, or returns `None` at the end of the input
+    fn receive_body(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if self.input.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+            let header = header.trim();
+            // The headers end with an empty line
+            if header.is_empty() && length.is_some() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                let value = value.trim().parse::<u64>();
+                length =
+                    Some(value.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?);
+            }
+        }
+        let length = length.unwrap_or_default();
+        // The body grows as it is read rather than being allocated up front, as the length is given by the client
+        let mut body = Vec::new();
+        let read = self.input.by_ref().take(length).read_to_end(&mut body)?;
+        if u64::try_from(read) != Ok(length) {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("the input ended after {read} of the {length} bytes of a message"),
+            ));
+        }
+        Ok(Some(body))
+    }
+
+    /// Internal helper: sends the response to the request, with the body or the error message
+    fn respond(&self, request: &Value, body: Result<Value, String>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": body.is_ok(),
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.connection.borrow_mut().send(response);
+    }
+
+    /// Internal helper: sends the event with the body
+    fn event(&self, event: &str, body: Value) {
+        self.connection.borrow_mut().event(event, body);
+    }
+
+    /// Handles the request, returning what to do next. Some requests are only valid while the program is paused
+    fn handle(&mut self, request: &Value, paused: Option<Paused<'_>>) -> Action {
+        let arguments = &request["arguments"];
+        let command = request["command"].as_str().unwrap_or_default();
+        let mut action = Action::Wait;
+        let body = match (command, paused) {
+            ("initialize", _) => {
+                self.lines_start_at_1 = arguments["linesStartAt1"].as_bool().unwrap_or(true);
+                self.columns_start_at_1 = arguments["columnsStartAt1"].as_bool().unwrap_or(true);
+                self.respond(
+                    request,
+                    Ok(json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                    })),
+                );
+                self.event("initialized", json!({}));
+                return action;
+            }
+            ("launch", _) => self.launch(arguments).map(|()| {
+                if self.configured {
+                    action = Action::Run;
+                }
+                json!({})
+            }),
+            ("setBreakpoints", _) => Ok(self.set_breakpoints(arguments)),
+            ("configurationDone", _) => {
+                // The program runs once both launched and configured
+                if self.program.is_some() && !self.configured {
+                    action = Action::Run;
+                }
+                self.configured = true;
+                Ok(json!({}))
+            }
+            ("threads", _) => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            ("disconnect", _) => {
+                self.disconnected = true;
+                action = Action::Disconnect;
+                Ok(json!({}))
+            }
+            ("stackTrace", Some(paused)) => Ok(self.stack_trace(arguments, paused)),
+            ("scopes", Some(_)) => Ok(json!({
+                "scopes": [{ "name": "Globals", "variablesReference": GLOBALS, "expensive": false }]
+            })),
+            ("variables", Some(paused)) => self.variables(arguments, paused.state),
+            ("evaluate", Some(paused)) => {
+                let expression = arguments["expression"].as_str().unwrap_or_default();
+                debugger::evaluate(expression.trim(), paused.state).map(|value| {
+                    let reference = self.reference(&value);
+                    json!({
+                        "result": debugger::format_value(&value),
+                        "type": value.kind_to_string(),
+                        "variablesReference": reference,
+                    })
+                })
+            }
+            ("continue" | "next" | "stepIn" | "stepOut", Some(paused)) => {
+                let depth = paused.state.loops.len();
+                action = Action::Resume(match command {
+                    "continue" => Mode::Continue,
+                    "next" => Mode::Next(depth),
+                    "stepIn" => Mode::Step,
+                    _ => Mode::Finish(depth),
+                });
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            (
+                "stackTrace" | "scopes" | "variables" | "evaluate" | "continue" | "next" | "stepIn"
+                | "stepOut",
+                None,
+            ) => Err("The program is not paused".to_owned()),
+            _ => Err(format!("Unsupported request `{command}`")),
+        };
+        self.respond(request, body);
+        action
+    }
+
+    /// Loads the program of the `launch` request
+    fn launch(&mut self, arguments: &Value) -> Result<(), String> {
+        if self.program.is_some() {
+            return Err("A program has already been launched".to_owned());
+        }
+        let path = arguments["program"]
+            .as_str()
+            .ok_or("Expected the path of the program in the `program` argument")?;
+        let source =
+            fs::read_to_string(path).map_err(|error| format!("Could not read {path}: {error}"))?;
+        let lines = SourceLines::new(&source);
+        let statements = debugger::parse(&source).map_err(|(message, span)| {
+            let (line, column) = lines.line_column(span.start);
+            format!("{message} at line {line}, column {column}")
+        })?;
+        let mut statement_lines = BTreeSet::new();
+        collect_lines(&statements, &lines, &mut statement_lines);
+
+        // Breakpoints set before the launch are checked now that the statements are known
+        for (line, id) in self.breakpoints.clone() {
+            if !statement_lines.contains(&line) {
+                self.breakpoints.remove(&line);
+                let breakpoint = self.breakpoint(id, line, false);
+                self.event(
+                    "breakpoint",
+                    json!({ "reason": "changed", "breakpoint": breakpoint }),
+                );
+            }
+        }
+
+        let stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.mode = if stop_on_entry {
+            Mode::Step
+        } else {
+            Mode::Continue
+        };
+        self.entry = stop_on_entry;
+        self.program = Some(Program {
+            path: path.to_owned(),
+            lines,
+            statements,
+            statement_lines,
+            input: arguments["input"].as_str().unwrap_or_default().to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Replaces the breakpoints with those of the `setBreakpoints` request
+    fn set_breakpoints(&mut self, arguments: &Value) -> Value {
+        let lines: Vec<u64> = match arguments["breakpoints"].as_array() {
+            Some(breakpoints) => breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                .collect(),
+            // Older clients send only the lines
+            None => arguments["lines"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_u64)
+                .collect(),
+        };
+        self.breakpoints.clear();
+        let mut breakpoints = Vec::new();
+        for line in lines {
+            let line =
+                usize::try_from(line).unwrap_or_default() + usize::from(!self.lines_start_at_1);
+            self.breakpoint_id += 1;
+            // Before the launch, the statements are not known yet
+            let verified = match &self.program {
+                Some(program) => program.statement_lines.contains(&line),
+                None => line > 0,
+            };
+            if verified {
+                self.breakpoints.insert(line, self.breakpoint_id);
+            }
+            breakpoints.push(self.breakpoint(self.breakpoint_id, line, verified));
+        }
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Internal helper: returns the breakpoint object of the protocol
+    fn breakpoint(&self, id: usize, line: usize, verified: bool) -> Value {
+        let mut breakpoint = json!({
+            "id": id,
+            "verified": verified,
+            "line": self.client_line(line),
+        });
+        if !verified {
+            breakpoint["message"] = json!("No statement starts on this line");
+        }
+        breakpoint
+    }
+
+    /// Returns the frames of the paused program: the innermost is at the current statement, and the others at the headers of the `for` loops
+    fn stack_trace(&self, arguments: &Value, paused: Paused<'_>) -> Value {
+        let mut frames = Vec::new();
+        let mut span = paused.statement.span;
+        for (variable, header) in paused.state.loops.iter().rev() {
+            let name = match paused.state.environment.get(variable, *header) {
+                Ok(value) => format!("for {variable} = {}", debugger::format_value(&value)),
+                Err(_) => format!("for {variable}"),
+            };
+            frames.push(self.frame(frames.len(), &name, span));
+            span = *header;
+        }
+        frames.push(self.frame(frames.len(), "main", span));
+        let total = frames.len();
+        let start = arguments["startFrame"]
+            .as_u64()
+            .and_then(|start| usize::try_from(start).ok())
+            .unwrap_or_default();
+        let levels = match arguments["levels"].as_u64() {
+            Some(levels) if levels > 0 => usize::try_from(levels).unwrap_or(usize::MAX),
+            _ => usize::MAX,
+        };
+        let frames: Vec<Value> = frames.into_iter().skip(start).take(levels).collect();
+        json!({ "stackFrames": frames, "totalFrames": total })
+    }
+
+    /// Internal helper: returns the stack frame object of the protocol, with the span translated to lines and columns
+    fn frame(&self, id: usize, name: &str, span: StartEndSpan) -> Value {
+        let (path, lines) = match &self.program {
+            Some(program) => (program.path.as_str(), &program.lines),
+            None => return json!({ "id": id, "name": name, "line": 0, "column": 0 }),
+        };
+        let (line, column) = lines.line_column(span.start);
+        let (end_line, end_column) = lines.line_column(span.end.max(span.start + 1) - 1);
+        let name_of_file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        json!({
+            "id": id,
+            "name": name,
+            "source": { "name": name_of_file, "path": path },
+            "line": self.client_line(line),
+            "column": self.client_column(column),
+            "endLine": self.client_line(end_line),
+            // The end column is exclusive
+            "endColumn": self.client_column(end_column + 1),
+        })
+    }
+
+    /// Returns the variables of the `variables` request: the global variables sorted by name, or the elements of an array
+    fn variables(&mut self, arguments: &Value, state: State<'_>) -> Result<Value, String> {
+        let reference = arguments["variablesReference"]
+            .as_u64()
+            .and_then(|reference| usize::try_from(reference).ok())
+            .unwrap_or_default();
+        let variables: Vec<(String, Object)> = if reference == GLOBALS {
+            let mut variables: Vec<(String, Object)> = state
+                .environment
+                .variables()
+                .map(|(name, value)| (name.to_owned(), value.clone()))
+                .collect();
+            variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+            variables
+        } else {
+            let elements = reference
+                .checked_sub(GLOBALS + 1)
+                .and_then(|index| self.arrays.get(index))
+                .ok_or_else(|| format!("Unknown variables reference {reference}"))?;
+            elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (format!("[{index}]"), element.clone()))
+                .collect()
+        };
+        let variables: Vec<Value> = variables
+            .into_iter()
+            .map(|(name, value)| {
+                json!({
+                    "name": name,
+                    "value": debugger::format_value(&value),
+                    "type": value.kind_to_string(),
+                    "variablesReference": self.reference(&value),
+                })
+            })
+            .collect();
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Internal helper: returns the variables reference of the value, which is non-zero for arrays only
+    fn reference(&mut self, value: &Object) -> usize {
+        match value {
+            Object::Array(elements) => {
+                self.arrays.push(elements.clone());
+                GLOBALS + self.arrays.len()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Internal helper: translates the 1-based line to the convention of the client
+    fn client_line(&self, line: usize) -> usize {
+        line - usize::from(!self.lines_start_at_1)
+    }
+
+    /// Internal helper: translates the 1-based column to the convention of the client
+    fn client_column(&self, column: usize) -> usize {
+        column - usize::from(!self.columns_start_at_1)
+    }
+
+    /// Runs the launched program to the end, and reports how it ended
+    fn execute(&mut self) {
+        let (statements, input) = match &self.program {
+            Some(program) => (
+                program.statements.clone(),
+                program
+                    .input
+                    .split_inclusive('\n')
+                    .map(str::to_owned)
+                    .collect(),
+            ),
+            None => return,
+        };
+        let console = DapConsole {
+            connection: Rc::clone(&self.connection),
+            input,
+        };
+        let mut interpreter = Interpreter::with_hook(&mut *self);
+        interpreter.set_console(Box::new(console));
+        let result = interpreter.eval(&statements);
+        drop(interpreter);
+        let exit_code = match result {
+            Ok(()) => 0,
+            // The client disconnected while the program was paused
+            Err(RuntimeError::Stopped(_)) => return,
+            Err(error) => {
+                let location = error
+                    .labels()
+                    .and_then(|mut labels| labels.next())
+                    .zip(self.program.as_ref())
+                    .map(|(label, program)| {
+                        let (line, column) = program.lines.line_column(label.offset());
+                        format!(" at line {line}, column {column}")
+                    })
+                    .unwrap_or_default();
+                let output = format!("Error: {}{location}\n", debugger::message(&error));
+                self.event("output", json!({ "category": "stderr", "output": output }));
+                1
+            }
+        };
+        self.event("exited", json!({ "exitCode": exit_code }));
+        self.event("terminated", json!({}));
+    }
+}
+
+impl<R: BufRead, W: Write + 'static> Hook for DapServer<R, W> {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let line = match &self.program {
+            Some(program) => program.lines.line_column(statement.span.start).0,
+            None => return Ok(()),
+        };
+        let breakpoint = self.breakpoints.get(&line).copied();
+        if breakpoint.is_none() && !self.mode.pauses(state.loops.len()) {
+            return Ok(());
+        }
+
+        let mut body = json!({ "threadId": THREAD_ID, "allThreadsStopped": true });
+        body["reason"] = match breakpoint {
+            Some(id) => {
+                body["hitBreakpointIds"] = json!([id]);
+                json!("breakpoint")
+            }
+            None if self.entry => json!("entry"),
+            None => json!("step"),
+        };
+        self.entry = false;
+        self.event("stopped", body);
+        let paused = Paused { statement, state };
+        loop {
+            let request = match self.receive() {
+                Ok(Some(request)) => request,
+                // Without a client, the program is stopped
+                Ok(None) | Err(_) => {
+                    self.disconnected = true;
+                    return Err(RuntimeError::Stopped(statement.span.into()));
+                }
+            };
+            match self.handle(&request, Some(paused)) {
+                Action::Wait | Action::Run => (),
+                Action::Resume(mode) => {
+                    self.mode = mode;
+                    self.arrays.clear();
+                    return Ok(());
+                }
+                Action::Disconnect => return Err(RuntimeError::Stopped(statement.span.into())),
+            }
+        }
+    }
+}
+
+/// Internal helper: collects the lines that the statements start on, including those in the bodies of `for` loops
+fn collect_lines(statements: &[Statement], lines: &SourceLines, into: &mut BTreeSet<usize>) {
+    for statement in statements {
+        into.insert(lines.line_column(statement.span.start).0);
+        if let Stmt::Forloop(forloop) = &statement.stmt {
+            collect_lines(&forloop.body, lines, into);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+
+    use serde_json::{json, Value};
+
+    use super::DapServer;
+
+    /// An output that can be read after the server is done with it
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Serves the requests, returning the messages sent in response
+    fn serve(requests: &[Value]) -> Vec<Value> {
+        let input: String = requests
+            .iter()
+            .map(|request| {
+                let body = request.to_string();
+                format!("Content-Length: {}\r\n\r\n{body}", body.len())
+            })
+            .collect();
+        let output = Shared::default();
+        DapServer::new(input.as_bytes(), output.clone())
+            .run()
+            .unwrap();
+        let output = String::from_utf8(output.0.take()).unwrap();
+        output
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|message| serde_json::from_str(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lines_and_columns_start_at_zero() {
+        let path = "tests/sources/valid/for_zero_to_ten_print.minipl";
+        let messages = serve(&[
+            json!({ "seq": 1, "command": "initialize", "arguments": { "linesStartAt1": false, "columnsStartAt1": false } }),
+            json!({ "seq": 2, "command": "launch", "arguments": { "program": path, "stopOnEntry": true } }),
+            json!({ "seq": 3, "command": "configurationDone" }),
+            json!({ "seq": 4, "command": "stackTrace", "arguments": { "threadId": 1 } }),
+            json!({ "seq": 5, "command": "disconnect" }),
+        ]);
+        let trace = messages
+            .iter()
+            .find(|message| message["command"] == "stackTrace")
+            .unwrap();
+        assert_eq!(trace["request_seq"], 4);
+        let frame = &trace["body"]["stackFrames"][0];
+        assert_eq!((&frame["line"], &frame["column"]), (&json!(0), &json!(0)));
+        // The sequence numbers of the messages are consecutive
+        let seqs: Vec<u64> = messages
+            .iter()
+            .map(|m| m["seq"].as_u64().unwrap())
+            .collect();
+        assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn breakpoints_before_launch_are_checked() {
+        let path = "tests/sources/valid/sample2.minipl";
+        let messages = serve(&[
+            json!({ "seq": 1, "command": "setBreakpoints", "arguments": { "breakpoints": [{ "line": 3 }, { "line": 10 }] } }),
+            json!({ "seq": 2, "command": "launch", "arguments": { "program": path } }),
+        ]);
+        let breakpoints = &messages[0]["body"]["breakpoints"];
+        assert_eq!(breakpoints[1]["verified"], true);
+        assert_eq!(
+            messages[1]["body"],
+            json!({ "reason": "changed", "breakpoint": { "id": 2, "line": 10, "verified": false, "message": "No statement starts on this line" } })
+        );
+        assert_eq!(messages[2]["success"], true);
+    }
+}
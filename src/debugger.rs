@@ -0,0 +1,430 @@
+//! Interactive step debugger for Mini-PL programs, see `minipl debug`.
+//!
+//! The [Debugger] is a [Hook] of the [Interpreter]: before each statement, it decides whether to pause,
+//! and while paused, reads commands from its input until one of them resumes the program.
+
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Write as _};
+use std::io::Write;
+
+use miette::Diagnostic;
+
+use crate::lexing::Lexer;
+use crate::parsing::{Parser, Statement, Stmt};
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, Interpreter, State, Visitor};
+
+const HELP: &str = "\
+Commands:
+  s, step              Run until the next statement, stepping into `for` loops
+  n, next              Run until the next statement, stepping over `for` loops
+  f, finish            Run until the end of the innermost `for` loop
+  c, continue          Run until the next breakpoint
+  b, break LINE        Set a breakpoint at the statements starting on the line
+  d, delete [LINE]     Delete the breakpoint at the line, or all breakpoints
+  breakpoints          List the breakpoints
+  p, print EXPRESSION  Evaluate the expression, e.g. a variable
+  vars                 Print all the variables
+  w, watch EXPRESSION  Evaluate the expression whenever the program pauses
+  unwatch NUMBER       Delete the watch expression with the number
+  l, list              Show the current statement
+  bt, backtrace        Show the `for` loops being executed
+  q, quit              Stop the program
+  h, help              Show this help
+An empty line repeats the previous command.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// When the [Debugger] pauses next, in addition to the breakpoints
+pub(crate) enum Mode {
+    /// Only at breakpoints
+    Continue,
+    /// Before the next statement
+    Step,
+    /// Before the next statement outside the `for` loops started at the given depth
+    Next(usize),
+    /// Before the next statement outside the innermost `for` loop at the given depth
+    Finish(usize),
+}
+
+impl Mode {
+    /// Whether to pause before a statement inside the given number of `for` loops
+    pub(crate) fn pauses(self, depth: usize) -> bool {
+        match self {
+            Mode::Continue => false,
+            Mode::Step => true,
+            Mode::Next(level) => depth <= level,
+            Mode::Finish(level) => depth < level,
+        }
+    }
+}
+
+/// What to do after a command
+enum Action {
+    /// Read the next command
+    Prompt,
+    /// Resume the program, pausing next as in the mode
+    Resume(Mode),
+    /// Stop the program
+    Quit,
+}
+
+/// The interactive step debugger. Reads commands as lines from its input, and writes to its output.
+///
+/// The program starts paused before its first statement.
+pub struct Debugger<I, W> {
+    /// The lines of the source code, for showing the current statement
+    source: Vec<String>,
+    lines: SourceLines,
+    breakpoints: BTreeSet<usize>,
+    watches: Vec<String>,
+    mode: Mode,
+    previous_command: String,
+    input: I,
+    output: W,
+}
+
+impl<I, W> Debug for Debugger<I, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .field("watches", &self.watches)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I: Iterator<Item = String>, W: Write> Debugger<I, W> {
+    /// Creates a new [Debugger] for the program with the given source code, reading commands from the input
+    pub fn new(source: &str, input: I, output: W) -> Self {
+        Self {
+            source: source.lines().map(str::to_owned).collect(),
+            lines: SourceLines::new(source),
+            breakpoints: BTreeSet::new(),
+            watches: Vec::new(),
+            mode: Mode::Step,
+            previous_command: String::new(),
+            input,
+            output,
+        }
+    }
+
+    /// Returns the output of the debugger
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    /// Internal helper: writes the text and a newline to the output
+    fn say(&mut self, text: &str) {
+        let _ = writeln!(self.output, "{text}");
+    }
+
+    /// Executes the command, returning what to do next
+    fn command(&mut self, line: &str, statement: &Statement, state: State<'_>) -> Action {
+        let line = if line.is_empty() {
+            self.previous_command.clone()
+        } else {
+            line.to_owned()
+        };
+        self.previous_command.clone_from(&line);
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line.as_str(), ""),
+        };
+        let depth = state.loops.len();
+        match command {
+            "s" | "step" => return Action::Resume(Mode::Step),
+            "n" | "next" => return Action::Resume(Mode::Next(depth)),
+            "f" | "finish" => return Action::Resume(Mode::Finish(depth)),
+            "c" | "continue" => return Action::Resume(Mode::Continue),
+            "q" | "quit" => return Action::Quit,
+            "b" | "break" => match argument.parse::<usize>() {
+                Ok(line) if (1..=self.source.len()).contains(&line) => {
+                    self.breakpoints.insert(line);
+                    self.say(&format!("Breakpoint set at line {line}"));
+                }
+                _ => self.say(&format!(
+                    "Expected a line number from 1 to {}",
+                    self.source.len()
+                )),
+            },
+            "d" | "delete" if argument.is_empty() => {
+                self.breakpoints.clear();
+                self.say("Deleted all breakpoints");
+            }
+            "d" | "delete" => match argument.parse::<usize>() {
+                Ok(line) if self.breakpoints.remove(&line) => {
+                    self.say(&format!("Deleted the breakpoint at line {line}"));
+                }
+                _ => self.say(&format!("No breakpoint at line {argument}")),
+            },
+            "breakpoints" => {
+                if self.breakpoints.is_empty() {
+                    self.say("No breakpoints");
+                }
+                for line in self.breakpoints.clone() {
+                    let text = self.source[line - 1].trim().to_owned();
+                    self.say(&format!("line {line}: {text}"));
+                }
+            }
+            "p" | "print" => {
+                let value = evaluate(argument, state);
+                self.say(&describe(argument, value));
+            }
+            "vars" => {
+                let mut variables: Vec<(&str, &Object)> = state.environment.variables().collect();
+                if variables.is_empty() {
+                    self.say("No variables declared");
+                }
+                variables.sort_by_key(|(name, _)| *name);
+                for (name, value) in variables {
+                    self.say(&describe(name, Ok(value.clone())));
+                }
+            }
+            "w" | "watch" if !argument.is_empty() => {
+                self.watches.push(argument.to_owned());
+                let value = evaluate(argument, state);
+                let description = describe(argument, value);
+                self.say(&format!("Watch {}: {description}", self.watches.len()));
+            }
+            "unwatch" => match argument.parse::<usize>() {
+                Ok(number) if (1..=self.watches.len()).contains(&number) => {
+                    let watch = self.watches.remove(number - 1);
+                    self.say(&format!("Deleted watch {number}: {watch}"));
+                }
+                _ => self.say(&format!("No watch {argument}")),
+            },
+            "l" | "list" => self.show(statement),
+            "bt" | "backtrace" => {
+                let (line, column) = self.lines.line_column(statement.span.start);
+                self.say(&format!("#0 statement at {line}:{column}"));
+                for (level, (variable, header)) in state.loops.iter().rev().enumerate() {
+                    let (line, column) = self.lines.line_column(header.start);
+                    self.say(&format!(
+                        "#{} for {variable} at {line}:{column}, {variable} = {}",
+                        level + 1,
+                        state
+                            .environment
+                            .get(variable, *header)
+                            .map_or_else(|_| "?".to_owned(), |value| value.to_string())
+                    ));
+                }
+            }
+            "h" | "help" => self.say(HELP),
+            _ => self.say(&format!(
+                "Unknown command `{line}`. Type `help` for the commands"
+            )),
+        }
+        Action::Prompt
+    }
+
+    /// Shows the location of the statement, and its first line with the statement underlined
+    fn show(&mut self, statement: &Statement) {
+        let span = statement.span;
+        let (line, column) = self.lines.line_column(span.start);
+        let text = self.source.get(line - 1).cloned().unwrap_or_default();
+        let (end_line, end_column) = self.lines.line_column(span.end.max(span.start + 1) - 1);
+        let length = if end_line == line {
+            end_column + 1 - column
+        } else {
+            (text.chars().count() + 1).saturating_sub(column).max(1)
+        };
+        let gutter = line.to_string().len();
+        let mut shown = format!("at line {line}, column {column}\n");
+        let _ = writeln!(shown, "{line} | {text}");
+        let _ = write!(
+            shown,
+            "{} | {}{}",
+            " ".repeat(gutter),
+            " ".repeat(column - 1),
+            "^".repeat(length)
+        );
+        self.say(&shown);
+    }
+}
+
+impl<I: Iterator<Item = String>, W: Write> Hook for Debugger<I, W> {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let (line, _) = self.lines.line_column(statement.span.start);
+        let depth = state.loops.len();
+        let breakpoint = self.breakpoints.contains(&line);
+        if !breakpoint && !self.mode.pauses(depth) {
+            return Ok(());
+        }
+
+        if breakpoint {
+            let _ = write!(self.output, "Breakpoint ");
+        } else {
+            let _ = write!(self.output, "Paused ");
+        }
+        self.show(statement);
+        for (number, watch) in self.watches.clone().iter().enumerate() {
+            let description = describe(watch, evaluate(watch, state));
+            self.say(&format!("Watch {}: {description}", number + 1));
+        }
+        loop {
+            let _ = write!(self.output, "(minipl) ");
+            let _ = self.output.flush();
+            let line = match self.input.next() {
+                Some(line) => line,
+                // Without any more commands, the program runs to the end
+                None => {
+                    self.breakpoints.clear();
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+            };
+            match self.command(line.trim(), statement, state) {
+                Action::Prompt => (),
+                Action::Resume(mode) => {
+                    self.mode = mode;
+                    return Ok(());
+                }
+                Action::Quit => return Err(RuntimeError::Stopped(statement.span.into())),
+            }
+        }
+    }
+}
+
+/// Internal helper: returns the error code and message of the diagnostic, e.g. `[E0340] Undeclared variable`
+pub(crate) fn message(error: &dyn Diagnostic) -> String {
+    match error.code() {
+        Some(code) => format!("[{code}] {error}"),
+        None => error.to_string(),
+    }
+}
+
+/// Internal helper: lexes and parses the source code, returning the first error with the span of its first label
+pub(crate) fn parse(source: &str) -> Result<Vec<Statement>, (String, StartEndSpan)> {
+    let location = |error: &dyn Diagnostic| {
+        error
+            .labels()
+            .and_then(|mut labels| labels.next())
+            .map_or(StartEndSpan::new(0, 0), |label| {
+                StartEndSpan::new(label.offset(), label.offset() + label.len())
+            })
+    };
+    let tokens = Lexer::new(source)
+        .scan()
+        .map_err(|report| (message(report.as_ref()), location(report.as_ref())))?;
+    if let Some((error, span)) = tokens.iter().find_map(|token| match &token.token {
+        RawToken::Error(error) => Some((error, token.span)),
+        _ => None,
+    }) {
+        return Err((format!("[{}] {error}", error.code()), span));
+    }
+    Parser::new(tokens)
+        .parse()
+        .map_err(|error| (message(&error), location(&error)))
+}
+
+/// Evaluates the expression in the source code against a copy of the variables, so that the program is not affected
+pub(crate) fn evaluate(expression: &str, state: State<'_>) -> Result<Object, String> {
+    if expression.is_empty() {
+        return Err("Expected an expression".to_owned());
+    }
+    let statements = parse(&format!("{expression};")).map_err(|(message, _)| message)?;
+    let expression = match statements.as_slice() {
+        [Statement {
+            stmt: Stmt::Expression(expression),
+            ..
+        }] => expression,
+        _ => return Err("Expected an expression".to_owned()),
+    };
+    let mut interpreter = Interpreter::new();
+    interpreter.environment = state.environment.clone();
+    interpreter
+        .visit_expression(expression)
+        .map_err(|error| message(&error))
+}
+
+/// Returns the value as shown to the user, with texts quoted
+pub(crate) fn format_value(value: &Object) -> String {
+    match value {
+        Object::Text(text) => format!("{text:?}"),
+        value => value.to_string(),
+    }
+}
+
+/// Returns the expression and its value or error, e.g. `x = 1 (Number)`
+fn describe(expression: &str, value: Result<Object, String>) -> String {
+    match value {
+        Ok(value) => format!(
+            "{expression} = {} ({})",
+            format_value(&value),
+            value.kind_to_string()
+        ),
+        Err(error) => format!("{expression}: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::Debugger;
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::runtime::RuntimeError;
+    use crate::visitors::Interpreter;
+
+    /// Debugs the program with the given commands, returning the result and the output of the debugger
+    fn debug(source: &str, commands: &[&str]) -> (Result<(), RuntimeError>, String) {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let commands = commands.iter().map(|command| (*command).to_owned());
+        let debugger = Debugger::new(source, commands.collect::<Vec<_>>().into_iter(), Vec::new());
+        let mut interpreter = Interpreter::with_hook(debugger);
+        let result = interpreter.eval(&statements);
+        let output = interpreter.into_hook().into_output();
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn pauses_before_the_first_statement() {
+        let (result, output) = debug("var x : int := 1;\nx := 2;", &["p x", "q"]);
+        assert!(matches!(result, Err(RuntimeError::Stopped(_))));
+        assert_eq!(
+            output,
+            "Paused at line 1, column 1\n1 | var x : int := 1;\n  | ^^^^^^^^^^^^^^^^\n(minipl) x: [E0340] Undeclared variable\n(minipl) "
+        );
+    }
+
+    #[test]
+    fn breakpoints_and_watches() {
+        let source =
+            "var x : int;\nvar i : int;\nfor i in 1..3 do\n  x := x + i;\nend for;\nprint x;";
+        let (result, output) = debug(source, &["b 4", "w x * 10", "c", "c", "d", "c"]);
+        assert!(result.is_ok());
+        assert!(output.contains("Breakpoint at line 4, column 3\n4 |   x := x + i;\n  |   ^^^^^^^^^^\nWatch 1: x * 10 = 0 (Number)"));
+        assert!(output.contains("Watch 1: x * 10 = 10 (Number)"));
+        assert_eq!(output.matches("Breakpoint at").count(), 2);
+    }
+
+    #[test]
+    fn next_steps_over_loops() {
+        let source = "var i : int;\nfor i in 1..3 do\n  print i;\nend for;\nprint \"done\";";
+        let (_, output) = debug(source, &["n", "n", "bt", "q"]);
+        assert!(output.contains("Paused at line 5, column 1"));
+        assert!(output.contains("#0 statement at 5:1\n"));
+
+        let (_, output) = debug(source, &["s", "s", "bt", "s", "p i", "q"]);
+        assert!(output.contains("#1 for i at 2:1, i = 1"));
+        assert!(output.contains("i = 2 (Number)"));
+    }
+
+    #[test]
+    fn printing_does_not_change_the_program() {
+        let (result, output) = debug(
+            "var x : int := 1;\nassert x = 1;",
+            &["s", "p x := 5", "vars", "c"],
+        );
+        assert!(result.is_ok());
+        assert!(output.contains("x := 5 = 5 (Number)\n(minipl) x = 1 (Number)"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Long-form explanations of the error codes of Mini-PL, printed with `minipl explain <code>`.
+//!
+//! Codes `E01xx` are lexing errors, `E02xx` parse errors, `E03xx` runtime errors, `E04xx` lint configuration errors,
+//! `E05xx` errors in replaying recorded sessions and `E06xx` errors in resuming snapshots.
+//! Each explanation describes the error, and unless the error is internal or caused by the environment of the program, shows an
+//! erroneous and a fixed example: a program, or a lint configuration, session or snapshot file.
+
+/// Returns the long-form explanation of the given error code, e.g. `E0101`. The code is case-insensitive.
+#[must_use]
+pub fn explain(code: &str) -> Option<&'static str> {
+    let explanation = match code.to_uppercase().as_str() {
+        "E0101" => include_str!("explanations/E0101.md"),
+        "E0102" => include_str!("explanations/E0102.md"),
+        "E0103" => include_str!("explanations/E0103.md"),
+        "E0104" => include_str!("explanations/E0104.md"),
+        "E0105" => include_str!("explanations/E0105.md"),
+        "E0106" => include_str!("explanations/E0106.md"),
+        "E0107" => include_str!("explanations/E0107.md"),
+        "E0108" => include_str!("explanations/E0108.md"),
+        "E0109" => include_str!("explanations/E0109.md"),
+        "E0110" => include_str!("explanations/E0110.md"),
+        "E0111" => include_str!("explanations/E0111.md"),
+        "E0112" => include_str!("explanations/E0112.md"),
+        "E0201" => include_str!("explanations/E0201.md"),
+        "E0202" => include_str!("explanations/E0202.md"),
+        "E0203" => include_str!("explanations/E0203.md"),
+        "E0204" => include_str!("explanations/E0204.md"),
+        "E0205" => include_str!("explanations/E0205.md"),
+        "E0206" => include_str!("explanations/E0206.md"),
+        "E0207" => include_str!("explanations/E0207.md"),
+        "E0208" => include_str!("explanations/E0208.md"),
+        "E0209" => include_str!("explanations/E0209.md"),
+        "E0210" => include_str!("explanations/E0210.md"),
+        "E0211" => include_str!("explanations/E0211.md"),
+        "E0212" => include_str!("explanations/E0212.md"),
+        "E0213" => include_str!("explanations/E0213.md"),
+        "E0214" => include_str!("explanations/E0214.md"),
+        "E0215" => include_str!("explanations/E0215.md"),
+        "E0216" => include_str!("explanations/E0216.md"),
+        "E0217" => include_str!("explanations/E0217.md"),
+        "E0218" => include_str!("explanations/E0218.md"),
+        "E0219" => include_str!("explanations/E0219.md"),
+        "E0220" => include_str!("explanations/E0220.md"),
+        "E0221" => include_str!("explanations/E0221.md"),
+        "E0222" => include_str!("explanations/E0222.md"),
+        "E0301" => include_str!("explanations/E0301.md"),
+        "E0302" => include_str!("explanations/E0302.md"),
+        "E0303" => include_str!("explanations/E0303.md"),
+        "E0304" => include_str!("explanations/E0304.md"),
+        "E0305" => include_str!("explanations/E0305.md"),
+        "E0306" => include_str!("explanations/E0306.md"),
+        "E0307" => include_str!("explanations/E0307.md"),
+        "E0308" => include_str!("explanations/E0308.md"),
+        "E0309" => include_str!("explanations/E0309.md"),
+        "E0310" => include_str!("explanations/E0310.md"),
+        "E0311" => include_str!("explanations/E0311.md"),
+        "E0312" => include_str!("explanations/E0312.md"),
+        "E0313" => include_str!("explanations/E0313.md"),
+        "E0314" => include_str!("explanations/E0314.md"),
+        "E0315" => include_str!("explanations/E0315.md"),
+        "E0316" => include_str!("explanations/E0316.md"),
+        "E0317" => include_str!("explanations/E0317.md"),
+        "E0318" => include_str!("explanations/E0318.md"),
+        "E0319" => include_str!("explanations/E0319.md"),
+        "E0320" => include_str!("explanations/E0320.md"),
+        "E0321" => include_str!("explanations/E0321.md"),
+        "E0322" => include_str!("explanations/E0322.md"),
+        "E0323" => include_str!("explanations/E0323.md"),
+        "E0324" => include_str!("explanations/E0324.md"),
+        "E0325" => include_str!("explanations/E0325.md"),
+        "E0326" => include_str!("explanations/E0326.md"),
+        "E0327" => include_str!("explanations/E0327.md"),
+        "E0328" => include_str!("explanations/E0328.md"),
+        "E0329" => include_str!("explanations/E0329.md"),
+        "E0330" => include_str!("explanations/E0330.md"),
+        "E0331" => include_str!("explanations/E0331.md"),
+        "E0332" => include_str!("explanations/E0332.md"),
+        "E0333" => include_str!("explanations/E0333.md"),
+        "E0334" => include_str!("explanations/E0334.md"),
+        "E0335" => include_str!("explanations/E0335.md"),
+        "E0336" => include_str!("explanations/E0336.md"),
+        "E0337" => include_str!("explanations/E0337.md"),
+        "E0338" => include_str!("explanations/E0338.md"),
+        "E0339" => include_str!("explanations/E0339.md"),
+        "E0340" => include_str!("explanations/E0340.md"),
+        "E0341" => include_str!("explanations/E0341.md"),
+        "E0342" => include_str!("explanations/E0342.md"),
+        "E0343" => include_str!("explanations/E0343.md"),
+        "E0344" => include_str!("explanations/E0344.md"),
+        "E0345" => include_str!("explanations/E0345.md"),
+        "E0401" => include_str!("explanations/E0401.md"),
+        "E0402" => include_str!("explanations/E0402.md"),
+        "E0501" => include_str!("explanations/E0501.md"),
+        "E0502" => include_str!("explanations/E0502.md"),
+        "E0503" => include_str!("explanations/E0503.md"),
+        "E0504" => include_str!("explanations/E0504.md"),
+        "E0601" => include_str!("explanations/E0601.md"),
+        _ => return None,
+    };
+    Some(explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::collections::VecDeque;
+    use std::io;
+
+    use miette::Diagnostic;
+
+    use super::explain;
+    use crate::lexing::Lexer;
+    use crate::lints::LintConfig;
+    use crate::parsing::Parser;
+    use crate::replay::{Recorder, Session};
+    use crate::runtime::Console;
+    use crate::snapshot::Snapshot;
+    use crate::tokens::RawToken;
+    use crate::visitors::Interpreter;
+
+    /// Codes without an erroneous and a fixed example. The internal errors cannot be caused by a program parsed from source
+    /// code, and the others are caused by the environment of the program rather than by the program itself
+    const WITHOUT_EXAMPLES: [&str; 13] = [
+        // Internal errors
+        "E0101", "E0103", "E0304", "E0318", "E0319", "E0320", "E0321", "E0326", "E0334", "E0345",
+        // A closed output, input that is not UTF-8, and a program stopped by a tool such as the debugger
+        "E0331", "E0332", "E0344",
+    ];
+
+    /// A console with the input lines given in `// input: <line>` comments of an example program, discarding the output
+    #[derive(Debug, Default)]
+    struct Scripted(VecDeque<String>);
+
+    impl Scripted {
+        fn new(source: &str) -> Self {
+            let input = source
+                .lines()
+                .filter_map(|line| Some(format!("{}\n", line.split_once("// input: ")?.1)))
+                .collect();
+            Self(input)
+        }
+    }
+
+    impl Console for Scripted {
+        fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+            let line = self.0.pop_front().unwrap_or_default();
+            buffer.push_str(&line);
+            Ok(line.len())
+        }
+
+        fn print(&mut self, _text: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Returns the code of the diagnostic
+    fn code(diagnostic: &dyn Diagnostic) -> Option<String> {
+        diagnostic.code().map(|code| code.to_string())
+    }
+
+    /// Runs the program with the input, returning the code of the first error
+    fn first_error_code(lexer: Lexer<'_>, input: Scripted) -> Option<String> {
+        let tokens = match lexer.scan() {
+            Ok(tokens) => tokens,
+            Err(report) => return code(report.as_ref()),
+        };
+        if let Some(RawToken::Error(error)) = tokens
+            .iter()
+            .map(|token| &token.token)
+            .find(|token| matches!(token, RawToken::Error(_)))
+        {
+            return Some(error.code().to_owned());
+        }
+        let statements = match Parser::new(tokens).parse() {
+            Ok(statements) => statements,
+            Err(error) => return code(&error),
+        };
+        let mut interpreter = Interpreter::new();
+        interpreter.set_console(Box::new(input));
+        match interpreter.eval(&statements) {
+            Ok(()) => None,
+            Err(error) => code(&error),
+        }
+    }
+
+    /// Replays the session for the program, returning the code of the first error
+    fn replay_error_code(source: &str, session: &str) -> Option<String> {
+        let session = Session::from_json(session).unwrap();
+        let statements = Parser::new(Lexer::new(source).scan().unwrap())
+            .parse()
+            .unwrap();
+        let recorder = Recorder::replay(source, session, Box::<Scripted>::default());
+        let mut interpreter = Interpreter::with_hook(recorder.clone());
+        interpreter.set_console(Box::new(recorder.clone()));
+        let result = interpreter.eval(&statements);
+        match recorder.finish() {
+            Err(error) => code(&error),
+            Ok(()) => result.err().and_then(|error| code(&error)),
+        }
+    }
+
+    /// Returns the code of the first error of the erroneous and the fixed example of the code, or None without examples
+    fn example_error_codes(code: &str) -> Option<(Option<String>, Option<String>)> {
+        let explanation = explain(code).unwrap();
+        let pair = |info: &str| -> Option<(&str, &str)> {
+            let blocks = code_blocks(explanation, info);
+            if blocks.is_empty() {
+                return None;
+            }
+            assert_eq!(
+                blocks.len(),
+                2,
+                "{code} should have an erroneous and a fixed example"
+            );
+            Some((blocks[0], blocks[1]))
+        };
+        let result = |json: &str, parse: fn(&str) -> Result<(), Box<dyn Diagnostic>>| {
+            parse(json)
+                .err()
+                .and_then(|error| self::code(error.as_ref()))
+        };
+        let codes = match code {
+            // Session and snapshot files
+            "E0501" => {
+                let (erroneous, fixed) = pair("json")?;
+                let parse = |json: &str| -> Result<(), Box<dyn Diagnostic>> {
+                    Session::from_json(json)?;
+                    Ok(())
+                };
+                (result(erroneous, parse), result(fixed, parse))
+            }
+            "E0601" => {
+                let (erroneous, fixed) = pair("json")?;
+                let parse = |json: &str| -> Result<(), Box<dyn Diagnostic>> {
+                    Snapshot::from_json(json)?.statements()?;
+                    Ok(())
+                };
+                (result(erroneous, parse), result(fixed, parse))
+            }
+            // Configuration files for the lints
+            _ if code.starts_with("E04") => {
+                let (erroneous, fixed) = pair("toml")?;
+                let parse = |toml: &str| -> Result<(), Box<dyn Diagnostic>> {
+                    LintConfig::from_toml(toml)?;
+                    Ok(())
+                };
+                (result(erroneous, parse), result(fixed, parse))
+            }
+            // Programs replayed with the recorded session of the explanation
+            _ if code.starts_with("E05") => {
+                let (erroneous, fixed) = pair("minipl")?;
+                let session = code_blocks(explanation, "json")[0];
+                (
+                    replay_error_code(erroneous, session),
+                    replay_error_code(fixed, session),
+                )
+            }
+            // Programs, of which the erroneous example of E0112 is read in the Latin-1 encoding
+            _ => {
+                let (erroneous, fixed) = pair("minipl")?;
+                let latin1: Vec<u8>;
+                let lexer = if code == "E0112" {
+                    latin1 = erroneous
+                        .chars()
+                        .map(|c| u8::try_from(u32::from(c)).unwrap())
+                        .collect();
+                    Lexer::from_reader(latin1.as_slice())
+                } else {
+                    Lexer::new(erroneous)
+                };
+                (
+                    first_error_code(lexer, Scripted::new(erroneous)),
+                    first_error_code(Lexer::new(fixed), Scripted::new(fixed)),
+                )
+            }
+        };
+        Some(codes)
+    }
+
+    /// Returns the contents of the code blocks with exactly the given info string
+    fn code_blocks<'a>(explanation: &'a str, info: &str) -> Vec<&'a str> {
+        explanation
+            .split("```")
+            .skip(1)
+            .step_by(2)
+            .filter_map(|block| block.strip_prefix(info)?.strip_prefix('\n'))
+            .collect()
+    }
+
+    /// Returns all error codes defined in the error enums
+    fn defined_codes() -> Vec<String> {
+        let sources = [
+            include_str!("lexing/errors.rs"),
+            include_str!("parsing/errors.rs"),
+            include_str!("runtime/errors.rs"),
+            include_str!("lints.rs"),
+            include_str!("replay.rs"),
+            include_str!("snapshot.rs"),
+        ];
+        let mut codes = Vec::new();
+        for source in sources {
+            let mut rest = source;
+            while let Some(index) = rest.find("E0") {
+                let candidate = &rest[index..];
+                if candidate.len() >= 5 && candidate[2..5].chars().all(|c| c.is_ascii_digit()) {
+                    codes.push(candidate[..5].to_owned());
+                }
+                rest = &rest[index + 2..];
+            }
+        }
+        codes
+    }
+
+    #[test]
+    fn every_code_is_explained() {
+        let codes = defined_codes();
+        assert!(codes.len() > 50);
+        for code in codes {
+            assert!(explain(&code).is_some(), "{code} has no explanation");
+        }
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let mut codes = defined_codes();
+        let count = codes.len();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), count);
+    }
+
+    #[test]
+    fn explain_is_case_insensitive() {
+        assert_eq!(explain("e0101"), explain("E0101"));
+        assert!(explain("E9999").is_none());
+    }
+
+    #[test]
+    fn examples_fail_and_fixed_examples_run() {
+        for code in defined_codes() {
+            let examples = example_error_codes(&code);
+            if WITHOUT_EXAMPLES.contains(&code.as_str()) {
+                assert!(
+                    examples.is_none(),
+                    "{code} has examples, remove it from WITHOUT_EXAMPLES"
+                );
+                continue;
+            }
+            let (erroneous, fixed) = examples
+                .unwrap_or_else(|| panic!("{code} should have an erroneous and a fixed example"));
+            assert_eq!(
+                erroneous.as_deref(),
+                Some(code.as_str()),
+                "erroneous example of {code}"
+            );
+            assert_eq!(fixed, None, "fixed example of {code}");
+        }
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    iter::{Enumerate, Peekable},
-    str::Chars,
-};
+use std::io::{BufReader, Read};
 
 use miette::Result;
 use tracing::debug;
@@ -13,8 +10,14 @@ mod errors;
 pub use errors::*;
 
 // implementation split into multiple files for convenience
+mod bang;
+mod chars;
+use chars::{CharStream, OwnedChars, ReaderChars};
 mod colon;
+mod greater;
 mod identifier;
+pub(crate) use identifier::KEYWORDS;
+mod less;
 mod number;
 mod range;
 mod slash;
@@ -22,22 +25,39 @@ mod string;
 mod whitespace;
 
 #[derive(Debug)]
-/// The lexer for the Mini-PL programming language
+/// The lexer for the Mini-PL programming language.
+///
+/// The lexer reads its input lazily, and is an [Iterator] over the scanned [Token]s, including ignorable tokens and ending with an [EOF].
+/// Use [Lexer::scan] or [Lexer::scan_verbose] to collect the tokens of a whole source at once.
 pub struct Lexer<'a> {
     _tokens: Vec<Token>, // TODO: use internal field instead of passing the vec around in returns
-    source: std::string::String,
-    iter: Peekable<Enumerate<Chars<'a>>>,
+    iter: CharStream<'a>,
+    /// Whether the EOF token or an unrecoverable error has already been returned
+    finished: bool,
 }
 
 impl<'a> Lexer<'a> {
     /// Initializes a lexer with the given input [`&str`]
     pub fn new(input: &'a str) -> Self {
-        let tokens = Vec::new();
-        let source = input.to_string();
+        Self::from_chars(CharStream::new(input.chars().map(Ok)))
+    }
+
+    /// Initializes a lexer reading UTF-8 source code from the given reader, one buffer at a time
+    pub fn from_reader(reader: impl Read + 'a) -> Self {
+        Self::from_chars(CharStream::new(ReaderChars::new(BufReader::new(reader))))
+    }
+
+    /// Initializes a lexer over source code split into chunks, e.g. as received over a network. Tokens may span several chunks
+    pub fn from_chunks(chunks: impl IntoIterator<Item = std::string::String> + 'a) -> Self {
+        let chars = chunks.into_iter().flat_map(OwnedChars::new).map(Ok);
+        Self::from_chars(CharStream::new(chars))
+    }
+
+    fn from_chars(iter: CharStream<'a>) -> Self {
         Self {
-            _tokens: tokens,
-            source,
-            iter: input.chars().enumerate().peekable(),
+            _tokens: Vec::new(),
+            iter,
+            finished: false,
         }
     }
 
@@ -46,7 +66,7 @@ impl<'a> Lexer<'a> {
     /// # Errors
     /// The Error case of this Result will only occur when an **unrecoverable** runtime error occurs in the parser itself.
     /// Any parse errors for the source code will be returned as [Token]s with type [`RawToken::Error`](crate::tokens::RawToken::Error) in order to recover error locations for use in error reporting for the user.
-    pub fn scan(&mut self) -> Result<Vec<Token>> {
+    pub fn scan(self) -> Result<Vec<Token>> {
         // Use the verbose version
         let mut tokens = self.scan_verbose()?;
         // Then remove ignorables
@@ -59,17 +79,14 @@ impl<'a> Lexer<'a> {
     /// # Errors
     /// The Error case of this Result will only occur when an **unrecoverable** runtime error occurs in the parser itself.
     /// Any parse errors for the source code will be returned as [Token]s with type [`RawToken::Error`](crate::tokens::RawToken::Error) in order to recover error locations for use in error reporting for the user.
-    pub fn scan_verbose(&mut self) -> Result<Vec<Token>> {
+    pub fn scan_verbose(self) -> Result<Vec<Token>> {
         let mut tokens: Vec<Token> = Vec::new();
-        let length = self.source.len();
-        while self.iter.peek().is_some() {
-            match self.scan_token() {
+        for token in self {
+            match token {
                 Ok(token) => tokens.push(token),
                 Err(error) => return Err(error.into()),
             }
         }
-
-        tokens.push(Token::new(EOF, StartEndSpan::new(length, length)));
         Ok(tokens)
     }
 
@@ -84,8 +101,9 @@ impl<'a> Lexer<'a> {
         let token: Token = match char {
             // Single-character tokens
             '&' => Token::new(And, StartEndSpan::new(start, start + 1)),
-            '!' => Token::new(Bang, StartEndSpan::new(start, start + 1)),
-            '<' => Token::new(Less, StartEndSpan::new(start, start + 1)),
+            '[' => Token::new(BracketLeft, StartEndSpan::new(start, start + 1)),
+            ']' => Token::new(BracketRight, StartEndSpan::new(start, start + 1)),
+            ',' => Token::new(Comma, StartEndSpan::new(start, start + 1)),
             '-' => Token::new(Minus, StartEndSpan::new(start, start + 1)),
             '(' => Token::new(ParenLeft, StartEndSpan::new(start, start + 1)),
             ')' => Token::new(ParenRight, StartEndSpan::new(start, start + 1)),
@@ -93,11 +111,21 @@ impl<'a> Lexer<'a> {
             ';' => Token::new(Semicolon, StartEndSpan::new(start, start + 1)),
             '*' => Token::new(Star, StartEndSpan::new(start, start + 1)),
             '=' => Token::new(Equal, StartEndSpan::new(start, start + 1)),
+            '|' => Token::new(Or, StartEndSpan::new(start, start + 1)),
             // NOTE: we consume the char for these ^ at the end with a glob match in order to reduce line noise
 
+            // Bang: possibly a NotEqual, or just a Bang
+            '!' => self.scan_bang()?,
+
             // Colon: possibly an Assign, or just a Colon
             ':' => self.scan_colon()?,
 
+            // Less: possibly a LessEqual or a NotEqual, or just a Less
+            '<' => self.scan_less()?,
+
+            // Greater: possibly a GreaterEqual, or just a Greater
+            '>' => self.scan_greater()?,
+
             // Range
             '.' => self.scan_range()?,
 
@@ -110,6 +138,9 @@ impl<'a> Lexer<'a> {
             // Text i.e. String literal
             '"' => self.scan_string()?,
 
+            // Raw string literal
+            'r' if self.is_raw_string_start() => self.scan_raw_string()?,
+
             // Whitespace - note https://doc.rust-lang.org/std/primitive.char.html#method.is_ascii_whitespace
             ' ' | '\t' | '\n' | '\u{000C}' | '\r' => self.scan_whitespace()?,
 
@@ -132,7 +163,7 @@ impl<'a> Lexer<'a> {
         // Slash is an exception because the comment parsing handling ends up always consuming the first slash.
         if matches!(
             char,
-            '&' | '!' | '<' | '-' | '(' | ')' | '+' | ';' | '*' | '='
+            '&' | '[' | ']' | ',' | '-' | '(' | ')' | '+' | ';' | '*' | '=' | '|'
         ) {
             self.iter.next();
         }
@@ -143,11 +174,12 @@ impl<'a> Lexer<'a> {
 
     /// Internal helper: returns the peeked next token, or an OutOfTokens error
     fn maybe_peek(&mut self) -> Result<&(usize, char), UnrecoverableLexingError> {
-        if let Some(next) = self.iter.peek() {
-            Ok(next)
-        } else {
-            let span = StartEndSpan::new(self.source.len(), self.source.len());
-            Err(UnrecoverableLexingError::OutOfChars(span.into()))
+        let end = self.iter.end();
+        match self.iter.peek() {
+            Some(next) => Ok(next),
+            None => Err(UnrecoverableLexingError::OutOfChars(
+                StartEndSpan::new(end, end).into(),
+            )),
         }
     }
 
@@ -156,21 +188,54 @@ impl<'a> Lexer<'a> {
         if let Some(next) = self.iter.next() {
             Ok(next)
         } else {
-            let span = StartEndSpan::new(self.source.len(), self.source.len());
+            let span = StartEndSpan::new(self.iter.end(), self.iter.end());
             Err(UnrecoverableLexingError::OutOfChars(span.into()))
         }
     }
 }
 
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, UnrecoverableLexingError>;
+
+    /// Scans the next token. After the [EOF] token or an unrecoverable error, returns None
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = match self.iter.peek() {
+            Some(_) => self.scan_token(),
+            None => {
+                self.finished = true;
+                let end = self.iter.end();
+                Ok(Token::new(EOF, StartEndSpan::new(end, end)))
+            }
+        };
+        // An error reading the input ends the stream, and takes precedence over the token scanned up to it
+        if let Some(error) = self.iter.take_error() {
+            let end = self.iter.end();
+            self.finished = true;
+            return Some(Err(UnrecoverableLexingError::ReadFailed(
+                error.to_string(),
+                StartEndSpan::new(end, end).into(),
+            )));
+        }
+        if result.is_err() {
+            self.finished = true;
+        }
+        Some(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
     use crate::lexing::*;
+    use crate::tokens::RawToken;
 
     #[test]
     fn single_character_token_and() {
         let source = "&";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(And, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -179,25 +244,52 @@ mod tests {
     #[test]
     fn single_character_token_bang() {
         let source = "!";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Bang, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
     }
 
+    #[test]
+    fn single_character_token_bracketleft() {
+        let source = "[";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(BracketLeft, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn single_character_token_bracketright() {
+        let source = "]";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(BracketRight, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
     #[test]
     fn single_character_token_colon() {
         let source = ":";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Colon, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
     }
 
+    #[test]
+    fn single_character_token_comma() {
+        let source = ",";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Comma, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
     #[test]
     fn single_character_token_equal() {
         let source = "=";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Equal, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -206,7 +298,7 @@ mod tests {
     #[test]
     fn single_character_token_less() {
         let source = "<";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Less, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -215,16 +307,25 @@ mod tests {
     #[test]
     fn single_character_token_minus() {
         let source = "-";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Minus, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
     }
 
+    #[test]
+    fn single_character_token_or() {
+        let source = "|";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Or, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
     #[test]
     fn single_character_token_parenleft() {
         let source = "(";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(ParenLeft, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -233,7 +334,7 @@ mod tests {
     #[test]
     fn single_character_token_parenright() {
         let source = ")";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(ParenRight, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -242,7 +343,7 @@ mod tests {
     #[test]
     fn single_character_token_plus() {
         let source = "+";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Plus, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -251,7 +352,7 @@ mod tests {
     #[test]
     fn single_character_token_semicolon() {
         let source = ";";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Semicolon, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -260,7 +361,7 @@ mod tests {
     #[test]
     fn single_character_token_slash() {
         let source = "/";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Slash, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -269,7 +370,7 @@ mod tests {
     #[test]
     fn single_character_token_star() {
         let source = "*";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Star, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -278,7 +379,7 @@ mod tests {
     #[test]
     fn simple_math1() {
         let source = "1+2";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let tokens = lexer.scan().unwrap();
         let expected = vec![
             Token::new(Number(1), StartEndSpan::new(0, 1)),
@@ -292,7 +393,7 @@ mod tests {
     #[test]
     fn simple_math2() {
         let source = "2*2/2=2";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let tokens = lexer.scan().unwrap();
         let expected = vec![
             Token::new(Number(2), StartEndSpan::new(0, 1)),
@@ -306,4 +407,53 @@ mod tests {
         ];
         assert_eq!(tokens, expected);
     }
+
+    #[test]
+    fn iterator_yields_ignorables_and_ends_with_eof() {
+        let mut lexer = Lexer::new("1 // one");
+        let tokens: Vec<RawToken> = lexer.by_ref().map(|token| token.unwrap().token).collect();
+        assert_eq!(tokens, vec![Number(1), Whitespace, Comment, EOF]);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn eof_span_counts_characters() {
+        let tokens = Lexer::new("\"äö\"").scan().unwrap();
+        assert_eq!(tokens[1], Token::new(EOF, StartEndSpan::new(4, 4)));
+    }
+
+    #[test]
+    fn reader_matches_string() {
+        let source = "var x : string := \"äö€\";\nprint x; /* 😀 */ print 1.5e3;";
+        let tokens = Lexer::new(source).scan_verbose().unwrap();
+        let from_reader = Lexer::from_reader(source.as_bytes())
+            .scan_verbose()
+            .unwrap();
+        assert_eq!(from_reader, tokens);
+    }
+
+    #[test]
+    fn tokens_span_chunks() {
+        let chunks = ["var count", "er : int := 1", "2; print r#\"a\"#", ";"];
+        let source = chunks.concat();
+        let tokens = Lexer::from_chunks(chunks.map(std::string::String::from))
+            .scan()
+            .unwrap();
+        assert_eq!(tokens, Lexer::new(&source).scan().unwrap());
+        assert_eq!(
+            tokens[1],
+            Token::new(Identifier("counter".to_owned()), StartEndSpan::new(4, 11))
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_unrecoverable() {
+        let mut lexer = Lexer::from_reader(&b"print 1;\xff"[..]);
+        let tokens: Vec<_> = lexer.by_ref().collect();
+        assert!(matches!(
+            tokens.last().unwrap(),
+            Err(UnrecoverableLexingError::ReadFailed(_, span)) if span.offset() == 8
+        ));
+        assert!(lexer.next().is_none());
+    }
 }
@@ -0,0 +1,46 @@
+use crate::span::StartEndSpan;
+
+use super::Bang;
+use super::Lexer;
+use super::NotEqual;
+use super::Token;
+use super::UnrecoverableLexingError;
+
+impl Lexer<'_> {
+    /// Internal helper function for scanning a lexeme that starts with an exclamation mark. This could be a [NotEqual], or just a [Bang].
+    pub(crate) fn scan_bang(&mut self) -> Result<Token, UnrecoverableLexingError> {
+        // Consume this token to peek the next
+        let (start, _) = self.maybe_next()?;
+        // Is this a NotEqual operator?
+        if let Some((_end, _)) = self.iter.next_if(|&(_, char)| char == '=') {
+            Ok(Token::new(NotEqual, StartEndSpan::new(start, start + 2)))
+        } else {
+            // Otherwise, it's just a Bang
+            Ok(Token::new(Bang, StartEndSpan::new(start, start + 1)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::{lexing::*, span::StartEndSpan};
+
+    #[test]
+    fn bang() {
+        let source = "!";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Bang, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn not_equal() {
+        let source = "!=";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(NotEqual, StartEndSpan::new(0, 2));
+        assert_eq!(token, expected);
+    }
+}
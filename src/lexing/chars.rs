@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{self, BufRead, ErrorKind};
+use std::str;
+
+/// The characters of the source code along with their offsets, read lazily from a string, a reader or chunks of text.
+/// Characters are buffered only as far as the lexer looks ahead, so the source never needs to be in memory as a whole.
+pub(crate) struct CharStream<'a> {
+    chars: Box<dyn Iterator<Item = io::Result<char>> + 'a>,
+    /// Characters read from the source, but not yet consumed
+    lookahead: VecDeque<(usize, char)>,
+    /// The offset of the next character to read from the source
+    offset: usize,
+    /// The first error reading the source. The stream ends at the error
+    error: Option<io::Error>,
+}
+
+impl<'a> CharStream<'a> {
+    pub(crate) fn new(chars: impl Iterator<Item = io::Result<char>> + 'a) -> Self {
+        Self {
+            chars: Box::new(chars),
+            lookahead: VecDeque::new(),
+            offset: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the next character without consuming it
+    pub(crate) fn peek(&mut self) -> Option<&(usize, char)> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the character `n` positions after the next one without consuming anything, reading ahead as needed
+    pub(crate) fn peek_nth(&mut self, n: usize) -> Option<&(usize, char)> {
+        while self.lookahead.len() <= n && self.error.is_none() {
+            match self.chars.next() {
+                Some(Ok(char)) => {
+                    self.lookahead.push_back((self.offset, char));
+                    self.offset += 1;
+                }
+                Some(Err(error)) => self.error = Some(error),
+                None => break,
+            }
+        }
+        self.lookahead.get(n)
+    }
+
+    /// Consumes and returns the next character if it satisfies the predicate
+    pub(crate) fn next_if(
+        &mut self,
+        predicate: impl FnOnce(&(usize, char)) -> bool,
+    ) -> Option<(usize, char)> {
+        match self.peek() {
+            Some(next) if predicate(next) => self.next(),
+            _ => None,
+        }
+    }
+
+    /// The offset right after the characters read so far. At the end of input, this is the length of the source in characters
+    pub(crate) fn end(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the error that ended the stream early, if any
+    pub(crate) fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl Iterator for CharStream<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peek();
+        self.lookahead.pop_front()
+    }
+}
+
+impl Debug for CharStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CharStream")
+            .field("lookahead", &self.lookahead)
+            .field("offset", &self.offset)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The characters of an owned string, decoded in place rather than copied out of it
+pub(crate) struct OwnedChars {
+    text: String,
+    /// The byte offset of the next character
+    position: usize,
+}
+
+impl OwnedChars {
+    pub(crate) fn new(text: String) -> Self {
+        Self { text, position: 0 }
+    }
+}
+
+impl Iterator for OwnedChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let char = self.text[self.position..].chars().next()?;
+        self.position += char.len_utf8();
+        Some(char)
+    }
+}
+
+/// Decodes the UTF-8 bytes of a reader into characters, one buffer at a time
+pub(crate) struct ReaderChars<R> {
+    reader: R,
+    /// The decoded characters of the last buffer
+    chars: OwnedChars,
+    /// Bytes of a character split between two buffers
+    partial: Vec<u8>,
+}
+
+impl<R: BufRead> ReaderChars<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chars: OwnedChars::new(String::new()),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Reads and decodes the next buffer. Returns false at the end of input
+    fn refill(&mut self) -> io::Result<bool> {
+        let buffer = loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => break buffer,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        };
+        if buffer.is_empty() {
+            if self.partial.is_empty() {
+                return Ok(false);
+            }
+            return Err(invalid_utf8());
+        }
+        self.partial.extend_from_slice(buffer);
+        let length = buffer.len();
+        self.reader.consume(length);
+
+        let valid = match str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            // An incomplete character at the end continues in the next buffer
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            // Characters before invalid bytes are still read, the error is returned on the next refill
+            Err(error) if error.valid_up_to() > 0 => error.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let bytes: Vec<u8> = self.partial.drain(..valid).collect();
+        let text = String::from_utf8(bytes).map_err(|_| invalid_utf8())?;
+        self.chars = OwnedChars::new(text);
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderChars<R> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(char) = self.chars.next() {
+                return Some(Ok(char));
+            }
+            match self.refill() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "the source is not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::io::{BufReader, Read};
+
+    use super::*;
+
+    /// Returns the characters decoded from the bytes, read with the given buffer size
+    fn decode(bytes: &[u8], capacity: usize) -> io::Result<String> {
+        ReaderChars::new(BufReader::with_capacity(capacity, bytes)).collect()
+    }
+
+    #[test]
+    fn characters_split_between_buffers() {
+        let source = "aä€😀";
+        for capacity in 1..8 {
+            assert_eq!(decode(source.as_bytes(), capacity).unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        assert!(decode(b"ab\xffc", 2).is_err());
+        // Truncated in the middle of a character
+        assert!(decode(&"ä".as_bytes()[..1], 8).is_err());
+    }
+
+    #[test]
+    fn owned_chars() {
+        let chars: String = OwnedChars::new("aä€😀".to_owned()).collect();
+        assert_eq!(chars, "aä€😀");
+    }
+
+    #[test]
+    fn lookahead_reads_lazily() {
+        let mut read = 0;
+        let chars = "abc".chars().inspect(|_| read += 1).map(Ok);
+        let mut stream = CharStream::new(chars);
+        assert_eq!(stream.peek_nth(1), Some(&(1, 'b')));
+        assert_eq!(stream.next(), Some((0, 'a')));
+        assert_eq!(stream.next_if(|&(_, char)| char == 'c'), None);
+        assert_eq!(stream.next(), Some((1, 'b')));
+        drop(stream);
+        assert_eq!(read, 2);
+    }
+
+    #[test]
+    fn errors_end_the_stream() {
+        let failing = BufReader::new(b"ab".chain(FailingReader));
+        let mut stream = CharStream::new(ReaderChars::new(failing));
+        assert_eq!(stream.by_ref().count(), 2);
+        assert_eq!(stream.end(), 2);
+        assert!(stream.take_error().is_some());
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+}
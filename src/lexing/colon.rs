@@ -29,7 +29,7 @@ mod tests {
     #[test]
     fn colon() {
         let source = ":";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Colon, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -38,7 +38,7 @@ mod tests {
     #[test]
     fn assign() {
         let source = ":=";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Assign, StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
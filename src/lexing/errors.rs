@@ -4,39 +4,82 @@ use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
 #[derive(Error, Debug, Diagnostic)]
-#[error("Lexing error")]
-#[diagnostic()]
 /// The unrecoverable error enum for the [Lexer](crate::lexing::Lexer)
 pub enum UnrecoverableLexingError {
     /// Ran out of tokens while scanning
+    #[error("Unexpected end of input")]
+    #[diagnostic(code(E0101))]
     OutOfChars(#[label = "Out of characters error. Lexer expected further input."] SourceSpan),
-    /// Unable to parse into an integer
-    ParseIntError(#[label = "Could not parse this into a number (i64)"] SourceSpan),
+    /// Unable to read the source code, e.g. due to an I/O error or invalid UTF-8
+    #[error("Could not read the source code: {0}")]
+    #[diagnostic(code(E0112))]
+    ReadFailed(
+        String,
+        #[label = "Reading failed after this position"] SourceSpan,
+    ),
+    /// Unable to parse into a real number
+    #[error("Invalid real number literal")]
+    #[diagnostic(code(E0103))]
+    ParseRealError(#[label = "Could not parse this into a real number (f64)"] SourceSpan),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 /// The recoverable error enum for the [Lexer](crate::lexing::Lexer), these will go in [RawToken::Error](crate::tokens::RawToken::Error)
 pub enum RecoverableLexingError {
-    /// Unknown escape code. Supported escape characters are: \\r \\t \\n \\' \\\"
+    /// Unknown escape code. Supported escape characters are: \\r \\t \\n \\0 \\' \\\" \\\\ \\{ \\} \\xNN \\u{NNNN}
     UnknownEscape,
-    /// Unterminated string or unescaped newline. Raw newlines in are not supported in strings in Mini-PL.
+    /// Invalid `\\xNN` escape. Requires exactly two hex digits, with a value of at most `7F`
+    InvalidHexEscape,
+    /// Invalid `\\u{NNNN}` escape. Requires one to six hex digits within braces, forming a valid Unicode scalar value
+    InvalidUnicodeEscape,
+    /// Unterminated string or unescaped newline. Raw newlines are only supported in raw strings `r"..."` in Mini-PL.
     Unterminated,
+    /// Unterminated embedded expression in a string literal, the opening brace `{` has no matching `}`
+    UnterminatedInterpolation,
     /// Encountered a single dot. Only use of dots in Mini-PL is the Range operator `..` which requires two dots
     SingleDot,
     /// Encountered an unknown character - something that is not an operator and not valid as a start of an identifier
     UnknownChar(char),
+    /// Number literal larger than the largest number [i64::MAX]
+    NumberOutOfRange,
+    /// Unterminated block comment, with the number of nesting levels still open at the end of input
+    UnterminatedComment(usize),
 }
 
 use RecoverableLexingError::*;
 
+impl RecoverableLexingError {
+    /// Returns the stable error code of this error, see `minipl explain <code>`
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match &self {
+            UnknownEscape => "E0104",
+            InvalidHexEscape => "E0105",
+            InvalidUnicodeEscape => "E0106",
+            Unterminated => "E0107",
+            UnterminatedInterpolation => "E0108",
+            SingleDot => "E0109",
+            UnknownChar(_) => "E0110",
+            NumberOutOfRange => "E0102",
+            UnterminatedComment(_) => "E0111",
+        }
+    }
+}
+
 // TODO: possibly use miette even for these? how?
 impl Display for RecoverableLexingError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match &self {
-            UnknownEscape => write!(f,"Unknown escape character or unescaped backslash. Supported escape characters are: \\r \\t \\n \\' \\\""),
-            Unterminated => write!(f, "Unterminated string or unescaped newline. If you need newlines, use \\n"),
+            UnknownEscape => write!(f,"Unknown escape character or unescaped backslash. Supported escape characters are: \\r \\t \\n \\0 \\' \\\" \\\\ \\{{ \\}} \\xNN \\u{{NNNN}}"),
+            InvalidHexEscape => write!(f, "Invalid hex escape. Use exactly two hex digits with a value of at most 7F, e.g. \\x41"),
+            InvalidUnicodeEscape => write!(f, "Invalid unicode escape. Use one to six hex digits within braces forming a valid character, e.g. \\u{{1F600}}"),
+            Unterminated => write!(f, "Unterminated string or unescaped newline. If you need newlines, use \\n or a raw string r\"...\""),
+            UnterminatedInterpolation => write!(f, "Unterminated embedded expression, expected a closing brace. If you need a literal brace, use \\{{"),
             SingleDot => write!(f, "Expected another '.' for Range operator"),
-            UnknownChar(t) => write!(f, "Unknown character: {}", t)
+            UnknownChar(t) => write!(f, "Unknown character: {}", t),
+            NumberOutOfRange => write!(f, "Number literal out of range, the largest number is {}", i64::MAX),
+            UnterminatedComment(1) => write!(f, "Unterminated block comment, expected a closing */"),
+            UnterminatedComment(depth) => write!(f, "Unterminated block comment, {depth} nested comments are still open. Close each /* with a */"),
         }
     }
 }
@@ -0,0 +1,49 @@
+use crate::span::StartEndSpan;
+
+use super::Greater;
+use super::GreaterEqual;
+use super::Lexer;
+use super::Token;
+use super::UnrecoverableLexingError;
+
+impl Lexer<'_> {
+    /// Internal helper function for scanning a lexeme that starts with a greater-than sign. This could be a [GreaterEqual], or just a [Greater].
+    pub(crate) fn scan_greater(&mut self) -> Result<Token, UnrecoverableLexingError> {
+        // Consume this token to peek the next
+        let (start, _) = self.maybe_next()?;
+        // Is this a GreaterEqual operator?
+        if let Some((_end, _)) = self.iter.next_if(|&(_, char)| char == '=') {
+            Ok(Token::new(
+                GreaterEqual,
+                StartEndSpan::new(start, start + 2),
+            ))
+        } else {
+            // Otherwise, it's just a Greater
+            Ok(Token::new(Greater, StartEndSpan::new(start, start + 1)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::{lexing::*, span::StartEndSpan};
+
+    #[test]
+    fn greater() {
+        let source = ">";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Greater, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn greater_equal() {
+        let source = ">=";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(GreaterEqual, StartEndSpan::new(0, 2));
+        assert_eq!(token, expected);
+    }
+}
@@ -1,42 +1,54 @@
 use crate::span::StartEndSpan;
 use crate::tokens::RawToken::{
-    Assert, Bool, Do, End, False, For, Identifier, In, Int, Print, Read, String, True, Var,
+    Array, Assert, Bool, Do, End, False, For, Identifier, In, Int, Of, Print, Read, Real, String,
+    True, Var,
 };
-use crate::tokens::Token;
+use crate::tokens::{RawToken, Token};
 
 use super::{Lexer, UnrecoverableLexingError};
 
+/// The keywords of the Mini-PL language, and their tokens
+pub(crate) const KEYWORDS: &[(&str, RawToken)] = &[
+    ("array", Array),
+    ("assert", Assert),
+    ("bool", Bool),
+    ("do", Do),
+    ("end", End),
+    ("false", False),
+    ("for", For),
+    ("in", In),
+    ("int", Int),
+    ("of", Of),
+    ("print", Print),
+    ("read", Read),
+    ("real", Real),
+    ("string", String),
+    ("true", True),
+    ("var", Var),
+];
+
 impl Lexer<'_> {
-    /// Internal helper function for scanning identifiers. Greedy / maximal munch, consumes all consecutive ascii-alphabetic chars.
+    /// Internal helper function for scanning identifiers. Greedy / maximal munch, consumes all consecutive ascii-alphanumeric chars and underscores.
     pub(crate) fn scan_identifier(&mut self) -> Result<Token, UnrecoverableLexingError> {
         // Grab the start location from the current, unconsumed char
         let &(start, _) = self.maybe_peek()?;
         let mut length = 0;
-        // Consume all alphabetic characters; [maximal munch](https://craftinginterpreters.com/scanning.html)
+        // Consume all alphanumeric characters and underscores; [maximal munch](https://craftinginterpreters.com/scanning.html)
+        // NOTE: the first character is always alphabetic, see [Lexer::scan_token]
         let mut identifier = std::string::String::new();
-        while let Some((_start, char)) = self.iter.next_if(|(_, char)| char.is_ascii_alphabetic()) {
+        while let Some((_start, char)) = self
+            .iter
+            .next_if(|(_, char)| char.is_ascii_alphanumeric() || *char == '_')
+        {
             identifier.push(char);
             length += 1;
         }
         let end = start + length;
 
-        let raw_token = match identifier.as_ref() {
-            // Is this a keyword?
-            "assert" => Assert,
-            "bool" => Bool,
-            "do" => Do,
-            "end" => End,
-            "false" => False,
-            "for" => For,
-            "in" => In,
-            "int" => Int,
-            "print" => Print,
-            "read" => Read,
-            "string" => String,
-            "true" => True,
-            "var" => Var,
-            // Otherwise, assume it's a user-defined identifier name
-            _ => Identifier(identifier),
+        // Is this a keyword? Otherwise, assume it's a user-defined identifier name
+        let raw_token = match KEYWORDS.iter().find(|(keyword, _)| *keyword == identifier) {
+            Some((_, token)) => token.clone(),
+            None => Identifier(identifier),
         };
         Ok(Token::new(raw_token, StartEndSpan::new(start, end)))
     }
@@ -47,10 +59,19 @@ mod tests {
     #![allow(clippy::unwrap_used)]
     use crate::{lexing::*, span::StartEndSpan};
 
+    #[test]
+    fn keyword_array() {
+        let source = "array";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Array, StartEndSpan::new(0, 5));
+        assert_eq!(token, expected);
+    }
+
     #[test]
     fn keyword_assert() {
         let source = "assert";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Assert, StartEndSpan::new(0, 6));
         assert_eq!(token, expected);
@@ -59,7 +80,7 @@ mod tests {
     #[test]
     fn keyword_bool() {
         let source = "bool";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Bool, StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -68,7 +89,7 @@ mod tests {
     #[test]
     fn keyword_do() {
         let source = "do";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Do, StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
@@ -77,7 +98,7 @@ mod tests {
     #[test]
     fn keyword_end() {
         let source = "end";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(End, StartEndSpan::new(0, 3));
         assert_eq!(token, expected);
@@ -86,7 +107,7 @@ mod tests {
     #[test]
     fn keyword_for() {
         let source = "for";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(For, StartEndSpan::new(0, 3));
         assert_eq!(token, expected);
@@ -95,7 +116,7 @@ mod tests {
     #[test]
     fn keyword_in() {
         let source = "in";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(In, StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
@@ -104,16 +125,25 @@ mod tests {
     #[test]
     fn keyword_int() {
         let source = "int";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Int, StartEndSpan::new(0, 3));
         assert_eq!(token, expected);
     }
 
+    #[test]
+    fn keyword_of() {
+        let source = "of";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Of, StartEndSpan::new(0, 2));
+        assert_eq!(token, expected);
+    }
+
     #[test]
     fn keyword_print() {
         let source = "print";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Print, StartEndSpan::new(0, 5));
         assert_eq!(token, expected);
@@ -122,7 +152,7 @@ mod tests {
     #[test]
     fn keyword_read() {
         let source = "read";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Read, StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -131,7 +161,7 @@ mod tests {
     #[test]
     fn keyword_string() {
         let source = "string";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(String, StartEndSpan::new(0, 6));
         assert_eq!(token, expected);
@@ -140,9 +170,27 @@ mod tests {
     #[test]
     fn keyword_var() {
         let source = "var";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Var, StartEndSpan::new(0, 3));
         assert_eq!(token, expected);
     }
+
+    #[test]
+    fn keyword_real() {
+        let source = "real";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Real, StartEndSpan::new(0, 4));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn identifier_with_digits_and_underscores() {
+        let source = "to_real2";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Identifier("to_real2".into()), StartEndSpan::new(0, 8));
+        assert_eq!(token, expected);
+    }
 }
@@ -0,0 +1,70 @@
+use crate::span::StartEndSpan;
+
+use super::Less;
+use super::LessEqual;
+use super::Lexer;
+use super::NotEqual;
+use super::Token;
+use super::UnrecoverableLexingError;
+
+impl Lexer<'_> {
+    /// Internal helper function for scanning a lexeme that starts with a less-than sign. This could be a [LessEqual], a [NotEqual], or just a [Less].
+    pub(crate) fn scan_less(&mut self) -> Result<Token, UnrecoverableLexingError> {
+        // Consume this token to peek the next
+        let (start, _) = self.maybe_next()?;
+        // Is this a LessEqual operator?
+        if let Some((_end, _)) = self.iter.next_if(|&(_, char)| char == '=') {
+            Ok(Token::new(LessEqual, StartEndSpan::new(start, start + 2)))
+        }
+        // Is this a NotEqual operator?
+        else if let Some((_end, _)) = self.iter.next_if(|&(_, char)| char == '>') {
+            Ok(Token::new(NotEqual, StartEndSpan::new(start, start + 2)))
+        } else {
+            // Otherwise, it's just a Less
+            Ok(Token::new(Less, StartEndSpan::new(start, start + 1)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::{lexing::*, span::StartEndSpan};
+
+    #[test]
+    fn less() {
+        let source = "<";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Less, StartEndSpan::new(0, 1));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn less_equal() {
+        let source = "<=";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(LessEqual, StartEndSpan::new(0, 2));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn not_equal() {
+        let source = "<>";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(NotEqual, StartEndSpan::new(0, 2));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn less_minus() {
+        // Maximal munch must not swallow unrelated characters
+        let source = "<-";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        assert_eq!(tokens[0], Token::new(Less, StartEndSpan::new(0, 1)));
+        assert_eq!(tokens[1], Token::new(Minus, StartEndSpan::new(1, 2)));
+    }
+}
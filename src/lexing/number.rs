@@ -1,34 +1,83 @@
 use crate::span::StartEndSpan;
 
+use super::Error;
 use super::Lexer;
 use super::Number;
+use super::RealNumber;
+use super::RecoverableLexingError;
 use super::Token;
 use super::UnrecoverableLexingError;
 
 use std::string::String;
 
 impl Lexer<'_> {
-    /// Internal helper function for scanning a number literal.
+    /// Internal helper function for scanning a number literal. Returns a [Number] for integers, or a [RealNumber] for literals with a fractional part or an exponent.
     pub(crate) fn scan_number(&mut self) -> Result<Token, UnrecoverableLexingError> {
         let mut number = String::new();
         let &(start, _) = self.maybe_peek()?;
-        let mut length = 0;
+        let mut is_real = false;
 
-        while let Some((_, char)) = self.iter.next_if(|(_, char)| char.is_ascii_digit()) {
-            length += 1;
-            number.push(char);
+        self.scan_digits(&mut number);
+
+        // Fractional part: a dot followed by a digit. Note that `1..10` is a Range, not a real number
+        if matches!(self.iter.peek(), Some((_, '.')))
+            && matches!(self.iter.peek_nth(1), Some((_, next)) if next.is_ascii_digit())
+        {
+            is_real = true;
+            self.iter.next();
+            number.push('.');
+            self.scan_digits(&mut number);
         }
 
-        let number: i64 = match number.parse() {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(UnrecoverableLexingError::ParseIntError(
-                    (start, length).into(),
-                ))
+        // Exponent: an `e` or `E`, an optional sign, and at least one digit
+        if let Some((_, 'e' | 'E')) = self.iter.peek() {
+            let sign = match self.iter.peek_nth(1) {
+                Some(&(_, sign @ ('+' | '-'))) => Some(sign),
+                _ => None,
+            };
+            let digit = if sign.is_some() { 2 } else { 1 };
+            if matches!(self.iter.peek_nth(digit), Some((_, next)) if next.is_ascii_digit()) {
+                is_real = true;
+                self.iter.next();
+                number.push('e');
+                if let Some(sign) = sign {
+                    self.iter.next();
+                    number.push(sign);
+                }
+                self.scan_digits(&mut number);
             }
-        };
+        }
+
+        let length = number.len();
         let end = start + length;
-        Ok(Token::new(Number(number), StartEndSpan::new(start, end)))
+        if is_real {
+            let number: f64 = match number.parse() {
+                Ok(n) => n,
+                Err(_) => {
+                    return Err(UnrecoverableLexingError::ParseRealError(
+                        (start, length).into(),
+                    ))
+                }
+            };
+            return Ok(Token::new(
+                RealNumber(number),
+                StartEndSpan::new(start, end),
+            ));
+        }
+
+        // The literal consists of digits only, so parsing can only fail by being out of range
+        let token = match number.parse() {
+            Ok(n) => Number(n),
+            Err(_) => Error(RecoverableLexingError::NumberOutOfRange),
+        };
+        Ok(Token::new(token, StartEndSpan::new(start, end)))
+    }
+
+    /// Internal helper function: consumes consecutive ascii digits into the given [String]
+    fn scan_digits(&mut self, number: &mut String) {
+        while let Some((_, char)) = self.iter.next_if(|(_, char)| char.is_ascii_digit()) {
+            number.push(char);
+        }
     }
 }
 
@@ -40,7 +89,7 @@ mod tests {
     #[test]
     fn number_singledigit() {
         let source = "1";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Number(1), StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -49,9 +98,89 @@ mod tests {
     #[test]
     fn number_multidigit() {
         let source = "1234567890";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Number(1_234_567_890), StartEndSpan::new(0, 10));
         assert_eq!(token, expected);
     }
+
+    #[test]
+    fn real_fraction() {
+        let source = "3.25";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(RealNumber(3.25), StartEndSpan::new(0, 4));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn real_exponent() {
+        let source = "1.5e-3";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(RealNumber(0.0015), StartEndSpan::new(0, 6));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn real_exponent_without_fraction() {
+        let source = "2E10";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(RealNumber(2e10), StartEndSpan::new(0, 4));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn number_followed_by_range() {
+        let source = "1..10";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let expected = vec![
+            Token::new(Number(1), StartEndSpan::new(0, 1)),
+            Token::new(Range, StartEndSpan::new(1, 3)),
+            Token::new(Number(10), StartEndSpan::new(3, 5)),
+            Token::new(EOF, StartEndSpan::new(5, 5)),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn number_followed_by_identifier() {
+        // An `e` without exponent digits is not part of the number
+        let source = "2e";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        assert_eq!(tokens[0], Token::new(Number(2), StartEndSpan::new(0, 1)));
+        assert_eq!(
+            tokens[1],
+            Token::new(Identifier("e".into()), StartEndSpan::new(1, 2))
+        );
+    }
+
+    #[test]
+    fn number_out_of_range() {
+        let source = "9223372036854775808 + 1";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let expected = vec![
+            Token::new(
+                Error(RecoverableLexingError::NumberOutOfRange),
+                StartEndSpan::new(0, 19),
+            ),
+            Token::new(Plus, StartEndSpan::new(20, 21)),
+            Token::new(Number(1), StartEndSpan::new(22, 23)),
+            Token::new(EOF, StartEndSpan::new(23, 23)),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn number_largest() {
+        let source = "9223372036854775807";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Number(i64::MAX), StartEndSpan::new(0, 19));
+        assert_eq!(token, expected);
+    }
 }
@@ -33,7 +33,7 @@ mod tests {
     #[test]
     fn range_singledot() {
         let source = ".";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(
             Error(RecoverableLexingError::SingleDot),
@@ -45,7 +45,7 @@ mod tests {
     #[test]
     fn range_empty() {
         let source = "..";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Range, StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
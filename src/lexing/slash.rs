@@ -1,7 +1,9 @@
 use crate::span::StartEndSpan;
 
 use super::Comment;
+use super::Error;
 use super::Lexer;
+use super::RecoverableLexingError;
 use super::Slash;
 use super::Token;
 use super::UnrecoverableLexingError;
@@ -31,12 +33,23 @@ impl Lexer<'_> {
         if let Some((_, _)) = self.iter.next_if(|&(_, char)| char == '*') {
             length += 1;
             let mut multiline_comment_level = 1;
+            // At the end of input, report the opening `/*` along with the nesting levels still open
+            let unterminated = |level| {
+                Token::new(
+                    Error(RecoverableLexingError::UnterminatedComment(level)),
+                    StartEndSpan::new(start, start + 2),
+                )
+            };
             while multiline_comment_level > 0 {
-                let (_, next) = self.maybe_next()?;
+                let Some((_, next)) = self.iter.next() else {
+                    return Ok(unterminated(multiline_comment_level));
+                };
                 length += 1;
                 // Do we exit one level of multiline comment nesting?
                 if next == '*' {
-                    let (_, next) = self.maybe_next()?;
+                    let Some((_, next)) = self.iter.next() else {
+                        return Ok(unterminated(multiline_comment_level));
+                    };
                     length += 1;
                     if next == '/' {
                         multiline_comment_level -= 1;
@@ -44,7 +57,9 @@ impl Lexer<'_> {
                 }
                 // Do we enter another level of multiline comment nesting?
                 if next == '/' {
-                    let (_, next) = self.maybe_next()?;
+                    let Some((_, next)) = self.iter.next() else {
+                        return Ok(unterminated(multiline_comment_level));
+                    };
                     length += 1;
                     if next == '*' {
                         multiline_comment_level += 1;
@@ -68,7 +83,7 @@ mod tests {
     #[test]
     fn comment_empty() {
         let source = "//\n";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Comment, StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
@@ -77,7 +92,7 @@ mod tests {
     #[test]
     fn comment_singleline() {
         let source = "// I am a comment\n";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Comment, StartEndSpan::new(0, 17));
         assert_eq!(token, expected);
@@ -86,7 +101,7 @@ mod tests {
     #[test]
     fn comment_multiline_simple() {
         let source = "/* I am a comment \n */";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Comment, StartEndSpan::new(0, 22));
         assert_eq!(token, expected);
@@ -95,7 +110,7 @@ mod tests {
     #[test]
     fn comment_multiline_nested() {
         let source = "/* \n /* \n */ \n */";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Comment, StartEndSpan::new(0, 17));
         assert_eq!(token, expected);
@@ -104,9 +119,37 @@ mod tests {
     #[test]
     fn comment_multiline_nested_with_stars_and_slashes() {
         let source = "/* \n /* * / / * */ \n */";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Comment, StartEndSpan::new(0, 23));
         assert_eq!(token, expected);
     }
+
+    #[test]
+    fn comment_multiline_unterminated() {
+        let source = "/* \n /* */ \n print 1;";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let expected = vec![
+            Token::new(
+                Error(RecoverableLexingError::UnterminatedComment(1)),
+                StartEndSpan::new(0, 2),
+            ),
+            Token::new(EOF, StartEndSpan::new(21, 21)),
+        ];
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn comment_multiline_unterminated_nested() {
+        // Ends right after a star, in the middle of a possible closing `*/`
+        let source = "/* /* /* */ *";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::UnterminatedComment(2)),
+            StartEndSpan::new(0, 2),
+        );
+        assert_eq!(token, expected);
+    }
 }
@@ -1,50 +1,59 @@
 use crate::span::StartEndSpan;
+use crate::tokens::InterpolationPart;
 
+use super::Comment;
 use super::Error;
+use super::Interpolation;
 use super::Lexer;
 use super::RecoverableLexingError;
 use super::Text;
 use super::Token;
 use super::UnrecoverableLexingError;
+use super::Whitespace;
+use super::EOF;
 use std::string::String;
 
 impl<'a> Lexer<'a> {
-    /// Internal helper function for scanning a string literal. Returns a [Token] with [`RawToken::Text(String)`]
+    /// Internal helper function for scanning a string literal. Returns a [Token] with [`RawToken::Text(String)`], or [`RawToken::Interpolation`] if the string contains embedded `{expression}`s
     pub(crate) fn scan_string(&mut self) -> Result<Token, UnrecoverableLexingError> {
         // Consume the first quote
         let (start, _) = self.maybe_next()?;
-        let mut length = 1;
 
         // Consume and collect all characters within the string
         let mut contents = String::new();
-        while let Some((_, char)) = self.iter.next_if(|&(_, char)| char != '"') {
-            length += 1;
-
+        let mut parts = Vec::new();
+        // The first error within the string. The rest of the string is still consumed, and the error is reported with its own span
+        let mut error = None;
+        while let Some((position, char)) = self.iter.next_if(|&(_, char)| char != '"') {
             // Specification forbids unescaped newlines
             if char == '\n' {
                 return Ok(Token::new(
                     Error(RecoverableLexingError::Unterminated),
-                    StartEndSpan::new(start, length),
+                    StartEndSpan::new(start, start + 1),
                 ));
             }
             // Parse escape characters, following https://doc.rust-lang.org/std/primitive.char.html
             else if char == '\\' {
-                let (_, next) = self.maybe_next()?;
-                length += 1;
-                match next {
-                    't' => contents.push('\t'),
-                    'r' => contents.push('\r'),
-                    'n' => contents.push('\n'),
-                    '\'' => contents.push('\''),
-                    '\"' => contents.push('\"'),
-                    '\\' => contents.push('\\'),
-                    _ => {
-                        return Ok(Token::new(
-                            Error(RecoverableLexingError::UnknownEscape),
-                            StartEndSpan::new(start, start + length),
-                        ))
+                match self.scan_escape(position) {
+                    Ok(char) => contents.push(char),
+                    Err(token) => {
+                        error.get_or_insert(token);
                     }
                 }
+            }
+            // Embedded expression
+            else if char == '{' {
+                if !contents.is_empty() {
+                    parts.push(InterpolationPart::Literal(std::mem::take(&mut contents)));
+                }
+                let tokens = match self.scan_embedded_expression(position)? {
+                    Ok(tokens) => tokens,
+                    Err(token) => return Ok(token),
+                };
+                if let Some(token) = tokens.iter().find(|token| token.is_error()) {
+                    error.get_or_insert(token.clone());
+                }
+                parts.push(InterpolationPart::Tokens(tokens));
             } else {
                 // Normal character, push as-is
                 contents.push(char);
@@ -52,17 +61,168 @@ impl<'a> Lexer<'a> {
         }
 
         // Check if we have an ending quote
-        if let Some((_, _)) = self.iter.next_if(|&(_, char)| char == '\"') {
-            length += 1;
-            Ok(Token::new(
-                Text(contents),
-                StartEndSpan::new(start, start + length),
-            ))
-        } else {
-            Ok(Token::new(
-                Error(RecoverableLexingError::Unterminated),
-                StartEndSpan::new(start, start + length),
-            ))
+        let end = match self.iter.next_if(|&(_, char)| char == '\"') {
+            Some((end, _)) => end + 1,
+            None => {
+                return Ok(Token::new(
+                    Error(RecoverableLexingError::Unterminated),
+                    StartEndSpan::new(start, start + 1),
+                ))
+            }
+        };
+        if let Some(error) = error {
+            return Ok(error);
+        }
+        if parts.is_empty() {
+            return Ok(Token::new(Text(contents), StartEndSpan::new(start, end)));
+        }
+        if !contents.is_empty() {
+            parts.push(InterpolationPart::Literal(contents));
+        }
+        Ok(Token::new(
+            Interpolation(parts),
+            StartEndSpan::new(start, end),
+        ))
+    }
+
+    /// Internal helper function for scanning an escape sequence, after the backslash at the given position.
+    /// Returns the escaped character, or an error token spanning the invalid escape sequence.
+    fn scan_escape(&mut self, backslash: usize) -> Result<char, Token> {
+        let error = |error, end| Err(Token::new(Error(error), StartEndSpan::new(backslash, end)));
+        // NOTE: a newline is not consumed, so that it terminates the string
+        let (position, next) = match self.iter.next_if(|&(_, char)| char != '\n') {
+            Some(next) => next,
+            None => return error(RecoverableLexingError::UnknownEscape, backslash + 1),
+        };
+        match next {
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            'n' => Ok('\n'),
+            '0' => Ok('\0'),
+            '\'' => Ok('\''),
+            '\"' => Ok('\"'),
+            '\\' => Ok('\\'),
+            '{' => Ok('{'),
+            '}' => Ok('}'),
+            // `\xNN`: exactly two hex digits, at most 0x7F
+            'x' => {
+                let (digits, end) = self.scan_hex_digits(position + 1, 2);
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && value <= 0x7F => Ok(char::from(value)),
+                    _ => error(RecoverableLexingError::InvalidHexEscape, end),
+                }
+            }
+            // `\u{NNNN}`: one to six hex digits within braces, forming a valid Unicode scalar value
+            'u' => {
+                let mut end = position + 1;
+                if let Some((brace, _)) = self.iter.next_if(|&(_, char)| char == '{') {
+                    let (digits, digits_end) = self.scan_hex_digits(brace + 1, 6);
+                    end = digits_end;
+                    if let Some((brace, _)) = self.iter.next_if(|&(_, char)| char == '}') {
+                        end = brace + 1;
+                        let value = u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32);
+                        if let Some(char) = value {
+                            return Ok(char);
+                        }
+                    }
+                }
+                error(RecoverableLexingError::InvalidUnicodeEscape, end)
+            }
+            _ => error(RecoverableLexingError::UnknownEscape, position + 1),
+        }
+    }
+
+    /// Internal helper function: consumes at most `max` hex digits, returning them and the end position of the last one
+    fn scan_hex_digits(&mut self, start: usize, max: usize) -> (String, usize) {
+        let mut digits = String::new();
+        let mut end = start;
+        while digits.len() < max {
+            match self.iter.next_if(|(_, char)| char.is_ascii_hexdigit()) {
+                Some((position, char)) => {
+                    digits.push(char);
+                    end = position + 1;
+                }
+                None => break,
+            }
+        }
+        (digits, end)
+    }
+
+    /// Internal helper function: checks whether the next characters start a raw string literal, `r"` or `r#"`
+    pub(crate) fn is_raw_string_start(&mut self) -> bool {
+        let mut next = 1;
+        while matches!(self.iter.peek_nth(next), Some((_, '#'))) {
+            next += 1;
+        }
+        matches!(self.iter.peek_nth(next), Some((_, '"')))
+    }
+
+    /// Internal helper function for scanning a raw string literal, `r"..."` or `r#"..."#` with any number of hashes.
+    /// Raw strings have no escape sequences or embedded expressions, and may contain newlines. Returns a [Token] with [`RawToken::Text(String)`]
+    pub(crate) fn scan_raw_string(&mut self) -> Result<Token, UnrecoverableLexingError> {
+        // Consume the r, the hashes and the opening quote
+        let (start, _) = self.maybe_next()?;
+        let mut hashes = 0;
+        while self.iter.next_if(|&(_, char)| char == '#').is_some() {
+            hashes += 1;
+        }
+        self.maybe_next()?;
+
+        let mut contents = String::new();
+        while let Some((position, char)) = self.iter.next() {
+            if char == '"' {
+                // The string ends at a quote followed by the same number of hashes
+                let closing = (0..hashes)
+                    .take_while(|&n| matches!(self.iter.peek_nth(n), Some((_, '#'))))
+                    .count();
+                if closing == hashes {
+                    for _ in 0..hashes {
+                        self.iter.next();
+                    }
+                    return Ok(Token::new(
+                        Text(contents),
+                        StartEndSpan::new(start, position + 1 + hashes),
+                    ));
+                }
+            }
+            contents.push(char);
+        }
+
+        Ok(Token::new(
+            Error(RecoverableLexingError::Unterminated),
+            StartEndSpan::new(start, start + 1),
+        ))
+    }
+
+    /// Internal helper function for scanning the tokens of an expression embedded in a string literal, after the opening brace at the given position.
+    /// Returns the tokens terminated by an [EOF] at the closing brace, or an error token if the string ends before the closing brace.
+    fn scan_embedded_expression(
+        &mut self,
+        open: usize,
+    ) -> Result<Result<Vec<Token>, Token>, UnrecoverableLexingError> {
+        let mut tokens = Vec::new();
+        loop {
+            match self.iter.peek() {
+                Some(&(close, '}')) => {
+                    self.iter.next();
+                    tokens.push(Token::new(EOF, StartEndSpan::new(close, close + 1)));
+                    return Ok(Ok(tokens));
+                }
+                Some(_) => {
+                    let token = self.scan_token()?;
+                    if !matches!(token.token, Whitespace | Comment) {
+                        tokens.push(token);
+                    }
+                }
+                None => {
+                    return Ok(Err(Token::new(
+                        Error(RecoverableLexingError::UnterminatedInterpolation),
+                        StartEndSpan::new(open, open + 1),
+                    )))
+                }
+            }
         }
     }
 }
@@ -72,12 +232,13 @@ mod tests {
     #![allow(clippy::unwrap_used)]
     use super::RecoverableLexingError;
     use crate::lexing::*;
+    use crate::tokens::InterpolationPart;
 
     #[test]
     fn empty() {
         // NOTE: original source code will have the literal quotes
         let source = "\"\"";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("".into()), StartEndSpan::new(0, 2));
         assert_eq!(token, expected);
@@ -86,7 +247,7 @@ mod tests {
     #[test]
     fn unterminated() {
         let source = "\"";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(
             Error(RecoverableLexingError::Unterminated),
@@ -99,11 +260,11 @@ mod tests {
     fn raw_newline() {
         // NOTE: Specification forbids raw newlines within a string
         let source = "\"multi\nline\"";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(
             Error(RecoverableLexingError::Unterminated),
-            StartEndSpan::new(0, 7),
+            StartEndSpan::new(0, 1),
         );
         assert_eq!(token, expected);
     }
@@ -111,11 +272,11 @@ mod tests {
     #[test]
     fn unknown_escape() {
         let source = r#""\ä""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(
             Error(RecoverableLexingError::UnknownEscape),
-            StartEndSpan::new(0, 3),
+            StartEndSpan::new(1, 3),
         );
         assert_eq!(token, expected);
     }
@@ -123,7 +284,7 @@ mod tests {
     #[test]
     fn escaped_tab() {
         let source = r#""\t""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\t".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -132,7 +293,7 @@ mod tests {
     #[test]
     fn escaped_carriage_return() {
         let source = r#""\r""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\r".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -141,7 +302,7 @@ mod tests {
     #[test]
     fn escaped_line_feed() {
         let source = r#""\n""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\n".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -150,7 +311,7 @@ mod tests {
     #[test]
     fn escaped_single_quote() {
         let source = r#""\'""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\'".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -159,7 +320,7 @@ mod tests {
     #[test]
     fn escaped_double_quote() {
         let source = r#""\"""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\"".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
@@ -168,9 +329,218 @@ mod tests {
     #[test]
     fn escaped_backslash() {
         let source = r#""\\""#;
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan().unwrap()[0].clone();
         let expected = Token::new(Text("\\".into()), StartEndSpan::new(0, 4));
         assert_eq!(token, expected);
     }
+
+    #[test]
+    fn escaped_braces() {
+        let source = r#""\{\}""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Text("{}".into()), StartEndSpan::new(0, 6));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn interpolation() {
+        let source = r#""x = {x + 1}!""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Interpolation(vec![
+                InterpolationPart::Literal("x = ".into()),
+                InterpolationPart::Tokens(vec![
+                    Token::new(Identifier("x".into()), StartEndSpan::new(6, 7)),
+                    Token::new(Plus, StartEndSpan::new(8, 9)),
+                    Token::new(Number(1), StartEndSpan::new(10, 11)),
+                    Token::new(EOF, StartEndSpan::new(11, 12)),
+                ]),
+                InterpolationPart::Literal("!".into()),
+            ]),
+            StartEndSpan::new(0, 14),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn interpolation_nested_string() {
+        let source = r#""{to_upper("a")}""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Interpolation(vec![InterpolationPart::Tokens(vec![
+                Token::new(Identifier("to_upper".into()), StartEndSpan::new(2, 10)),
+                Token::new(ParenLeft, StartEndSpan::new(10, 11)),
+                Token::new(Text("a".into()), StartEndSpan::new(11, 14)),
+                Token::new(ParenRight, StartEndSpan::new(14, 15)),
+                Token::new(EOF, StartEndSpan::new(15, 16)),
+            ])]),
+            StartEndSpan::new(0, 17),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn interpolation_unterminated() {
+        let source = r#""{x"#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::UnterminatedInterpolation),
+            StartEndSpan::new(1, 2),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn interpolation_error_span() {
+        let source = r#""{x # 1}""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::UnknownChar('#')),
+            StartEndSpan::new(4, 5),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn unknown_escape_consumes_string() {
+        let source = r#""\q abc" x"#;
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let expected = Token::new(Identifier("x".into()), StartEndSpan::new(9, 10));
+        assert_eq!(tokens[1], expected);
+    }
+
+    #[test]
+    fn unterminated_points_at_opening_quote() {
+        let source = r#"print "abc"#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[1].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::Unterminated),
+            StartEndSpan::new(6, 7),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_null() {
+        let source = r#""\0""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Text("\0".into()), StartEndSpan::new(0, 4));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_hex() {
+        let source = r#""\x41\x7e""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Text("A~".into()), StartEndSpan::new(0, 10));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_hex_invalid() {
+        let source = r#""ab\x8g""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::InvalidHexEscape),
+            StartEndSpan::new(3, 6),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_hex_out_of_range() {
+        let source = r#""\xff""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::InvalidHexEscape),
+            StartEndSpan::new(1, 5),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_unicode() {
+        let source = r#""\u{e4}\u{1F600}""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(Text("ä😀".into()), StartEndSpan::new(0, 17));
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_unicode_surrogate() {
+        let source = r#""a \u{D800} b""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::InvalidUnicodeEscape),
+            StartEndSpan::new(3, 11),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn escaped_unicode_missing_brace() {
+        let source = r#""\u{41""#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::InvalidUnicodeEscape),
+            StartEndSpan::new(1, 6),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn raw_string() {
+        let source = "r\"multi\nline \\n {x}\";";
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let token = tokens[0].clone();
+        let expected = Token::new(Text("multi\nline \\n {x}".into()), StartEndSpan::new(0, 20));
+        assert_eq!(token, expected);
+        assert_eq!(tokens[1], Token::new(Semicolon, StartEndSpan::new(20, 21)));
+    }
+
+    #[test]
+    fn raw_string_with_hashes() {
+        let source = r###"r#"say "hi""#;"###;
+        let lexer = Lexer::new(source);
+        let tokens = lexer.scan().unwrap();
+        let expected = Token::new(Text("say \"hi\"".into()), StartEndSpan::new(0, 13));
+        assert_eq!(tokens[0], expected);
+        assert_eq!(tokens[1], Token::new(Semicolon, StartEndSpan::new(13, 14)));
+    }
+
+    #[test]
+    fn raw_string_unterminated() {
+        let source = r#"r"abc"#;
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        let expected = Token::new(
+            Error(RecoverableLexingError::Unterminated),
+            StartEndSpan::new(0, 1),
+        );
+        assert_eq!(token, expected);
+    }
+
+    #[test]
+    fn identifier_starting_with_r() {
+        let source = "read";
+        let lexer = Lexer::new(source);
+        let token = lexer.scan().unwrap()[0].clone();
+        assert_eq!(token, Token::new(Read, StartEndSpan::new(0, 4)));
+    }
 }
@@ -30,7 +30,7 @@ mod tests {
     #[test]
     fn space() {
         let source = " ";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Whitespace, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -39,7 +39,7 @@ mod tests {
     #[test]
     fn newline() {
         let source = "\n";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Whitespace, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -48,7 +48,7 @@ mod tests {
     #[test]
     fn carriage_return() {
         let source = "\r";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Whitespace, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -57,7 +57,7 @@ mod tests {
     #[test]
     fn horizontal_tab() {
         let source = "\t";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Whitespace, StartEndSpan::new(0, 1));
         assert_eq!(token, expected);
@@ -66,7 +66,7 @@ mod tests {
     #[test]
     fn mixed_whitespace() {
         let source = " \n \r \t ";
-        let mut lexer = Lexer::new(source);
+        let lexer = Lexer::new(source);
         let token = lexer.scan_verbose().unwrap()[0].clone();
         let expected = Token::new(Whitespace, StartEndSpan::new(0, 7));
         assert_eq!(token, expected);
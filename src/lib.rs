@@ -14,12 +14,36 @@
 
 // High-Level stuff
 
+/// The bytecode of the Mini-PL language, and its disassembler
+pub mod bytecode;
+/// The code coverage of Mini-PL programs
+pub mod coverage;
+/// The Debug Adapter Protocol server for debugging Mini-PL programs in editors
+pub mod dap;
+/// The interactive step debugger for Mini-PL programs
+pub mod debugger;
+/// The explanations of the error codes of the Mini-PL language
+pub mod explanations;
 /// The lexing for the Mini-PL language
 pub mod lexing;
+/// The lint rules for the Mini-PL language
+pub mod lints;
+/// The optimization pass over the abstract syntax tree
+pub mod optimizer;
 /// The parsing for the Mini-PL language
 pub mod parsing;
+/// The statement-level profiling of Mini-PL programs
+pub mod profiler;
+/// The deterministic record and replay of the input of Mini-PL programs
+pub mod replay;
+/// The snapshots of paused Mini-PL programs, to be resumed later
+pub mod snapshot;
+/// The "did you mean" suggestions for misspelled identifiers and keywords
+pub mod suggestions;
 /// The tokens of the Mini-PL language
 pub mod tokens;
+/// The execution tracing of Mini-PL programs
+pub mod tracer;
 
 // Plumbing
 
@@ -0,0 +1,264 @@
+//! Lint rules of the Mini-PL language: warnings about programs that are valid, but likely not what was intended.
+//!
+//! The lints are reported by the [Linter](crate::visitors::Linter). Each rule can be individually allowed, warned about or denied,
+//! with a [LintConfig] built from a `minipl.toml` project config file and command line flags.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, Severity};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::span::StartEndSpan;
+use crate::suggestions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The lint rules
+pub enum Rule {
+    /// A variable is declared, but never used afterwards
+    UnusedVariable,
+    /// A variable is assigned, but its value is never read
+    VariableNeverRead,
+    /// The value of an expression statement is discarded
+    UnusedResult,
+    /// A variable is assigned to itself
+    SelfAssignment,
+    /// The condition of an `assert` statement does not depend on the program state
+    ConstantAssert,
+    /// A `for` loop has an empty body
+    EmptyForBody,
+    /// A variable is compared to itself
+    SelfComparison,
+    /// Statements that can never be executed, after an `assert false;`
+    UnreachableCode,
+}
+
+impl Rule {
+    /// All lint rules
+    pub const ALL: [Rule; 8] = [
+        Rule::UnusedVariable,
+        Rule::VariableNeverRead,
+        Rule::UnusedResult,
+        Rule::SelfAssignment,
+        Rule::ConstantAssert,
+        Rule::EmptyForBody,
+        Rule::SelfComparison,
+        Rule::UnreachableCode,
+    ];
+
+    /// Returns the name of the rule, as used in the config file and the command line flags
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused_variable",
+            Rule::VariableNeverRead => "variable_never_read",
+            Rule::UnusedResult => "unused_result",
+            Rule::SelfAssignment => "self_assignment",
+            Rule::ConstantAssert => "constant_assert",
+            Rule::EmptyForBody => "empty_for_body",
+            Rule::SelfComparison => "self_comparison",
+            Rule::UnreachableCode => "unreachable_code",
+        }
+    }
+
+    /// Returns the rule with the given name, if any
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The level of a lint rule
+pub enum Level {
+    /// The lint is not reported
+    Allow,
+    /// The lint is reported as a warning
+    Warn,
+    /// The lint is reported as an error, and the program is not run
+    Deny,
+}
+
+/// The name of the lint group that contains all rules, e.g. `--deny warnings`
+pub const ALL_RULES_GROUP: &str = "warnings";
+
+#[derive(Debug, Clone, Default)]
+/// The configured levels of the lint rules. Rules are warned about by default
+pub struct LintConfig {
+    levels: HashMap<Rule, Level>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// The contents of a `minipl.toml` project config file
+struct ConfigFile {
+    #[serde(default)]
+    lints: HashMap<String, Level>,
+}
+
+impl LintConfig {
+    /// Parses the lint configuration from the contents of a `minipl.toml` project config file, e.g.
+    ///
+    /// ```toml
+    /// [lints]
+    /// unused_variable = "allow"
+    /// self_comparison = "deny"
+    /// ```
+    pub fn from_toml(source: &str) -> Result<Self, LintConfigError> {
+        let file: ConfigFile = toml::from_str(source)
+            .map_err(|error| LintConfigError::InvalidConfig(error.message().to_owned()))?;
+        let mut config = LintConfig::default();
+        // NOTE: sorted, so that the group and errors are handled deterministically
+        let mut lints: Vec<_> = file.lints.into_iter().collect();
+        lints.sort_by_key(|(name, _)| (name != ALL_RULES_GROUP, name.clone()));
+        for (name, level) in lints {
+            config.set(&name, level)?;
+        }
+        Ok(config)
+    }
+
+    /// Returns the configured level of the rule
+    #[must_use]
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels.get(&rule).copied().unwrap_or(Level::Warn)
+    }
+
+    /// Sets the level of the rule with the given name, or of all rules for the name `warnings`
+    pub fn set(&mut self, name: &str, level: Level) -> Result<(), LintConfigError> {
+        if name == ALL_RULES_GROUP {
+            for rule in Rule::ALL {
+                self.levels.insert(rule, level);
+            }
+            return Ok(());
+        }
+        match Rule::from_name(name) {
+            Some(rule) => {
+                self.levels.insert(rule, level);
+                Ok(())
+            }
+            None => {
+                let names = Rule::ALL.iter().map(|rule| rule.name());
+                let help = match suggestions::closest(name, names) {
+                    Some(rule) => format!("Did you mean `{rule}`?"),
+                    None => format!(
+                        "The lint rules are: {}",
+                        Rule::ALL.map(Rule::name).join(", ")
+                    ),
+                };
+                Err(LintConfigError::UnknownRule(name.to_owned(), help))
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+/// Errors in the lint configuration
+pub enum LintConfigError {
+    #[error("Unknown lint rule `{0}`")]
+    #[diagnostic(code(E0401))]
+    /// The config file or a command line flag refers to a lint rule that does not exist
+    UnknownRule(String, #[help] String),
+    #[error("Invalid lint configuration: {0}")]
+    #[diagnostic(
+        code(E0402),
+        help("Lint levels are configured in the [lints] table, e.g. unused_variable = \"allow\"")
+    )]
+    /// The config file could not be parsed
+    InvalidConfig(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A lint found in the program: a [Diagnostic] with a severity depending on the configured [Level] of its rule
+pub struct Lint {
+    /// The rule that found this lint
+    pub rule: Rule,
+    /// The configured level of the rule
+    pub level: Level,
+    /// The primary message
+    pub message: String,
+    /// The message of the label pointing into the source code
+    pub label: String,
+    /// The location of the lint in the source code
+    pub span: StartEndSpan,
+    /// Optional help text
+    pub help: Option<String>,
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Lint {}
+
+impl Diagnostic for Lint {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.rule))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        match self.level {
+            Level::Deny => Some(Severity::Error),
+            Level::Allow | Level::Warn => Some(Severity::Warning),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let help: Box<dyn Display + 'a> = Box::new(self.help.as_ref()?);
+        Some(help)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(self.label.clone()),
+            self.span,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn rules_are_warned_by_default() {
+        let config = LintConfig::default();
+        assert!(Rule::ALL
+            .iter()
+            .all(|&rule| config.level(rule) == Level::Warn));
+    }
+
+    #[test]
+    fn config_file_sets_levels() {
+        let source = "[lints]\nwarnings = \"deny\"\nunused_variable = \"allow\"\n";
+        let config = LintConfig::from_toml(source).unwrap();
+        assert_eq!(config.level(Rule::UnusedVariable), Level::Allow);
+        assert_eq!(config.level(Rule::SelfComparison), Level::Deny);
+    }
+
+    #[test]
+    fn config_file_unknown_rule() {
+        let source = "[lints]\nunused_variables = \"allow\"\n";
+        let error = LintConfig::from_toml(source).unwrap_err();
+        assert!(
+            matches!(error, LintConfigError::UnknownRule(name, help) if name == "unused_variables" && help == "Did you mean `unused_variable`?")
+        );
+    }
+
+    #[test]
+    fn config_file_invalid_level() {
+        let source = "[lints]\nunused_variable = \"ignore\"\n";
+        let error = LintConfig::from_toml(source).unwrap_err();
+        assert!(matches!(error, LintConfigError::InvalidConfig(_)));
+    }
+}
@@ -1,7 +1,7 @@
 use camino::Utf8PathBuf;
 use clap::{Args, Parser, Subcommand};
-use miette::{miette, Result};
-use tracing::{debug, error, Level};
+use miette::Result;
+use tracing::{debug, Level};
 use tracing_subscriber::fmt::time;
 
 mod commands;
@@ -25,6 +25,41 @@ enum Command {
     Ast {
         /// The path to the file to run
         path: Utf8PathBuf,
+        #[clap(long, short)]
+        /// Print the tree after constant folding and other optimizations
+        optimize: bool,
+    },
+
+    /// Check the given file for errors and lint warnings, without running it
+    Check {
+        /// The path to the file to check
+        path: Utf8PathBuf,
+        #[clap(flatten)]
+        lints: LintOptions,
+    },
+
+    /// Serve the Debug Adapter Protocol on the standard input and output, for debugging programs in editors
+    Dap,
+
+    /// Run the given file in the interactive step debugger
+    Debug {
+        /// The path to the file to debug
+        path: Utf8PathBuf,
+    },
+
+    /// Print the bytecode listing of the given file: the constant pool, the variables, and the instructions with their source spans
+    Disasm {
+        /// The path to the file to disassemble
+        path: Utf8PathBuf,
+        #[clap(long, short)]
+        /// Show each source line before the instructions compiled from it
+        interleave: bool,
+    },
+
+    /// Print a long-form explanation of the given error code, e.g. E0101
+    Explain {
+        /// The error code to explain
+        code: String,
     },
 
     /// Run the lexer on the given file
@@ -36,16 +71,40 @@ enum Command {
         verbose: bool,
     },
 
+    /// Continue a program paused into the given snapshot file by `minipl run --snapshot`
+    Resume {
+        /// The path to the snapshot file
+        path: Utf8PathBuf,
+        #[clap(flatten)]
+        pause: PauseOptions,
+    },
+
     /// Run a given file with the interpreter
     Run {
         /// The path to the file to run
         path: Utf8PathBuf,
+        #[clap(flatten)]
+        lints: LintOptions,
+        #[clap(flatten)]
+        tools: Box<ToolOptions>,
+        /// Compile the program into bytecode and execute it on the virtual machine instead of the interpreter
+        #[clap(
+            long,
+            conflicts_with_all = &["trace", "profile", "coverage", "record", "replay", "snapshot"]
+        )]
+        bytecode: bool,
     },
 
-    /// Build a given file with the compiler. Not implemented yet.
+    /// Compile the given file into a program in the target language
     Build {
         /// The path to the file to build
         path: Utf8PathBuf,
+        /// The target language
+        #[clap(long, short, arg_enum, default_value = "c")]
+        target: Target,
+        /// The path of the output file. Defaults to the source path with the extension of the target
+        #[clap(long, short)]
+        output: Option<Utf8PathBuf>,
     },
 }
 
@@ -54,6 +113,9 @@ struct GlobalOpts {
     /// Debug tracing of the application flow
     #[clap(long, short, global = true)]
     debug: bool,
+    /// Output format of the error reports
+    #[clap(long, arg_enum, default_value = "human", global = true)]
+    message_format: MessageFormat,
 }
 
 fn main() -> Result<()> {
@@ -82,26 +144,69 @@ fn main() -> Result<()> {
     }
 
     match app.command {
-        Command::Ast { path } => {
+        Command::Ast { path, optimize } => {
             debug!("AST subcommand called");
             debug!("File path: {}", path);
-            ast(path)?;
+            ast(path, app.global_opts.message_format, optimize)?;
+        }
+        Command::Check { path, lints } => {
+            debug!("Check subcommand called");
+            debug!("File path: {}", path);
+            check(path, app.global_opts.message_format, &lints)?;
+        }
+        Command::Dap => {
+            debug!("Dap subcommand called");
+            dap()?;
+        }
+        Command::Debug { path } => {
+            debug!("Debug subcommand called");
+            debug!("File path: {}", path);
+            debug(path, app.global_opts.message_format)?;
+        }
+        Command::Disasm { path, interleave } => {
+            debug!("Disasm subcommand called");
+            debug!("File path: {}", path);
+            disasm(path, app.global_opts.message_format, interleave)?;
+        }
+        Command::Explain { code } => {
+            debug!("Explain subcommand called");
+            debug!("Error code: {}", code);
+            explain(&code)?;
         }
         Command::Lex { path, verbose } => {
             debug!("Lex subcommand called");
             debug!("File path: {}", path);
             lex(path, verbose)?;
         }
-        Command::Run { path } => {
+        Command::Resume { path, pause } => {
+            debug!("Resume subcommand called");
+            debug!("Snapshot path: {}", path);
+            resume(path, app.global_opts.message_format, &pause)?;
+        }
+        Command::Run {
+            path,
+            lints,
+            tools,
+            bytecode,
+        } => {
             debug!("Run subcommand called");
             debug!("File path: {}", path);
-            run(path)?;
+            run(
+                path,
+                app.global_opts.message_format,
+                &lints,
+                &tools,
+                bytecode,
+            )?;
         }
-        Command::Build { path } => {
+        Command::Build {
+            path,
+            target,
+            output,
+        } => {
             debug!("Build subcommand called");
             debug!("File path: {}", path);
-            error!("Compiler mode not implemented yet");
-            return Err(miette!("Compiler mode not implemented yet"));
+            build(path, app.global_opts.message_format, target, output)?;
         }
     }
 
@@ -0,0 +1,460 @@
+//! Optimization pass over the abstract syntax tree of a Mini-PL program.
+//!
+//! The [Optimizer] folds constant expressions, strips groupings, propagates the values of variables that are never reassigned,
+//! and replaces `for` loops that do nothing but count. The optimized program behaves exactly like the original one:
+//! an expression whose evaluation fails is left as is, so that the runtime error is still reported at the original location.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parsing::*;
+use crate::runtime::Object;
+use crate::tokens::{RawToken, Token};
+use crate::visitors::{Interpreter, Visitor};
+
+#[derive(Debug, Default)]
+/// [Optimizer] transforms a parsed program into an equivalent, simpler one
+pub struct Optimizer {
+    /// Variables that are declared once and never assigned afterwards
+    immutable: HashSet<String>,
+    /// The values of the immutable variables declared so far, as literal tokens
+    constants: HashMap<String, RawToken>,
+    /// The types of the variables declared so far
+    kinds: HashMap<String, VarType>,
+}
+
+impl Optimizer {
+    /// The primary function of the [Optimizer]: returns the optimized program
+    pub fn optimize(&mut self, statements: &[Statement]) -> Vec<Statement> {
+        let mut declarations = HashMap::new();
+        let mut written = HashSet::new();
+        collect_writes(statements, &mut declarations, &mut written);
+        self.immutable = declarations
+            .into_iter()
+            .filter(|(name, count)| *count == 1 && !written.contains(name))
+            .map(|(name, _)| name)
+            .collect();
+        self.constants.clear();
+        self.kinds.clear();
+        self.block(statements)
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Vec<Statement> {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Statement {
+        let stmt = match &statement.stmt {
+            Stmt::Assert(e) => Stmt::Assert(self.fold(e)),
+            Stmt::Expression(e) => Stmt::Expression(self.fold(e)),
+            Stmt::Print(e) => Stmt::Print(self.fold(e)),
+            Stmt::Read(name) => Stmt::Read(name.clone()),
+            Stmt::VariableDefinition(v) => {
+                let initializer = v.initializer.as_ref().map(|e| self.fold(e));
+                if self.immutable.contains(&v.name) {
+                    let value = match &initializer {
+                        Some(Expression {
+                            expr: Expr::Literal(l),
+                            ..
+                        }) => Some(l.value.token.clone()),
+                        Some(_) => None,
+                        None => default_value(&v.kind),
+                    };
+                    // A mismatching initializer is a runtime error, reported by the declaration itself
+                    if let Some(value) = value.filter(|value| has_kind(value, &v.kind)) {
+                        self.constants.insert(v.name.clone(), value);
+                    }
+                }
+                self.kinds.insert(v.name.clone(), v.kind.clone());
+                Stmt::VariableDefinition(Variable::new(
+                    &v.name,
+                    v.kind.clone(),
+                    initializer,
+                    v.span,
+                ))
+            }
+            Stmt::Forloop(f) => {
+                let left = self.fold(&f.left);
+                let right = f.right.as_ref().map(|right| self.fold(right));
+                let body = self.block(&f.body);
+                // NOTE: a range with start > end is a runtime error rather than an empty range, so such loops are kept.
+                // A loop with an empty body over a valid constant range only leaves the end value in the control variable.
+                if let (Some(start), Some(end), true) = (
+                    number(&left),
+                    right.as_ref().and_then(number),
+                    body.is_empty(),
+                ) {
+                    if start <= end && self.kinds.get(&f.variable) == Some(&VarType::Number) {
+                        let value = Expression::new(
+                            Expr::Literal(Literal::new(Token::new(RawToken::Number(end), f.span))),
+                            f.span,
+                        );
+                        let token = Token::new(RawToken::Assign, f.header);
+                        let assign = Expr::Assign(Assign::new(&f.variable, token, value));
+                        return Statement::new(
+                            Stmt::Expression(Expression::new(assign, f.span)),
+                            statement.span,
+                        );
+                    }
+                }
+                Stmt::Forloop(Forloop::new(
+                    &f.variable,
+                    left,
+                    right,
+                    body,
+                    f.header,
+                    f.span,
+                ))
+            }
+        };
+        Statement::new(stmt, statement.span)
+    }
+
+    fn fold(&mut self, expression: &Expression) -> Expression {
+        let span = expression.span;
+        let expr = match &expression.expr {
+            // The tree structure already encodes the grouping
+            Expr::Grouping(g) => return self.fold(&g.expression),
+            Expr::Literal(_) => return expression.clone(),
+            Expr::VariableUsage(name) => match self.constants.get(name) {
+                Some(value) => Expr::Literal(Literal::new(Token::new(value.clone(), span))),
+                None => Expr::VariableUsage(name.clone()),
+            },
+            Expr::Binary(b) => {
+                let (left, right) = (self.fold(&b.left), self.fold(&b.right));
+                if is_literal(&left) && is_literal(&right) {
+                    return evaluate(Expression::new(
+                        Expr::Binary(Binary::new(left, b.operator.clone(), right)),
+                        span,
+                    ));
+                }
+                Expr::Binary(Binary::new(left, b.operator.clone(), right))
+            }
+            Expr::Logical(l) => {
+                let (left, right) = (self.fold(&l.left), self.fold(&l.right));
+                // Short-circuiting: the right operand is never evaluated when the left one determines the result
+                let decided = match (&left.expr, &l.operator.token) {
+                    (Expr::Literal(literal), RawToken::And) => {
+                        literal.value.token == RawToken::False
+                    }
+                    (Expr::Literal(literal), RawToken::Or) => literal.value.token == RawToken::True,
+                    _ => false,
+                };
+                let constant = is_literal(&left) && is_literal(&right);
+                let folded = Expression::new(
+                    Expr::Logical(Logical::new(left, l.operator.clone(), right)),
+                    span,
+                );
+                if decided || constant {
+                    return evaluate(folded);
+                }
+                folded.expr
+            }
+            Expr::Unary(u) => {
+                let right = self.fold(&u.right);
+                let literal = is_literal(&right);
+                let folded =
+                    Expression::new(Expr::Unary(Unary::new(u.operator.clone(), right)), span);
+                if literal {
+                    return evaluate(folded);
+                }
+                folded.expr
+            }
+            Expr::Assign(a) => {
+                Expr::Assign(Assign::new(&a.name, a.token.clone(), self.fold(&a.value)))
+            }
+            Expr::AssignIndex(a) => Expr::AssignIndex(AssignIndex::new(
+                &a.name,
+                self.fold(&a.index),
+                a.token.clone(),
+                self.fold(&a.value),
+            )),
+            Expr::Call(c) => Expr::Call(Call::new(
+                &c.name,
+                c.arguments
+                    .iter()
+                    .map(|argument| self.fold(argument))
+                    .collect(),
+            )),
+            Expr::Index(i) => Expr::Index(Index::new(self.fold(&i.array), self.fold(&i.index))),
+            Expr::Interpolation(i) => Expr::Interpolation(Interpolation::new(
+                i.segments
+                    .iter()
+                    .map(|segment| match segment {
+                        InterpolationSegment::Expression(e) => {
+                            InterpolationSegment::Expression(self.fold(e))
+                        }
+                        literal => literal.clone(),
+                    })
+                    .collect(),
+            )),
+        };
+        Expression::new(expr, span)
+    }
+}
+
+/// Evaluates an expression with constant operands into a literal. If the evaluation fails,
+/// the expression is returned unchanged, so that the runtime error is reported at the same location.
+fn evaluate(expression: Expression) -> Expression {
+    let value = match Interpreter::new().visit_expression(&expression) {
+        Ok(Object::Number(n)) => RawToken::Number(n),
+        Ok(Object::Real(r)) => RawToken::RealNumber(r),
+        Ok(Object::Text(t)) => RawToken::Text(t),
+        Ok(Object::Boolean(true)) => RawToken::True,
+        Ok(Object::Boolean(false)) => RawToken::False,
+        _ => return expression,
+    };
+    let span = expression.span;
+    Expression::new(Expr::Literal(Literal::new(Token::new(value, span))), span)
+}
+
+fn is_literal(expression: &Expression) -> bool {
+    matches!(expression.expr, Expr::Literal(_))
+}
+
+fn number(expression: &Expression) -> Option<i64> {
+    match &expression.expr {
+        Expr::Literal(Literal {
+            value:
+                Token {
+                    token: RawToken::Number(n),
+                    ..
+                },
+        }) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Returns the default value of a variable of the given type as a literal token, if it has one
+fn default_value(kind: &VarType) -> Option<RawToken> {
+    match kind {
+        VarType::Boolean => Some(RawToken::False),
+        VarType::Number => Some(RawToken::Number(0)),
+        VarType::Real => Some(RawToken::RealNumber(0.0)),
+        VarType::Text => Some(RawToken::Text(String::new())),
+        VarType::Array(_, _) => None,
+    }
+}
+
+fn has_kind(value: &RawToken, kind: &VarType) -> bool {
+    matches!(
+        (value, kind),
+        (RawToken::True | RawToken::False, VarType::Boolean)
+            | (RawToken::Number(_), VarType::Number)
+            | (RawToken::RealNumber(_), VarType::Real)
+            | (RawToken::Text(_), VarType::Text)
+    )
+}
+
+/// Counts the declarations of each variable, and collects the variables that are assigned after their declaration
+fn collect_writes(
+    statements: &[Statement],
+    declarations: &mut HashMap<String, usize>,
+    written: &mut HashSet<String>,
+) {
+    for statement in statements {
+        match &statement.stmt {
+            Stmt::Assert(e) | Stmt::Expression(e) | Stmt::Print(e) => expression_writes(e, written),
+            Stmt::Read(name) => {
+                written.insert(name.clone());
+            }
+            Stmt::VariableDefinition(v) => {
+                if let Some(initializer) = &v.initializer {
+                    expression_writes(initializer, written);
+                }
+                *declarations.entry(v.name.clone()).or_default() += 1;
+            }
+            Stmt::Forloop(f) => {
+                written.insert(f.variable.clone());
+                expression_writes(&f.left, written);
+                if let Some(right) = &f.right {
+                    expression_writes(right, written);
+                }
+                collect_writes(&f.body, declarations, written);
+            }
+        }
+    }
+}
+
+fn expression_writes(expression: &Expression, written: &mut HashSet<String>) {
+    match &expression.expr {
+        Expr::Assign(a) => {
+            written.insert(a.name.clone());
+            expression_writes(&a.value, written);
+        }
+        Expr::AssignIndex(a) => {
+            written.insert(a.name.clone());
+            expression_writes(&a.index, written);
+            expression_writes(&a.value, written);
+        }
+        Expr::Binary(b) => {
+            expression_writes(&b.left, written);
+            expression_writes(&b.right, written);
+        }
+        Expr::Call(c) => {
+            for argument in &c.arguments {
+                expression_writes(argument, written);
+            }
+        }
+        Expr::Grouping(g) => expression_writes(&g.expression, written),
+        Expr::Index(i) => {
+            expression_writes(&i.array, written);
+            expression_writes(&i.index, written);
+        }
+        Expr::Interpolation(i) => {
+            for segment in &i.segments {
+                if let InterpolationSegment::Expression(e) = segment {
+                    expression_writes(e, written);
+                }
+            }
+        }
+        Expr::Logical(l) => {
+            expression_writes(&l.left, written);
+            expression_writes(&l.right, written);
+        }
+        Expr::Unary(u) => expression_writes(&u.right, written),
+        Expr::Literal(_) | Expr::VariableUsage(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::lexing::Lexer;
+    use crate::runtime::RuntimeError;
+    use crate::span::StartEndSpan;
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let tokens = Lexer::new(source).scan().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    fn optimize(source: &str) -> Vec<Statement> {
+        Optimizer::default().optimize(&parse(source))
+    }
+
+    fn printed(statement: &Statement) -> &Expression {
+        match &statement.stmt {
+            Stmt::Print(e) => e,
+            other => panic!("expected a print statement, got {other:?}"),
+        }
+    }
+
+    fn literal(expression: &Expression) -> &RawToken {
+        match &expression.expr {
+            Expr::Literal(l) => &l.value.token,
+            other => panic!("expected a literal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn folds_arithmetic() {
+        let statements = optimize("print 1 + 2 * 3;");
+        let expression = printed(&statements[0]);
+        assert_eq!(literal(expression), &RawToken::Number(7));
+        assert_eq!(expression.span, StartEndSpan::new(6, 15));
+    }
+
+    #[test]
+    fn folds_unary_and_logical() {
+        let statements = optimize("print -(2 * 3);\nprint !(1 < 2) | true;\nprint -1.5;");
+        assert_eq!(literal(printed(&statements[0])), &RawToken::Number(-6));
+        assert_eq!(literal(printed(&statements[1])), &RawToken::True);
+        assert_eq!(
+            literal(printed(&statements[2])),
+            &RawToken::RealNumber(-1.5)
+        );
+    }
+
+    #[test]
+    fn short_circuits_logical() {
+        let statements = optimize("var b : bool;\nread b;\nprint false & b;\nprint b | true;");
+        assert_eq!(literal(printed(&statements[2])), &RawToken::False);
+        assert!(matches!(printed(&statements[3]).expr, Expr::Logical(_)));
+    }
+
+    #[test]
+    fn strips_groupings() {
+        let statements = optimize("var x : int;\nread x;\nprint ((x)) * (x + 1);");
+        let Expr::Binary(b) = &printed(&statements[2]).expr else {
+            panic!("expected a binary expression");
+        };
+        assert!(matches!(b.left.expr, Expr::VariableUsage(_)));
+        assert!(matches!(b.right.expr, Expr::Binary(_)));
+    }
+
+    #[test]
+    fn keeps_division_by_zero() {
+        let source = "print 1 + 10 / (5 - 5);";
+        let original = Interpreter::new().eval(&parse(source)).unwrap_err();
+        let optimized = optimize(source);
+        let Expr::Binary(b) = &printed(&optimized[0]).expr else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(literal(&b.left), &RawToken::Number(1));
+        let error = Interpreter::new().eval(&optimized).unwrap_err();
+        match (original, error) {
+            (RuntimeError::DivisionByZero(l1, r1), RuntimeError::DivisionByZero(l2, r2)) => {
+                assert_eq!((l1, r1), (l2, r2));
+            }
+            other => panic!("expected division by zero errors, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn propagates_constants() {
+        let source = "var n : int := 2 + 3;\nvar s : string;\nprint n * 2;\nprint s + \"!\";";
+        let statements = optimize(source);
+        assert_eq!(literal(printed(&statements[2])), &RawToken::Number(10));
+        assert_eq!(
+            literal(printed(&statements[3])),
+            &RawToken::Text("!".to_owned())
+        );
+    }
+
+    #[test]
+    fn does_not_propagate_reassigned_variables() {
+        let source = "var n : int := 1;\nvar m : int := 1;\nvar i : int;\nprint n;\nn := 2;\nread m;\nfor i in 1..2 do\nprint i;\nend for;\nprint m + i;";
+        let statements = optimize(source);
+        assert!(matches!(
+            printed(&statements[3]).expr,
+            Expr::VariableUsage(_)
+        ));
+        assert!(matches!(printed(&statements[7]).expr, Expr::Binary(_)));
+    }
+
+    #[test]
+    fn does_not_propagate_mismatching_initializer() {
+        let statements = optimize("var n : int := \"one\";\nprint n;");
+        assert!(matches!(
+            printed(&statements[1]).expr,
+            Expr::VariableUsage(_)
+        ));
+    }
+
+    #[test]
+    fn replaces_empty_loop() {
+        let statements = optimize("var i : int;\nfor i in 1..1 + 2 do\nend for;\nprint i;");
+        assert_eq!(statements.len(), 3);
+        let Stmt::Expression(Expression {
+            expr: Expr::Assign(a),
+            ..
+        }) = &statements[1].stmt
+        else {
+            panic!("expected an assignment");
+        };
+        assert_eq!(literal(&a.value), &RawToken::Number(3));
+    }
+
+    #[test]
+    fn keeps_reversed_range() {
+        let source = "var i : int;\nfor i in 3..1 do\nend for;";
+        let statements = optimize(source);
+        assert!(matches!(statements[1].stmt, Stmt::Forloop(_)));
+        let error = Interpreter::new().eval(&statements).unwrap_err();
+        assert!(matches!(error, RuntimeError::ForEndLarger(_, _)));
+    }
+}
@@ -1,5 +1,4 @@
-use std::iter::Peekable;
-use std::vec::IntoIter;
+use std::fmt::Debug;
 
 use tracing::debug;
 
@@ -18,34 +17,191 @@ pub(crate) use variable::*;
 mod errors;
 pub use errors::ParseError;
 
+use crate::lexing::{Lexer, UnrecoverableLexingError, KEYWORDS};
+use crate::runtime::builtins;
 use crate::span::StartEndSpan;
+use crate::suggestions::{self, Suggestion};
 use crate::tokens::RawToken::{
-    self, And, Assert, Bang, Bool, Colon, End, Equal, False, For, Identifier, Int, Less, Minus,
-    Number, ParenLeft, ParenRight, Plus, Print, Range, Read, Semicolon, Slash, Star, Text, True,
-    Var,
+    self, And, Array, Assert, Bang, Bool, BracketLeft, BracketRight, Colon, Comma, End, Equal,
+    False, For, Greater, GreaterEqual, Identifier, Int, Less, LessEqual, Minus, NotEqual, Number,
+    Of, Or, ParenLeft, ParenRight, Plus, Print, Range, Read, Real, RealNumber, Semicolon, Slash,
+    Star, Text, True, Var,
 };
-use crate::tokens::Token;
+use crate::tokens::{InterpolationPart, Token};
 use errors::ParseError::*;
 
+/// The tokens for the parser to pull, either from a [`Vec`] or lazily from a [`Lexer`]
+struct TokenStream<'a> {
+    tokens: Box<dyn Iterator<Item = Result<Token, UnrecoverableLexingError>> + 'a>,
+    /// Whether to skip ignorable tokens and set aside lexing errors, for tokens straight from the lexer
+    filter: bool,
+    /// The recoverable lexing errors skipped so far
+    lexing_errors: Vec<Token>,
+    /// The unrecoverable lexing error that ended the stream, if any
+    error: Option<UnrecoverableLexingError>,
+    /// Whether a lexing error was set aside right before the token pulled last
+    after_error: bool,
+    peeked: Option<Token>,
+}
+
+impl<'a> TokenStream<'a> {
+    fn from_lexer(lexer: Lexer<'a>) -> Self {
+        Self {
+            tokens: Box::new(lexer),
+            filter: true,
+            lexing_errors: Vec::new(),
+            error: None,
+            after_error: false,
+            peeked: None,
+        }
+    }
+
+    /// Returns the next token without consuming it
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = self.pull();
+        }
+        self.peeked.as_ref()
+    }
+
+    /// Consumes and returns the next token if it satisfies the predicate
+    fn next_if(&mut self, predicate: impl FnOnce(&Token) -> bool) -> Option<Token> {
+        match self.peek() {
+            Some(token) if predicate(token) => self.next(),
+            _ => None,
+        }
+    }
+
+    /// Pulls the next token to parse from the underlying iterator
+    fn pull(&mut self) -> Option<Token> {
+        self.after_error = false;
+        loop {
+            let token = match self.tokens.next()? {
+                Ok(token) => token,
+                Err(error) => {
+                    self.error = Some(error);
+                    return None;
+                }
+            };
+            if !self.filter {
+                return Some(token);
+            }
+            match token.token {
+                RawToken::Whitespace | RawToken::Comment => (),
+                RawToken::Error(_) => {
+                    self.lexing_errors.push(token);
+                    self.after_error = true;
+                }
+                _ => return Some(token),
+            }
+        }
+    }
+
+    /// Pulls the rest of the tokens, setting aside their lexing errors
+    fn drain(&mut self) {
+        while self.pull().is_some() {}
+    }
+}
+
+impl From<Vec<Token>> for TokenStream<'_> {
+    fn from(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens: Box::new(tokens.into_iter().map(Ok)),
+            filter: false,
+            lexing_errors: Vec::new(),
+            error: None,
+            after_error: false,
+            peeked: None,
+        }
+    }
+}
+
+impl Iterator for TokenStream<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peeked.take().or_else(|| self.pull())
+    }
+}
+
+impl Debug for TokenStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenStream")
+            .field("filter", &self.filter)
+            .field("lexing_errors", &self.lexing_errors)
+            .field("error", &self.error)
+            .field("after_error", &self.after_error)
+            .field("peeked", &self.peeked)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 /// The parser for the Mini-PL programming language
-pub struct Parser {
-    tokens: Peekable<IntoIter<Token>>,
+pub struct Parser<'a> {
+    tokens: TokenStream<'a>,
+    /// Control variables of the `for` loops currently being parsed, with the spans of their loop headers
+    for_variables: Vec<(String, StartEndSpan)>,
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     #[must_use]
     /// Initializes a parser with the given [`Vec`] of [`Token`]s
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
-            tokens: tokens.into_iter().peekable(),
+            tokens: TokenStream::from(tokens),
+            for_variables: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    /// Initializes a parser pulling its tokens lazily from the given [`Lexer`], so that the tokens of the whole source are never in memory at once.
+    /// Ignorable tokens are skipped, and recoverable lexing errors are set aside, see [`Parser::lexing_errors`]
+    pub fn from_lexer(lexer: Lexer<'a>) -> Self {
+        Self {
+            tokens: TokenStream::from_lexer(lexer),
+            for_variables: Vec::new(),
         }
     }
 
     /// Parses the tokens, returning [`Vec<Statement>`] or [`ParseError`]
+    ///
+    /// When parsing from a [`Lexer`], the rest of the source is still lexed after a parse error, so that all of its lexing errors
+    /// are found. A parse error directly following a skipped lexing error is caused by the skipped token, and is left to the lexing
+    /// error to explain: the statements parsed before it are returned instead. The program is only valid if there are no
+    /// [`Parser::lexing_errors`] either
+    ///
+    /// # Errors
+    /// Returns the first [`ParseError`] found. When parsing from a [`Lexer`], an unrecoverable lexing error is returned as [`ParseError::Lexing`]
     pub fn parse(&mut self) -> Result<Vec<Statement>, ParseError> {
-        // Collect a list of declarations
-        let mut declarations: Vec<Statement> = Vec::new();
+        let mut statements = Vec::new();
+        let result = self.declarations(&mut statements);
+        // A failure to lex explains any parse error caused by the tokens ending early
+        if let Some(error) = self.tokens.error.take() {
+            return Err(Lexing(error));
+        }
+        match result {
+            Ok(()) => Ok(statements),
+            Err(error) => {
+                let after_error = self.tokens.after_error;
+                self.tokens.drain();
+                if after_error {
+                    Ok(statements)
+                } else {
+                    Err(error)
+                }
+            }
+        }
+    }
+
+    /// The recoverable lexing errors found in the tokens pulled from the [`Lexer`]
+    #[must_use]
+    pub fn lexing_errors(&self) -> &[Token] {
+        &self.tokens.lexing_errors
+    }
+
+    /// Internal helper: collects the declarations into the given list, up to the first [`ParseError`]
+    fn declarations(&mut self, declarations: &mut Vec<Statement>) -> Result<(), ParseError> {
         while let Some(token) = self.tokens.peek() {
             // TODO: better handling
             if token.tokentype() == RawToken::EOF {
@@ -57,7 +213,7 @@ impl Parser {
             declarations.push(declaration);
         }
 
-        Ok(declarations)
+        Ok(())
     }
 
     /// Internal helper: returns the peeked next token, or an OutOfTokens error
@@ -95,6 +251,12 @@ impl Parser {
             .next_if(|token| &token.tokentype() == tokentype1 || &token.tokentype() == tokentype2)
     }
 
+    /// Internal helper: if the next token matches any of the given types, returns it and consumes it from the iterator. If the next one does not match, does not consume it and returns None
+    fn next_if_tokentypes(&mut self, tokentypes: &[RawToken]) -> Option<Token> {
+        self.tokens
+            .next_if(|token| tokentypes.contains(&token.tokentype()))
+    }
+
     /// Internal helper: expect the next token to be a Semicolon, and consume it, or return a MissingSemicolon error with the given span
     fn expect_semicolon(&mut self, span: StartEndSpan) -> Result<(), ParseError> {
         // get semicolon
@@ -105,6 +267,18 @@ impl Parser {
         Err(MissingSemicolon(span.into()))
     }
 
+    /// Internal helper: returns a ForVariableModified error if the given name is the control variable of an enclosing `for` loop
+    fn check_for_variable(&self, name: &str, span: StartEndSpan) -> Result<(), ParseError> {
+        match self.for_variables.iter().find(|(n, _)| n == name) {
+            Some((_, header)) => Err(ForVariableModified(
+                name.to_owned(),
+                (*header).into(),
+                span.into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
     fn declaration(&mut self) -> Result<Statement, ParseError> {
         let next = self.maybe_peek()?;
         match next.tokentype() {
@@ -129,6 +303,10 @@ impl Parser {
                 next.span.into(),
             ));
         };
+        self.check_for_variable(
+            &identifier,
+            StartEndSpan::new(var.span.start, next.span.end),
+        )?;
 
         // require type annotation colon
         let next = self.maybe_next()?;
@@ -140,18 +318,7 @@ impl Parser {
         }
 
         // get type annotation
-        let next = self.maybe_next()?;
-        let kind = match next.tokentype() {
-            Bool => VarType::Boolean,
-            Int => VarType::Number,
-            RawToken::String => VarType::Text,
-            _ => {
-                return Err(ExpectedTypeAnnotation(
-                    format!("{:?}", next.token),
-                    next.span.into(),
-                ));
-            }
-        };
+        let kind = self.var_type()?;
 
         // optional assignment
         let next = self.maybe_next()?;
@@ -192,6 +359,60 @@ impl Parser {
         }
     }
 
+    fn var_type(&mut self) -> Result<VarType, ParseError> {
+        let next = self.maybe_next()?;
+        match next.tokentype() {
+            Bool => Ok(VarType::Boolean),
+            Int => Ok(VarType::Number),
+            Real => Ok(VarType::Real),
+            RawToken::String => Ok(VarType::Text),
+            Array => {
+                // array[size] of type
+                let bracket = self.maybe_next()?;
+                if !matches!(bracket.tokentype(), BracketLeft) {
+                    return Err(ExpectedArraySize(
+                        format!("{:?}", bracket.token),
+                        bracket.span.into(),
+                    ));
+                }
+                let next = self.maybe_next()?;
+                let size = match next.tokentype() {
+                    Number(n) if n > 0 => usize::try_from(n).unwrap_or(usize::MAX),
+                    _ => {
+                        return Err(ExpectedArraySize(
+                            format!("{:?}", next.token),
+                            next.span.into(),
+                        ))
+                    }
+                };
+                if size > MAX_ARRAY_SIZE {
+                    return Err(ArraySizeTooLarge(size, MAX_ARRAY_SIZE, next.span.into()));
+                }
+                if self.next_if_tokentype(&BracketRight).is_none() {
+                    return Err(MissingBracket(bracket.span.into()));
+                }
+                let next = self.maybe_next()?;
+                if !matches!(next.tokentype(), Of) {
+                    return Err(ExpectedOf(format!("{:?}", next.token), next.span.into()));
+                }
+                // Only arrays of the basic types are supported
+                let next = self.maybe_peek()?;
+                if matches!(next.tokentype(), Array) {
+                    return Err(ExpectedTypeAnnotation(
+                        format!("{:?}", next.token),
+                        next.span.into(),
+                    ));
+                }
+                let kind = self.var_type()?;
+                Ok(VarType::Array(Box::new(kind), size))
+            }
+            _ => Err(ExpectedTypeAnnotation(
+                format!("{:?}", next.token),
+                next.span.into(),
+            )),
+        }
+    }
+
     fn statement(&mut self) -> Result<Statement, ParseError> {
         let next = self.maybe_peek()?;
 
@@ -238,10 +459,14 @@ impl Parser {
 
         // left expr
         let left = self.expression()?;
-        // range literal
+        // range literal and right expr, or an array to iterate over
         let next = self.maybe_next()?;
-        match next.tokentype() {
-            Range => (),
+        let (right, next) = match next.tokentype() {
+            Range => {
+                let right = self.expression()?;
+                (Some(right), self.maybe_next()?)
+            }
+            RawToken::Do => (None, next),
             _ => {
                 return Err(ForMissingRange(
                     format!("{:?}", next.token),
@@ -250,18 +475,26 @@ impl Parser {
             }
         };
 
-        // right expr
-        let right = self.expression()?;
         // do keyword
-        let next = self.maybe_next()?;
         match next.tokentype() {
             RawToken::Do => (),
             _ => return Err(ForMissingDo(format!("{:?}", next.token), next.span.into())),
         };
+        let header = StartEndSpan::new(start.span.start, next.span.end);
 
-        // loop body
+        // NOTE: "The for control variable behaves like a constant inside the loop: it cannot be assigned another value (before exiting the for statement)"
+        // This also forbids reusing the control variable of an enclosing loop
+        self.check_for_variable(&name, header)?;
+        self.for_variables.push((name.clone(), header));
+
+        // loop body, which may be empty
         let mut body = Vec::new();
-        while let Some(next) = self.tokens.peek() {
+        let end = loop {
+            let next = match self.tokens.peek() {
+                Some(next) if next.tokentype() != RawToken::EOF => next,
+                // Ran out of tokens before `end for`
+                _ => return Err(OutOfTokens(header.into())),
+            };
             // Have we found the end?
             if matches!(next.tokentype(), End) {
                 // consume the end token
@@ -272,7 +505,7 @@ impl Parser {
                     For => {
                         // expect to find semicolon
                         self.expect_semicolon(next.span)?;
-                        break;
+                        break next;
                     }
                     _ => return Err(EndMissingFor(format!("{:?}", next.token), next.span.into())),
                 }
@@ -280,14 +513,12 @@ impl Parser {
             // Otherwise, parse full declarations into the loop body
             let declaration = self.declaration()?;
             body.push(declaration);
-        }
+        };
+        self.for_variables.pop();
 
-        let last = body
-            .last()
-            .ok_or_else(|| OutOfTokens(StartEndSpan::new(0, 0).into()))?;
-        let span = StartEndSpan::new(start.span.start, last.span.end);
+        let span = StartEndSpan::new(start.span.start, end.span.end);
         Ok(Statement::new(
-            Stmt::Forloop(Forloop::new(&name, left, right, body, span)),
+            Stmt::Forloop(Forloop::new(&name, left, right, body, header, span)),
             span,
         ))
     }
@@ -316,29 +547,78 @@ impl Parser {
             }
             _ => return Err(ReadToNonVariable(format!("{:?}", next.token), span.into())),
         };
+        self.check_for_variable(&name, span)?;
         self.expect_semicolon(expr.span)?;
         Ok(Statement::new(Stmt::Read(name), span))
     }
 
     fn epxr_statement(&mut self) -> Result<Statement, ParseError> {
         let expr = self.expression()?;
+        self.check_misspelled_keyword(&expr)?;
         self.expect_semicolon(expr.span)?;
         Ok(Statement::new(Stmt::Expression(expr.clone()), expr.span))
     }
 
+    /// Internal helper: recognizes an `identifier expression;` statement, or a statement-level call to an unknown function,
+    /// where the identifier looks like a misspelled statement keyword e.g. `pritn x;`, and returns a MisspelledKeyword error
+    fn check_misspelled_keyword(&mut self, expr: &Expression) -> Result<(), ParseError> {
+        let name = match &expr.expr {
+            Expr::VariableUsage(name) if self.tokens.peek().is_some_and(starts_expression) => name,
+            Expr::Call(call) if !builtins::NAMES.contains(&call.name.as_str()) => &call.name,
+            _ => return Ok(()),
+        };
+        let keywords = KEYWORDS
+            .iter()
+            .filter(|(_, token)| matches!(token, Assert | For | Print | Read | Var))
+            .map(|(keyword, _)| *keyword);
+        match suggestions::closest(name, keywords) {
+            Some(keyword) => {
+                // The statement starts with the identifier
+                let span =
+                    StartEndSpan::new(expr.span.start, expr.span.start + name.chars().count());
+                Err(MisspelledKeyword(
+                    name.clone(),
+                    keyword.to_owned(),
+                    span.into(),
+                    Suggestion::new(keyword, span),
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
     fn expression(&mut self) -> Result<Expression, ParseError> {
         self.assignment()
     }
 
     fn assignment(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.and()?;
+        let mut expr = self.or()?;
         let spanstart = expr.span.start;
         // Handle repeated assigns // TODO: does this actually make sense? x = y = 2 or similar
         while let Some(assign) = self.next_if_tokentype(&RawToken::Assign) {
-            let right = self.and()?;
+            let right = self.or()?;
+            let span = StartEndSpan::new(spanstart, right.span.end);
             // TODO: better name getter
-            let name = match expr.expr {
-                Expr::VariableUsage(s) => s,
+            expr = match expr.expr {
+                Expr::VariableUsage(name) => {
+                    self.check_for_variable(&name, span)?;
+                    Expression::new(Expr::Assign(Assign::new(&name, assign, right)), span)
+                }
+                Expr::Index(Index { array, index }) => match array.expr {
+                    Expr::VariableUsage(name) => {
+                        self.check_for_variable(&name, span)?;
+                        Expression::new(
+                            Expr::AssignIndex(AssignIndex::new(&name, *index, assign, right)),
+                            span,
+                        )
+                    }
+                    _ => {
+                        return Err(AssignToNonVariable(
+                            format!("{:?}", array.expr),
+                            array.span.into(),
+                        ))
+                    }
+                },
                 _ => {
                     return Err(AssignToNonVariable(
                         format!("{:?}", expr.expr),
@@ -346,12 +626,17 @@ impl Parser {
                     ))
                 }
             };
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.and()?;
+        let spanstart = expr.span.start;
+        while let Some(operator) = self.next_if_tokentype(&Or) {
+            let right = self.and()?;
             expr = Expression::new(
-                Expr::Assign(crate::parsing::expression::Assign::new(
-                    &name,
-                    assign,
-                    right.clone(),
-                )),
+                Expr::Logical(Logical::new(expr, operator, right.clone())),
                 StartEndSpan::new(spanstart, right.span.end),
             );
         }
@@ -374,7 +659,7 @@ impl Parser {
     fn equality(&mut self) -> Result<Expression, ParseError> {
         let mut expr = self.comparison()?;
         let spanstart = expr.span.start;
-        while let Some(operator) = self.next_if_tokentype(&Equal) {
+        while let Some(operator) = self.next_if_tokentypes(&[Equal, NotEqual]) {
             let right = self.comparison()?;
             expr = Expression::new(
                 Expr::Binary(Binary::new(expr, operator, right.clone())),
@@ -387,7 +672,9 @@ impl Parser {
     fn comparison(&mut self) -> Result<Expression, ParseError> {
         let mut expr = self.term()?;
         let spanstart = expr.span.start;
-        while let Some(operator) = self.next_if_tokentype(&Less) {
+        while let Some(operator) =
+            self.next_if_tokentypes(&[Less, LessEqual, Greater, GreaterEqual])
+        {
             let right = self.term()?;
             expr = Expression::new(
                 Expr::Binary(Binary::new(expr, operator, right.clone())),
@@ -434,18 +721,109 @@ impl Parser {
             ));
         }
 
-        self.primary()
+        self.index()
+    }
+
+    fn index(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.primary()?;
+        let spanstart = expr.span.start;
+        while let Some(bracket) = self.next_if_tokentype(&BracketLeft) {
+            let index = self.expression()?;
+            match self.next_if_tokentype(&BracketRight) {
+                Some(end) => {
+                    expr = Expression::new(
+                        Expr::Index(Index::new(expr, index)),
+                        StartEndSpan::new(spanstart, end.span.end),
+                    );
+                }
+                None => return Err(MissingBracket(bracket.span.into())),
+            }
+        }
+        Ok(expr)
+    }
+
+    fn interpolation(
+        &mut self,
+        parts: Vec<InterpolationPart>,
+        span: StartEndSpan,
+    ) -> Result<Expression, ParseError> {
+        let mut segments = Vec::new();
+        for part in parts {
+            match part {
+                InterpolationPart::Literal(text) => {
+                    segments.push(InterpolationSegment::Literal(text));
+                }
+                InterpolationPart::Tokens(tokens) => {
+                    // Parse the embedded tokens in place of the outer ones, keeping the rest of the parser state
+                    let outer = std::mem::replace(&mut self.tokens, TokenStream::from(tokens));
+                    let result = self.embedded_expression();
+                    self.tokens = outer;
+                    segments.push(InterpolationSegment::Expression(result?));
+                }
+            }
+        }
+        Ok(Expression::new(
+            Expr::Interpolation(Interpolation::new(segments)),
+            span,
+        ))
+    }
+
+    fn embedded_expression(&mut self) -> Result<Expression, ParseError> {
+        let expr = self.expression()?;
+        // The embedded token stream ends with an EOF at the closing brace
+        let next = self.maybe_next()?;
+        match next.tokentype() {
+            RawToken::EOF => Ok(expr),
+            _ => Err(ExpectedClosingBrace(
+                format!("{:?}", next.token),
+                next.span.into(),
+            )),
+        }
+    }
+
+    fn call(&mut self, name: &str, start: StartEndSpan) -> Result<Expression, ParseError> {
+        // consume the left paren
+        let paren = self.maybe_next()?;
+        let mut arguments = Vec::new();
+        if let Some(end) = self.next_if_tokentype(&ParenRight) {
+            return Ok(Expression::new(
+                Expr::Call(Call::new(name, arguments)),
+                StartEndSpan::new(start.start, end.span.end),
+            ));
+        }
+        loop {
+            arguments.push(self.expression()?);
+            if self.next_if_tokentype(&Comma).is_some() {
+                continue;
+            }
+            match self.next_if_tokentype(&ParenRight) {
+                Some(end) => {
+                    return Ok(Expression::new(
+                        Expr::Call(Call::new(name, arguments)),
+                        StartEndSpan::new(start.start, end.span.end),
+                    ))
+                }
+                None => return Err(MissingParen(paren.span.into())),
+            }
+        }
     }
 
     fn primary(&mut self) -> Result<Expression, ParseError> {
         // At a terminal value, we need to always consume the token
         let next = self.maybe_next()?;
         match next.tokentype() {
-            False | True | Number(_) | Text(_) => Ok(Expression::new(
+            False | True | Number(_) | RealNumber(_) | Text(_) => Ok(Expression::new(
                 Expr::Literal(Literal::new(next.clone())),
                 next.span,
             )),
-            Identifier(name) => Ok(Expression::new(Expr::VariableUsage(name), next.span)),
+            RawToken::Interpolation(parts) => self.interpolation(parts, next.span),
+            Identifier(name) => {
+                // Builtin function call
+                if matches!(self.tokens.peek(), Some(token) if token.tokentype() == ParenLeft) {
+                    return self.call(&name, next.span);
+                }
+                Ok(Expression::new(Expr::VariableUsage(name), next.span))
+            }
             ParenLeft => {
                 let expr = self.expression()?;
                 if let Some(_token) = self.next_if_tokentype(&ParenRight) {
@@ -465,6 +843,21 @@ impl Parser {
     }
 }
 
+/// Internal helper: returns true if the token can start an expression on its own, e.g. a literal or an identifier
+fn starts_expression(token: &Token) -> bool {
+    matches!(
+        token.token,
+        Identifier(_)
+            | Number(_)
+            | RealNumber(_)
+            | Text(_)
+            | RawToken::Interpolation(_)
+            | True
+            | False
+            | Bang
+    )
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
@@ -508,4 +901,57 @@ mod tests {
         );
         assert_eq!(parsed[0], expected);
     }
+
+    #[test]
+    fn array_size_limit() {
+        let declaration = |size: usize| format!("var a : array[{size}] of int;");
+        let parse = |source: &str| Parser::new(Lexer::new(source).scan().unwrap()).parse();
+        assert!(parse(&declaration(MAX_ARRAY_SIZE)).is_ok());
+        assert!(matches!(
+            parse(&declaration(MAX_ARRAY_SIZE + 1)),
+            Err(ArraySizeTooLarge(_, MAX_ARRAY_SIZE, _))
+        ));
+    }
+
+    #[test]
+    fn from_lexer_skips_ignorables() {
+        let source = "var x : int := 1; // one\nprint x;";
+        let lazy = Parser::from_lexer(Lexer::new(source)).parse().unwrap();
+        let tokens = Lexer::new(source).scan().unwrap();
+        assert_eq!(lazy, Parser::new(tokens).parse().unwrap());
+    }
+
+    #[test]
+    fn from_lexer_sets_aside_lexing_errors() {
+        let mut parser = Parser::from_lexer(Lexer::new("print 1; $ print 2;"));
+        assert_eq!(parser.parse().unwrap().len(), 2);
+        let errors = parser.lexing_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, StartEndSpan::new(9, 10));
+    }
+
+    #[test]
+    fn from_lexer_finds_lexing_errors_after_parse_errors() {
+        let mut parser = Parser::from_lexer(Lexer::new("print 1 +;\nprint $;"));
+        assert!(matches!(parser.parse(), Err(ExpectedExpression(..))));
+        assert_eq!(parser.lexing_errors().len(), 1);
+    }
+
+    #[test]
+    fn from_lexer_leaves_parse_errors_at_lexing_errors_to_them() {
+        let source = "print 99999999999999999999;\nprint 1;\nprint $;";
+        let mut parser = Parser::from_lexer(Lexer::new(source));
+        assert_eq!(parser.parse().unwrap().len(), 0);
+        let spans: Vec<StartEndSpan> = parser.lexing_errors().iter().map(|t| t.span).collect();
+        assert_eq!(
+            spans,
+            vec![StartEndSpan::new(6, 26), StartEndSpan::new(43, 44)]
+        );
+    }
+
+    #[test]
+    fn from_lexer_reports_read_errors() {
+        let mut parser = Parser::from_lexer(Lexer::from_reader(&b"print \"\xff"[..]));
+        assert!(matches!(parser.parse(), Err(Lexing(_))));
+    }
 }
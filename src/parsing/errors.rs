@@ -3,64 +3,148 @@
 use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
+use crate::lexing::UnrecoverableLexingError;
+use crate::suggestions::Suggestion;
+
 #[derive(Error, Debug, Diagnostic)]
-#[error("Parse error")]
-#[diagnostic()]
 pub enum ParseError {
+    #[error("Missing closing parenthesis")]
+    #[diagnostic(code(E0201))]
     MissingParen(#[label = "Expected ) after this grouping"] SourceSpan),
+    #[error("Missing closing bracket")]
+    #[diagnostic(code(E0202))]
+    MissingBracket(#[label = "Expected ] after this index"] SourceSpan),
+    #[error("Missing closing brace in embedded expression")]
+    #[diagnostic(
+        code(E0203),
+        help("Embedded expressions in strings are written as \"text {{expression}} text\"")
+    )]
+    ExpectedClosingBrace(
+        String,
+        #[label = "Expected }} after the embedded expression, found token {0}"] SourceSpan,
+    ),
+    #[error("Expected an array size")]
+    #[diagnostic(code(E0204), help("Usage: var name : array[size] of type"))]
+    ExpectedArraySize(
+        String,
+        #[label = "Expected [ and a positive array size, found token {0}"] SourceSpan,
+    ),
+    #[error("Array size too large")]
+    #[diagnostic(code(E0222), help("Arrays can have at most {1} elements"))]
+    ArraySizeTooLarge(usize, usize, #[label = "Array of {0} elements"] SourceSpan),
+    #[error("Expected `of` in array type")]
+    #[diagnostic(code(E0205), help("Usage: var name : array[size] of type"))]
+    ExpectedOf(
+        String,
+        #[label = "Expected keyword `of`, found token {0}"] SourceSpan,
+    ),
+    #[error("Expected an expression")]
+    #[diagnostic(code(E0206))]
     ExpectedExpression(
         String,
         #[label = "Expected expression, found token {0}"] SourceSpan,
     ),
+    #[error("Expected an identifier")]
+    #[diagnostic(code(E0207))]
     ExpectedIdentifier(
         String,
         #[label = "Expected identifier, found token {0}"] SourceSpan,
     ),
+    #[error("Expected a type annotation")]
+    #[diagnostic(code(E0208))]
     ExpectedTypeAnnotation(
         String,
         #[label = "Expected identifier, found token {0}"] SourceSpan,
     ),
+    #[error("Expected an assignment")]
+    #[diagnostic(code(E0209))]
     ExpectedAssignFoundToken(
         String,
         #[label = "Expected assignment operator :=, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: variable_name := new_value"))]
+    #[error("Invalid assignment target")]
+    #[diagnostic(code(E0210), help("Usage: variable_name := new_value"))]
     AssignToNonVariable(
         String,
         #[label = "Expected assignment to variable, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: read variable_name"))]
+    #[error("Invalid read target")]
+    #[diagnostic(code(E0211), help("Usage: read variable_name"))]
     ReadToNonVariable(
         String,
         #[label = "Expected read to variable, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Use the assignment operator := instead of = for declaring a variable"))]
+    #[error("Expected `:=`, found `=`")]
+    #[diagnostic(
+        code(E0212),
+        help("Use the assignment operator := instead of = for declaring a variable")
+    )]
     ExpectedAssignFoundEqual(#[label = "Expected assignment operator `:=`, found `=`"] SourceSpan),
+    #[error("Unexpected end of input")]
+    #[diagnostic(code(E0213))]
     OutOfTokens(#[label = "Ran out of tokens while parsing"] SourceSpan),
+    #[error("Missing semicolon")]
+    #[diagnostic(code(E0214))]
     MissingSemicolon(#[label = "Expected ; after statement"] SourceSpan),
-    #[diagnostic(help("Usage: for x in a..b do \\n [body] \\n end for;"))]
+    #[error("Missing for loop control variable")]
+    #[diagnostic(code(E0215), help("Usage: for x in a..b do \\n [body] \\n end for;"))]
     ForMissingVariable(
         String,
         #[label = "Expected variable name, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: for x in a..b do \\n [body] \\n end for;"))]
+    #[error("Missing for loop range")]
+    #[diagnostic(code(E0216), help("Usage: for x in a..b do \\n [body] \\n end for;"))]
     ForMissingRange(
         String,
         #[label = "Expected range syntax `..`, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: for x in a..b do \\n [body] \\n end for;"))]
+    #[error("Missing `in` in for loop")]
+    #[diagnostic(code(E0217), help("Usage: for x in a..b do \\n [body] \\n end for;"))]
     ForMissingIn(
         String,
         #[label = "Expected keyword `in`, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: for x in a..b do \\n [body] \\n end for;"))]
+    #[error("Missing `do` in for loop")]
+    #[diagnostic(code(E0218), help("Usage: for x in a..b do \\n [body] \\n end for;"))]
     ForMissingDo(
         String,
         #[label = "Expected keyword `do`, found token {0}"] SourceSpan,
     ),
-    #[diagnostic(help("Usage: for x in a..b do \\n [body] \\n end for;"))]
+    #[error("Missing `for` after `end`")]
+    #[diagnostic(code(E0219), help("Usage: for x in a..b do \\n [body] \\n end for;"))]
     EndMissingFor(
         String,
         #[label = "Expected keyword `for`, found token {0}"] SourceSpan,
     ),
+    #[error("For loop control variable modified")]
+    #[diagnostic(code(E0220), help(
+        "The for control variable behaves like a constant inside the loop. Use a different variable instead"
+    ))]
+    ForVariableModified(
+        String,
+        #[label = "Control variable {0} of this loop"] SourceSpan,
+        #[label = "Cannot be modified inside the loop"] SourceSpan,
+    ),
+    #[error("Misspelled keyword")]
+    #[diagnostic(code(E0221), help("Did you mean the keyword `{1}`?"))]
+    MisspelledKeyword(
+        String,
+        String,
+        #[label = "Unknown statement `{0}`"] SourceSpan,
+        Suggestion,
+    ),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Lexing(#[from] UnrecoverableLexingError),
+}
+
+impl ParseError {
+    /// Returns the suggested fix for this error, if any
+    #[must_use]
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        match self {
+            ParseError::MisspelledKeyword(_, _, _, suggestion) => Some(suggestion),
+            _ => None,
+        }
+    }
 }
@@ -9,13 +9,21 @@ use crate::{span::StartEndSpan, tokens::Token};
 pub enum Expr {
     /// Assignment to a variable
     Assign(Assign),
+    /// Assignment to an element of an array variable
+    AssignIndex(AssignIndex),
     /// Binary expression
     Binary(Binary),
+    /// Call of a builtin function
+    Call(Call),
     /// Grouping expression, mostly transparent
     Grouping(Grouping),
+    /// Indexing into an array
+    Index(Index),
+    /// String literal with embedded expressions
+    Interpolation(Interpolation),
     /// Literal value expression
     Literal(Literal),
-    /// Logical expression. Currently only AND exists
+    /// Logical expression, AND `&` or OR `|`
     Logical(Logical),
     /// Unary expression
     Unary(Unary),
@@ -63,6 +71,32 @@ impl Assign {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+/// Assignment to an element of an array variable, `name[index] := value`
+pub struct AssignIndex {
+    /// Name of the array variable
+    pub name: String,
+    /// The [Expression] to evaluate as the index of the element
+    pub index: Box<Expression>,
+    /// The [Token] used in this assignment
+    pub token: Token,
+    /// The [Expression] to evaluate and then assign as the new value of the element
+    pub value: Box<Expression>,
+}
+
+impl AssignIndex {
+    #[must_use]
+    /// Creates a new [AssignIndex] [Expression]
+    pub fn new(name: &str, index: Expression, token: Token, value: Expression) -> Self {
+        Self {
+            name: name.to_owned(),
+            index: Box::new(index),
+            token,
+            value: Box::new(value),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// Binary expression. Any expression that applies an operator between two sub-expressions.
 pub struct Binary {
@@ -86,6 +120,26 @@ impl Binary {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+/// Call of a builtin function, `name(arguments)`
+pub struct Call {
+    /// Name of the builtin function
+    pub name: String,
+    /// The argument expressions, evaluated left to right
+    pub arguments: Vec<Expression>,
+}
+
+impl Call {
+    #[must_use]
+    /// Creates a new [Call] [Expression]
+    pub fn new(name: &str, arguments: Vec<Expression>) -> Self {
+        Self {
+            name: name.to_owned(),
+            arguments,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// Grouping expression. These are constructed with the use of parens `()`.
 pub struct Grouping {
@@ -103,6 +157,50 @@ impl Grouping {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+/// Indexing expression, `array[index]`. Indices start from zero.
+pub struct Index {
+    /// The expression to index into
+    pub array: Box<Expression>,
+    /// The expression to evaluate as the index of the element
+    pub index: Box<Expression>,
+}
+
+impl Index {
+    #[must_use]
+    /// Creates a new [Index] [Expression]
+    pub fn new(array: Expression, index: Expression) -> Self {
+        Self {
+            array: Box::new(array),
+            index: Box::new(index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A segment of an [Interpolation]
+pub enum InterpolationSegment {
+    /// Literal text
+    Literal(String),
+    /// Embedded expression, whose value is printed into the string
+    Expression(Expression),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// String literal with embedded expressions, e.g. `"x = {x}"`. Evaluates into a text value.
+pub struct Interpolation {
+    /// The segments of the string, in order
+    pub segments: Vec<InterpolationSegment>,
+}
+
+impl Interpolation {
+    #[must_use]
+    /// Creates a new [Interpolation] [Expression]
+    pub fn new(segments: Vec<InterpolationSegment>) -> Self {
+        Self { segments }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// Literal "expression". Contains a literal value.
 pub struct Literal {
@@ -119,7 +217,7 @@ impl Literal {
 }
 
 #[derive(Clone, Debug, PartialEq)]
-/// Logical operator expression. Currently, only logical AND `&` exists.
+/// Logical operator expression. Logical AND `&` and logical OR `|` are short-circuiting.
 pub struct Logical {
     /// Left hand side of the logical expression
     pub left: Box<Expression>,
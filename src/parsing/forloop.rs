@@ -7,9 +7,13 @@ use super::{Expression, Statement};
 #[derive(Clone, Debug, PartialEq)]
 pub struct Forloop {
     pub variable: String,
+    /// The start of the range, or the array to iterate over when there is no `right`
     pub left: Box<Expression>,
-    pub right: Box<Expression>,
+    /// The end of the range, or `None` when iterating over the elements of an array
+    pub right: Option<Box<Expression>>,
     pub body: Vec<Statement>,
+    /// The span of the loop header `for x in a..b do`
+    pub header: StartEndSpan,
     pub span: StartEndSpan,
 }
 
@@ -18,15 +22,17 @@ impl Forloop {
     pub fn new(
         variable: &str,
         left: Expression,
-        right: Expression,
+        right: Option<Expression>,
         body: Vec<Statement>,
+        header: StartEndSpan,
         span: StartEndSpan,
     ) -> Self {
         Self {
             variable: variable.to_owned(),
             left: Box::new(left),
-            right: Box::new(right),
+            right: right.map(Box::new),
             body,
+            header,
             span,
         }
     }
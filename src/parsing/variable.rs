@@ -2,19 +2,40 @@
 //!
 //! This is a run-time construct. // TODO: better docss
 
+use std::fmt::Display;
+
 use crate::span::StartEndSpan;
 
 use super::expression::Expression;
 
+/// The maximum number of elements in an array. Larger arrays are rejected by the parser, before any memory is allocated for them
+pub const MAX_ARRAY_SIZE: usize = 1_000_000;
+
 #[derive(Debug, Clone, PartialEq)]
-/// Enum of the possible data types in the Mini-PL programming language. Boolean, Integer and Text.
+/// Enum of the possible data types in the Mini-PL programming language. Boolean, Integer, Real, Text and fixed-size Array.
 pub enum VarType {
     /// Boolean value, `true` or `false`
     Boolean,
     /// Integer value, internally represented as an [i64]
     Number,
+    /// Real value, internally represented as an [f64]
+    Real,
     /// Text value, internally represented as a [String]
     Text,
+    /// Fixed-size array of the given element type and length
+    Array(Box<VarType>, usize),
+}
+
+impl Display for VarType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VarType::Boolean => write!(f, "Boolean"),
+            VarType::Number => write!(f, "Number"),
+            VarType::Real => write!(f, "Real"),
+            VarType::Text => write!(f, "Text"),
+            VarType::Array(kind, size) => write!(f, "Array[{size}] of {kind}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
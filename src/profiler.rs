@@ -0,0 +1,363 @@
+//! Statement-level profiling of Mini-PL programs, see `minipl run --profile`.
+//!
+//! The [Profiler] is a [Hook] of the [Interpreter](crate::visitors::Interpreter) that counts the executions of each statement and
+//! measures their time, keyed by the spans of the statements. The total time of a statement includes the statements in its body,
+//! which only `for` loops have, and the self time excludes them. For `for` loops, the iterations are counted as well.
+//!
+//! The results are shown as a table of the hot spots, see [Profiler::report], and as collapsed stacks for flamegraph tools,
+//! see [Profiler::collapsed_stacks].
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::parsing::{Statement, Stmt};
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, State, VariableWrite};
+
+/// The number of statements shown in the table of hot spots
+const HOT_SPOTS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The profile of a single statement
+pub struct StatementProfile {
+    /// The span of the statement
+    pub span: StartEndSpan,
+    /// The line the statement starts on
+    pub line: usize,
+    /// The column the statement starts on
+    pub column: usize,
+    /// The first line of the source code of the statement
+    pub source: String,
+    /// The number of times the statement was executed
+    pub count: u64,
+    /// The time spent executing the statement, including the statements in its body
+    pub total: Duration,
+    /// The time spent executing the statement, excluding the statements in its body
+    pub self_time: Duration,
+    /// The number of iterations, for `for` loops only
+    pub iterations: Option<u64>,
+}
+
+/// A statement being executed
+#[derive(Debug)]
+struct Frame {
+    span: StartEndSpan,
+    /// The span of the loop header, for `for` loops only
+    header: Option<StartEndSpan>,
+    started: Instant,
+    /// The time spent in the statements of the body so far
+    children: Duration,
+    /// The iterations so far, for `for` loops
+    iterations: u64,
+}
+
+#[derive(Debug)]
+/// The statement-level profiler
+pub struct Profiler {
+    /// The characters of the source code, as the spans are character offsets
+    source: Vec<char>,
+    lines: SourceLines,
+    statements: HashMap<StartEndSpan, StatementProfile>,
+    /// The self times of the stacks of statements, outermost first
+    stacks: HashMap<Vec<StartEndSpan>, Duration>,
+    frames: Vec<Frame>,
+}
+
+impl Profiler {
+    /// Creates a new [Profiler] for the program with the given source code
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.chars().collect(),
+            lines: SourceLines::new(source),
+            statements: HashMap::new(),
+            stacks: HashMap::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Ends the measurement of the statements still being executed, i.e. those that a runtime error stopped
+    pub fn finish(&mut self) {
+        while !self.frames.is_empty() {
+            self.pop();
+        }
+    }
+
+    /// Returns the profiles of the executed statements, by decreasing self time
+    #[must_use]
+    pub fn statements(&self) -> Vec<StatementProfile> {
+        let mut statements: Vec<StatementProfile> = self.statements.values().cloned().collect();
+        statements.sort_by(|a, b| {
+            b.self_time
+                .cmp(&a.self_time)
+                .then(a.span.start.cmp(&b.span.start))
+        });
+        statements
+    }
+
+    /// Returns the table of the hot spots i.e. the statements with the most self time, followed by the table of the `for` loops
+    #[must_use]
+    pub fn report(&self) -> String {
+        let statements = self.statements();
+        let total: Duration = statements.iter().map(|s| s.self_time).sum();
+        let percent = |time: Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                100.0 * time.as_secs_f64() / total.as_secs_f64()
+            }
+        };
+        let mut report = format!("Hot spots by self time, of {} in total:\n", duration(total));
+        let _ = writeln!(
+            report,
+            "  {:<10} {:>10} {:>12} {:>7} {:>12} {:>7}  Source",
+            "Location", "Count", "Self", "Self%", "Total", "Total%"
+        );
+        for statement in statements.iter().take(HOT_SPOTS) {
+            let _ = writeln!(
+                report,
+                "  {:<10} {:>10} {:>12} {:>6.1}% {:>12} {:>6.1}%  {}",
+                format!("{}:{}", statement.line, statement.column),
+                statement.count,
+                duration(statement.self_time),
+                percent(statement.self_time),
+                duration(statement.total),
+                percent(statement.total),
+                statement.source
+            );
+        }
+        if statements.len() > HOT_SPOTS {
+            let _ = writeln!(
+                report,
+                "  ... and {} more statements",
+                statements.len() - HOT_SPOTS
+            );
+        }
+
+        let mut loops: Vec<&StatementProfile> = statements
+            .iter()
+            .filter(|statement| statement.iterations.is_some())
+            .collect();
+        if loops.is_empty() {
+            return report;
+        }
+        loops.sort_by(|a, b| b.total.cmp(&a.total).then(a.span.start.cmp(&b.span.start)));
+        let _ = writeln!(report, "\nfor loops by total time:");
+        let _ = writeln!(
+            report,
+            "  {:<10} {:>10} {:>11} {:>12} {:>14}  Source",
+            "Location", "Runs", "Iterations", "Total", "Per iteration"
+        );
+        for statement in loops {
+            let iterations = statement.iterations.unwrap_or_default();
+            let per_iteration = match u32::try_from(iterations) {
+                Ok(iterations) if iterations > 0 => duration(statement.total / iterations),
+                _ => "-".to_owned(),
+            };
+            let _ = writeln!(
+                report,
+                "  {:<10} {:>10} {:>11} {:>12} {:>14}  {}",
+                format!("{}:{}", statement.line, statement.column),
+                statement.count,
+                iterations,
+                duration(statement.total),
+                per_iteration,
+                statement.source
+            );
+        }
+        report
+    }
+
+    /// Returns the collapsed stacks for flamegraph tools: one line per stack of statements, with its self time in nanoseconds.
+    ///
+    /// Each frame is the location and the source code of a statement, e.g. `main;5:1 for x in 0..n do;6:3 print x 1200`
+    #[must_use]
+    pub fn collapsed_stacks(&self) -> String {
+        let mut stacks: Vec<(String, u128)> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| {
+                let frames: Vec<String> = stack
+                    .iter()
+                    .map(|span| match self.statements.get(span) {
+                        // The frames are separated by semicolons, and the count by the last space
+                        Some(statement) => format!(
+                            "{}:{} {}",
+                            statement.line,
+                            statement.column,
+                            statement.source.replace(';', ",")
+                        ),
+                        None => format!("{}..{}", span.start, span.end),
+                    })
+                    .collect();
+                (format!("main;{}", frames.join(";")), time.as_nanos())
+            })
+            .collect();
+        stacks.sort();
+        stacks
+            .into_iter()
+            .map(|(stack, time)| format!("{stack} {time}\n"))
+            .collect()
+    }
+
+    /// Internal helper: returns the first line of the source code of the span
+    fn text(&self, span: StartEndSpan) -> String {
+        let end = span.end.min(self.source.len());
+        let text: String = self.source[span.start.min(end)..end].iter().collect();
+        text.lines()
+            .next()
+            .unwrap_or_default()
+            .trim_end()
+            .to_owned()
+    }
+
+    /// Internal helper: ends the measurement of the innermost statement being executed
+    fn pop(&mut self) {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let elapsed = frame.started.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += elapsed;
+        }
+        let mut stack: Vec<StartEndSpan> = self.frames.iter().map(|frame| frame.span).collect();
+        stack.push(frame.span);
+        *self.stacks.entry(stack).or_default() += self_time;
+
+        let (line, column) = self.lines.line_column(frame.span.start);
+        let source = self.text(frame.span);
+        let statement = self
+            .statements
+            .entry(frame.span)
+            .or_insert_with(|| StatementProfile {
+                span: frame.span,
+                line,
+                column,
+                source,
+                count: 0,
+                total: Duration::ZERO,
+                self_time: Duration::ZERO,
+                iterations: frame.header.map(|_| 0),
+            });
+        statement.count += 1;
+        statement.total += elapsed;
+        statement.self_time += self_time;
+        if let Some(iterations) = &mut statement.iterations {
+            *iterations += frame.iterations;
+        }
+    }
+}
+
+impl Hook for Profiler {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        _state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let header = match &statement.stmt {
+            Stmt::Forloop(forloop) => Some(forloop.header),
+            _ => None,
+        };
+        self.frames.push(Frame {
+            span: statement.span,
+            header,
+            started: Instant::now(),
+            children: Duration::ZERO,
+            iterations: 0,
+        });
+        Ok(())
+    }
+
+    fn after_statement(&mut self, _statement: &Statement, _result: &Object) {
+        self.pop();
+    }
+
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        // Each iteration of a `for` loop begins by assigning to the control variable at the loop header
+        if let Some(frame) = self.frames.last_mut() {
+            if frame.header == Some(write.span) {
+                frame.iterations += 1;
+            }
+        }
+    }
+}
+
+/// Internal helper: formats the duration with three significant decimals, e.g. `1.234ms`
+fn duration(duration: Duration) -> String {
+    format!("{duration:.3?}")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::Profiler;
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::runtime::RuntimeError;
+    use crate::visitors::Interpreter;
+
+    /// Profiles the program, returning the result and the profiler
+    fn profile(source: &str) -> (Result<(), RuntimeError>, Profiler) {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::with_hook(Profiler::new(source));
+        let result = interpreter.eval(&statements);
+        let mut profiler = interpreter.into_hook();
+        profiler.finish();
+        (result, profiler)
+    }
+
+    #[test]
+    fn counts_and_iterations() {
+        let source = "var i : int;\nvar j : int;\nvar x : int;\nfor i in 1..3 do\n  for j in 1..i do\n    x := x + j;\n  end for;\nend for;";
+        let (result, profiler) = profile(source);
+        assert!(result.is_ok());
+        let statements = profiler.statements();
+        let find = |line: usize| statements.iter().find(|s| s.line == line).unwrap();
+        assert_eq!((find(4).count, find(4).iterations), (1, Some(3)));
+        assert_eq!((find(5).count, find(5).iterations), (3, Some(6)));
+        assert_eq!((find(6).count, find(6).iterations), (6, None));
+        assert_eq!(find(6).source, "x := x + j");
+        assert!(find(4).total >= find(5).total);
+        assert!(find(5).total >= find(6).total);
+        assert!(find(4).total >= find(4).self_time);
+
+        let report = profiler.report();
+        assert!(report.starts_with("Hot spots by self time"));
+        assert!(report.contains("\nfor loops by total time:\n"));
+    }
+
+    #[test]
+    fn collapsed_stacks() {
+        let source = "var i : int;\nfor i in 1..2 do\n  assert \"a;b\" = \"a;b\";\nend for;";
+        let (_, profiler) = profile(source);
+        let stacks: Vec<String> = profiler
+            .collapsed_stacks()
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_owned())
+            .collect();
+        assert_eq!(
+            stacks,
+            [
+                "main;1:1 var i : int",
+                "main;2:1 for i in 1..2 do",
+                "main;2:1 for i in 1..2 do;3:3 assert \"a,b\" = \"a,b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn runtime_errors_end_the_measurement() {
+        let (result, profiler) =
+            profile("var i : int;\nfor i in 1..2 do\n  assert false;\nend for;");
+        assert!(result.is_err());
+        let statements = profiler.statements();
+        assert_eq!(statements.len(), 3);
+        assert!(statements.iter().all(|statement| statement.count == 1));
+    }
+}
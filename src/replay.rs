@@ -0,0 +1,409 @@
+//! Deterministic record and replay of the input of Mini-PL programs, see `minipl run --record` and `minipl run --replay`.
+//!
+//! The [Recorder] is both the [Console] and a [Hook] of the [Interpreter](crate::visitors::Interpreter): the console sees the
+//! lines read by `read` statements and the text printed by `print` statements, and the hook sees which statement is being
+//! executed, for the locations of these events. When recording, the events are stored into a [Session] that can be saved as
+//! JSON. When replaying, the inputs of the session are fed to the program, and its output is verified against the session,
+//! stopping at the first divergence.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::rc::Rc;
+
+use miette::{Diagnostic, SourceSpan};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::parsing::Statement;
+use crate::runtime::{Console, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The kind of an [Event]
+pub enum EventKind {
+    /// A line read by a `read` statement
+    Input,
+    /// Text printed by a `print` statement
+    Output,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A line read or text printed by the program
+pub struct Event {
+    /// Whether the text was read or printed
+    pub kind: EventKind,
+    /// The text. An input line includes its newline, and is empty at the end of the input
+    pub text: String,
+    /// The line of the statement that read or printed the text
+    pub line: usize,
+    /// The column of the statement that read or printed the text
+    pub column: usize,
+    /// The span of the statement that read or printed the text
+    pub span: StartEndSpan,
+}
+
+impl Event {
+    /// Returns the description of the event as the recording, e.g. `printed "Hello" at line 2, column 1`
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let action = match self.kind {
+            EventKind::Input => format!("read {:?}", self.text),
+            EventKind::Output => format!("printed {:?}", self.text),
+        };
+        format!("{action} at line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// The recorded input and output of a run of a program
+pub struct Session {
+    /// The path of the program
+    pub program: String,
+    /// The lines read and the text printed, in order
+    pub events: Vec<Event>,
+}
+
+impl Session {
+    /// Parses the session from JSON
+    ///
+    /// # Errors
+    /// Returns [ReplayError::InvalidSession] if the JSON is not a session
+    pub fn from_json(json: &str) -> Result<Self, ReplayError> {
+        serde_json::from_str(json).map_err(|error| ReplayError::InvalidSession(error.to_string()))
+    }
+
+    /// Returns the session as pretty-printed JSON
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+/// Errors in replaying a recorded session
+pub enum ReplayError {
+    #[error("Invalid session file: {0}")]
+    #[diagnostic(
+        code(E0501),
+        help("Sessions are recorded with `minipl run --record <PATH>`")
+    )]
+    /// The session file could not be parsed
+    InvalidSession(String),
+    #[error("The program printed {0:?}, but the recording {1}")]
+    #[diagnostic(
+        code(E0502),
+        help("The output of the program differs from the recorded session from this statement on")
+    )]
+    /// The program printed text that differs from the recording
+    OutputDiverged(String, String, #[label = "printed here"] SourceSpan),
+    #[error("The program read input, but the recording {0}")]
+    #[diagnostic(
+        code(E0503),
+        help("The program reads input where the recorded session did not")
+    )]
+    /// The program read input where the recording did not
+    InputDiverged(String, #[label = "read here"] SourceSpan),
+    #[error("The program ended, but the recording {0}")]
+    #[diagnostic(
+        code(E0504),
+        help("The recorded session has {1} more events after the end of the program")
+    )]
+    /// The program ended before the recording did
+    EndDiverged(String, usize),
+}
+
+#[derive(Debug)]
+enum Mode {
+    /// The events recorded so far
+    Record(Vec<Event>),
+    /// The events not yet replayed
+    Replay(VecDeque<Event>),
+}
+
+#[derive(Debug)]
+struct Shared {
+    lines: SourceLines,
+    program: String,
+    /// The span of the statement being executed
+    statement: StartEndSpan,
+    mode: Mode,
+    /// The first divergence from the replayed session
+    divergence: Option<ReplayError>,
+    /// The console that the input is read from when recording, and that the output is printed to
+    console: Box<dyn Console>,
+}
+
+#[derive(Debug, Clone)]
+/// The recorder of the input and output of a program, either recording or replaying a [Session].
+///
+/// The clones of a recorder share their state, so that one clone can be the console of the interpreter and another its hook
+pub struct Recorder(Rc<RefCell<Shared>>);
+
+impl Recorder {
+    /// Creates a new [Recorder] that records the input read from and the output printed to the console, for the program at the
+    /// given path with the given source code
+    #[must_use]
+    pub fn record(source: &str, program: &str, console: Box<dyn Console>) -> Self {
+        Self::new(source, program, Mode::Record(Vec::new()), console)
+    }
+
+    /// Creates a new [Recorder] that feeds the inputs of the session to the program with the given source code, and verifies
+    /// its output against the session. The output is printed to the console as well
+    #[must_use]
+    pub fn replay(source: &str, session: Session, console: Box<dyn Console>) -> Self {
+        let mode = Mode::Replay(session.events.into());
+        Self::new(source, &session.program, mode, console)
+    }
+
+    fn new(source: &str, program: &str, mode: Mode, console: Box<dyn Console>) -> Self {
+        Self(Rc::new(RefCell::new(Shared {
+            lines: SourceLines::new(source),
+            program: program.to_owned(),
+            statement: StartEndSpan::new(0, 0),
+            mode,
+            divergence: None,
+            console,
+        })))
+    }
+
+    /// Returns the session recorded so far. When replaying, returns the events not yet replayed
+    #[must_use]
+    pub fn session(&self) -> Session {
+        let shared = self.0.borrow();
+        let events = match &shared.mode {
+            Mode::Record(events) => events.clone(),
+            Mode::Replay(remaining) => remaining.iter().cloned().collect(),
+        };
+        Session {
+            program: shared.program.clone(),
+            events,
+        }
+    }
+
+    /// Ends the replay after the program has ended. Does nothing when recording
+    ///
+    /// # Errors
+    /// Returns the first divergence of the program from the recording, including the recording not having ended with the program
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        let mut shared = self.0.borrow_mut();
+        if let Some(divergence) = shared.divergence.take() {
+            return Err(divergence);
+        }
+        match &shared.mode {
+            Mode::Replay(remaining) if !remaining.is_empty() => Err(ReplayError::EndDiverged(
+                expected(remaining.front()),
+                remaining.len(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Shared {
+    /// Returns an event of the statement being executed
+    fn event(&self, kind: EventKind, text: &str) -> Event {
+        let (line, column) = self.lines.line_column(self.statement.start);
+        Event {
+            kind,
+            text: text.to_owned(),
+            line,
+            column,
+            span: self.statement,
+        }
+    }
+
+    /// Stores the first divergence, and returns the error that stops the program
+    fn diverge(&mut self, error: ReplayError) -> io::Error {
+        self.divergence.get_or_insert(error);
+        io::Error::other("the program diverged from the recording")
+    }
+}
+
+/// Internal helper: describes what the recording does next
+fn expected(next: Option<&Event>) -> String {
+    match next {
+        Some(event) => event.describe(),
+        None => "ended".to_owned(),
+    }
+}
+
+impl Console for Recorder {
+    fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+        let mut shared = self.0.borrow_mut();
+        let shared = &mut *shared;
+        match &mut shared.mode {
+            Mode::Record(_) => {
+                let mut line = String::new();
+                let read = shared.console.read_line(&mut line)?;
+                let event = shared.event(EventKind::Input, &line);
+                if let Mode::Record(events) = &mut shared.mode {
+                    events.push(event);
+                }
+                buffer.push_str(&line);
+                Ok(read)
+            }
+            Mode::Replay(remaining) => match remaining.front() {
+                Some(event) if event.kind == EventKind::Input => {
+                    let text = event.text.clone();
+                    remaining.pop_front();
+                    buffer.push_str(&text);
+                    Ok(text.len())
+                }
+                next => {
+                    let error = ReplayError::InputDiverged(expected(next), shared.statement.into());
+                    Err(shared.diverge(error))
+                }
+            },
+        }
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut shared = self.0.borrow_mut();
+        let shared = &mut *shared;
+        match &mut shared.mode {
+            Mode::Record(_) => {
+                let event = shared.event(EventKind::Output, text);
+                if let Mode::Record(events) = &mut shared.mode {
+                    events.push(event);
+                }
+            }
+            Mode::Replay(remaining) => match remaining.front() {
+                Some(event) if event.kind == EventKind::Output && event.text == text => {
+                    remaining.pop_front();
+                }
+                next => {
+                    let error = ReplayError::OutputDiverged(
+                        text.to_owned(),
+                        expected(next),
+                        shared.statement.into(),
+                    );
+                    return Err(shared.diverge(error));
+                }
+            },
+        }
+        shared.console.print(text)
+    }
+}
+
+impl Hook for Recorder {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        _state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        self.0.borrow_mut().statement = statement.span;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::collections::VecDeque;
+    use std::io;
+
+    use super::{EventKind, Recorder, ReplayError, Session};
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::runtime::{Console, RuntimeError};
+    use crate::visitors::Interpreter;
+
+    /// A console with scripted input, discarding the output
+    #[derive(Debug, Default)]
+    struct Scripted(VecDeque<&'static str>);
+
+    impl Console for Scripted {
+        fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+            let line = self.0.pop_front().unwrap_or_default();
+            buffer.push_str(line);
+            Ok(line.len())
+        }
+
+        fn print(&mut self, _text: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs the program with the recorder as its console and hook
+    fn run(source: &str, recorder: &Recorder) -> Result<(), RuntimeError> {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::with_hook(recorder.clone());
+        interpreter.set_console(Box::new(recorder.clone()));
+        interpreter.eval(&statements)
+    }
+
+    /// Records a run of the program with the input lines
+    fn record(source: &str, input: &[&'static str]) -> Session {
+        let console = Scripted(input.iter().copied().collect());
+        let recorder = Recorder::record(source, "test.minipl", Box::new(console));
+        run(source, &recorder).unwrap();
+        recorder.session()
+    }
+
+    /// Replays the session for the program, returning the result of the replay
+    fn replay(source: &str, session: Session) -> Result<(), ReplayError> {
+        let recorder = Recorder::replay(source, session, Box::<Scripted>::default());
+        let _ = run(source, &recorder);
+        recorder.finish()
+    }
+
+    const PROGRAM: &str = "var n : int;\nprint \"n?\";\nread n;\nprint n * 2;";
+
+    #[test]
+    fn records_inputs_and_outputs_with_locations() {
+        let session = record(PROGRAM, &["21\n"]);
+        let events: Vec<(EventKind, &str, usize)> = session
+            .events
+            .iter()
+            .map(|event| (event.kind, event.text.as_str(), event.line))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (EventKind::Output, "n?", 2),
+                (EventKind::Input, "21\n", 3),
+                (EventKind::Output, "42", 4),
+            ]
+        );
+        assert_eq!(session.events[1].span.start, 25);
+        assert_eq!(Session::from_json(&session.to_json()).unwrap(), session);
+    }
+
+    #[test]
+    fn replays_the_inputs() {
+        let session = record(PROGRAM, &["21\n"]);
+        assert!(replay(PROGRAM, session).is_ok());
+    }
+
+    #[test]
+    fn reports_the_first_divergence() {
+        let session = record(PROGRAM, &["21\n"]);
+        let changed = PROGRAM.replace("n * 2", "n * 3");
+        match replay(&changed, session.clone()) {
+            Err(ReplayError::OutputDiverged(actual, expected, span)) => {
+                assert_eq!(actual, "63");
+                assert_eq!(expected, "printed \"42\" at line 4, column 1");
+                assert_eq!(span.offset(), 33);
+            }
+            other => panic!("{other:?}"),
+        }
+
+        let changed = "var n : int;\nread n;";
+        let error = replay(changed, session.clone()).unwrap_err();
+        assert!(matches!(error, ReplayError::InputDiverged(..)), "{error:?}");
+
+        let error = replay("var n : int;\nprint \"n?\";", session).unwrap_err();
+        assert!(matches!(error, ReplayError::EndDiverged(_, 2)), "{error:?}");
+    }
+
+    #[test]
+    fn invalid_sessions() {
+        let error = Session::from_json("{\"program\": \"a.minipl\"}").unwrap_err();
+        assert!(matches!(error, ReplayError::InvalidSession(_)));
+    }
+}
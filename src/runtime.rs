@@ -4,5 +4,14 @@ pub use object::Object;
 /// Runtime [Environment] type of the Mini-PL language, used for variable storage
 mod environment;
 pub use environment::Environment;
+/// Input and output of the running program
+mod console;
+pub use console::{Console, Stdio};
 mod errors;
 pub use errors::RuntimeError;
+/// Builtin functions of the Mini-PL language
+pub mod builtins;
+/// Operators of the Mini-PL language
+pub(crate) mod operators;
+/// String builtin functions of the Mini-PL language
+mod strings;
@@ -0,0 +1,124 @@
+//! Builtin functions of the Mini-PL language, called with the syntax `name(arguments)`.
+
+use crate::runtime::{strings, Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::suggestions::{self, Suggestion};
+
+/// The names of the builtin functions
+pub const NAMES: [&str; 9] = [
+    "char_at",
+    "find",
+    "length",
+    "substring",
+    "to_int",
+    "to_lower",
+    "to_real",
+    "to_string",
+    "to_upper",
+];
+
+/// Calls the builtin function with the given name. Arguments are given as evaluated [Object]s with the spans of their expressions.
+/// The span is that of the whole call, and the name span that of the function name
+pub fn call(
+    name: &str,
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+    name_span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    match name {
+        "char_at" => strings::char_at(arguments, span),
+        "find" => strings::find(arguments, span),
+        "length" => length(arguments, span),
+        "substring" => strings::substring(arguments, span),
+        "to_int" => to_int(arguments, span),
+        "to_lower" => strings::to_lower(arguments, span),
+        "to_real" => to_real(arguments, span),
+        "to_string" => strings::to_string(arguments, span),
+        "to_upper" => strings::to_upper(arguments, span),
+        _ => {
+            let suggestion = suggestions::closest(name, NAMES)
+                .map(|builtin| Suggestion::new(builtin, name_span));
+            Err(RuntimeError::UnknownBuiltin(
+                name.to_owned(),
+                name_span.into(),
+                suggestions::help(
+                    &suggestion,
+                    "See `minipl explain E0313` for the builtin functions",
+                ),
+                suggestion,
+            ))
+        }
+    }
+}
+
+/// Internal helper: returns an error unless the call has exactly the given number of arguments
+pub(crate) fn expect_arguments(
+    name: &str,
+    arguments: &[(Object, StartEndSpan)],
+    count: usize,
+    span: StartEndSpan,
+) -> Result<(), RuntimeError> {
+    if arguments.len() == count {
+        Ok(())
+    } else {
+        Err(RuntimeError::BuiltinArgumentCount(
+            name.to_owned(),
+            count,
+            arguments.len(),
+            span.into(),
+        ))
+    }
+}
+
+/// Internal helper: returns an argument type error for the builtin function with the given name
+pub(crate) fn argument_type_error(
+    name: &str,
+    expected: &str,
+    argument: &(Object, StartEndSpan),
+) -> RuntimeError {
+    RuntimeError::BuiltinArgumentType(
+        name.to_owned(),
+        expected.to_owned(),
+        argument.0.kind_to_string(),
+        argument.1.into(),
+    )
+}
+
+/// `length(array)`: returns the number of elements in the array. `length(text)`: returns the number of characters in the text
+fn length(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("length", arguments, 1, span)?;
+    match &arguments[0] {
+        (Object::Array(elements), _) => Ok(Object::Number(elements.len() as i64)),
+        (Object::Text(text), _) => Ok(Object::Number(text.chars().count() as i64)),
+        argument => Err(argument_type_error("length", "Array or Text", argument)),
+    }
+}
+
+/// `to_int(real)`: converts a real number into a number, truncating towards zero. Saturates at the bounds of [i64], NaN becomes zero.
+/// `to_int(text)`: parses the text as a number
+fn to_int(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("to_int", arguments, 1, span)?;
+    match &arguments[0] {
+        (Object::Real(n), _) => Ok(Object::Number(*n as i64)),
+        (Object::Text(_), _) => strings::parse_int(&arguments[0]),
+        argument => Err(argument_type_error("to_int", "Real or Text", argument)),
+    }
+}
+
+/// `to_real(number)`: converts a number into a real number
+fn to_real(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("to_real", arguments, 1, span)?;
+    match &arguments[0] {
+        (Object::Number(n), _) => Ok(Object::Real(*n as f64)),
+        argument => Err(argument_type_error("to_real", "Number", argument)),
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+/// The input and output of a running program: the lines read by `read` statements, and the text printed by `print` statements.
+///
+/// The [Interpreter](crate::visitors::Interpreter) uses the standard input and output by default, see [Stdio].
+pub trait Console: Debug {
+    /// Reads a line of input, including the newline, and appends it to the buffer. Returns the number of bytes read, which is zero at the end of the input
+    ///
+    /// # Errors
+    /// Returns an error if the input could not be read
+    fn read_line(&mut self, buffer: &mut String) -> io::Result<usize>;
+
+    /// Prints the text, without adding a newline
+    ///
+    /// # Errors
+    /// Returns an error if the output could not be written
+    fn print(&mut self, text: &str) -> io::Result<()>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The [Console] of the standard input and output of the process
+pub struct Stdio;
+
+impl Console for Stdio {
+    fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+        io::stdin().read_line(buffer)
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    }
+}
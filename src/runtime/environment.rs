@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use crate::runtime::Object;
 use crate::span::StartEndSpan;
+use crate::suggestions::{self, Suggestion};
 
 use super::RuntimeError;
 
@@ -28,22 +29,22 @@ impl Environment {
         Ok(())
     }
 
-    /// Gets the value of the variable with the given name from the [Environment]
-    pub fn get(&self, name: &str) -> Result<Object, RuntimeError> {
+    /// Gets the value of the variable with the given name from the [Environment]. The span is that of the variable usage
+    pub fn get(&self, name: &str, span: StartEndSpan) -> Result<Object, RuntimeError> {
         self.values
             .get(name)
             .cloned()
-            .ok_or_else(|| RuntimeError::VariableGetFailed(name.to_owned()))
+            .ok_or_else(|| self.variable_get_failed(name, span))
     }
 
-    /// Assigns a new value to an existing variable in the [Environment].
+    /// Assigns a new value to an existing variable in the [Environment], returning the previous value.
     pub fn assign(
         &mut self,
         name: &str,
         value: Object,
         span: StartEndSpan,
     ) -> Result<Object, RuntimeError> {
-        let current = match self.values.get(name) {
+        let current = match self.values.get_mut(name) {
             Some(v) => v,
             None => return Err(RuntimeError::VariableAssignToUndeclared(span.into())),
         };
@@ -56,7 +57,85 @@ impl Environment {
                 span.into(),
             ));
         }
-        self.values.insert(name.to_owned(), value.clone());
-        Ok(value)
+        Ok(std::mem::replace(current, value))
+    }
+
+    /// Gets the value of an element of the array variable with the given name from the [Environment].
+    /// The spans are those of the variable usage and the index expression
+    pub fn get_index(
+        &self,
+        name: &str,
+        index: i64,
+        name_span: StartEndSpan,
+        index_span: StartEndSpan,
+    ) -> Result<Object, RuntimeError> {
+        match self.values.get(name) {
+            Some(Object::Array(elements)) => usize::try_from(index)
+                .ok()
+                .and_then(|i| elements.get(i))
+                .cloned()
+                .ok_or_else(|| {
+                    RuntimeError::IndexOutOfBounds(index, elements.len(), index_span.into())
+                }),
+            Some(other) => Err(RuntimeError::IndexNonArray(
+                other.kind_to_string(),
+                name_span.into(),
+            )),
+            None => Err(self.variable_get_failed(name, name_span)),
+        }
+    }
+
+    /// Assigns a new value to an element of an existing array variable in the [Environment], returning the previous value of the element.
+    pub fn assign_index(
+        &mut self,
+        name: &str,
+        index: i64,
+        value: Object,
+        index_span: StartEndSpan,
+        value_span: StartEndSpan,
+    ) -> Result<Object, RuntimeError> {
+        let elements = match self.values.get_mut(name) {
+            Some(Object::Array(elements)) => elements,
+            Some(other) => {
+                return Err(RuntimeError::IndexNonArray(
+                    other.kind_to_string(),
+                    index_span.into(),
+                ))
+            }
+            None => return Err(RuntimeError::VariableAssignToUndeclared(index_span.into())),
+        };
+        let length = elements.len();
+        let element = usize::try_from(index)
+            .ok()
+            .and_then(|i| elements.get_mut(i))
+            .ok_or_else(|| RuntimeError::IndexOutOfBounds(index, length, index_span.into()))?;
+        if value.kind_to_string() != element.kind_to_string() {
+            return Err(RuntimeError::VariableAssignTypeMismatch(
+                element.kind_to_string(),
+                value.kind_to_string(),
+                index_span.into(),
+                value_span.into(),
+            ));
+        }
+        Ok(std::mem::replace(element, value))
+    }
+
+    /// Returns the names and values of all the variables in the [Environment], in no particular order
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &Object)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Internal helper: returns a VariableGetFailed error, suggesting the closest declared variable name
+    fn variable_get_failed(&self, name: &str, span: StartEndSpan) -> RuntimeError {
+        let suggestion = suggestions::closest(name, self.values.keys().map(String::as_str))
+            .map(|variable| Suggestion::new(variable, span));
+        RuntimeError::VariableGetFailed(
+            name.to_owned(),
+            span.into(),
+            suggestions::help(&suggestion, "Use the keyword `var` to declare the variable"),
+            suggestion,
+        )
     }
 }
@@ -3,18 +3,26 @@
 use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
+use crate::suggestions::Suggestion;
+
 #[derive(Error, Debug, Diagnostic)]
-#[error("Runtime error")]
-#[diagnostic()]
 /// Runtime errors of the interpreter
 pub enum RuntimeError {
-    #[diagnostic(help = "Expected a numeric value, got: {0}")]
-    AsNumericFailed(String), // TODO: span
-    #[diagnostic(help = "Expected a boolean value, got: {0}")]
-    AsBooleanFailed(String), // TODO: span
-    #[diagnostic(help = "Expected a text value, got: {0}")]
-    AsTextFailed(String), // TODO: span
+    #[error("Expected a number")]
+    #[diagnostic(code(E0301), help = "Expected a numeric value, got: {0}")]
+    AsNumericFailed(String, #[label = "{0}"] SourceSpan),
+    #[error("Expected a real number")]
+    #[diagnostic(code(E0302), help = "Expected a real value, got: {0}")]
+    AsRealFailed(String, #[label = "{0}"] SourceSpan),
+    #[error("Expected a boolean")]
+    #[diagnostic(code(E0303), help = "Expected a boolean value, got: {0}")]
+    AsBooleanFailed(String, #[label = "{0}"] SourceSpan),
+    #[error("Expected a text")]
+    #[diagnostic(code(E0304), help = "Expected a text value, got: {0}")]
+    AsTextFailed(String, #[label = "{0}"] SourceSpan),
+    #[error("Type mismatch in `=` comparison")]
     #[diagnostic(
+        code(E0305),
         help = "Equal operator can only be used for Number=Number or Text=Text, got: {0} = {1}"
     )]
     EqualTypeMismatch(
@@ -23,7 +31,9 @@ pub enum RuntimeError {
         #[label = "{0}"] SourceSpan,
         #[label = "{1}"] SourceSpan,
     ),
+    #[error("Type mismatch in `<` comparison")]
     #[diagnostic(
+        code(E0306),
         help = "Less operator can only be used for Number=Number or Text=Text, got: {0} < {1}"
     )]
     LessTypeMismatch(
@@ -32,28 +42,144 @@ pub enum RuntimeError {
         #[label = "{0}"] SourceSpan,
         #[label = "{1}"] SourceSpan,
     ),
-    #[diagnostic(help = "Unexpected operator for a binary expression: {0}")]
+    #[error("Number and Real values mixed")]
+    #[diagnostic(
+        code(E0307),
+        help = "Number and Real values cannot be mixed, got: {0} and {1}. Use to_real(number) or to_int(real) to convert"
+    )]
+    NumberRealMismatch(
+        String,
+        String,
+        #[label = "{0}"] SourceSpan,
+        #[label = "{1}"] SourceSpan,
+    ),
+    #[error("Type mismatch in `<>` comparison")]
+    #[diagnostic(
+        code(E0308),
+        help = "Not equal operator can only be used for Number<>Number or Text<>Text, got: {0} <> {1}"
+    )]
+    NotEqualTypeMismatch(
+        String,
+        String,
+        #[label = "{0}"] SourceSpan,
+        #[label = "{1}"] SourceSpan,
+    ),
+    #[error("Type mismatch in comparison")]
+    #[diagnostic(
+        code(E0309),
+        help = "Comparison operators can only be used for Number and Number or Text and Text, got: {0} and {1}"
+    )]
+    ComparisonTypeMismatch(
+        String,
+        String,
+        #[label = "{0}"] SourceSpan,
+        #[label = "{1}"] SourceSpan,
+    ),
+    #[error("Array index out of bounds")]
+    #[diagnostic(
+        code(E0310),
+        help = "Index {0} is out of bounds for an array of length {1}"
+    )]
+    IndexOutOfBounds(i64, usize, #[label = "index out of bounds"] SourceSpan),
+    #[error("Indexing a value that is not an array")]
+    #[diagnostic(code(E0311), help = "Only arrays can be indexed, got: {0}")]
+    IndexNonArray(String, #[label = "{0}"] SourceSpan),
+    #[error("Array index is not a number")]
+    #[diagnostic(code(E0312), help = "Array index must be a number, got: {0}")]
+    IndexNonNumeric(String, #[label = "{0}"] SourceSpan),
+    #[error("Unknown builtin function")]
+    #[diagnostic(code(E0313))]
+    UnknownBuiltin(
+        String,
+        #[label = "{0}"] SourceSpan,
+        #[help] String,
+        Option<Suggestion>,
+    ),
+    #[error("Wrong number of arguments to a builtin function")]
+    #[diagnostic(
+        code(E0314),
+        help = "Builtin function {0} takes {1} argument(s), got: {2}"
+    )]
+    BuiltinArgumentCount(String, usize, usize, #[label = "{0}"] SourceSpan),
+    #[error("Wrong argument type for a builtin function")]
+    #[diagnostic(
+        code(E0315),
+        help = "Builtin function {0} expected an argument of type {1}, got: {2}"
+    )]
+    BuiltinArgumentType(String, String, String, #[label = "{2}"] SourceSpan),
+    #[error("Text is not a number")]
+    #[diagnostic(code(E0316), help = "Could not parse the text {0:?} as a number (i64)")]
+    ParseIntFailed(String, #[label = "not a number"] SourceSpan),
+    #[error("Substring out of bounds")]
+    #[diagnostic(
+        code(E0317),
+        help = "Substring from {0} to {1} is out of bounds for a text of length {2}. Indices count characters from zero, and the end is exclusive"
+    )]
+    SubstringOutOfBounds(i64, i64, usize, #[label = "out of bounds"] SourceSpan),
+    #[error("Unexpected binary operator")]
+    #[diagnostic(code(E0318), help = "Unexpected operator for a binary expression: {0}")]
     UnexpectedBinaryOperator(String, #[label = "{0}"] SourceSpan),
-    #[diagnostic(help = "Unexpected value for a literal expression: {0}")]
+    #[error("Unexpected literal")]
+    #[diagnostic(code(E0319), help = "Unexpected value for a literal expression: {0}")]
     UnexpectedLiteral(String, #[label = "{0}"] SourceSpan),
-    #[diagnostic(help = "Unexpected value for a logical operator: {0}")]
+    #[error("Unexpected logical operator")]
+    #[diagnostic(code(E0320), help = "Unexpected value for a logical operator: {0}")]
     UnexpectedLogicalOperator(String, #[label = "{0}"] SourceSpan),
-    #[diagnostic(help = "Unexpected value for a unary operator: {0}")]
+    #[error("Unexpected unary operator")]
+    #[diagnostic(code(E0321), help = "Unexpected value for a unary operator: {0}")]
     UnexpectedUnaryOperator(String, #[label = "{0}"] SourceSpan),
-    #[diagnostic(help = "Assertion statement must evaluate to true or false")]
+    #[error("Assertion is not a boolean")]
+    #[diagnostic(
+        code(E0322),
+        help = "Assertion statement must evaluate to true or false"
+    )]
     AssertExprNotTruthy(#[label = "not a truthy statement"] SourceSpan),
-    #[diagnostic(help = "Assertion failed")]
+    #[error("Assertion failed")]
+    #[diagnostic(code(E0323), help = "Assertion failed")]
     AssertionFailed(#[label = "false"] SourceSpan),
-    #[diagnostic(help = "Variable assignment failed during for loop")]
+    #[error("For loop control variable assignment failed")]
+    #[diagnostic(code(E0324), help = "Variable assignment failed during for loop")]
     ForBadAssignment(String, #[label = "the variable"] SourceSpan),
-    #[diagnostic(help = "End of the for loop should be larger than the start")]
+    #[error("For loop range is reversed")]
+    #[diagnostic(
+        code(E0325),
+        help = "End of the for loop should be larger than the start"
+    )]
     ForEndLarger(
         #[label = "larger"] SourceSpan,
         #[label = "smaller"] SourceSpan,
     ),
+    #[error("For loop control variable modified")]
+    #[diagnostic(
+        code(E0326),
+        help = "The for control variable behaves like a constant inside the loop. Use a different variable instead"
+    )]
+    ForVariableModified(
+        String,
+        #[label = "Control variable {0} of this loop"] SourceSpan,
+        #[label = "Cannot be modified inside the loop"] SourceSpan,
+    ),
+    #[error("For loop range end is not a number")]
+    #[diagnostic(
+        code(E0327),
+        help = "End of the for loop range must be a number, got: {0}"
+    )]
     ForEndNonNumeric(String, #[label = "{0}"] SourceSpan),
+    #[error("For loop iterates over a value that is not an array")]
+    #[diagnostic(
+        code(E0328),
+        help = "A for loop without a range must iterate over an array, got: {0}"
+    )]
+    ForIterateNonArray(String, #[label = "{0}"] SourceSpan),
+    #[error("For loop range start is not a number")]
+    #[diagnostic(
+        code(E0329),
+        help = "Start of the for loop range must be a number, got: {0}"
+    )]
     ForStartNonNumeric(String, #[label = "{0}"] SourceSpan),
+    #[error("Type mismatch in `+` operation")]
     #[diagnostic(
+        code(E0330),
         help = "Plus operator can only be used for Number+Number or Text+Text, got: {0} + {1}"
     )]
     PlusTypeMismatch(
@@ -62,33 +188,105 @@ pub enum RuntimeError {
         #[label = "{0}"] SourceSpan,
         #[label = "{1}"] SourceSpan,
     ),
-    #[diagnostic(help = "Failed to flush stdout after print")]
-    PrintCouldNotFlush,
-    #[diagnostic(help = "Failed to read a variable from stdin")]
-    ReadLineFailed,
+    #[error("Printing failed")]
+    #[diagnostic(code(E0331), help = "Failed to flush stdout after print")]
+    PrintCouldNotFlush(#[label = "print"] SourceSpan),
+    #[error("Reading from stdin failed")]
+    #[diagnostic(code(E0332), help = "Failed to read a variable from stdin")]
+    ReadLineFailed(#[label = "read"] SourceSpan),
+    #[error("Reading into an array")]
     #[diagnostic(
+        code(E0333),
+        help = "Arrays cannot be read from stdin. Read into a variable and assign it to an element instead"
+    )]
+    ReadArray(#[label = "array variable"] SourceSpan),
+    #[error("Reading into a Nothing value")]
+    #[diagnostic(
+        code(E0334),
         help = "Internal compiler error. Tried to read a variable into a Nothing object."
     )]
-    ReadNothing,
-    #[diagnostic(help = "Failed to parse a variable from stdin as a boolean")]
-    ReadParseBoolFailed,
-    #[diagnostic(help = "Failed to parse a variable from stdin as an integer (i64)")]
-    ReadParseIntFailed,
-    #[diagnostic(help(
-        "Try removing the latter `var` to reassign, or use a different identifier"
-    ))]
+    ReadNothing(#[label = "read"] SourceSpan),
+    #[error("Input is not a boolean")]
+    #[diagnostic(
+        code(E0335),
+        help = "Failed to parse the input {0:?} from stdin as a boolean"
+    )]
+    ReadParseBoolFailed(String, #[label = "read"] SourceSpan),
+    #[error("Input is not a number")]
+    #[diagnostic(
+        code(E0336),
+        help = "Failed to parse the input {0:?} from stdin as an integer (i64)"
+    )]
+    ReadParseIntFailed(String, #[label = "read"] SourceSpan),
+    #[error("Input is not a real number")]
+    #[diagnostic(
+        code(E0337),
+        help = "Failed to parse the input {0:?} from stdin as a real number (f64)"
+    )]
+    ReadParseRealFailed(String, #[label = "read"] SourceSpan),
+    #[error("Variable declared twice")]
+    #[diagnostic(
+        code(E0338),
+        help("Try removing the latter `var` to reassign, or use a different identifier")
+    )]
     VariableReDeclaration(#[label = "Attempted to re-declare existing variable name"] SourceSpan),
-    #[diagnostic(help("Use the keyword `var` to declare the variable"))]
+    #[error("Assignment to an undeclared variable")]
+    #[diagnostic(code(E0339), help("Use the keyword `var` to declare the variable"))]
     VariableAssignToUndeclared(
         #[label = "Attempted to assign to a variable that has not been declared"] SourceSpan,
     ),
-    #[diagnostic(help = "Unable to find variable with name: {0}")]
-    VariableGetFailed(String), // TODO: span
-    #[diagnostic(help = "Variable assignment type mismatch, expected: {0} got: {1}")]
+    #[error("Undeclared variable")]
+    #[diagnostic(code(E0340))]
+    VariableGetFailed(
+        String,
+        #[label = "undeclared variable"] SourceSpan,
+        #[help] String,
+        Option<Suggestion>,
+    ),
+    #[error("Type mismatch in assignment")]
+    #[diagnostic(
+        code(E0341),
+        help = "Variable assignment type mismatch, expected: {0} got: {1}"
+    )]
     VariableAssignTypeMismatch(
         String,
         String,
         #[label = "{0}"] SourceSpan,
         #[label = "{1}"] SourceSpan,
     ),
+    #[error("Division by zero")]
+    #[diagnostic(code(E0342))]
+    DivisionByZero(
+        #[label = "Dividing this number"] SourceSpan,
+        #[label = "by zero"] SourceSpan,
+    ),
+    #[error("Integer overflow")]
+    #[diagnostic(
+        code(E0343),
+        help(
+            "Numbers are 64-bit signed integers, from -9223372036854775808 to 9223372036854775807"
+        )
+    )]
+    IntegerOverflow(#[label = "The result of this expression does not fit in a number"] SourceSpan),
+    #[error("Execution stopped")]
+    #[diagnostic(code(E0344))]
+    Stopped(#[label = "Stopped before this statement"] SourceSpan),
+    #[error("Invalid bytecode")]
+    #[diagnostic(
+        code(E0345),
+        help = "Internal compiler error. The virtual machine cannot execute the instruction: {0}"
+    )]
+    InvalidBytecode(String, #[label = "{0}"] SourceSpan),
+}
+
+impl RuntimeError {
+    /// Returns the suggested fix for this error, if any
+    #[must_use]
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        match self {
+            RuntimeError::UnknownBuiltin(_, _, _, suggestion)
+            | RuntimeError::VariableGetFailed(_, _, _, suggestion) => suggestion.as_ref(),
+            _ => None,
+        }
+    }
 }
@@ -1,53 +1,115 @@
 use std::fmt::Display;
 
 use miette::Result;
+use serde::{Deserialize, Serialize};
 
+use crate::parsing::VarType;
 use crate::runtime::RuntimeError::{self, *};
+use crate::span::StartEndSpan;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// The main enum of the runtime values within the language interpretation process
 pub enum Object {
     /// Number value
     Number(i64),
+    /// Real value
+    Real(f64),
     /// Text value
     Text(String),
     /// Boolean value
     Boolean(bool),
+    /// Fixed-size array value. All elements have the same type
+    Array(Vec<Object>),
     /// Empty value
     Nothing,
 }
 
 impl Object {
-    /// Fallible cast of an [Object] to an [i64].
-    pub fn as_numeric(&self) -> Result<i64, RuntimeError> {
+    /// Fallible cast of an [Object] to an [i64]. The span of the originating expression is used for the error report.
+    pub fn as_numeric(&self, span: StartEndSpan) -> Result<i64, RuntimeError> {
         match self {
             Object::Number(n) => Ok(*n),
-            _ => Err(AsNumericFailed(self.to_string())),
+            _ => Err(AsNumericFailed(self.kind_to_string(), span.into())),
         }
     }
 
-    /// Fallible cast of an [Object] to a [bool].
-    pub fn as_bool(&self) -> Result<bool, RuntimeError> {
+    /// Fallible cast of an [Object] to an [f64]. The span of the originating expression is used for the error report.
+    pub fn as_real(&self, span: StartEndSpan) -> Result<f64, RuntimeError> {
+        match self {
+            Object::Real(n) => Ok(*n),
+            _ => Err(AsRealFailed(self.kind_to_string(), span.into())),
+        }
+    }
+
+    /// Fallible cast of an [Object] to a [bool]. The span of the originating expression is used for the error report.
+    pub fn as_bool(&self, span: StartEndSpan) -> Result<bool, RuntimeError> {
         match self {
             Object::Boolean(b) => Ok(*b),
-            _ => Err(AsBooleanFailed(self.to_string())),
+            _ => Err(AsBooleanFailed(self.kind_to_string(), span.into())),
         }
     }
 
-    /// Fallible cast of an [Object] to a [String].
-    pub fn as_text(&self) -> Result<String, RuntimeError> {
+    /// Fallible cast of an [Object] to a [String]. The span of the originating expression is used for the error report.
+    pub fn as_text(&self, span: StartEndSpan) -> Result<String, RuntimeError> {
         match self {
             Object::Text(s) => Ok(s.to_string()),
-            _ => Err(AsTextFailed(self.to_string())),
+            _ => Err(AsTextFailed(self.kind_to_string(), span.into())),
         }
     }
 
+    /// Returns the default value of a variable of the given type
+    #[must_use]
+    pub fn default_value(kind: &VarType) -> Self {
+        // Language spec: "If not explicitly initialized, variables are assigned an appropriate default value."
+        match kind {
+            VarType::Boolean => Object::Boolean(false),
+            VarType::Number => Object::Number(0),
+            VarType::Real => Object::Real(0.0),
+            VarType::Text => Object::Text("".to_owned()),
+            VarType::Array(kind, size) => Object::Array(vec![Object::default_value(kind); *size]),
+        }
+    }
+
+    /// Parses a line of input read into a variable with this value, returning the new value of the same type.
+    /// Texts keep the whole line including the newline, other values are parsed from the trimmed line.
+    /// The span of the `read` statement is used for the error report.
+    pub fn parse_input(&self, line: String, span: StartEndSpan) -> Result<Object, RuntimeError> {
+        let input = line.trim();
+        let value = match self {
+            Object::Number(_) => Object::Number(
+                input
+                    .parse()
+                    .map_err(|_| ReadParseIntFailed(input.to_owned(), span.into()))?,
+            ),
+            Object::Real(_) => Object::Real(
+                input
+                    .parse()
+                    .map_err(|_| ReadParseRealFailed(input.to_owned(), span.into()))?,
+            ),
+            Object::Text(_) => Object::Text(line),
+            Object::Boolean(_) => Object::Boolean(
+                input
+                    .parse()
+                    .map_err(|_| ReadParseBoolFailed(input.to_owned(), span.into()))?,
+            ),
+            Object::Array(_) => return Err(ReadArray(span.into())),
+            Object::Nothing => return Err(ReadNothing(span.into())),
+        };
+        Ok(value)
+    }
+
     /// Returns the type of the object as a string. Used for diagnostic purposes
     pub fn kind_to_string(&self) -> String {
         match self {
             Object::Number(_) => "Number".to_string(),
+            Object::Real(_) => "Real".to_string(),
             Object::Text(_) => "Text".to_string(),
             Object::Boolean(_) => "Boolean".to_string(),
+            // NOTE: arrays always have at least one element, enforced by the parser
+            Object::Array(elements) => match elements.first() {
+                Some(first) => format!("Array[{}] of {}", elements.len(), first.kind_to_string()),
+                None => "Array[0]".to_string(),
+            },
             Object::Nothing => "Nothing".to_string(),
         }
     }
@@ -57,8 +119,20 @@ impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Object::Number(val) => write!(f, "{val}"),
+            // NOTE: the Debug format is the shortest representation that round-trips, and always shows a fractional part or an exponent, e.g. `1.0` or `1e100`
+            Object::Real(val) => write!(f, "{val:?}"),
             Object::Text(val) => write!(f, "{val}"),
             Object::Boolean(val) => write!(f, "{val}"),
+            Object::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                write!(f, "]")
+            }
             Object::Nothing => write!(f, "Nothing"),
         }
     }
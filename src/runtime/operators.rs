@@ -0,0 +1,192 @@
+//! Operators of the Mini-PL language, shared by the [Interpreter](crate::visitors::Interpreter) and the
+//! [VirtualMachine](crate::bytecode::VirtualMachine) so that both evaluate expressions in exactly the same way.
+
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken::{
+    Bang, Equal, Greater, GreaterEqual, Less, LessEqual, Minus, NotEqual, Plus, Slash, Star,
+};
+use crate::tokens::Token;
+
+/// Applies the binary operator to the evaluated operands. The spans are those of the operand expressions and the whole binary expression
+pub(crate) fn binary(
+    operator: &Token,
+    left: &Object,
+    right: &Object,
+    left_span: StartEndSpan,
+    right_span: StartEndSpan,
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    let tokentype = operator.tokentype();
+    // Number and Real values are never converted implicitly
+    if let (Object::Number(_), Object::Real(_)) | (Object::Real(_), Object::Number(_)) =
+        (left, right)
+    {
+        return Err(RuntimeError::NumberRealMismatch(
+            left.kind_to_string(),
+            right.kind_to_string(),
+            left_span.into(),
+            right_span.into(),
+        ));
+    }
+    let result = match tokentype {
+        Minus | Slash | Star if matches!(left, Object::Real(_)) => {
+            let (l, r) = (left.as_real(left_span)?, right.as_real(right_span)?);
+            Object::Real(match tokentype {
+                Minus => l - r,
+                Slash => l / r,
+                _ => l * r,
+            })
+        }
+        Minus | Slash | Star => {
+            let (l, r) = (left.as_numeric(left_span)?, right.as_numeric(right_span)?);
+            let result = match tokentype {
+                Minus => l.checked_sub(r),
+                Slash if r == 0 => {
+                    return Err(RuntimeError::DivisionByZero(
+                        left_span.into(),
+                        right_span.into(),
+                    ))
+                }
+                Slash => l.checked_div(r),
+                _ => l.checked_mul(r),
+            };
+            Object::Number(result.ok_or(RuntimeError::IntegerOverflow(span.into()))?)
+        }
+        Plus => match (left, right) {
+            (Object::Number(l), Object::Number(r)) => Object::Number(
+                l.checked_add(*r)
+                    .ok_or(RuntimeError::IntegerOverflow(span.into()))?,
+            ),
+            (Object::Real(_), Object::Real(_)) => {
+                Object::Real(left.as_real(left_span)? + right.as_real(right_span)?)
+            }
+            (Object::Text(_), Object::Text(_)) => Object::Text(format!(
+                "{}{}",
+                left.as_text(left_span)?,
+                right.as_text(right_span)?
+            )),
+            (l_object, r_object) => {
+                return Err(RuntimeError::PlusTypeMismatch(
+                    l_object.kind_to_string(),
+                    r_object.kind_to_string(),
+                    left_span.into(),
+                    right_span.into(),
+                ))
+            }
+        },
+        Equal => match (left, right) {
+            (Object::Number(_), Object::Number(_)) => {
+                Object::Boolean(left.as_numeric(left_span)? == right.as_numeric(right_span)?)
+            }
+            (Object::Real(_), Object::Real(_)) => {
+                Object::Boolean(left.as_real(left_span)? == right.as_real(right_span)?)
+            }
+            (Object::Text(_), Object::Text(_)) => {
+                Object::Boolean(left.as_text(left_span)? == right.as_text(right_span)?)
+            }
+            (l_object, r_object) => {
+                return Err(RuntimeError::EqualTypeMismatch(
+                    l_object.kind_to_string(),
+                    r_object.kind_to_string(),
+                    left_span.into(),
+                    right_span.into(),
+                ))
+            }
+        },
+        Less => match (left, right) {
+            (Object::Number(_), Object::Number(_)) => {
+                Object::Boolean(left.as_numeric(left_span)? < right.as_numeric(right_span)?)
+            }
+            (Object::Real(_), Object::Real(_)) => {
+                Object::Boolean(left.as_real(left_span)? < right.as_real(right_span)?)
+            }
+            (Object::Text(_), Object::Text(_)) => {
+                Object::Boolean(left.as_text(left_span)? < right.as_text(right_span)?)
+            }
+            (l_object, r_object) => {
+                return Err(RuntimeError::LessTypeMismatch(
+                    l_object.kind_to_string(),
+                    r_object.kind_to_string(),
+                    left_span.into(),
+                    right_span.into(),
+                ))
+            }
+        },
+        NotEqual => match (left, right) {
+            (Object::Number(_), Object::Number(_)) => {
+                Object::Boolean(left.as_numeric(left_span)? != right.as_numeric(right_span)?)
+            }
+            (Object::Real(_), Object::Real(_)) => {
+                Object::Boolean(left.as_real(left_span)? != right.as_real(right_span)?)
+            }
+            (Object::Text(_), Object::Text(_)) => {
+                Object::Boolean(left.as_text(left_span)? != right.as_text(right_span)?)
+            }
+            (l_object, r_object) => {
+                return Err(RuntimeError::NotEqualTypeMismatch(
+                    l_object.kind_to_string(),
+                    r_object.kind_to_string(),
+                    left_span.into(),
+                    right_span.into(),
+                ))
+            }
+        },
+        LessEqual | Greater | GreaterEqual => {
+            // NOTE: comparisons involving a NaN are always false
+            let ordering = match (left, right) {
+                (Object::Number(l), Object::Number(r)) => Some(l.cmp(r)),
+                (Object::Real(l), Object::Real(r)) => l.partial_cmp(r),
+                (Object::Text(l), Object::Text(r)) => Some(l.cmp(r)),
+                (l_object, r_object) => {
+                    return Err(RuntimeError::ComparisonTypeMismatch(
+                        l_object.kind_to_string(),
+                        r_object.kind_to_string(),
+                        left_span.into(),
+                        right_span.into(),
+                    ))
+                }
+            };
+            Object::Boolean(match tokentype {
+                LessEqual => matches!(ordering, Some(o) if o.is_le()),
+                Greater => matches!(ordering, Some(o) if o.is_gt()),
+                _ => matches!(ordering, Some(o) if o.is_ge()),
+            })
+        }
+        _ => {
+            return Err(RuntimeError::UnexpectedBinaryOperator(
+                format!("{:?}", operator.token),
+                operator.span.into(),
+            ))
+        }
+    };
+    Ok(result)
+}
+
+/// Applies the unary operator to the evaluated operand. The spans are those of the operand expression and the whole unary expression
+pub(crate) fn unary(
+    operator: &Token,
+    right: &Object,
+    right_span: StartEndSpan,
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    let result = match operator.tokentype() {
+        Minus => match right {
+            Object::Real(r) => Object::Real(-r),
+            _ => Object::Number(
+                right
+                    .as_numeric(right_span)?
+                    .checked_neg()
+                    .ok_or(RuntimeError::IntegerOverflow(span.into()))?,
+            ),
+        },
+        Bang => Object::Boolean(!right.as_bool(right_span)?),
+        _ => {
+            return Err(RuntimeError::UnexpectedUnaryOperator(
+                format!("{:?}", operator.token),
+                operator.span.into(),
+            ))
+        }
+    };
+    Ok(result)
+}
@@ -0,0 +1,174 @@
+//! String builtin functions. All indices and lengths count Unicode characters, not bytes.
+
+use crate::runtime::builtins::{argument_type_error, expect_arguments};
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+
+/// Internal helper: returns the text argument at the given position, or an argument type error
+fn text_argument<'a>(
+    name: &str,
+    arguments: &'a [(Object, StartEndSpan)],
+    position: usize,
+) -> Result<&'a str, RuntimeError> {
+    match &arguments[position] {
+        (Object::Text(text), _) => Ok(text),
+        argument => Err(argument_type_error(name, "Text", argument)),
+    }
+}
+
+/// Internal helper: returns the number argument at the given position, or an argument type error
+fn number_argument(
+    name: &str,
+    arguments: &[(Object, StartEndSpan)],
+    position: usize,
+) -> Result<i64, RuntimeError> {
+    match &arguments[position] {
+        (Object::Number(n), _) => Ok(*n),
+        argument => Err(argument_type_error(name, "Number", argument)),
+    }
+}
+
+/// `substring(text, start, end)`: returns the characters from `start` up to, but not including, `end`
+pub(crate) fn substring(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("substring", arguments, 3, span)?;
+    let text = text_argument("substring", arguments, 0)?;
+    let start = number_argument("substring", arguments, 1)?;
+    let end = number_argument("substring", arguments, 2)?;
+    let length = text.chars().count();
+    match (usize::try_from(start), usize::try_from(end)) {
+        (Ok(s), Ok(e)) if s <= e && e <= length => {
+            Ok(Object::Text(text.chars().skip(s).take(e - s).collect()))
+        }
+        _ => Err(RuntimeError::SubstringOutOfBounds(
+            start,
+            end,
+            length,
+            span.into(),
+        )),
+    }
+}
+
+/// `char_at(text, index)`: returns the character at the given index as a text of length one
+pub(crate) fn char_at(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("char_at", arguments, 2, span)?;
+    let text = text_argument("char_at", arguments, 0)?;
+    let index = number_argument("char_at", arguments, 1)?;
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| text.chars().nth(i))
+        .map(|char| Object::Text(char.to_string()))
+        .ok_or_else(|| {
+            RuntimeError::IndexOutOfBounds(index, text.chars().count(), arguments[1].1.into())
+        })
+}
+
+/// `find(text, pattern)`: returns the character index of the first occurrence of the pattern, or -1 if it is not found
+pub(crate) fn find(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("find", arguments, 2, span)?;
+    let text = text_argument("find", arguments, 0)?;
+    let pattern = text_argument("find", arguments, 1)?;
+    let index = match text.find(pattern) {
+        Some(byte_index) => text[..byte_index].chars().count() as i64,
+        None => -1,
+    };
+    Ok(Object::Number(index))
+}
+
+/// `to_upper(text)`: returns the text in upper case
+pub(crate) fn to_upper(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("to_upper", arguments, 1, span)?;
+    Ok(Object::Text(
+        text_argument("to_upper", arguments, 0)?.to_uppercase(),
+    ))
+}
+
+/// `to_lower(text)`: returns the text in lower case
+pub(crate) fn to_lower(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("to_lower", arguments, 1, span)?;
+    Ok(Object::Text(
+        text_argument("to_lower", arguments, 0)?.to_lowercase(),
+    ))
+}
+
+/// `to_string(value)`: returns the printed representation of a number, real number or boolean
+pub(crate) fn to_string(
+    arguments: &[(Object, StartEndSpan)],
+    span: StartEndSpan,
+) -> Result<Object, RuntimeError> {
+    expect_arguments("to_string", arguments, 1, span)?;
+    match &arguments[0] {
+        (object @ (Object::Number(_) | Object::Real(_) | Object::Boolean(_)), _) => {
+            Ok(Object::Text(object.to_string()))
+        }
+        argument => Err(argument_type_error(
+            "to_string",
+            "Number, Real or Boolean",
+            argument,
+        )),
+    }
+}
+
+/// Parses a text argument into a number, with an error pointing at the argument
+pub(crate) fn parse_int(argument: &(Object, StartEndSpan)) -> Result<Object, RuntimeError> {
+    match argument {
+        (Object::Text(text), span) => text
+            .trim()
+            .parse()
+            .map(Object::Number)
+            .map_err(|_| RuntimeError::ParseIntFailed(text.clone(), (*span).into())),
+        argument => Err(argument_type_error("to_int", "Text", argument)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn text(s: &str) -> (Object, StartEndSpan) {
+        (Object::Text(s.to_owned()), StartEndSpan::new(0, 0))
+    }
+
+    fn number(n: i64) -> (Object, StartEndSpan) {
+        (Object::Number(n), StartEndSpan::new(0, 0))
+    }
+
+    #[test]
+    fn substring_counts_characters() {
+        let span = StartEndSpan::new(0, 0);
+        let result = substring(&[text("häät"), number(1), number(3)], span).unwrap();
+        assert_eq!(result.to_string(), "ää");
+    }
+
+    #[test]
+    fn find_returns_character_index() {
+        let span = StartEndSpan::new(0, 0);
+        let result = find(&[text("äiti"), text("ti")], span).unwrap();
+        assert_eq!(result.to_string(), "2");
+    }
+
+    #[test]
+    fn char_at_out_of_bounds() {
+        let span = StartEndSpan::new(0, 0);
+        let result = char_at(&[text("ä"), number(1)], span);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::IndexOutOfBounds(1, 1, _))
+        ));
+    }
+}
@@ -0,0 +1,492 @@
+//! Snapshots of paused Mini-PL programs, see `minipl run --snapshot` and `minipl resume`.
+//!
+//! The [Pause] hook stops the [Interpreter](crate::visitors::Interpreter) before a statement: after a number of steps, on a
+//! line, or on a `read` statement awaiting input. The state of the stopped interpreter, see [ExecutionState], is saved into a
+//! [Snapshot] together with the source code of the program, so that the program can be resumed later in another process with
+//! [Interpreter::restore](crate::visitors::Interpreter::restore) and [Interpreter::resume](crate::visitors::Interpreter::resume).
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::rc::Rc;
+
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::lexing::Lexer;
+use crate::parsing::{Parser, Statement, Stmt};
+use crate::runtime::{Console, Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The values a `for` loop has yet to assign to its control variable
+pub enum LoopValues {
+    /// The numbers from `next` to `end`, inclusive. Empty if `next` is larger than `end`
+    Range {
+        /// The next number
+        next: i64,
+        /// The last number
+        end: i64,
+    },
+    /// The elements of an array from the index `next` on. The array is copied when the loop starts
+    Elements {
+        /// The elements of the array
+        elements: Vec<Object>,
+        /// The index of the next element
+        next: usize,
+    },
+}
+
+impl LoopValues {
+    /// Returns the next value, and advances past it
+    pub(crate) fn next_value(&mut self) -> Option<Object> {
+        match self {
+            LoopValues::Range { next, end } => {
+                if next > end {
+                    return None;
+                }
+                let value = *next;
+                // The last number of the range may be the largest one
+                match value.checked_add(1) {
+                    Some(following) => *next = following,
+                    None => (*next, *end) = (1, 0),
+                }
+                Some(Object::Number(value))
+            }
+            LoopValues::Elements { elements, next } => {
+                let value = elements.get(*next).cloned();
+                *next += 1;
+                value
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A `for` loop being executed
+pub struct LoopState {
+    /// The name of the control variable
+    pub variable: String,
+    /// The span of the loop header
+    pub header: StartEndSpan,
+    /// The values the loop has yet to assign to the control variable
+    pub values: LoopValues,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// The state of an interpreter stopped before a statement
+pub struct ExecutionState {
+    /// The variables of the program, by name
+    pub variables: BTreeMap<String, Object>,
+    /// The index of the statement being executed among the statements of the program, followed by the index of the statement
+    /// being executed in the body of each `for` loop being executed
+    pub positions: Vec<usize>,
+    /// The `for` loops being executed, outermost first
+    pub loops: Vec<LoopState>,
+}
+
+impl ExecutionState {
+    /// Returns the statement the program was stopped at
+    ///
+    /// # Errors
+    /// Returns [SnapshotError::InvalidSnapshot] if the positions are not those of a statement of the program inside the loops
+    pub fn statement<'a>(
+        &self,
+        statements: &'a [Statement],
+    ) -> Result<&'a Statement, SnapshotError> {
+        let invalid = |reason: &str| SnapshotError::InvalidSnapshot(reason.to_owned());
+        if self.positions.len() != self.loops.len() + 1 {
+            return Err(invalid("each `for` loop needs the position in its body"));
+        }
+        let mut block = statements;
+        for (depth, index) in self.positions.iter().enumerate() {
+            let statement = block
+                .get(*index)
+                .ok_or_else(|| invalid("the position is past the end of the statements"))?;
+            let state = match self.loops.get(depth) {
+                Some(state) => state,
+                None => return Ok(statement),
+            };
+            match &statement.stmt {
+                Stmt::Forloop(forloop)
+                    if forloop.header == state.header && forloop.variable == state.variable =>
+                {
+                    block = &forloop.body;
+                }
+                _ => {
+                    return Err(invalid(
+                        "the loops do not match the `for` loops of the program",
+                    ))
+                }
+            }
+        }
+        Err(invalid("the position is missing"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// The reason a program was paused
+pub enum PauseReason {
+    /// The program executed the given number of statements
+    Steps,
+    /// The program reached one of the given lines
+    Line,
+    /// The program is about to read a line of input
+    Read,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A paused program, with everything needed to resume it
+pub struct Snapshot {
+    /// The source code of the program
+    pub source: String,
+    /// The state of the interpreter
+    #[serde(flatten)]
+    pub state: ExecutionState,
+    /// Why the program was paused
+    pub reason: PauseReason,
+    /// The line of the statement the program was paused before
+    pub line: usize,
+    /// The column of the statement the program was paused before
+    pub column: usize,
+    /// The number of statements executed before the pause, in all the runs of the program
+    pub steps: u64,
+    /// The text printed since the program was started or last resumed, e.g. the prompt of the `read` it is paused at
+    pub output: String,
+}
+
+impl Snapshot {
+    /// Parses the snapshot from JSON
+    ///
+    /// # Errors
+    /// Returns [SnapshotError::InvalidSnapshot] if the JSON is not a snapshot
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        serde_json::from_str(json)
+            .map_err(|error| SnapshotError::InvalidSnapshot(error.to_string()))
+    }
+
+    /// Parses the program of the snapshot, and checks that the state points to one of its statements
+    ///
+    /// # Errors
+    /// Returns [SnapshotError::InvalidSnapshot] if the program does not parse, or the state does not match it
+    pub fn statements(&self) -> Result<Vec<Statement>, SnapshotError> {
+        let invalid = |error: &dyn std::fmt::Display| {
+            SnapshotError::InvalidSnapshot(format!("the program does not parse: {error}"))
+        };
+        let tokens = Lexer::new(&self.source)
+            .scan()
+            .map_err(|error| invalid(&error))?;
+        let statements = Parser::new(tokens)
+            .parse()
+            .map_err(|error| invalid(&error))?;
+        self.state.statement(&statements)?;
+        Ok(statements)
+    }
+
+    /// Returns the snapshot as pretty-printed JSON
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+/// Errors in resuming a snapshot
+pub enum SnapshotError {
+    #[error("Invalid snapshot: {0}")]
+    #[diagnostic(
+        code(E0601),
+        help("Snapshots are saved with `minipl run --snapshot <PATH>` and one of the `--pause` options")
+    )]
+    /// The snapshot file could not be parsed, or its state does not match its program
+    InvalidSnapshot(String),
+}
+
+#[derive(Debug, Clone)]
+/// A [Console] that keeps a copy of the text printed to another console, for the output of a [Snapshot].
+///
+/// The clones of a capture share the copy, so that one clone can be the console of the interpreter
+pub struct Capture(Rc<RefCell<(Box<dyn Console>, String)>>);
+
+impl Capture {
+    /// Creates a new [Capture] of the text printed to the console
+    #[must_use]
+    pub fn new(console: Box<dyn Console>) -> Self {
+        Self(Rc::new(RefCell::new((console, String::new()))))
+    }
+
+    /// Returns the text printed so far
+    #[must_use]
+    pub fn output(&self) -> String {
+        self.0.borrow().1.clone()
+    }
+}
+
+impl Console for Capture {
+    fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+        self.0.borrow_mut().0.read_line(buffer)
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut capture = self.0.borrow_mut();
+        capture.0.print(text)?;
+        capture.1.push_str(text);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+/// The hook that pauses the program before a statement, by stopping it with [RuntimeError::Stopped]
+pub struct Pause {
+    lines: SourceLines,
+    /// The number of statements to execute before pausing
+    after: Option<u64>,
+    /// The lines to pause on
+    at: Vec<usize>,
+    /// Whether to pause before `read` statements
+    reads: bool,
+    /// The number of statements executed so far
+    steps: u64,
+    /// Whether the next statement is the one the program was resumed at, which never pauses
+    resumed: bool,
+    paused: Option<(PauseReason, StartEndSpan)>,
+}
+
+impl Pause {
+    /// Creates a new [Pause] for the program with the given source code, that never pauses
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self {
+            lines: SourceLines::new(source),
+            after: None,
+            at: Vec::new(),
+            reads: false,
+            steps: 0,
+            resumed: false,
+            paused: None,
+        }
+    }
+
+    /// Pauses after executing the given number of statements
+    #[must_use]
+    pub fn after(mut self, steps: u64) -> Self {
+        self.after = Some(steps);
+        self
+    }
+
+    /// Pauses before the statements on the given lines
+    #[must_use]
+    pub fn at(mut self, lines: Vec<usize>) -> Self {
+        self.at = lines;
+        self
+    }
+
+    /// Pauses before each `read` statement, i.e. whenever the program is about to await input
+    #[must_use]
+    pub fn on_read(mut self) -> Self {
+        self.reads = true;
+        self
+    }
+
+    /// Does not pause before the first statement, as the program is resumed at it
+    #[must_use]
+    pub fn resumed(mut self) -> Self {
+        self.resumed = true;
+        self
+    }
+
+    /// Returns the number of statements executed so far
+    #[must_use]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns why the program was paused and the line and column of the statement it was paused before, if it was paused
+    #[must_use]
+    pub fn paused(&self) -> Option<(PauseReason, usize, usize)> {
+        self.paused.map(|(reason, span)| {
+            let (line, column) = self.lines.line_column(span.start);
+            (reason, line, column)
+        })
+    }
+}
+
+impl Hook for Pause {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        _state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let (line, _) = self.lines.line_column(statement.span.start);
+        let reason = if std::mem::take(&mut self.resumed) {
+            None
+        } else if self.after == Some(self.steps) {
+            Some(PauseReason::Steps)
+        } else if self.at.contains(&line) {
+            Some(PauseReason::Line)
+        } else if self.reads && matches!(statement.stmt, Stmt::Read(_)) {
+            Some(PauseReason::Read)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.paused = Some((reason, statement.span));
+            return Err(RuntimeError::Stopped(statement.span.into()));
+        }
+        self.steps += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::collections::VecDeque;
+    use std::io;
+
+    use super::{Capture, Pause, PauseReason, Snapshot};
+    use crate::lexing::Lexer;
+    use crate::parsing::{Parser, Statement};
+    use crate::runtime::{Console, RuntimeError};
+    use crate::visitors::Interpreter;
+
+    /// A console with scripted input, discarding the output
+    #[derive(Debug, Default)]
+    struct Scripted(VecDeque<&'static str>);
+
+    impl Console for Scripted {
+        fn read_line(&mut self, buffer: &mut String) -> io::Result<usize> {
+            let line = self.0.pop_front().unwrap_or_default();
+            buffer.push_str(line);
+            Ok(line.len())
+        }
+
+        fn print(&mut self, _text: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let tokens = Lexer::new(source).scan().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    /// Runs or resumes the program with the pause and the input, returning the result, the output, and the snapshot if paused
+    fn run(
+        source: &str,
+        snapshot: Option<Snapshot>,
+        pause: Pause,
+        input: &[&'static str],
+    ) -> (Result<(), RuntimeError>, String, Option<Snapshot>) {
+        let statements = parse(source);
+        let capture = Capture::new(Box::new(Scripted(input.iter().copied().collect())));
+        let mut interpreter = Interpreter::with_hook(pause);
+        interpreter.set_console(Box::new(capture.clone()));
+        let result = match snapshot {
+            Some(snapshot) => {
+                snapshot.state.statement(&statements).unwrap();
+                interpreter.restore(snapshot.state);
+                interpreter.resume(&statements)
+            }
+            None => interpreter.eval(&statements),
+        };
+        let state = interpreter.state();
+        let pause = interpreter.into_hook();
+        let snapshot = pause.paused().map(|(reason, line, column)| Snapshot {
+            source: source.to_owned(),
+            state,
+            reason,
+            line,
+            column,
+            steps: pause.steps(),
+            output: capture.output(),
+        });
+        (result, capture.output(), snapshot)
+    }
+
+    const LOOPS: &str = "var i : int;\nvar j : int;\nvar a : array[2] of int;\nfor i in 1..3 do\n  print i;\n  for j in a do\n    a[i - 1 - i / 3 * 2] := i;\n    print j;\n  end for;\nend for;\nprint a[1];";
+
+    #[test]
+    fn resuming_after_any_step_gives_the_same_output() {
+        let (result, expected, _) = run(LOOPS, None, Pause::new(LOOPS), &[]);
+        assert!(result.is_ok());
+        assert_eq!(expected, "1002103122");
+        let mut steps = 0;
+        loop {
+            let (result, before, snapshot) = run(LOOPS, None, Pause::new(LOOPS).after(steps), &[]);
+            let snapshot = match snapshot {
+                Some(snapshot) => snapshot,
+                None => break,
+            };
+            assert!(matches!(result, Err(RuntimeError::Stopped(_))));
+            assert_eq!(snapshot.reason, PauseReason::Steps);
+            let snapshot = Snapshot::from_json(&snapshot.to_json()).unwrap();
+            let pause = Pause::new(LOOPS).resumed();
+            let (result, after, _) = run(LOOPS, Some(snapshot), pause, &[]);
+            assert!(result.is_ok(), "{result:?}");
+            assert_eq!(before + &after, expected, "paused after {steps} steps");
+            steps += 1;
+        }
+        assert_eq!(steps, 23);
+    }
+
+    #[test]
+    fn pauses_in_nested_loops() {
+        let (_, output, snapshot) = run(LOOPS, None, Pause::new(LOOPS).at(vec![8]), &[]);
+        let snapshot = snapshot.unwrap();
+        assert_eq!(output, "1");
+        assert_eq!((snapshot.line, snapshot.column), (8, 5));
+        assert_eq!(snapshot.state.positions, [3, 1, 1]);
+        let variables: Vec<&str> = snapshot
+            .state
+            .loops
+            .iter()
+            .map(|state| state.variable.as_str())
+            .collect();
+        assert_eq!(variables, ["i", "j"]);
+
+        // The breakpoint pauses again in the next iteration, but not at the statement the program was resumed at
+        let pause = Pause::new(LOOPS).at(vec![8]).resumed();
+        let (_, output, snapshot) = run(LOOPS, Some(snapshot), pause, &[]);
+        assert_eq!(output, "0");
+        assert_eq!(snapshot.unwrap().state.positions, [3, 1, 1]);
+    }
+
+    #[test]
+    fn pauses_awaiting_input() {
+        let source = "var n : int;\nprint \"n?\";\nread n;\nprint n * 2;";
+        let pause = Pause::new(source).on_read();
+        let (_, output, snapshot) = run(source, None, pause, &["1\n"]);
+        let snapshot = snapshot.unwrap();
+        assert_eq!((snapshot.reason, snapshot.line), (PauseReason::Read, 3));
+        assert_eq!(output, snapshot.output);
+        assert_eq!(output, "n?");
+
+        let pause = Pause::new(source).on_read().resumed();
+        let (result, output, snapshot) = run(source, Some(snapshot), pause, &["21\n"]);
+        assert!(result.is_ok());
+        assert!(snapshot.is_none());
+        assert_eq!(output, "42");
+    }
+
+    #[test]
+    fn invalid_positions() {
+        let (_, _, snapshot) = run(LOOPS, None, Pause::new(LOOPS).at(vec![5]), &[]);
+        let mut snapshot = snapshot.unwrap();
+        let statements = parse(LOOPS);
+        assert_eq!(
+            snapshot.state.statement(&statements).unwrap().span.start,
+            70
+        );
+        snapshot.state.positions[0] = 2;
+        assert!(snapshot.state.statement(&statements).is_err());
+        snapshot.state.positions = vec![3];
+        assert!(snapshot.state.statement(&statements).is_err());
+        assert!(Snapshot::from_json("{}").is_err());
+    }
+}
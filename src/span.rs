@@ -1,4 +1,5 @@
 use miette::SourceSpan;
+use serde::{Deserialize, Serialize};
 
 /// The span i.e. the location descriptor of the token, in terms of bytes in the source code.
 ///
@@ -7,7 +8,7 @@ use miette::SourceSpan;
 ///
 /// There is also a conversion method that converts from `(start, end)` to `(start, length)` style [`SourceSpan`] used in [`miette`].
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StartEndSpan {
     /// Start location of the span, as a byte offset, inclusive
     pub start: usize,
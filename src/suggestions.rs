@@ -0,0 +1,125 @@
+//! "Did you mean" suggestions for misspelled identifiers and keywords, based on edit distance.
+
+use std::fmt::Display;
+
+use crate::span::StartEndSpan;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A suggested replacement for a span of the source code, e.g. for a misspelled variable name.
+/// Shown as the help text of a diagnostic, and usable as an automatic quick fix by editors.
+pub struct Suggestion {
+    /// The text to replace the span with
+    pub replacement: String,
+    /// The span of the source code to replace
+    pub span: StartEndSpan,
+}
+
+impl Suggestion {
+    /// Creates a new [Suggestion] for replacing the given span
+    #[must_use]
+    pub fn new(replacement: &str, span: StartEndSpan) -> Self {
+        Self {
+            replacement: replacement.to_owned(),
+            span,
+        }
+    }
+}
+
+impl Display for Suggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Did you mean `{}`?", self.replacement)
+    }
+}
+
+/// Returns the help text for a diagnostic: the suggestion if there is one, otherwise the given default
+#[must_use]
+pub fn help(suggestion: &Option<Suggestion>, default: &str) -> String {
+    match suggestion {
+        Some(suggestion) => suggestion.to_string(),
+        None => default.to_owned(),
+    }
+}
+
+/// Returns the candidate closest to the given name, if any is close enough to be a likely misspelling.
+/// Ties are broken alphabetically, so that the suggestion does not depend on the order of the candidates.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    // Allow one edit per three characters, and at least one edit
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|&candidate| candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the optimal string alignment distance between the two strings: the number of single-character
+/// insertions, deletions, substitutions and transpositions of adjacent characters needed to turn one into the other
+#[must_use]
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i characters of a and the first j characters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_counts_edits() {
+        assert_eq!(edit_distance("count", "count"), 0);
+        assert_eq!(edit_distance("cout", "count"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn distance_counts_transposition_as_one() {
+        assert_eq!(edit_distance("pritn", "print"), 1);
+    }
+
+    #[test]
+    fn closest_picks_nearest_candidate() {
+        let candidates = ["counter", "count", "amount"];
+        assert_eq!(closest("cont", candidates), Some("count"));
+    }
+
+    #[test]
+    fn closest_rejects_distant_candidates() {
+        let candidates = ["print", "read"];
+        assert_eq!(closest("total", candidates), None);
+    }
+
+    #[test]
+    fn closest_ignores_exact_match() {
+        assert_eq!(closest("x", ["x"]), None);
+    }
+
+    #[test]
+    fn closest_breaks_ties_alphabetically() {
+        assert_eq!(closest("b", ["c", "a"]), Some("a"));
+        assert_eq!(closest("b", ["a", "c"]), Some("a"));
+    }
+}
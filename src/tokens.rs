@@ -3,3 +3,6 @@ pub use token::Token;
 
 mod rawtoken;
 pub use rawtoken::RawToken;
+
+mod interpolation;
+pub use interpolation::InterpolationPart;
@@ -0,0 +1,10 @@
+use crate::tokens::Token;
+
+/// A part of an interpolated string literal, such as `"x = {x}"`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    /// Literal text, with escape sequences already processed
+    Literal(String),
+    /// The tokens of an embedded expression, terminated by an [`EOF`](crate::tokens::RawToken::EOF) token at the closing brace
+    Tokens(Vec<Token>),
+}
@@ -1,4 +1,5 @@
 use crate::lexing::RecoverableLexingError;
+use crate::tokens::InterpolationPart;
 
 /// All raw tokens (also known as lexemes) of the Mini-PL programming language.
 #[derive(Debug, Clone, PartialEq)]
@@ -8,14 +9,24 @@ pub enum RawToken {
     And,
     /// `!` Logical NOT operator
     Bang,
+    /// `[` Left bracket used for array types and indexing
+    BracketLeft,
+    /// `]` Right bracket used for array types and indexing
+    BracketRight,
     /// `:` Colon operator used for type ascription
     Colon,
+    /// `,` Comma used for separating the arguments of a builtin function call
+    Comma,
     /// `=` Equal operator used for equality checking. Note: not used for assignment
     Equal,
-    /// `<` Less operator used for comparison. Note: this is the only comparison operator
+    /// `>` Greater operator used for comparison
+    Greater,
+    /// `<` Less operator used for comparison
     Less,
     /// `-` Minus operator used for substraction
     Minus,
+    /// `|` Logical OR operator
+    Or,
     /// `(` Left paren used for grouping
     ParenLeft,
     /// `)` Right paren used for grouping
@@ -34,16 +45,28 @@ pub enum RawToken {
     Assign,
     /// `..` Range operator used for defining ranges for for loops
     Range,
+    /// `>=` Greater or equal operator used for comparison
+    GreaterEqual,
+    /// `<=` Less or equal operator used for comparison
+    LessEqual,
+    /// `<>` or `!=` Not equal operator used for inequality checking
+    NotEqual,
 
     // Literals
     /// Identifier, a name for a variable. Internally represented as a [String]
     Identifier(String),
     /// Literal number. Internally represented as an [i64]
     Number(i64),
+    /// Literal real number, with a fractional part and/or an exponent. Internally represented as an [f64]
+    RealNumber(f64),
     /// Literal string. Internally represented as a [String]
     Text(String),
+    /// Literal string with embedded expressions, e.g. `"x = {x}"`. Internally represented as a list of [InterpolationPart]s
+    Interpolation(Vec<InterpolationPart>),
 
     // Keywords
+    /// `array` type keyword for fixed-size array data, used as `array[size] of type`
+    Array,
     /// `assert` used for evaluating truthy statements and stopping execution on false
     Assert,
     /// `bool` type keyword for boolean data
@@ -60,10 +83,14 @@ pub enum RawToken {
     In,
     /// `int` type keyword for numeric data
     Int,
+    /// `of` used in the array type definitions as a keyword before the element type
+    Of,
     /// `print` keyword for printing to standard output
     Print,
     /// `read` keyword for reading a variable from standard input
     Read,
+    /// `real` type keyword for floating-point numeric data
+    Real,
     /// `string` type keyword for string data
     String,
     /// `true` boolean literal
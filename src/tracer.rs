@@ -0,0 +1,246 @@
+//! Execution tracing of Mini-PL programs, see `minipl run --trace`.
+//!
+//! The [Tracer] is a [Hook] of the [Interpreter](crate::visitors::Interpreter) that logs each executed statement, each evaluated
+//! expression with its value, and each write to a variable with its old and new values, in the order they happen.
+//! Expressions and writes are nested under the statement they belong to, and the statements in a `for` loop under the loop.
+
+use std::io::Write;
+
+use serde_json::{json, Value};
+
+use crate::debugger::format_value;
+use crate::parsing::{Expression, Statement};
+use crate::runtime::{Object, RuntimeError};
+use crate::span::StartEndSpan;
+use crate::visitors::codegen::SourceLines;
+use crate::visitors::{Hook, State, VariableWrite};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The output format of the [Tracer]
+pub enum TraceFormat {
+    /// One readable line per event, e.g. `2:6       expression x + 1 => 2 (Number)`
+    Text,
+    /// JSON Lines: one object per event, with the kind of the event in the `event` field
+    Json,
+}
+
+/// The execution tracer. Writes one line per event to its output
+pub struct Tracer<W> {
+    /// The characters of the source code, as the spans are character offsets
+    source: Vec<char>,
+    lines: SourceLines,
+    format: TraceFormat,
+    /// The number of statements being executed, i.e. the nesting of the next event
+    depth: usize,
+    output: W,
+}
+
+impl<W> std::fmt::Debug for Tracer<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tracer")
+            .field("format", &self.format)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> Tracer<W> {
+    /// Creates a new [Tracer] for the program with the given source code, writing in the format to the output
+    pub fn new(source: &str, format: TraceFormat, output: W) -> Self {
+        Self {
+            source: source.chars().collect(),
+            lines: SourceLines::new(source),
+            format,
+            depth: 0,
+            output,
+        }
+    }
+
+    /// Returns the output of the tracer
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    /// Internal helper: returns the first line of the source code of the span
+    fn text(&self, span: StartEndSpan) -> String {
+        let end = span.end.min(self.source.len());
+        let text: String = self.source[span.start.min(end)..end].iter().collect();
+        text.lines()
+            .next()
+            .unwrap_or_default()
+            .trim_end()
+            .to_owned()
+    }
+
+    /// Writes the event: the text format shows the details after the location and the kind, and the JSON format adds the fields to the location
+    fn log(&mut self, event: &str, span: StartEndSpan, details: &str, mut fields: Value) {
+        let (line, column) = self.lines.line_column(span.start);
+        let _ = match self.format {
+            TraceFormat::Text => {
+                let location = format!("{line}:{column}");
+                let indent = "  ".repeat(self.depth);
+                writeln!(self.output, "{location:<8}{indent}{event:<10} {details}")
+            }
+            TraceFormat::Json => {
+                fields["event"] = json!(event);
+                fields["line"] = json!(line);
+                fields["column"] = json!(column);
+                fields["span"] = json!({ "start": span.start, "end": span.end });
+                fields["depth"] = json!(self.depth);
+                writeln!(self.output, "{fields}")
+            }
+        };
+    }
+}
+
+impl<W: Write> Hook for Tracer<W> {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        _state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let text = self.text(statement.span);
+        self.log(
+            "statement",
+            statement.span,
+            &text,
+            json!({ "source": text }),
+        );
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn after_statement(&mut self, _statement: &Statement, _result: &Object) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    fn after_expression(&mut self, expression: &Expression, value: &Object) {
+        let text = self.text(expression.span);
+        let details = format!(
+            "{text} => {} ({})",
+            format_value(value),
+            value.kind_to_string()
+        );
+        let fields = json!({
+            "source": text,
+            "value": to_json(value),
+            "type": value.kind_to_string(),
+        });
+        self.log("expression", expression.span, &details, fields);
+    }
+
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        let target = match write.index {
+            Some(index) => format!("{}[{index}]", write.name),
+            None => write.name.to_owned(),
+        };
+        let kind = write.new.kind_to_string();
+        let new = format_value(write.new);
+        let (event, details) = match write.old {
+            Some(old) => (
+                "assign",
+                format!("{target}: {} → {new} ({kind})", format_value(old)),
+            ),
+            None => ("define", format!("{target} = {new} ({kind})")),
+        };
+        let fields = json!({
+            "name": write.name,
+            "index": write.index,
+            "old": write.old.map(to_json),
+            "new": to_json(write.new),
+            "type": kind,
+        });
+        self.log(event, write.span, &details, fields);
+    }
+}
+
+/// Internal helper: returns the value as JSON. Reals that are not finite become `null`
+fn to_json(value: &Object) -> Value {
+    match value {
+        Object::Number(number) => json!(number),
+        Object::Real(real) => json!(real),
+        Object::Text(text) => json!(text),
+        Object::Boolean(boolean) => json!(boolean),
+        Object::Array(elements) => elements.iter().map(to_json).collect(),
+        Object::Nothing => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use serde_json::Value;
+
+    use super::{TraceFormat, Tracer};
+    use crate::lexing::Lexer;
+    use crate::parsing::Parser;
+    use crate::visitors::Interpreter;
+
+    /// Traces the program, returning the trace
+    fn trace(source: &str, format: TraceFormat) -> String {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::with_hook(Tracer::new(source, format, Vec::new()));
+        interpreter.eval(&statements).unwrap();
+        String::from_utf8(interpreter.into_hook().into_output()).unwrap()
+    }
+
+    #[test]
+    fn text() {
+        let output = trace("var x : int := 1;\nx := x + 1;", TraceFormat::Text);
+        let expected = "\
+1:1     statement  var x : int := 1
+1:16      expression 1 => 1 (Number)
+1:1       define     x = 1 (Number)
+2:1     statement  x := x + 1
+2:6       expression x => 1 (Number)
+2:10      expression 1 => 1 (Number)
+2:6       expression x + 1 => 2 (Number)
+2:3       assign     x: 1 → 2 (Number)
+2:1       expression x := x + 1 => 2 (Number)
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn loops_are_nested() {
+        let source = "var i : int;\nvar a : array[2] of string;\nfor i in 0..1 do\n  a[i] := \"x\";\nend for;";
+        let output = trace(source, TraceFormat::Text);
+        assert!(output.contains(
+            "3:1       assign     i: 0 → 0 (Number)\n4:3       statement  a[i] := \"x\"\n"
+        ));
+        assert!(
+            output.contains("4:8         assign     a[1]: \"\" → \"x\" (Text)\n"),
+            "{output}"
+        );
+        // The control variable is assigned at the depth of the loop after each iteration
+        assert!(output.contains("\n3:1       assign     i: 0 → 1 (Number)\n"));
+    }
+
+    #[test]
+    fn json_lines() {
+        let output = trace("var b : bool;\nb := !b;", TraceFormat::Json);
+        let events: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 7);
+        assert_eq!(events[1]["event"], "define");
+        assert_eq!(events[1]["old"], Value::Null);
+        assert_eq!(events[1]["new"], false);
+        let assign = &events[5];
+        assert_eq!(
+            (
+                &assign["event"],
+                &assign["name"],
+                &assign["old"],
+                &assign["new"]
+            ),
+            (&"assign".into(), &"b".into(), &false.into(), &true.into())
+        );
+        assert_eq!(assign["type"], "Boolean");
+        assert_eq!(assign["line"], 2);
+        assert_eq!(events[6]["source"], "b := !b");
+        assert_eq!(events[6]["depth"], 1);
+    }
+}
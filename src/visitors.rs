@@ -8,6 +8,29 @@ pub use astprinter::ASTPrinter;
 mod interpreter;
 pub use interpreter::Interpreter;
 
+/// Instrumentation of the interpreter, for tools observing the execution
+mod hook;
+pub use hook::{Hook, NoHook, State, VariableWrite};
+
+/// Linter for finding likely mistakes in valid programs
+mod linter;
+pub use linter::Linter;
+
+/// Helpers shared by the code generators
+pub(crate) mod codegen;
+
+/// Code generator compiling the program into bytecode
+mod bytecodegenerator;
+pub use bytecodegenerator::BytecodeGenerator;
+
+/// Code generator compiling the program into C source code
+mod cgenerator;
+pub use cgenerator::CGenerator;
+
+/// Code generator compiling the program into a WebAssembly module
+mod watgenerator;
+pub use watgenerator::WatGenerator;
+
 use miette::Result;
 
 /// The main visitor trait for the visitor pattern. See e.g. [Rust Design Patterns: Visitor](https://rust-unofficial.github.io/patterns/patterns/behavioural/visitor.html)
@@ -18,6 +18,7 @@ pub struct ASTPrinter {
 impl ASTPrinter {
     /// Creates a new [ASTPrinter]
     #[must_use]
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Self {
         Self { nest_level: 0 }
     }
@@ -77,8 +78,12 @@ impl ASTPrinter {
     fn visit_expr(&mut self, expr: &Expr) -> Result<String> {
         match &expr {
             Expr::Assign(a) => self.visit_assign(a),
+            Expr::AssignIndex(a) => self.visit_assign_index(a),
             Expr::Binary(b) => self.visit_binary(b),
+            Expr::Call(c) => self.visit_call(c),
             Expr::Grouping(g) => self.visit_grouping(g),
+            Expr::Index(i) => self.visit_index(i),
+            Expr::Interpolation(i) => self.visit_interpolation(i),
             Expr::Literal(l) => self.visit_literal(l),
             Expr::Logical(l) => self.visit_logical(l),
             Expr::Unary(u) => self.visit_unary(u),
@@ -97,6 +102,17 @@ impl ASTPrinter {
         Ok(string)
     }
 
+    fn visit_assign_index(&mut self, a: &AssignIndex) -> Result<String> {
+        let exprs = vec![a.index.expr.clone(), a.value.expr.clone()].into_iter();
+        self.nest_level += 1;
+        let string = self.parenthesize_exprs(
+            format!("Assign into array element, name: {:?}", a.name).as_str(),
+            exprs,
+        )?;
+        self.nest_level -= 1;
+        Ok(string)
+    }
+
     // TODO: clean up the .expr. nesting stuff
 
     fn visit_binary(&mut self, b: &Binary) -> Result<String> {
@@ -107,6 +123,15 @@ impl ASTPrinter {
         Ok(string)
     }
 
+    fn visit_call(&mut self, c: &Call) -> Result<String> {
+        let exprs = c.arguments.iter().map(|argument| argument.expr.clone());
+        self.nest_level += 1;
+        let string =
+            self.parenthesize_exprs(format!("Call builtin, name: {:?}", c.name).as_str(), exprs)?;
+        self.nest_level -= 1;
+        Ok(string)
+    }
+
     fn visit_grouping(&mut self, g: &Grouping) -> Result<String> {
         let exprs = vec![g.expression.expr.clone()].into_iter();
         self.nest_level += 1;
@@ -115,6 +140,36 @@ impl ASTPrinter {
         Ok(string)
     }
 
+    fn visit_index(&mut self, i: &Index) -> Result<String> {
+        let exprs = vec![i.array.expr.clone(), i.index.expr.clone()].into_iter();
+        self.nest_level += 1;
+        let string = self.parenthesize_exprs("Index", exprs)?;
+        self.nest_level -= 1;
+        Ok(string)
+    }
+
+    fn visit_interpolation(&mut self, i: &Interpolation) -> Result<String> {
+        // Show the literal segments as a template, with the embedded expressions as children
+        let mut template = String::new();
+        let mut exprs = Vec::new();
+        for segment in &i.segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => template.push_str(literal),
+                InterpolationSegment::Expression(expression) => {
+                    template.push_str("{}");
+                    exprs.push(expression.expr.clone());
+                }
+            }
+        }
+        self.nest_level += 1;
+        let string = self.parenthesize_exprs(
+            format!("Interpolation {template:?}").as_str(),
+            exprs.into_iter(),
+        )?;
+        self.nest_level -= 1;
+        Ok(string)
+    }
+
     fn visit_literal(&mut self, l: &Literal) -> Result<String> {
         self.nest_level += 1;
         let string = self.indented_print(&l.value.token);
@@ -185,13 +240,22 @@ impl Visitor<String, Error> for ASTPrinter {
             Stmt::Forloop(f) => {
                 // TODO: better AST prettyprinting for for loops...
                 let start = self.visit_expression(&f.left)?;
-                let end = self.visit_expression(&f.right)?;
-                let loop_start = format!(
-                    "For loop start, variable name: {}, start: {:?}, end: {:?}",
-                    f.variable,
-                    start.trim(),
-                    end.trim()
-                );
+                let loop_start = match &f.right {
+                    Some(right) => {
+                        let end = self.visit_expression(right)?;
+                        format!(
+                            "For loop start, variable name: {}, start: {:?}, end: {:?}",
+                            f.variable,
+                            start.trim(),
+                            end.trim()
+                        )
+                    }
+                    None => format!(
+                        "For loop start, variable name: {}, elements of: {:?}",
+                        f.variable,
+                        start.trim()
+                    ),
+                };
                 let mut loop_body = String::new();
                 for statement in &f.body {
                     let str = self.visit_statement(statement)?;
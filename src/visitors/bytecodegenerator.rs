@@ -0,0 +1,366 @@
+use crate::bytecode::{Chunk, Constant, Instruction};
+use crate::parsing::*;
+use crate::runtime::RuntimeError;
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken;
+
+use super::codegen::{
+    unexpected_binary_operator, unexpected_literal, unexpected_logical_operator,
+    unexpected_unary_operator,
+};
+use super::Visitor;
+
+#[derive(Debug, Default)]
+/// [BytecodeGenerator] is a [Visitor] for compiling the program into a [Chunk] of bytecode.
+///
+/// Operands are evaluated in the same order as in the [Interpreter](super::Interpreter), and each instruction
+/// records the span of the expression or statement it originates from, see the [Disassembler](crate::bytecode::Disassembler).
+pub struct BytecodeGenerator {
+    chunk: Chunk,
+}
+
+impl BytecodeGenerator {
+    /// The primary function of the [BytecodeGenerator]: returns the bytecode of the whole program
+    ///
+    /// # Errors
+    /// Returns the same [RuntimeError] as the [Interpreter](super::Interpreter) for an expression the parser never constructs,
+    /// e.g. a binary expression with a logical operator in a syntax tree built by hand
+    pub fn generate(&mut self, statements: &[Statement]) -> Result<Chunk, RuntimeError> {
+        self.chunk = Chunk::default();
+        for statement in statements {
+            self.visit_statement(statement)?;
+        }
+        Ok(std::mem::take(&mut self.chunk))
+    }
+
+    /// Internal helper: appends the instruction, returning its offset
+    fn emit(&mut self, instruction: Instruction, span: StartEndSpan) -> usize {
+        self.chunk.push(instruction, span)
+    }
+
+    /// Internal helper: points the jump at the given offset to the next instruction to be emitted
+    fn patch_jump(&mut self, offset: usize) {
+        let target = self.chunk.code.len();
+        self.chunk.code[offset] = match self.chunk.code[offset] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::JumpIfTrue(_) => Instruction::JumpIfTrue(target),
+            Instruction::LoopNext(_) => Instruction::LoopNext(target),
+            instruction => unreachable!("Patching a non-jump instruction {instruction:?}"),
+        };
+    }
+
+    fn compile_binary(&mut self, b: &Binary, span: StartEndSpan) -> Result<(), RuntimeError> {
+        self.visit_expression(&b.left)?;
+        self.visit_expression(&b.right)?;
+        let instruction = match b.operator.tokentype() {
+            RawToken::Plus => Instruction::Add,
+            RawToken::Minus => Instruction::Subtract,
+            RawToken::Star => Instruction::Multiply,
+            RawToken::Slash => Instruction::Divide,
+            RawToken::Equal => Instruction::Equal,
+            RawToken::NotEqual => Instruction::NotEqual,
+            RawToken::Less => Instruction::Less,
+            RawToken::LessEqual => Instruction::LessEqual,
+            RawToken::Greater => Instruction::Greater,
+            RawToken::GreaterEqual => Instruction::GreaterEqual,
+            // NOTE: the parser only constructs binary expressions of the operators above
+            _ => return Err(unexpected_binary_operator(&b.operator)),
+        };
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    fn compile_interpolation(
+        &mut self,
+        i: &Interpolation,
+        span: StartEndSpan,
+    ) -> Result<(), RuntimeError> {
+        let empty = self.chunk.constant(Constant::Text(String::new()));
+        self.emit(Instruction::Constant(empty), span);
+        for segment in &i.segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => {
+                    let text = self.chunk.constant(Constant::Text(literal.clone()));
+                    self.emit(Instruction::Constant(text), span);
+                }
+                InterpolationSegment::Expression(expression) => {
+                    self.visit_expression(expression)?;
+                    self.emit(Instruction::ToText, expression.span);
+                }
+            }
+            self.emit(Instruction::Concat, span);
+        }
+        Ok(())
+    }
+
+    fn compile_literal(&mut self, l: &Literal, span: StartEndSpan) -> Result<(), RuntimeError> {
+        let constant = match &l.value.token {
+            RawToken::Number(n) => Constant::Number(*n),
+            RawToken::RealNumber(n) => Constant::Real(*n),
+            RawToken::Text(t) => Constant::Text(t.clone()),
+            RawToken::False => {
+                self.emit(Instruction::False, span);
+                return Ok(());
+            }
+            RawToken::True => {
+                self.emit(Instruction::True, span);
+                return Ok(());
+            }
+            // NOTE: the parser only constructs literals of the tokens above
+            _ => return Err(unexpected_literal(&l.value)),
+        };
+        let index = self.chunk.constant(constant);
+        self.emit(Instruction::Constant(index), span);
+        Ok(())
+    }
+
+    fn compile_logical(&mut self, l: &Logical, span: StartEndSpan) -> Result<(), RuntimeError> {
+        // Short-circuiting: the right operand is only evaluated if the left one does not determine the result
+        self.visit_expression(&l.left)?;
+        self.emit(Instruction::AsBoolean, l.left.span);
+        let (short_circuit, result) = match l.operator.tokentype() {
+            RawToken::And => (Instruction::JumpIfFalse(0), Instruction::False),
+            RawToken::Or => (Instruction::JumpIfTrue(0), Instruction::True),
+            // NOTE: the parser only constructs logical expressions of the operators above
+            _ => return Err(unexpected_logical_operator(&l.operator)),
+        };
+        let short_circuit = self.emit(short_circuit, span);
+        self.visit_expression(&l.right)?;
+        // Without short-circuiting, the checked right operand is the value of the whole expression
+        self.emit(Instruction::AsBoolean, span);
+        let end = self.emit(Instruction::Jump(0), span);
+        self.patch_jump(short_circuit);
+        self.emit(result, span);
+        self.patch_jump(end);
+        Ok(())
+    }
+
+    fn compile_unary(&mut self, u: &Unary, span: StartEndSpan) -> Result<(), RuntimeError> {
+        self.visit_expression(&u.right)?;
+        let instruction = match u.operator.tokentype() {
+            RawToken::Minus => Instruction::Negate,
+            RawToken::Bang => Instruction::Not,
+            // NOTE: the parser only constructs unary expressions of the operators above
+            _ => return Err(unexpected_unary_operator(&u.operator)),
+        };
+        self.emit(instruction, span);
+        Ok(())
+    }
+
+    fn compile_variable_declaration(
+        &mut self,
+        v: &Variable,
+        span: StartEndSpan,
+    ) -> Result<(), RuntimeError> {
+        let variable = self.chunk.variable(&v.name);
+        let define = match &v.initializer {
+            Some(initializer) => {
+                self.visit_expression(initializer)?;
+                Instruction::DefineInit
+            }
+            None => Instruction::Define,
+        };
+        let kind = self.chunk.constant(Constant::Type(v.kind.clone()));
+        self.emit(define(variable, kind), span);
+        Ok(())
+    }
+
+    fn compile_forloop(&mut self, f: &Forloop) -> Result<(), RuntimeError> {
+        let variable = self.chunk.variable(&f.variable);
+        self.visit_expression(&f.left)?;
+        match &f.right {
+            Some(right) => {
+                self.visit_expression(right)?;
+                self.emit(Instruction::RangeLoop(variable), f.header);
+            }
+            // Iterating over the elements of an array
+            None => {
+                self.emit(Instruction::ArrayLoop(variable), f.header);
+            }
+        }
+        let next = self.emit(Instruction::LoopNext(0), f.span);
+        for statement in &f.body {
+            self.visit_statement(statement)?;
+        }
+        self.emit(Instruction::Jump(next), f.span);
+        self.patch_jump(next);
+        self.emit(Instruction::LoopEnd, f.span);
+        Ok(())
+    }
+}
+
+impl Visitor<(), RuntimeError> for BytecodeGenerator {
+    /// Appends the instructions evaluating the expression, leaving its value on the stack
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), RuntimeError> {
+        let span = expression.span;
+        match &expression.expr {
+            // Assignments originate from their `:=` token, as in the error reports of the interpreter
+            Expr::Assign(a) => {
+                self.visit_expression(&a.value)?;
+                let variable = self.chunk.variable(&a.name);
+                self.emit(Instruction::Assign(variable), a.token.span);
+            }
+            Expr::AssignIndex(a) => {
+                self.visit_expression(&a.index)?;
+                self.visit_expression(&a.value)?;
+                let variable = self.chunk.variable(&a.name);
+                self.emit(Instruction::AssignIndex(variable), a.token.span);
+            }
+            Expr::Binary(b) => self.compile_binary(b, span)?,
+            Expr::Call(c) => {
+                for argument in &c.arguments {
+                    self.visit_expression(argument)?;
+                }
+                let name = self.chunk.constant(Constant::Text(c.name.clone()));
+                self.emit(Instruction::Call(name, c.arguments.len()), span);
+            }
+            Expr::Grouping(g) => self.visit_expression(&g.expression)?,
+            Expr::Index(i) => {
+                // The index is evaluated before the array
+                self.visit_expression(&i.index)?;
+                self.visit_expression(&i.array)?;
+                self.emit(Instruction::Index, span);
+            }
+            Expr::Interpolation(i) => self.compile_interpolation(i, span)?,
+            Expr::Literal(l) => self.compile_literal(l, span)?,
+            Expr::Logical(l) => self.compile_logical(l, span)?,
+            Expr::Unary(u) => self.compile_unary(u, span)?,
+            Expr::VariableUsage(name) => {
+                let variable = self.chunk.variable(name);
+                self.emit(Instruction::Get(variable), span);
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the instructions of the statement
+    fn visit_statement(&mut self, statement: &Statement) -> Result<(), RuntimeError> {
+        let span = statement.span;
+        match &statement.stmt {
+            Stmt::Assert(e) => {
+                self.visit_expression(e)?;
+                self.emit(Instruction::Assert, span);
+            }
+            Stmt::Expression(e) => {
+                self.visit_expression(e)?;
+                self.emit(Instruction::Pop, span);
+            }
+            Stmt::Forloop(f) => self.compile_forloop(f)?,
+            Stmt::Print(e) => {
+                self.visit_expression(e)?;
+                self.emit(Instruction::Print, span);
+            }
+            Stmt::Read(name) => {
+                let variable = self.chunk.variable(name);
+                self.emit(Instruction::Read(variable), span);
+            }
+            Stmt::VariableDefinition(v) => self.compile_variable_declaration(v, span)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::bytecode::{Constant, Instruction::*};
+    use crate::lexing::Lexer;
+    use crate::parsing::*;
+    use crate::runtime::RuntimeError;
+    use crate::span::StartEndSpan;
+    use crate::tokens::{RawToken, Token};
+    use crate::visitors::BytecodeGenerator;
+
+    fn generate(source: &str) -> crate::bytecode::Chunk {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        BytecodeGenerator::default().generate(&statements).unwrap()
+    }
+
+    #[test]
+    fn unexpected_operators_are_errors() {
+        // Building the expression by hand bypasses the parser, which never puts a comparison in a logical expression
+        let one = Expression::new(
+            Expr::Literal(Literal::new(Token::new(
+                RawToken::Number(1),
+                StartEndSpan::new(0, 1),
+            ))),
+            StartEndSpan::new(0, 1),
+        );
+        let less = Token::new(RawToken::Less, StartEndSpan::new(2, 3));
+        let logical = Expression::new(
+            Expr::Logical(Logical::new(one.clone(), less, one)),
+            StartEndSpan::new(0, 5),
+        );
+        let statements = [Statement::new(
+            Stmt::Print(logical),
+            StartEndSpan::new(0, 5),
+        )];
+        let result = BytecodeGenerator::default().generate(&statements);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::UnexpectedLogicalOperator(_, _))
+        ));
+    }
+
+    #[test]
+    fn constants_are_pooled() {
+        let chunk = generate("print 1 + 1; print \"a\" + \"a\";");
+        assert_eq!(
+            chunk.constants,
+            vec![Constant::Number(1), Constant::Text("a".to_owned())]
+        );
+        assert_eq!(
+            chunk.code,
+            vec![
+                Constant(0),
+                Constant(0),
+                Add,
+                Print,
+                Constant(1),
+                Constant(1),
+                Add,
+                Print
+            ]
+        );
+        assert_eq!(chunk.spans.len(), chunk.code.len());
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let chunk = generate("print true & false;");
+        assert_eq!(
+            chunk.code,
+            vec![
+                True,
+                AsBoolean,
+                JumpIfFalse(6),
+                False,
+                AsBoolean,
+                Jump(7),
+                False,
+                Print
+            ]
+        );
+    }
+
+    #[test]
+    fn array_loop() {
+        let chunk = generate("var a : array[2] of int; for x in a do print x; end for;");
+        assert_eq!(
+            chunk.code,
+            vec![
+                Define(0, 0),
+                Get(0),
+                ArrayLoop(1),
+                LoopNext(7),
+                Get(1),
+                Print,
+                Jump(3),
+                LoopEnd
+            ]
+        );
+        assert_eq!(chunk.jump_targets(), vec![3, 7]);
+    }
+}
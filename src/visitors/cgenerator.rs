@@ -0,0 +1,591 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::mem;
+
+use crate::parsing::*;
+use crate::runtime::RuntimeError;
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken;
+
+use super::codegen::{
+    runtime_errors, unexpected_binary_operator, unexpected_literal, unexpected_logical_operator,
+    unexpected_unary_operator, SourceLines,
+};
+use super::Visitor;
+
+const INDENT: &str = "    ";
+
+/// The runtime library of the generated programs: values, operators, builtins and error reporting
+const RUNTIME: &str = include_str!("cgenerator/runtime.c");
+
+#[derive(Debug)]
+/// [CGenerator] is a [Visitor] for compiling the program into a standalone C99 source file.
+///
+/// Values stay dynamically typed in the generated code, and all runtime checks of the [Interpreter](super::Interpreter)
+/// are performed with the same error codes, messages and source locations. Expressions are compiled into
+/// temporaries, one per subexpression, so that evaluation order is left to right as in the interpreter.
+pub struct CGenerator {
+    lines: SourceLines,
+    /// The path of the source file, printed in error reports
+    path: String,
+    /// Indices of the variables in the generated variable table
+    variables: HashMap<String, usize>,
+    temporaries: usize,
+    nest_level: usize,
+    /// The code generated for the current statement
+    code: String,
+}
+
+impl CGenerator {
+    /// Creates a new [CGenerator] for the given source code, located at the given path
+    #[must_use]
+    pub fn new(source: &str, path: &str) -> Self {
+        Self {
+            lines: SourceLines::new(source),
+            path: path.to_owned(),
+            variables: HashMap::new(),
+            temporaries: 0,
+            nest_level: 1,
+            code: String::new(),
+        }
+    }
+
+    /// The primary function of the [CGenerator]: returns the C source code of the whole program
+    ///
+    /// # Errors
+    /// Returns the same [RuntimeError] as the [Interpreter](super::Interpreter) for an expression the parser never constructs,
+    /// e.g. a binary expression with a logical operator in a syntax tree built by hand
+    pub fn generate(&mut self, statements: &[Statement]) -> Result<String, RuntimeError> {
+        self.variables.clear();
+        self.temporaries = 0;
+        let mut body = String::new();
+        for statement in statements {
+            body.push_str(&self.visit_statement(statement)?);
+        }
+
+        let mut program = String::from(
+            "/* Generated by minipl. Compile with e.g. `cc -std=c99 program.c -lm` */\n\n",
+        );
+        for (code, message) in runtime_errors() {
+            let _ = writeln!(
+                program,
+                "#define MPL_{code} {}, {}",
+                c_string(&code),
+                c_string(&message)
+            );
+        }
+        program.push('\n');
+        program.push_str(RUNTIME);
+        program.push('\n');
+        if !self.variables.is_empty() {
+            let mut names: Vec<(&String, &usize)> = self.variables.iter().collect();
+            names.sort_by_key(|(_, index)| **index);
+            let names: Vec<&str> = names.iter().map(|(name, _)| name.as_str()).collect();
+            let _ = writeln!(program, "/* Variables: {} */", names.join(", "));
+            let _ = writeln!(
+                program,
+                "static mpl_var mpl_vars[{}];\n",
+                self.variables.len()
+            );
+        }
+        program.push_str("int main(void) {\n");
+        let _ = writeln!(program, "{INDENT}mpl_init({});", c_string(&self.path));
+        program.push_str(&body);
+        let _ = writeln!(program, "{INDENT}return 0;\n}}");
+        Ok(program)
+    }
+
+    /// Internal helper: appends a line of code at the current nesting level
+    fn emit(&mut self, line: &str) {
+        for _ in 0..self.nest_level {
+            self.code.push_str(INDENT);
+        }
+        self.code.push_str(line);
+        self.code.push('\n');
+    }
+
+    /// Internal helper: opens a nested block with the given header, e.g. `if (x) {`
+    fn open(&mut self, header: &str) {
+        self.emit(header);
+        self.nest_level += 1;
+    }
+
+    /// Internal helper: closes the innermost block, optionally continuing it, e.g. `} else {`
+    fn close(&mut self, footer: &str) {
+        self.nest_level -= 1;
+        self.emit(footer);
+    }
+
+    fn temporary(&mut self) -> String {
+        self.temporaries += 1;
+        format!("t{}", self.temporaries)
+    }
+
+    /// Internal helper: declares a new temporary initialized with the given C expression
+    fn value(&mut self, value: &str) -> String {
+        let temporary = self.temporary();
+        self.emit(&format!("mpl_value {temporary} = {value};"));
+        temporary
+    }
+
+    /// Internal helper: returns a pointer to the variable with the given name
+    fn variable(&mut self, name: &str) -> String {
+        let next = self.variables.len();
+        let index = *self.variables.entry(name.to_owned()).or_insert(next);
+        format!("&mpl_vars[{index}]")
+    }
+
+    /// Internal helper: returns the source location of the start of the span, as a C expression
+    fn location(&self, span: StartEndSpan) -> String {
+        let (line, column) = self.lines.line_column(span.start);
+        format!("mpl_at({line}, {column})")
+    }
+
+    fn compile_binary(&mut self, b: &Binary, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let left = self.visit_expression(&b.left)?;
+        let right = self.visit_expression(&b.right)?;
+        let operator = match b.operator.tokentype() {
+            RawToken::Plus => "MPL_PLUS",
+            RawToken::Minus => "MPL_MINUS",
+            RawToken::Star => "MPL_STAR",
+            RawToken::Slash => "MPL_SLASH",
+            RawToken::Equal => "MPL_EQUAL",
+            RawToken::NotEqual => "MPL_NOT_EQUAL",
+            RawToken::Less => "MPL_LESS",
+            RawToken::LessEqual => "MPL_LESS_EQUAL",
+            RawToken::Greater => "MPL_GREATER",
+            RawToken::GreaterEqual => "MPL_GREATER_EQUAL",
+            // NOTE: the parser only constructs binary expressions of the operators above
+            _ => return Err(unexpected_binary_operator(&b.operator)),
+        };
+        let (left_location, right_location, location) = (
+            self.location(b.left.span),
+            self.location(b.right.span),
+            self.location(span),
+        );
+        Ok(self.value(&format!(
+            "mpl_binary({operator}, {left}, {right}, {left_location}, {right_location}, {location})"
+        )))
+    }
+
+    fn compile_call(&mut self, c: &Call, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let mut arguments = Vec::new();
+        for argument in &c.arguments {
+            let value = self.visit_expression(argument)?;
+            arguments.push(value);
+        }
+        let locations: Vec<String> = c
+            .arguments
+            .iter()
+            .map(|argument| self.location(argument.span))
+            .collect();
+        let location = self.location(span);
+        let count = match c.name.as_str() {
+            "length" | "to_int" | "to_lower" | "to_real" | "to_string" | "to_upper" => Some(1),
+            "char_at" | "find" => Some(2),
+            "substring" => Some(3),
+            _ => None,
+        };
+        let error = match count {
+            Some(count) if count == arguments.len() => None,
+            Some(_) => Some(format!("mpl_error(MPL_E0314, {location})")),
+            None => {
+                // The call expression starts with the name of the function
+                let name_span = StartEndSpan::new(span.start, span.start + c.name.chars().count());
+                Some(format!(
+                    "mpl_error(MPL_E0313, {})",
+                    self.location(name_span)
+                ))
+            }
+        };
+        if let Some(error) = error {
+            // The arguments are evaluated before the call fails
+            for argument in &arguments {
+                self.emit(&format!("(void){argument};"));
+            }
+            return Ok(self.value(&error));
+        }
+        let parameters: Vec<String> = arguments.into_iter().chain(locations).collect();
+        Ok(self.value(&format!(
+            "mpl_{}({}, {location})",
+            c.name,
+            parameters.join(", ")
+        )))
+    }
+
+    fn compile_index(&mut self, i: &Index) -> Result<String, RuntimeError> {
+        // The index is evaluated before the array
+        let index = self.visit_expression(&i.index)?;
+        let index_location = self.location(i.index.span);
+        let number = self.temporary();
+        self.emit(&format!(
+            "int64_t {number} = mpl_as_index({index}, {index_location});"
+        ));
+        let array = self.visit_expression(&i.array)?;
+        let array_location = self.location(i.array.span);
+        Ok(self.value(&format!(
+            "mpl_index({array}, {number}, {array_location}, {index_location})"
+        )))
+    }
+
+    fn compile_interpolation(&mut self, i: &Interpolation) -> Result<String, RuntimeError> {
+        let buffer = self.temporary();
+        self.emit(&format!("mpl_buffer {buffer} = {{NULL, 0, 0}};"));
+        for segment in &i.segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => self.emit(&format!(
+                    "mpl_buffer_push(&{buffer}, {}, {});",
+                    c_string(literal),
+                    literal.len()
+                )),
+                InterpolationSegment::Expression(expression) => {
+                    let value = self.visit_expression(expression)?;
+                    self.emit(&format!("mpl_write({value}, &{buffer});"));
+                }
+            }
+        }
+        Ok(self.value(&format!("mpl_text_from_buffer(&{buffer})")))
+    }
+
+    fn compile_literal(&mut self, l: &Literal) -> Result<String, RuntimeError> {
+        let value = match &l.value.token {
+            RawToken::Number(n) if *n == i64::MIN => "mpl_number(INT64_MIN)".to_owned(),
+            RawToken::Number(n) => format!("mpl_number(INT64_C({n}))"),
+            RawToken::RealNumber(n) => format!("mpl_real({})", c_real(*n)),
+            RawToken::Text(t) => format!("mpl_text({}, {})", c_string(t), t.len()),
+            RawToken::False => "mpl_boolean(0)".to_owned(),
+            RawToken::True => "mpl_boolean(1)".to_owned(),
+            // NOTE: the parser only constructs literals of the tokens above
+            _ => return Err(unexpected_literal(&l.value)),
+        };
+        Ok(self.value(&value))
+    }
+
+    fn compile_logical(&mut self, l: &Logical) -> Result<String, RuntimeError> {
+        // Short-circuiting: the right operand is only evaluated if the left one does not determine the result
+        let result = self.temporary();
+        self.emit(&format!("mpl_value {result};"));
+        let left = self.visit_expression(&l.left)?;
+        let left_location = self.location(l.left.span);
+        let condition = match l.operator.tokentype() {
+            RawToken::And => format!("if (!mpl_as_boolean({left}, {left_location})) {{"),
+            RawToken::Or => format!("if (mpl_as_boolean({left}, {left_location})) {{"),
+            // NOTE: the parser only constructs logical expressions of the operators above
+            _ => return Err(unexpected_logical_operator(&l.operator)),
+        };
+        let short_circuit = matches!(l.operator.tokentype(), RawToken::Or);
+        self.open(&condition);
+        self.emit(&format!("{result} = mpl_boolean({});", short_circuit as u8));
+        self.close("} else {");
+        self.nest_level += 1;
+        let right = self.visit_expression(&l.right)?;
+        let right_location = self.location(l.right.span);
+        self.emit(&format!(
+            "{result} = mpl_boolean(mpl_as_boolean({right}, {right_location}));"
+        ));
+        self.close("}");
+        Ok(result)
+    }
+
+    fn compile_unary(&mut self, u: &Unary, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let right = self.visit_expression(&u.right)?;
+        let right_location = self.location(u.right.span);
+        let value = match u.operator.tokentype() {
+            RawToken::Minus => {
+                let location = self.location(span);
+                format!("mpl_negate({right}, {right_location}, {location})")
+            }
+            RawToken::Bang => format!("mpl_not({right}, {right_location})"),
+            // NOTE: the parser only constructs unary expressions of the operators above
+            _ => return Err(unexpected_unary_operator(&u.operator)),
+        };
+        Ok(self.value(&value))
+    }
+
+    fn compile_assign(&mut self, a: &Assign) -> Result<String, RuntimeError> {
+        let variable = self.variable(&a.name);
+        let location = self.location(a.token.span);
+        self.emit(&format!("mpl_check_loop({variable}, {location});"));
+        let value = self.visit_expression(&a.value)?;
+        Ok(self.value(&format!("mpl_assign({variable}, {value}, {location})")))
+    }
+
+    fn compile_assign_index(&mut self, a: &AssignIndex) -> Result<String, RuntimeError> {
+        let variable = self.variable(&a.name);
+        let location = self.location(a.token.span);
+        self.emit(&format!("mpl_check_loop({variable}, {location});"));
+        let index = self.visit_expression(&a.index)?;
+        let index_location = self.location(a.index.span);
+        let number = self.temporary();
+        self.emit(&format!(
+            "int64_t {number} = mpl_as_index({index}, {index_location});"
+        ));
+        let value = self.visit_expression(&a.value)?;
+        let value_location = self.location(a.value.span);
+        Ok(self.value(&format!(
+            "mpl_assign_index({variable}, {number}, {value}, {index_location}, {value_location})"
+        )))
+    }
+
+    fn compile_variable_declaration(&mut self, v: &Variable) -> Result<(), RuntimeError> {
+        let variable = self.variable(&v.name);
+        let location = self.location(v.span);
+        self.emit(&format!("mpl_check_loop({variable}, {location});"));
+        match &v.initializer {
+            Some(initializer) => {
+                let value = self.visit_expression(initializer)?;
+                let value_location = self.location(initializer.span);
+                self.emit(&format!(
+                    "mpl_define({variable}, {value}, {}, {location}, {value_location});",
+                    c_string(&v.kind.to_string())
+                ));
+            }
+            None => self.emit(&format!(
+                "mpl_define_default({variable}, {}, {location});",
+                default_value(&v.kind)
+            )),
+        }
+        Ok(())
+    }
+
+    fn compile_forloop(&mut self, f: &Forloop) -> Result<(), RuntimeError> {
+        let variable = self.variable(&f.variable);
+        let header = self.location(f.header);
+        let location = self.location(f.span);
+        let start = self.visit_expression(&f.left)?;
+        let start_location = self.location(f.left.span);
+        match &f.right {
+            Some(right) => {
+                let (first, last, counter) = (self.temporary(), self.temporary(), self.temporary());
+                self.emit(&format!(
+                    "int64_t {first} = mpl_for_start({start}, {start_location});"
+                ));
+                let end = self.visit_expression(right)?;
+                let end_location = self.location(right.span);
+                self.emit(&format!(
+                    "int64_t {last} = mpl_for_end({end}, {end_location});"
+                ));
+                self.emit(&format!(
+                    "if ({first} > {last}) mpl_fail(MPL_E0325, {start_location});"
+                ));
+                self.emit(&format!("mpl_loop_begin({variable}, {header});"));
+                // The counter stops at the end without incrementing past it, so that ranges ending at the largest number do not overflow
+                self.open(&format!(
+                    "for (int64_t {counter} = {first};; {counter}++) {{"
+                ));
+                self.emit(&format!(
+                    "mpl_loop_assign({variable}, mpl_number({counter}), {location});"
+                ));
+                self.compile_body(&f.body)?;
+                self.emit(&format!("if ({counter} == {last}) break;"));
+                self.close("}");
+            }
+            // Iterating over the elements of an array
+            None => {
+                let (elements, counter) = (self.temporary(), self.temporary());
+                self.emit(&format!(
+                    "mpl_array *{elements} = mpl_for_array({start}, {start_location});"
+                ));
+                self.emit(&format!("mpl_loop_begin({variable}, {header});"));
+                self.open(&format!(
+                    "for (size_t {counter} = 0; {counter} < {elements}->len; {counter}++) {{"
+                ));
+                self.emit(&format!(
+                    "mpl_loop_assign({variable}, {elements}->items[{counter}], {location});"
+                ));
+                self.compile_body(&f.body)?;
+                self.close("}");
+            }
+        }
+        self.emit(&format!("mpl_loop_end({variable});"));
+        Ok(())
+    }
+
+    fn compile_body(&mut self, body: &[Statement]) -> Result<(), RuntimeError> {
+        for statement in body {
+            let code = self.visit_statement(statement)?;
+            self.code.push_str(&code);
+        }
+        Ok(())
+    }
+}
+
+impl Visitor<String, RuntimeError> for CGenerator {
+    /// Emits the code evaluating the expression, and returns the name of the temporary holding its value
+    fn visit_expression(&mut self, expression: &Expression) -> Result<String, RuntimeError> {
+        let span = expression.span;
+        let temporary = match &expression.expr {
+            Expr::Assign(a) => self.compile_assign(a)?,
+            Expr::AssignIndex(a) => self.compile_assign_index(a)?,
+            Expr::Binary(b) => self.compile_binary(b, span)?,
+            Expr::Call(c) => self.compile_call(c, span)?,
+            Expr::Grouping(g) => self.visit_expression(&g.expression)?,
+            Expr::Index(i) => self.compile_index(i)?,
+            Expr::Interpolation(i) => self.compile_interpolation(i)?,
+            Expr::Literal(l) => self.compile_literal(l)?,
+            Expr::Logical(l) => self.compile_logical(l)?,
+            Expr::Unary(u) => self.compile_unary(u, span)?,
+            Expr::VariableUsage(name) => {
+                let variable = self.variable(name);
+                let location = self.location(span);
+                self.value(&format!("mpl_get({variable}, {location})"))
+            }
+        };
+        Ok(temporary)
+    }
+
+    /// Returns the code of the statement, as a block of its own
+    fn visit_statement(&mut self, statement: &Statement) -> Result<String, RuntimeError> {
+        let outer = mem::take(&mut self.code);
+        self.open("{");
+        let location = self.location(statement.span);
+        match &statement.stmt {
+            Stmt::Assert(e) => {
+                let value = self.visit_expression(e)?;
+                let location = self.location(e.span);
+                self.emit(&format!("mpl_assert({value}, {location});"));
+            }
+            Stmt::Expression(e) => {
+                let value = self.visit_expression(e)?;
+                self.emit(&format!("(void){value};"));
+            }
+            Stmt::Forloop(f) => self.compile_forloop(f)?,
+            Stmt::Print(e) => {
+                let value = self.visit_expression(e)?;
+                self.emit(&format!("mpl_print({value}, {location});"));
+            }
+            Stmt::Read(name) => {
+                let variable = self.variable(name);
+                self.emit(&format!("mpl_read({variable}, {location});"));
+            }
+            Stmt::VariableDefinition(v) => self.compile_variable_declaration(v)?,
+        }
+        self.close("}");
+        Ok(mem::replace(&mut self.code, outer))
+    }
+}
+
+/// Returns the C expression for the default value of a variable of the given type
+fn default_value(kind: &VarType) -> String {
+    match kind {
+        VarType::Boolean => "mpl_boolean(0)".to_owned(),
+        VarType::Number => "mpl_number(0)".to_owned(),
+        VarType::Real => "mpl_real(0.0)".to_owned(),
+        VarType::Text => "mpl_text(\"\", 0)".to_owned(),
+        VarType::Array(kind, size) => format!("mpl_array_of({size}, {})", default_value(kind)),
+    }
+}
+
+/// Returns the real number as a C expression that evaluates to exactly the same value
+fn c_real(real: f64) -> String {
+    if real.is_nan() {
+        "NAN".to_owned()
+    } else if real.is_infinite() {
+        format!("{}HUGE_VAL", if real < 0.0 { "-" } else { "" })
+    } else {
+        // NOTE: the Debug format is the shortest representation that round-trips, and is valid C
+        format!("{real:?}")
+    }
+}
+
+/// Returns the text as a C string literal. Bytes outside printable ASCII are escaped in octal
+fn c_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in text.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' => literal.push(byte as char),
+            _ => {
+                let _ = write!(literal, "\\{byte:03o}");
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::lexing::Lexer;
+    use crate::tokens::Token;
+
+    fn generate(source: &str) -> String {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        CGenerator::new(source, "test.minipl")
+            .generate(&statements)
+            .unwrap()
+    }
+
+    #[test]
+    fn unexpected_operators_are_errors() {
+        // Building the expression by hand bypasses the parser, which never puts a logical operator in a binary expression
+        let one = Expression::new(
+            Expr::Literal(Literal::new(Token::new(
+                RawToken::Number(1),
+                StartEndSpan::new(0, 1),
+            ))),
+            StartEndSpan::new(0, 1),
+        );
+        let and = Token::new(RawToken::And, StartEndSpan::new(2, 3));
+        let binary = Expression::new(
+            Expr::Binary(Binary::new(one.clone(), and, one)),
+            StartEndSpan::new(0, 5),
+        );
+        let statements = [Statement::new(Stmt::Print(binary), StartEndSpan::new(0, 5))];
+        let result = CGenerator::new("1 & 1", "test.minipl").generate(&statements);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::UnexpectedBinaryOperator(_, _))
+        ));
+    }
+
+    #[test]
+    fn c_string_escapes() {
+        assert_eq!(c_string("a\"b\\c?\n"), "\"a\\\"b\\\\c\\?\\012\"");
+        assert_eq!(c_string("ä"), "\"\\303\\244\"");
+    }
+
+    #[test]
+    fn c_real_round_trips() {
+        assert_eq!(c_real(1.0), "1.0");
+        assert_eq!(c_real(1e100), "1e100");
+        assert_eq!(c_real(f64::NEG_INFINITY), "-HUGE_VAL");
+        assert_eq!(c_real(f64::NAN), "NAN");
+    }
+
+    #[test]
+    fn locations_are_lines_and_columns() {
+        let generator = CGenerator::new("var x : int;\n  print ä;\nx;", "test.minipl");
+        assert_eq!(generator.location(StartEndSpan::new(0, 3)), "mpl_at(1, 1)");
+        assert_eq!(
+            generator.location(StartEndSpan::new(21, 22)),
+            "mpl_at(2, 9)"
+        );
+        assert_eq!(
+            generator.location(StartEndSpan::new(24, 25)),
+            "mpl_at(3, 1)"
+        );
+    }
+
+    #[test]
+    fn error_messages_match_runtime_errors() {
+        let program = generate("print 1 / 0;");
+        assert!(program.contains("#define MPL_E0342 \"E0342\", \"Division by zero\"\n"));
+        assert!(program
+            .contains("mpl_binary(MPL_SLASH, t1, t2, mpl_at(1, 7), mpl_at(1, 11), mpl_at(1, 7))"));
+    }
+
+    #[test]
+    fn variables_are_numbered_in_order_of_appearance() {
+        let program = generate("var b : int; var a : int := b;");
+        assert!(program.contains("/* Variables: b, a */\nstatic mpl_var mpl_vars[2];"));
+        assert!(program
+            .contains("mpl_define(&mpl_vars[1], t1, \"Number\", mpl_at(1, 14), mpl_at(1, 29));"));
+    }
+}
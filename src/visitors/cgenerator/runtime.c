@@ -0,0 +1,1110 @@
+/*
+ * Runtime of Mini-PL programs compiled to C. Mirrors the semantics of the interpreter:
+ * values are dynamically typed, and every runtime error of the interpreter is reported
+ * with the same error code, message and source location.
+ *
+ * Text values are immutable UTF-8 byte strings. All memory allocated for texts and arrays
+ * is owned by the runtime, and released when the program exits.
+ */
+
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* Programs use only some of the runtime functions */
+#if defined(__GNUC__)
+#define MPL_RUNTIME static __attribute__((unused))
+#else
+#define MPL_RUNTIME static
+#endif
+
+/* Memory */
+
+typedef union mpl_block {
+    union mpl_block *next;
+    /* Alignment of the allocations that follow the header */
+    long double align_float;
+    int64_t align_int;
+    void *align_pointer;
+} mpl_block;
+
+static mpl_block *mpl_blocks = NULL;
+
+MPL_RUNTIME void *mpl_alloc(size_t size) {
+    mpl_block *block = malloc(sizeof(mpl_block) + size);
+    if (block == NULL) {
+        fputs("Error: out of memory\n", stderr);
+        exit(1);
+    }
+    block->next = mpl_blocks;
+    mpl_blocks = block;
+    return block + 1;
+}
+
+MPL_RUNTIME void mpl_free_all(void) {
+    while (mpl_blocks != NULL) {
+        mpl_block *next = mpl_blocks->next;
+        free(mpl_blocks);
+        mpl_blocks = next;
+    }
+}
+
+/* Growable byte buffer for building texts */
+
+typedef struct {
+    char *data;
+    size_t len;
+    size_t cap;
+} mpl_buffer;
+
+MPL_RUNTIME void mpl_buffer_push(mpl_buffer *buffer, const char *data, size_t len) {
+    if (buffer->len + len > buffer->cap) {
+        size_t cap = buffer->cap == 0 ? 64 : buffer->cap;
+        while (cap < buffer->len + len) {
+            cap *= 2;
+        }
+        buffer->data = realloc(buffer->data, cap);
+        if (buffer->data == NULL) {
+            fputs("Error: out of memory\n", stderr);
+            exit(1);
+        }
+        buffer->cap = cap;
+    }
+    if (len > 0) {
+        memcpy(buffer->data + buffer->len, data, len);
+    }
+    buffer->len += len;
+}
+
+MPL_RUNTIME void mpl_buffer_push_str(mpl_buffer *buffer, const char *data) {
+    mpl_buffer_push(buffer, data, strlen(data));
+}
+
+/* Values */
+
+typedef enum { MPL_NUMBER, MPL_REAL, MPL_TEXT, MPL_BOOLEAN, MPL_ARRAY } mpl_kind;
+
+typedef struct mpl_array mpl_array;
+
+typedef struct {
+    mpl_kind kind;
+    union {
+        int64_t number;
+        double real;
+        struct {
+            const char *data;
+            size_t len;
+        } text;
+        int boolean;
+        mpl_array *array;
+    } as;
+} mpl_value;
+
+struct mpl_array {
+    size_t len;
+    mpl_value *items;
+};
+
+MPL_RUNTIME mpl_value mpl_number(int64_t number) {
+    mpl_value value;
+    value.kind = MPL_NUMBER;
+    value.as.number = number;
+    return value;
+}
+
+MPL_RUNTIME mpl_value mpl_real(double real) {
+    mpl_value value;
+    value.kind = MPL_REAL;
+    value.as.real = real;
+    return value;
+}
+
+MPL_RUNTIME mpl_value mpl_boolean(int boolean) {
+    mpl_value value;
+    value.kind = MPL_BOOLEAN;
+    value.as.boolean = boolean != 0;
+    return value;
+}
+
+/* A text referring to the given bytes, e.g. a literal. The bytes are not copied */
+MPL_RUNTIME mpl_value mpl_text(const char *data, size_t len) {
+    mpl_value value;
+    value.kind = MPL_TEXT;
+    value.as.text.data = data;
+    value.as.text.len = len;
+    return value;
+}
+
+/* A text with a copy of the given bytes */
+MPL_RUNTIME mpl_value mpl_text_copy(const char *data, size_t len) {
+    char *copy = mpl_alloc(len + 1);
+    if (len > 0) {
+        memcpy(copy, data, len);
+    }
+    copy[len] = '\0';
+    return mpl_text(copy, len);
+}
+
+MPL_RUNTIME mpl_value mpl_text_from_buffer(mpl_buffer *buffer) {
+    mpl_value value = mpl_text_copy(buffer->data, buffer->len);
+    free(buffer->data);
+    buffer->data = NULL;
+    buffer->len = buffer->cap = 0;
+    return value;
+}
+
+/* Arrays have value semantics: they are copied when stored into a variable */
+MPL_RUNTIME mpl_value mpl_copy(mpl_value value) {
+    if (value.kind == MPL_ARRAY) {
+        mpl_array *array = mpl_alloc(sizeof(mpl_array));
+        size_t i;
+        array->len = value.as.array->len;
+        array->items = mpl_alloc(sizeof(mpl_value) * array->len);
+        for (i = 0; i < array->len; i++) {
+            array->items[i] = mpl_copy(value.as.array->items[i]);
+        }
+        value.as.array = array;
+    }
+    return value;
+}
+
+MPL_RUNTIME mpl_value mpl_array_of(size_t len, mpl_value element) {
+    mpl_value value;
+    size_t i;
+    value.kind = MPL_ARRAY;
+    value.as.array = mpl_alloc(sizeof(mpl_array));
+    value.as.array->len = len;
+    value.as.array->items = mpl_alloc(sizeof(mpl_value) * len);
+    for (i = 0; i < len; i++) {
+        value.as.array->items[i] = mpl_copy(element);
+    }
+    return value;
+}
+
+/* The type of the value as a string, e.g. `Array[3] of Number`. Used for type checking assignments */
+MPL_RUNTIME void mpl_kind_string(mpl_value value, mpl_buffer *out) {
+    char size[32];
+    switch (value.kind) {
+    case MPL_NUMBER:
+        mpl_buffer_push_str(out, "Number");
+        break;
+    case MPL_REAL:
+        mpl_buffer_push_str(out, "Real");
+        break;
+    case MPL_TEXT:
+        mpl_buffer_push_str(out, "Text");
+        break;
+    case MPL_BOOLEAN:
+        mpl_buffer_push_str(out, "Boolean");
+        break;
+    case MPL_ARRAY:
+        sprintf(size, "Array[%lu]", (unsigned long)value.as.array->len);
+        mpl_buffer_push_str(out, size);
+        if (value.as.array->len > 0) {
+            mpl_buffer_push_str(out, " of ");
+            mpl_kind_string(value.as.array->items[0], out);
+        }
+        break;
+    }
+}
+
+MPL_RUNTIME int mpl_has_kind(mpl_value value, const char *kind) {
+    mpl_buffer buffer = {NULL, 0, 0};
+    int result;
+    mpl_kind_string(value, &buffer);
+    result = buffer.len == strlen(kind) && memcmp(buffer.data, kind, buffer.len) == 0;
+    free(buffer.data);
+    return result;
+}
+
+MPL_RUNTIME int mpl_same_kind(mpl_value a, mpl_value b) {
+    mpl_buffer buffer = {NULL, 0, 0};
+    int result;
+    mpl_kind_string(b, &buffer);
+    mpl_buffer_push(&buffer, "", 1);
+    result = mpl_has_kind(a, buffer.data);
+    free(buffer.data);
+    return result;
+}
+
+/* Errors */
+
+typedef struct {
+    int line;
+    int column;
+} mpl_loc;
+
+static const char *mpl_path = "";
+
+MPL_RUNTIME mpl_loc mpl_at(int line, int column) {
+    mpl_loc loc;
+    loc.line = line;
+    loc.column = column;
+    return loc;
+}
+
+/* Reports a runtime error and exits. Called as e.g. `mpl_fail(MPL_E0342, loc)` */
+MPL_RUNTIME void mpl_fail(const char *code, const char *message, mpl_loc loc) {
+    fflush(stdout);
+    fprintf(stderr, "Error: [%s] %s\n  --> %s:%d:%d\n", code, message, mpl_path, loc.line, loc.column);
+    exit(1);
+}
+
+/* Reports a runtime error in an expression */
+MPL_RUNTIME mpl_value mpl_error(const char *code, const char *message, mpl_loc loc) {
+    mpl_fail(code, message, loc);
+    return mpl_boolean(0);
+}
+
+/* Printing */
+
+/* Real numbers are printed in the shortest representation that round-trips, always with a fractional part or an exponent */
+MPL_RUNTIME void mpl_write_real(double real, mpl_buffer *out) {
+    char buffer[64];
+    char digits[32];
+    int count = 0;
+    int exponent;
+    int precision;
+    double magnitude = fabs(real);
+    const char *sign = signbit(real) ? "-" : "";
+    const char *c;
+
+    if (real != real) {
+        mpl_buffer_push_str(out, "NaN");
+        return;
+    }
+    mpl_buffer_push_str(out, sign);
+    if (magnitude == HUGE_VAL) {
+        mpl_buffer_push_str(out, "inf");
+        return;
+    }
+    if (magnitude == 0.0) {
+        mpl_buffer_push_str(out, "0.0");
+        return;
+    }
+    for (precision = 1; precision <= 17; precision++) {
+        sprintf(buffer, "%.*e", precision - 1, magnitude);
+        if (strtod(buffer, NULL) == magnitude) {
+            break;
+        }
+    }
+    for (c = buffer; *c != 'e'; c++) {
+        if (*c >= '0' && *c <= '9') {
+            digits[count++] = *c;
+        }
+    }
+    exponent = atoi(c + 1);
+    while (count > 1 && digits[count - 1] == '0') {
+        count--;
+    }
+    digits[count] = '\0';
+
+    if (magnitude < 1e-4 || magnitude >= 1e16) {
+        mpl_buffer_push(out, digits, 1);
+        if (count > 1) {
+            mpl_buffer_push_str(out, ".");
+            mpl_buffer_push_str(out, digits + 1);
+        }
+        sprintf(buffer, "e%d", exponent);
+        mpl_buffer_push_str(out, buffer);
+    } else if (exponent < 0) {
+        mpl_buffer_push_str(out, "0.");
+        for (; exponent < -1; exponent++) {
+            mpl_buffer_push_str(out, "0");
+        }
+        mpl_buffer_push_str(out, digits);
+    } else if (count <= exponent + 1) {
+        mpl_buffer_push_str(out, digits);
+        for (; count < exponent + 1; count++) {
+            mpl_buffer_push_str(out, "0");
+        }
+        mpl_buffer_push_str(out, ".0");
+    } else {
+        mpl_buffer_push(out, digits, (size_t)exponent + 1);
+        mpl_buffer_push_str(out, ".");
+        mpl_buffer_push_str(out, digits + exponent + 1);
+    }
+}
+
+MPL_RUNTIME void mpl_write(mpl_value value, mpl_buffer *out) {
+    char buffer[32];
+    size_t i;
+    switch (value.kind) {
+    case MPL_NUMBER:
+        sprintf(buffer, "%lld", (long long)value.as.number);
+        mpl_buffer_push_str(out, buffer);
+        break;
+    case MPL_REAL:
+        mpl_write_real(value.as.real, out);
+        break;
+    case MPL_TEXT:
+        mpl_buffer_push(out, value.as.text.data, value.as.text.len);
+        break;
+    case MPL_BOOLEAN:
+        mpl_buffer_push_str(out, value.as.boolean ? "true" : "false");
+        break;
+    case MPL_ARRAY:
+        mpl_buffer_push_str(out, "[");
+        for (i = 0; i < value.as.array->len; i++) {
+            if (i > 0) {
+                mpl_buffer_push_str(out, ", ");
+            }
+            mpl_write(value.as.array->items[i], out);
+        }
+        mpl_buffer_push_str(out, "]");
+        break;
+    }
+}
+
+MPL_RUNTIME mpl_value mpl_to_text(mpl_value value) {
+    mpl_buffer buffer = {NULL, 0, 0};
+    if (value.kind == MPL_TEXT) {
+        return value;
+    }
+    mpl_write(value, &buffer);
+    return mpl_text_from_buffer(&buffer);
+}
+
+MPL_RUNTIME void mpl_print(mpl_value value, mpl_loc loc) {
+    mpl_value text = mpl_to_text(value);
+    fwrite(text.as.text.data, 1, text.as.text.len, stdout);
+    if (fflush(stdout) != 0) {
+        mpl_fail(MPL_E0331, loc);
+    }
+}
+
+/* Conversions */
+
+MPL_RUNTIME int64_t mpl_as_number(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_NUMBER) {
+        mpl_fail(MPL_E0301, loc);
+    }
+    return value.as.number;
+}
+
+MPL_RUNTIME double mpl_as_real(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_REAL) {
+        mpl_fail(MPL_E0302, loc);
+    }
+    return value.as.real;
+}
+
+MPL_RUNTIME int mpl_as_boolean(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_BOOLEAN) {
+        mpl_fail(MPL_E0303, loc);
+    }
+    return value.as.boolean;
+}
+
+MPL_RUNTIME int64_t mpl_as_index(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_NUMBER) {
+        mpl_fail(MPL_E0312, loc);
+    }
+    return value.as.number;
+}
+
+/* Operators */
+
+typedef enum {
+    MPL_PLUS,
+    MPL_MINUS,
+    MPL_STAR,
+    MPL_SLASH,
+    MPL_EQUAL,
+    MPL_NOT_EQUAL,
+    MPL_LESS,
+    MPL_LESS_EQUAL,
+    MPL_GREATER,
+    MPL_GREATER_EQUAL
+} mpl_operator;
+
+/* Three-way comparison of texts, by their bytes */
+MPL_RUNTIME int mpl_compare_texts(mpl_value left, mpl_value right) {
+    size_t len = left.as.text.len < right.as.text.len ? left.as.text.len : right.as.text.len;
+    int result = len > 0 ? memcmp(left.as.text.data, right.as.text.data, len) : 0;
+    if (result != 0) {
+        return result < 0 ? -1 : 1;
+    }
+    if (left.as.text.len == right.as.text.len) {
+        return 0;
+    }
+    return left.as.text.len < right.as.text.len ? -1 : 1;
+}
+
+/* The binary operators. `loc` is the location of the whole expression, `left_loc` and `right_loc` those of the operands */
+MPL_RUNTIME mpl_value mpl_binary(mpl_operator operator, mpl_value left, mpl_value right, mpl_loc left_loc,
+                            mpl_loc right_loc, mpl_loc loc) {
+    int same = left.kind == right.kind;
+    int comparable = same && (left.kind == MPL_NUMBER || left.kind == MPL_REAL || left.kind == MPL_TEXT);
+    int64_t l, r;
+    if ((left.kind == MPL_NUMBER && right.kind == MPL_REAL) || (left.kind == MPL_REAL && right.kind == MPL_NUMBER)) {
+        mpl_fail(MPL_E0307, left_loc);
+    }
+    switch (operator) {
+    case MPL_MINUS:
+    case MPL_SLASH:
+    case MPL_STAR:
+        if (left.kind == MPL_REAL) {
+            double a = mpl_as_real(left, left_loc);
+            double b = mpl_as_real(right, right_loc);
+            return mpl_real(operator == MPL_MINUS ? a - b : operator == MPL_SLASH ? a / b : a * b);
+        }
+        l = mpl_as_number(left, left_loc);
+        r = mpl_as_number(right, right_loc);
+        if (operator == MPL_MINUS) {
+            if ((r < 0 && l > INT64_MAX + r) || (r > 0 && l < INT64_MIN + r)) {
+                mpl_fail(MPL_E0343, loc);
+            }
+            return mpl_number(l - r);
+        }
+        if (operator == MPL_SLASH) {
+            if (r == 0) {
+                mpl_fail(MPL_E0342, left_loc);
+            }
+            if (l == INT64_MIN && r == -1) {
+                mpl_fail(MPL_E0343, loc);
+            }
+            return mpl_number(l / r);
+        }
+        if (l != 0 && r != 0) {
+            int overflow = (l == -1 && r == INT64_MIN) || (r == -1 && l == INT64_MIN);
+            if (!overflow && l != -1 && r != -1) {
+                overflow = (l > 0) == (r > 0) ? (l > 0 ? l > INT64_MAX / r : l < INT64_MAX / r)
+                                              : (l > 0 ? r < INT64_MIN / l : l < INT64_MIN / r);
+            }
+            if (overflow) {
+                mpl_fail(MPL_E0343, loc);
+            }
+        }
+        return mpl_number(l * r);
+    case MPL_PLUS:
+        if (same && left.kind == MPL_NUMBER) {
+            l = left.as.number;
+            r = right.as.number;
+            if ((r > 0 && l > INT64_MAX - r) || (r < 0 && l < INT64_MIN - r)) {
+                mpl_fail(MPL_E0343, loc);
+            }
+            return mpl_number(l + r);
+        }
+        if (same && left.kind == MPL_REAL) {
+            return mpl_real(left.as.real + right.as.real);
+        }
+        if (same && left.kind == MPL_TEXT) {
+            mpl_buffer buffer = {NULL, 0, 0};
+            mpl_buffer_push(&buffer, left.as.text.data, left.as.text.len);
+            mpl_buffer_push(&buffer, right.as.text.data, right.as.text.len);
+            return mpl_text_from_buffer(&buffer);
+        }
+        return mpl_error(MPL_E0330, left_loc);
+    case MPL_EQUAL:
+    case MPL_NOT_EQUAL:
+    case MPL_LESS:
+        if (!comparable) {
+            return operator == MPL_EQUAL ? mpl_error(MPL_E0305, left_loc)
+                   : operator == MPL_NOT_EQUAL ? mpl_error(MPL_E0308, left_loc)
+                                               : mpl_error(MPL_E0306, left_loc);
+        }
+        break;
+    case MPL_LESS_EQUAL:
+    case MPL_GREATER:
+    case MPL_GREATER_EQUAL:
+        if (!comparable) {
+            return mpl_error(MPL_E0309, left_loc);
+        }
+        break;
+    }
+    /* Comparisons of two values of the same comparable type. Comparisons involving a NaN are false, except `<>` */
+    if (left.kind == MPL_REAL) {
+        double a = left.as.real, b = right.as.real;
+        switch (operator) {
+        case MPL_EQUAL:
+            return mpl_boolean(a == b);
+        case MPL_NOT_EQUAL:
+            return mpl_boolean(a != b);
+        case MPL_LESS:
+            return mpl_boolean(a < b);
+        case MPL_LESS_EQUAL:
+            return mpl_boolean(a <= b);
+        case MPL_GREATER:
+            return mpl_boolean(a > b);
+        default:
+            return mpl_boolean(a >= b);
+        }
+    } else {
+        int order = left.kind == MPL_TEXT ? mpl_compare_texts(left, right)
+                                          : (left.as.number > right.as.number) - (left.as.number < right.as.number);
+        switch (operator) {
+        case MPL_EQUAL:
+            return mpl_boolean(order == 0);
+        case MPL_NOT_EQUAL:
+            return mpl_boolean(order != 0);
+        case MPL_LESS:
+            return mpl_boolean(order < 0);
+        case MPL_LESS_EQUAL:
+            return mpl_boolean(order <= 0);
+        case MPL_GREATER:
+            return mpl_boolean(order > 0);
+        default:
+            return mpl_boolean(order >= 0);
+        }
+    }
+}
+
+MPL_RUNTIME mpl_value mpl_negate(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    int64_t number;
+    if (value.kind == MPL_REAL) {
+        return mpl_real(-value.as.real);
+    }
+    number = mpl_as_number(value, value_loc);
+    if (number == INT64_MIN) {
+        mpl_fail(MPL_E0343, loc);
+    }
+    return mpl_number(-number);
+}
+
+MPL_RUNTIME mpl_value mpl_not(mpl_value value, mpl_loc value_loc) {
+    return mpl_boolean(!mpl_as_boolean(value, value_loc));
+}
+
+/* Arrays */
+
+MPL_RUNTIME mpl_value mpl_index(mpl_value array, int64_t index, mpl_loc array_loc, mpl_loc index_loc) {
+    if (array.kind != MPL_ARRAY) {
+        mpl_fail(MPL_E0311, array_loc);
+    }
+    if (index < 0 || (uint64_t)index >= array.as.array->len) {
+        mpl_fail(MPL_E0310, index_loc);
+    }
+    return mpl_copy(array.as.array->items[index]);
+}
+
+/* Variables */
+
+typedef struct {
+    int declared;
+    /* Set while the variable is the control variable of an executing for loop */
+    int loop_active;
+    mpl_loc loop_header;
+    mpl_value value;
+} mpl_var;
+
+/* The control variable of an executing for loop cannot be modified */
+MPL_RUNTIME void mpl_check_loop(mpl_var *var, mpl_loc loc) {
+    (void)loc;
+    if (var->loop_active) {
+        mpl_fail(MPL_E0326, var->loop_header);
+    }
+}
+
+MPL_RUNTIME mpl_value mpl_get(mpl_var *var, mpl_loc loc) {
+    if (!var->declared) {
+        mpl_fail(MPL_E0340, loc);
+    }
+    return var->value;
+}
+
+/* Declares the variable. `kind` is the declared type, and `value_loc` the location of the initializer */
+MPL_RUNTIME void mpl_define(mpl_var *var, mpl_value value, const char *kind, mpl_loc loc, mpl_loc value_loc) {
+    (void)value_loc;
+    if (!mpl_has_kind(value, kind)) {
+        mpl_fail(MPL_E0341, loc);
+    }
+    if (var->declared) {
+        mpl_fail(MPL_E0338, loc);
+    }
+    var->declared = 1;
+    var->value = mpl_copy(value);
+}
+
+MPL_RUNTIME void mpl_define_default(mpl_var *var, mpl_value value, mpl_loc loc) {
+    if (var->declared) {
+        mpl_fail(MPL_E0338, loc);
+    }
+    var->declared = 1;
+    var->value = value;
+}
+
+MPL_RUNTIME mpl_value mpl_assign(mpl_var *var, mpl_value value, mpl_loc loc) {
+    if (!var->declared) {
+        mpl_fail(MPL_E0339, loc);
+    }
+    if (!mpl_same_kind(value, var->value)) {
+        mpl_fail(MPL_E0341, loc);
+    }
+    var->value = mpl_copy(value);
+    return value;
+}
+
+MPL_RUNTIME mpl_value mpl_assign_index(mpl_var *var, int64_t index, mpl_value value, mpl_loc index_loc,
+                                  mpl_loc value_loc) {
+    mpl_value *element;
+    (void)value_loc;
+    if (!var->declared) {
+        mpl_fail(MPL_E0339, index_loc);
+    }
+    if (var->value.kind != MPL_ARRAY) {
+        mpl_fail(MPL_E0311, index_loc);
+    }
+    if (index < 0 || (uint64_t)index >= var->value.as.array->len) {
+        mpl_fail(MPL_E0310, index_loc);
+    }
+    element = &var->value.as.array->items[index];
+    if (!mpl_same_kind(value, *element)) {
+        mpl_fail(MPL_E0341, index_loc);
+    }
+    *element = mpl_copy(value);
+    return value;
+}
+
+/* For loops */
+
+MPL_RUNTIME int64_t mpl_for_start(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_NUMBER) {
+        mpl_fail(MPL_E0329, loc);
+    }
+    return value.as.number;
+}
+
+MPL_RUNTIME int64_t mpl_for_end(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_NUMBER) {
+        mpl_fail(MPL_E0327, loc);
+    }
+    return value.as.number;
+}
+
+/* The elements to iterate over. The array is copied, so that the loop body cannot change them */
+MPL_RUNTIME mpl_array *mpl_for_array(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_ARRAY) {
+        mpl_fail(MPL_E0328, loc);
+    }
+    return mpl_copy(value).as.array;
+}
+
+MPL_RUNTIME void mpl_loop_begin(mpl_var *var, mpl_loc header) {
+    mpl_check_loop(var, header);
+    var->loop_active = 1;
+    var->loop_header = header;
+}
+
+MPL_RUNTIME void mpl_loop_assign(mpl_var *var, mpl_value value, mpl_loc loc) {
+    if (!var->declared || !mpl_same_kind(value, var->value)) {
+        mpl_fail(MPL_E0324, loc);
+    }
+    var->value = mpl_copy(value);
+}
+
+MPL_RUNTIME void mpl_loop_end(mpl_var *var) {
+    var->loop_active = 0;
+}
+
+/* Assertions */
+
+MPL_RUNTIME void mpl_assert(mpl_value value, mpl_loc loc) {
+    if (value.kind != MPL_BOOLEAN) {
+        mpl_fail(MPL_E0322, loc);
+    }
+    if (!value.as.boolean) {
+        mpl_fail(MPL_E0323, loc);
+    }
+}
+
+/* Reading */
+
+MPL_RUNTIME int mpl_is_space(char c) {
+    return c == ' ' || c == '\t' || c == '\n' || c == '\v' || c == '\f' || c == '\r';
+}
+
+MPL_RUNTIME int mpl_valid_utf8(const char *data, size_t len) {
+    size_t i = 0;
+    while (i < len) {
+        unsigned char c = (unsigned char)data[i];
+        size_t extra, j;
+        uint32_t point;
+        if (c < 0x80) {
+            i++;
+            continue;
+        } else if (c >= 0xC2 && c <= 0xDF) {
+            extra = 1;
+            point = c & 0x1F;
+        } else if (c >= 0xE0 && c <= 0xEF) {
+            extra = 2;
+            point = c & 0x0F;
+        } else if (c >= 0xF0 && c <= 0xF4) {
+            extra = 3;
+            point = c & 0x07;
+        } else {
+            return 0;
+        }
+        if (i + extra >= len) {
+            return 0;
+        }
+        for (j = 1; j <= extra; j++) {
+            unsigned char next = (unsigned char)data[i + j];
+            if ((next & 0xC0) != 0x80) {
+                return 0;
+            }
+            point = (point << 6) | (next & 0x3F);
+        }
+        if ((extra == 2 && (point < 0x800 || (point >= 0xD800 && point <= 0xDFFF))) ||
+            (extra == 3 && (point < 0x10000 || point > 0x10FFFF))) {
+            return 0;
+        }
+        i += extra + 1;
+    }
+    return 1;
+}
+
+/* Parses a number in the syntax of the interpreter: an optional sign and decimal digits */
+MPL_RUNTIME int mpl_parse_number(const char *data, size_t len, int64_t *result) {
+    size_t i = 0;
+    int negative = 0;
+    uint64_t magnitude = 0;
+    uint64_t limit;
+    if (i < len && (data[i] == '+' || data[i] == '-')) {
+        negative = data[i] == '-';
+        i++;
+    }
+    if (i == len) {
+        return 0;
+    }
+    limit = negative ? (uint64_t)INT64_MAX + 1 : (uint64_t)INT64_MAX;
+    for (; i < len; i++) {
+        unsigned digit;
+        if (data[i] < '0' || data[i] > '9') {
+            return 0;
+        }
+        digit = (unsigned)(data[i] - '0');
+        if (magnitude > (limit - digit) / 10) {
+            return 0;
+        }
+        magnitude = magnitude * 10 + digit;
+    }
+    *result = negative ? (magnitude == (uint64_t)INT64_MAX + 1 ? INT64_MIN : -(int64_t)magnitude) : (int64_t)magnitude;
+    return 1;
+}
+
+MPL_RUNTIME int mpl_equals_ignore_case(const char *data, size_t len, const char *word) {
+    size_t i;
+    if (len != strlen(word)) {
+        return 0;
+    }
+    for (i = 0; i < len; i++) {
+        char c = data[i];
+        if (c >= 'A' && c <= 'Z') {
+            c = (char)(c - 'A' + 'a');
+        }
+        if (c != word[i]) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+/* Parses a real number in the syntax of the interpreter: decimal digits with an optional fraction and exponent, or inf / infinity / nan */
+MPL_RUNTIME int mpl_parse_real(const char *data, size_t len, double *result) {
+    size_t i = 0, digits = 0;
+    char *copy;
+    if (i < len && (data[i] == '+' || data[i] == '-')) {
+        i++;
+    }
+    if (mpl_equals_ignore_case(data + i, len - i, "inf") || mpl_equals_ignore_case(data + i, len - i, "infinity")) {
+        *result = data[0] == '-' ? -HUGE_VAL : HUGE_VAL;
+        return 1;
+    }
+    if (mpl_equals_ignore_case(data + i, len - i, "nan")) {
+        *result = NAN;
+        return 1;
+    }
+    for (; i < len && data[i] >= '0' && data[i] <= '9'; i++) {
+        digits++;
+    }
+    if (i < len && data[i] == '.') {
+        for (i++; i < len && data[i] >= '0' && data[i] <= '9'; i++) {
+            digits++;
+        }
+    }
+    if (digits == 0) {
+        return 0;
+    }
+    if (i < len && (data[i] == 'e' || data[i] == 'E')) {
+        size_t exponent_digits = 0;
+        i++;
+        if (i < len && (data[i] == '+' || data[i] == '-')) {
+            i++;
+        }
+        for (; i < len && data[i] >= '0' && data[i] <= '9'; i++) {
+            exponent_digits++;
+        }
+        if (exponent_digits == 0) {
+            return 0;
+        }
+    }
+    if (i != len) {
+        return 0;
+    }
+    copy = mpl_alloc(len + 1);
+    memcpy(copy, data, len);
+    copy[len] = '\0';
+    *result = strtod(copy, NULL);
+    return 1;
+}
+
+MPL_RUNTIME void mpl_trim(const char **data, size_t *len) {
+    while (*len > 0 && mpl_is_space((*data)[0])) {
+        (*data)++;
+        (*len)--;
+    }
+    while (*len > 0 && mpl_is_space((*data)[*len - 1])) {
+        (*len)--;
+    }
+}
+
+MPL_RUNTIME void mpl_read(mpl_var *var, mpl_loc loc) {
+    mpl_buffer line = {NULL, 0, 0};
+    mpl_value old, new_value;
+    const char *input;
+    size_t len;
+    int c;
+    mpl_check_loop(var, loc);
+    while ((c = getchar()) != EOF) {
+        char byte = (char)c;
+        mpl_buffer_push(&line, &byte, 1);
+        if (byte == '\n') {
+            break;
+        }
+    }
+    if (ferror(stdin) || !mpl_valid_utf8(line.data, line.len)) {
+        mpl_fail(MPL_E0332, loc);
+    }
+    old = mpl_get(var, loc);
+    new_value = mpl_text_from_buffer(&line);
+    input = new_value.as.text.data;
+    len = new_value.as.text.len;
+    mpl_trim(&input, &len);
+    switch (old.kind) {
+    case MPL_NUMBER: {
+        int64_t number;
+        if (!mpl_parse_number(input, len, &number)) {
+            mpl_fail(MPL_E0336, loc);
+        }
+        new_value = mpl_number(number);
+        break;
+    }
+    case MPL_REAL: {
+        double real;
+        if (!mpl_parse_real(input, len, &real)) {
+            mpl_fail(MPL_E0337, loc);
+        }
+        new_value = mpl_real(real);
+        break;
+    }
+    case MPL_TEXT:
+        break;
+    case MPL_BOOLEAN:
+        if (len == 4 && memcmp(input, "true", 4) == 0) {
+            new_value = mpl_boolean(1);
+        } else if (len == 5 && memcmp(input, "false", 5) == 0) {
+            new_value = mpl_boolean(0);
+        } else {
+            mpl_fail(MPL_E0335, loc);
+        }
+        break;
+    case MPL_ARRAY:
+        mpl_fail(MPL_E0333, loc);
+        break;
+    }
+    mpl_assign(var, new_value, loc);
+}
+
+/* Builtin functions. `loc` is the location of the whole call, the other locations those of the arguments */
+
+MPL_RUNTIME size_t mpl_utf8_length(const char *data, size_t len) {
+    size_t count = 0, i;
+    for (i = 0; i < len; i++) {
+        if (((unsigned char)data[i] & 0xC0) != 0x80) {
+            count++;
+        }
+    }
+    return count;
+}
+
+/* The byte offset of the character with the given index, or the length of the text */
+MPL_RUNTIME size_t mpl_utf8_offset(const char *data, size_t len, size_t index) {
+    size_t i;
+    for (i = 0; i < len; i++) {
+        if (((unsigned char)data[i] & 0xC0) != 0x80) {
+            if (index == 0) {
+                return i;
+            }
+            index--;
+        }
+    }
+    return len;
+}
+
+MPL_RUNTIME mpl_value mpl_length(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    if (value.kind == MPL_ARRAY) {
+        return mpl_number((int64_t)value.as.array->len);
+    }
+    if (value.kind == MPL_TEXT) {
+        return mpl_number((int64_t)mpl_utf8_length(value.as.text.data, value.as.text.len));
+    }
+    return mpl_error(MPL_E0315, value_loc);
+}
+
+MPL_RUNTIME mpl_value mpl_to_int(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    if (value.kind == MPL_REAL) {
+        double real = value.as.real;
+        /* Truncates towards zero, saturating at the bounds. NaN becomes zero */
+        if (real != real) {
+            return mpl_number(0);
+        }
+        if (real >= 9223372036854775808.0) {
+            return mpl_number(INT64_MAX);
+        }
+        if (real <= -9223372036854775808.0) {
+            return mpl_number(INT64_MIN);
+        }
+        return mpl_number((int64_t)real);
+    }
+    if (value.kind == MPL_TEXT) {
+        const char *data = value.as.text.data;
+        size_t len = value.as.text.len;
+        int64_t number;
+        mpl_trim(&data, &len);
+        if (!mpl_parse_number(data, len, &number)) {
+            mpl_fail(MPL_E0316, value_loc);
+        }
+        return mpl_number(number);
+    }
+    return mpl_error(MPL_E0315, value_loc);
+}
+
+MPL_RUNTIME mpl_value mpl_to_real(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    if (value.kind != MPL_NUMBER) {
+        return mpl_error(MPL_E0315, value_loc);
+    }
+    return mpl_real((double)value.as.number);
+}
+
+MPL_RUNTIME mpl_value mpl_to_string(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    if (value.kind != MPL_NUMBER && value.kind != MPL_REAL && value.kind != MPL_BOOLEAN) {
+        return mpl_error(MPL_E0315, value_loc);
+    }
+    return mpl_to_text(value);
+}
+
+/* Case conversion of ASCII and Latin-1 letters. Other characters are kept as is */
+MPL_RUNTIME mpl_value mpl_convert_case(mpl_value value, mpl_loc value_loc, int upper) {
+    mpl_buffer buffer = {NULL, 0, 0};
+    size_t i;
+    if (value.kind != MPL_TEXT) {
+        return mpl_error(MPL_E0315, value_loc);
+    }
+    for (i = 0; i < value.as.text.len; i++) {
+        unsigned char c = (unsigned char)value.as.text.data[i];
+        if (upper && c >= 'a' && c <= 'z') {
+            c = (unsigned char)(c - 'a' + 'A');
+        } else if (!upper && c >= 'A' && c <= 'Z') {
+            c = (unsigned char)(c - 'A' + 'a');
+        } else if (c == 0xC3 && i + 1 < value.as.text.len) {
+            /* U+00C0 to U+00FF are encoded as 0xC3 0x80 to 0xC3 0xBF */
+            unsigned char next = (unsigned char)value.as.text.data[i + 1];
+            char pair[2];
+            if (upper && next == 0x9F) {
+                mpl_buffer_push_str(&buffer, "SS");
+                i++;
+                continue;
+            }
+            if (upper && next >= 0xA0 && next <= 0xBE && next != 0xB7) {
+                next = (unsigned char)(next - 0x20);
+            } else if (!upper && next >= 0x80 && next <= 0x9E && next != 0x97) {
+                next = (unsigned char)(next + 0x20);
+            }
+            pair[0] = (char)c;
+            pair[1] = (char)next;
+            mpl_buffer_push(&buffer, pair, 2);
+            i++;
+            continue;
+        }
+        mpl_buffer_push(&buffer, (const char *)&c, 1);
+    }
+    return mpl_text_from_buffer(&buffer);
+}
+
+MPL_RUNTIME mpl_value mpl_to_upper(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    return mpl_convert_case(value, value_loc, 1);
+}
+
+MPL_RUNTIME mpl_value mpl_to_lower(mpl_value value, mpl_loc value_loc, mpl_loc loc) {
+    (void)loc;
+    return mpl_convert_case(value, value_loc, 0);
+}
+
+MPL_RUNTIME mpl_value mpl_substring(mpl_value text, mpl_value start, mpl_value end, mpl_loc text_loc, mpl_loc start_loc,
+                               mpl_loc end_loc, mpl_loc loc) {
+    size_t length, from, to;
+    if (text.kind != MPL_TEXT) {
+        return mpl_error(MPL_E0315, text_loc);
+    }
+    if (start.kind != MPL_NUMBER) {
+        return mpl_error(MPL_E0315, start_loc);
+    }
+    if (end.kind != MPL_NUMBER) {
+        return mpl_error(MPL_E0315, end_loc);
+    }
+    length = mpl_utf8_length(text.as.text.data, text.as.text.len);
+    if (start.as.number < 0 || end.as.number < 0 || start.as.number > end.as.number ||
+        (uint64_t)end.as.number > length) {
+        return mpl_error(MPL_E0317, loc);
+    }
+    from = mpl_utf8_offset(text.as.text.data, text.as.text.len, (size_t)start.as.number);
+    to = mpl_utf8_offset(text.as.text.data, text.as.text.len, (size_t)end.as.number);
+    return mpl_text(text.as.text.data + from, to - from);
+}
+
+MPL_RUNTIME mpl_value mpl_char_at(mpl_value text, mpl_value index, mpl_loc text_loc, mpl_loc index_loc, mpl_loc loc) {
+    size_t from, to;
+    (void)loc;
+    if (text.kind != MPL_TEXT) {
+        return mpl_error(MPL_E0315, text_loc);
+    }
+    if (index.kind != MPL_NUMBER) {
+        return mpl_error(MPL_E0315, index_loc);
+    }
+    if (index.as.number < 0 || (uint64_t)index.as.number >= mpl_utf8_length(text.as.text.data, text.as.text.len)) {
+        return mpl_error(MPL_E0310, index_loc);
+    }
+    from = mpl_utf8_offset(text.as.text.data, text.as.text.len, (size_t)index.as.number);
+    to = mpl_utf8_offset(text.as.text.data, text.as.text.len, (size_t)index.as.number + 1);
+    return mpl_text(text.as.text.data + from, to - from);
+}
+
+MPL_RUNTIME mpl_value mpl_find(mpl_value text, mpl_value pattern, mpl_loc text_loc, mpl_loc pattern_loc, mpl_loc loc) {
+    size_t i;
+    (void)loc;
+    if (text.kind != MPL_TEXT) {
+        return mpl_error(MPL_E0315, text_loc);
+    }
+    if (pattern.kind != MPL_TEXT) {
+        return mpl_error(MPL_E0315, pattern_loc);
+    }
+    for (i = 0; i + pattern.as.text.len <= text.as.text.len; i++) {
+        if (pattern.as.text.len == 0 || memcmp(text.as.text.data + i, pattern.as.text.data, pattern.as.text.len) == 0) {
+            return mpl_number((int64_t)mpl_utf8_length(text.as.text.data, i));
+        }
+    }
+    return mpl_number(-1);
+}
+
+MPL_RUNTIME void mpl_init(const char *path) {
+    mpl_path = path;
+    atexit(mpl_free_all);
+}
@@ -0,0 +1,111 @@
+//! Helpers shared by the code generators of the compiler backends.
+
+use miette::{Diagnostic, SourceSpan};
+
+use crate::runtime::RuntimeError;
+use crate::span::StartEndSpan;
+use crate::tokens::Token;
+
+#[derive(Debug)]
+/// Converts character offsets of the source code into line and column numbers, both starting from one
+pub(crate) struct SourceLines {
+    /// Start offsets of the lines, in characters
+    line_starts: Vec<usize>,
+}
+
+impl SourceLines {
+    pub(crate) fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        for (offset, char) in source.chars().enumerate() {
+            if char == '\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+        Self { line_starts }
+    }
+
+    /// Returns the line and column of the given character offset
+    pub(crate) fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+/// Returns the error of a binary expression with an operator the parser never constructs, as reported by the [Interpreter](super::Interpreter)
+pub(super) fn unexpected_binary_operator(operator: &Token) -> RuntimeError {
+    RuntimeError::UnexpectedBinaryOperator(format!("{:?}", operator.token), operator.span.into())
+}
+
+/// Returns the error of a literal of a token the parser never constructs, as reported by the [Interpreter](super::Interpreter)
+pub(super) fn unexpected_literal(value: &Token) -> RuntimeError {
+    RuntimeError::UnexpectedLiteral(format!("{:?}", value.token), value.span.into())
+}
+
+/// Returns the error of a logical expression with an operator the parser never constructs, as reported by the [Interpreter](super::Interpreter)
+pub(super) fn unexpected_logical_operator(operator: &Token) -> RuntimeError {
+    RuntimeError::UnexpectedLogicalOperator(format!("{:?}", operator.token), operator.span.into())
+}
+
+/// Returns the error of a unary expression with an operator the parser never constructs, as reported by the [Interpreter](super::Interpreter)
+pub(super) fn unexpected_unary_operator(operator: &Token) -> RuntimeError {
+    RuntimeError::UnexpectedUnaryOperator(format!("{:?}", operator.token), operator.span.into())
+}
+
+/// The error codes and messages of the runtime errors, as reported by the [Interpreter](super::Interpreter)
+pub(super) fn runtime_errors() -> Vec<(String, String)> {
+    placeholder_errors()
+        .into_iter()
+        .map(|error| {
+            let code = error
+                .code()
+                .map(|code| code.to_string())
+                .unwrap_or_default();
+            (code, error.to_string())
+        })
+        .collect()
+}
+
+/// The runtime errors checked by the generated programs. Only the codes and messages are used, so the fields are placeholders
+fn placeholder_errors() -> Vec<RuntimeError> {
+    let s = || SourceSpan::from(StartEndSpan::new(0, 0));
+    let t = String::new;
+    vec![
+        RuntimeError::AsNumericFailed(t(), s()),
+        RuntimeError::AsRealFailed(t(), s()),
+        RuntimeError::AsBooleanFailed(t(), s()),
+        RuntimeError::EqualTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::LessTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::NumberRealMismatch(t(), t(), s(), s()),
+        RuntimeError::NotEqualTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::ComparisonTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::IndexOutOfBounds(0, 0, s()),
+        RuntimeError::IndexNonArray(t(), s()),
+        RuntimeError::IndexNonNumeric(t(), s()),
+        RuntimeError::UnknownBuiltin(t(), s(), t(), None),
+        RuntimeError::BuiltinArgumentCount(t(), 0, 0, s()),
+        RuntimeError::BuiltinArgumentType(t(), t(), t(), s()),
+        RuntimeError::ParseIntFailed(t(), s()),
+        RuntimeError::SubstringOutOfBounds(0, 0, 0, s()),
+        RuntimeError::AssertExprNotTruthy(s()),
+        RuntimeError::AssertionFailed(s()),
+        RuntimeError::ForBadAssignment(t(), s()),
+        RuntimeError::ForEndLarger(s(), s()),
+        RuntimeError::ForVariableModified(t(), s(), s()),
+        RuntimeError::ForEndNonNumeric(t(), s()),
+        RuntimeError::ForIterateNonArray(t(), s()),
+        RuntimeError::ForStartNonNumeric(t(), s()),
+        RuntimeError::PlusTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::PrintCouldNotFlush(s()),
+        RuntimeError::ReadLineFailed(s()),
+        RuntimeError::ReadArray(s()),
+        RuntimeError::ReadParseBoolFailed(t(), s()),
+        RuntimeError::ReadParseIntFailed(t(), s()),
+        RuntimeError::ReadParseRealFailed(t(), s()),
+        RuntimeError::VariableReDeclaration(s()),
+        RuntimeError::VariableAssignToUndeclared(s()),
+        RuntimeError::VariableGetFailed(t(), s(), t(), None),
+        RuntimeError::VariableAssignTypeMismatch(t(), t(), s(), s()),
+        RuntimeError::DivisionByZero(s(), s()),
+        RuntimeError::IntegerOverflow(s()),
+    ]
+}
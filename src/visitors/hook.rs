@@ -0,0 +1,147 @@
+use crate::parsing::{Expression, Statement};
+use crate::runtime::{Environment, Object, RuntimeError};
+use crate::span::StartEndSpan;
+
+#[derive(Debug, Clone, Copy)]
+/// The state of the [Interpreter](super::Interpreter) as seen by a [Hook]
+pub struct State<'a> {
+    /// The variables of the program
+    pub environment: &'a Environment,
+    /// The control variables of the `for` loops being executed, outermost first, with the spans of their loop headers
+    pub loops: &'a [(String, StartEndSpan)],
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A declaration of or an assignment to a variable, as seen by a [Hook]
+pub struct VariableWrite<'a> {
+    /// The name of the variable
+    pub name: &'a str,
+    /// The index of the array element assigned to, if any
+    pub index: Option<i64>,
+    /// The previous value of the variable or the element, or `None` for a declaration
+    pub old: Option<&'a Object>,
+    /// The new value of the variable or the element
+    pub new: &'a Object,
+    /// The span of the declaration, the assignment, the `read` statement or the `for` loop writing the variable
+    pub span: StartEndSpan,
+}
+
+/// Instrumentation of the [Interpreter](super::Interpreter), for tools such as the debugger.
+///
+/// The interpreter is generic over its hook, so that the calls compile away when there is nothing to observe, see [NoHook].
+/// An optional hook, `Option<H>`, observes the execution only if it is present, and a pair of hooks `(A, B)` observes it with both.
+pub trait Hook {
+    /// Called before each statement is executed, including the statements in the bodies of `for` loops.
+    ///
+    /// # Errors
+    /// Returning an error stops the program with it, e.g. [RuntimeError::Stopped]
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        let _ = (statement, state);
+        Ok(())
+    }
+
+    /// Called after each statement is executed successfully, with its result
+    fn after_statement(&mut self, statement: &Statement, result: &Object) {
+        let _ = (statement, result);
+    }
+
+    /// Called after each expression is evaluated successfully, including the operands of other expressions, with its value
+    fn after_expression(&mut self, expression: &Expression, value: &Object) {
+        let _ = (expression, value);
+    }
+
+    /// Called after a variable is declared or assigned to, including the control variables of `for` loops
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        let _ = write;
+    }
+}
+
+impl<H: Hook + ?Sized> Hook for &mut H {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        (**self).before_statement(statement, state)
+    }
+
+    fn after_statement(&mut self, statement: &Statement, result: &Object) {
+        (**self).after_statement(statement, result);
+    }
+
+    fn after_expression(&mut self, expression: &Expression, value: &Object) {
+        (**self).after_expression(expression, value);
+    }
+
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        (**self).variable_written(write);
+    }
+}
+
+impl<H: Hook> Hook for Option<H> {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        match self {
+            Some(hook) => hook.before_statement(statement, state),
+            None => Ok(()),
+        }
+    }
+
+    fn after_statement(&mut self, statement: &Statement, result: &Object) {
+        if let Some(hook) = self {
+            hook.after_statement(statement, result);
+        }
+    }
+
+    fn after_expression(&mut self, expression: &Expression, value: &Object) {
+        if let Some(hook) = self {
+            hook.after_expression(expression, value);
+        }
+    }
+
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        if let Some(hook) = self {
+            hook.variable_written(write);
+        }
+    }
+}
+
+/// A pair of hooks observes the execution with both, in order
+impl<A: Hook, B: Hook> Hook for (A, B) {
+    fn before_statement(
+        &mut self,
+        statement: &Statement,
+        state: State<'_>,
+    ) -> Result<(), RuntimeError> {
+        self.0.before_statement(statement, state)?;
+        self.1.before_statement(statement, state)
+    }
+
+    fn after_statement(&mut self, statement: &Statement, result: &Object) {
+        self.0.after_statement(statement, result);
+        self.1.after_statement(statement, result);
+    }
+
+    fn after_expression(&mut self, expression: &Expression, value: &Object) {
+        self.0.after_expression(expression, value);
+        self.1.after_expression(expression, value);
+    }
+
+    fn variable_written(&mut self, write: VariableWrite<'_>) {
+        self.0.variable_written(write);
+        self.1.variable_written(write);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The [Hook] of an [Interpreter](super::Interpreter) that is not instrumented
+pub struct NoHook;
+
+impl Hook for NoHook {}
@@ -1,140 +1,334 @@
-use std::io::{self, Write};
-
 use crate::{
-    parsing::{Statement, Stmt, VarType, Variable},
-    runtime::{Environment, Object},
-    tokens::RawToken::{
-        And, Bang, Equal, False, Less, Minus, Number, Plus, Slash, Star, Text, True,
-    },
+    parsing::{Statement, Stmt, Variable},
+    runtime::{builtins, operators, Console, Environment, Object, Stdio},
+    tokens::RawToken::{And, False, Number, Or, RealNumber, Text, True},
 };
 
+use super::hook::{Hook, NoHook, State, VariableWrite};
 use super::Visitor;
 use crate::parsing::*;
 use crate::runtime::RuntimeError;
+use crate::snapshot::{ExecutionState, LoopState, LoopValues};
+use crate::span::StartEndSpan;
 
 use miette::Result;
-use tracing::debug;
 
-#[derive(Debug, Default)]
-/// [Interpreter] is a [Visitor] for interpreting i.e. evaluating the program
-pub struct Interpreter {
+#[derive(Debug)]
+/// [Interpreter] is a [Visitor] for interpreting i.e. evaluating the program.
+///
+/// Tools observe the execution through the [Hook] of the interpreter, see [Interpreter::with_hook],
+/// and the program reads and prints through its [Console], see [Interpreter::set_console]
+pub struct Interpreter<H = NoHook> {
     /// Environment for storing variables
     pub environment: Environment,
+    /// Control variables of the currently executing `for` loops, with the spans of their loop headers
+    for_variables: Vec<(String, StartEndSpan)>,
+    /// The values the currently executing `for` loops have yet to assign, kept here rather than on the stack so that a stopped
+    /// program can be resumed, see [Interpreter::resume]
+    loop_values: Vec<LoopValues>,
+    /// The index of the statement being executed in the program, and in the body of each currently executing `for` loop
+    positions: Vec<usize>,
+    hook: H,
+    console: Box<dyn Console>,
 }
 
 impl Interpreter {
     /// Creates a new [Interpreter] object
     #[must_use]
     pub fn new() -> Self {
+        Self::with_hook(NoHook)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: Hook> Interpreter<H> {
+    /// Creates a new [Interpreter] object, instrumented with the given [Hook]
+    #[must_use]
+    pub fn with_hook(hook: H) -> Self {
         Self {
             environment: Environment::default(),
+            for_variables: Vec::new(),
+            loop_values: Vec::new(),
+            positions: Vec::new(),
+            hook,
+            console: Box::new(Stdio),
+        }
+    }
+
+    /// Replaces the [Console] the program reads its input from and prints its output to
+    pub fn set_console(&mut self, console: Box<dyn Console>) {
+        self.console = console;
+    }
+
+    /// Returns the [Hook] of the interpreter
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    /// Consumes the interpreter, returning its [Hook]
+    pub fn into_hook(self) -> H {
+        self.hook
+    }
+
+    /// Internal helper: returns a ForVariableModified error if the given name is the control variable of an active `for` loop
+    fn check_for_variable(&self, name: &str, span: StartEndSpan) -> Result<(), RuntimeError> {
+        match self.for_variables.iter().find(|(n, _)| n == name) {
+            Some((_, header)) => Err(RuntimeError::ForVariableModified(
+                name.to_owned(),
+                (*header).into(),
+                span.into(),
+            )),
+            None => Ok(()),
         }
     }
-}
 
-impl Interpreter {
     /// The primary function of the [Interpreter]: evaluates all statements
     pub fn eval(&mut self, statements: &[Statement]) -> Result<(), RuntimeError> {
-        for statement in statements {
-            let result = self.visit_statement(statement)?;
-            debug!("Interpreted: {result:?}");
+        self.for_variables.clear();
+        self.loop_values.clear();
+        self.positions = vec![0];
+        self.resume(statements)
+    }
+
+    /// Returns the state of the interpreter, to be restored with [Interpreter::restore]. When a [Hook] has stopped the program,
+    /// the state is that before the statement it was stopped at
+    #[must_use]
+    pub fn state(&self) -> ExecutionState {
+        let loops = self
+            .for_variables
+            .iter()
+            .zip(&self.loop_values)
+            .map(|((variable, header), values)| LoopState {
+                variable: variable.clone(),
+                header: *header,
+                values: values.clone(),
+            })
+            .collect();
+        ExecutionState {
+            variables: self
+                .environment
+                .variables()
+                .map(|(name, value)| (name.to_owned(), value.clone()))
+                .collect(),
+            positions: self.positions.clone(),
+            loops,
         }
+    }
 
+    /// Restores the state of a stopped program, to be continued with [Interpreter::resume]
+    pub fn restore(&mut self, state: ExecutionState) {
+        self.environment = Environment::default();
+        for (name, value) in state.variables {
+            // The names of a state are unique, so defining them cannot fail
+            let _ = self
+                .environment
+                .define(&name, value, StartEndSpan::new(0, 0));
+        }
+        self.for_variables.clear();
+        self.loop_values.clear();
+        for state in state.loops {
+            self.for_variables.push((state.variable, state.header));
+            self.loop_values.push(state.values);
+        }
+        self.positions = state.positions;
+    }
+
+    /// Continues the program from the statement it was stopped at, in the `for` loops it was stopped in. The statement is
+    /// executed from its start, including the [Hook::before_statement] that may have stopped it
+    pub fn resume(&mut self, statements: &[Statement]) -> Result<(), RuntimeError> {
+        if self.positions.is_empty() {
+            self.positions.push(0);
+        }
+        self.execute_block(statements, 0)?;
+        self.positions.clear();
+        Ok(())
+    }
+
+    /// Internal helper: executes the statements from the position at the given depth on. When resuming, the statement at the
+    /// position may be a `for` loop stopped in its body, which is continued rather than started
+    fn execute_block(
+        &mut self,
+        statements: &[Statement],
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        let mut index = self.positions[depth];
+        while let Some(statement) = statements.get(index) {
+            self.positions[depth] = index;
+            match &statement.stmt {
+                Stmt::Forloop(f) if self.positions.len() > depth + 1 => {
+                    // The rest of the current iteration, then the remaining ones
+                    self.execute_block(&f.body, depth + 1)?;
+                    self.iterate(f)?;
+                    self.hook.after_statement(statement, &Object::Nothing);
+                }
+                _ => {
+                    self.visit_statement(statement)?;
+                }
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+
+    /// Internal helper: executes the remaining iterations of the innermost `for` loop, and ends the loop
+    fn iterate(&mut self, f: &Forloop) -> Result<(), RuntimeError> {
+        let depth = self.positions.len() - 1;
+        while let Some(value) = self.loop_values.last_mut().and_then(LoopValues::next_value) {
+            match self.assign(&f.variable, value, f.header) {
+                Ok(_) => (),
+                Err(_) => {
+                    return Err(RuntimeError::ForBadAssignment(
+                        f.variable.clone(),
+                        f.span.into(),
+                    ))
+                }
+            };
+            self.positions[depth] = 0;
+            self.execute_block(&f.body, depth)?;
+        }
+        self.for_variables.pop();
+        self.loop_values.pop();
+        self.positions.pop();
         Ok(())
     }
 
     // TODO: cleanup
 
-    //TODO: this should probably be private or deprecated
-    /// Internal helper function: evaluates a single [Expr], a raw metadata-less version of [Expression]
-    fn eval_expr(&mut self, expr: &Expr) -> Result<Object, RuntimeError> {
-        match expr {
+    /// Internal helper function: evaluates a single [Expression]
+    fn eval_expr(&mut self, expression: &Expression) -> Result<Object, RuntimeError> {
+        let value = match &expression.expr {
             Expr::Assign(a) => self.visit_assign(a),
-            Expr::Binary(b) => self.visit_binary(b),
+            Expr::AssignIndex(a) => self.visit_assign_index(a),
+            Expr::Binary(b) => self.visit_binary(b, expression.span),
+            Expr::Call(c) => self.visit_call(c, expression.span),
             Expr::Grouping(g) => self.visit_grouping(g),
+            Expr::Index(i) => self.visit_index(i),
+            Expr::Interpolation(i) => self.visit_interpolation(i),
             Expr::Literal(l) => self.visit_literal(l),
             Expr::Logical(l) => self.visit_logical(l),
-            Expr::Unary(u) => self.visit_unary(u),
-            Expr::VariableUsage(v) => self.visit_variable_usage(v),
+            Expr::Unary(u) => self.visit_unary(u, expression.span),
+            Expr::VariableUsage(v) => self.visit_variable_usage(v, expression.span),
+        }?;
+        self.hook.after_expression(expression, &value);
+        Ok(value)
+    }
+
+    /// Internal helper: declares the variable, notifying the [Hook]
+    fn define(
+        &mut self,
+        name: &str,
+        value: &Object,
+        span: StartEndSpan,
+    ) -> Result<(), RuntimeError> {
+        self.environment.define(name, value.clone(), span)?;
+        self.hook.variable_written(VariableWrite {
+            name,
+            index: None,
+            old: None,
+            new: value,
+            span,
+        });
+        Ok(())
+    }
+
+    /// Internal helper: assigns to the variable, notifying the [Hook]. Returns the new value
+    fn assign(
+        &mut self,
+        name: &str,
+        value: Object,
+        span: StartEndSpan,
+    ) -> Result<Object, RuntimeError> {
+        let old = self.environment.assign(name, value.clone(), span)?;
+        self.hook.variable_written(VariableWrite {
+            name,
+            index: None,
+            old: Some(&old),
+            new: &value,
+            span,
+        });
+        Ok(value)
+    }
+
+    fn visit_binary(&mut self, b: &Binary, span: StartEndSpan) -> Result<Object, RuntimeError> {
+        // Operands are evaluated left to right
+        let left = self.eval_expr(&b.left)?;
+        let right = self.eval_expr(&b.right)?;
+        operators::binary(&b.operator, &left, &right, b.left.span, b.right.span, span)
+    }
+
+    fn visit_call(&mut self, c: &Call, span: StartEndSpan) -> Result<Object, RuntimeError> {
+        let mut arguments = Vec::new();
+        for argument in &c.arguments {
+            arguments.push((self.eval_expr(argument)?, argument.span));
         }
+        // The call expression starts with the name of the function
+        let name_span = StartEndSpan::new(span.start, span.start + c.name.chars().count());
+        builtins::call(&c.name, &arguments, span, name_span)
     }
 
-    // TODO: clean up the .expr. stuff
+    fn visit_grouping(&mut self, g: &Grouping) -> Result<Object, RuntimeError> {
+        // Ignore the grouping; evaluate inner expression
+        self.eval_expr(&g.expression)
+    }
 
-    fn visit_binary(&mut self, b: &Binary) -> Result<Object, RuntimeError> {
-        let right = self.eval_expr(&b.right.expr)?;
-        let left = self.eval_expr(&b.left.expr)?;
-        let tokentype = b.operator.tokentype();
-        let result = match tokentype {
-            Minus => Object::Number(left.as_numeric()? - right.as_numeric()?),
-            Slash => Object::Number(left.as_numeric()? / right.as_numeric()?),
-            Star => Object::Number(left.as_numeric()? * right.as_numeric()?),
-            Plus => match (&left, &right) {
-                (Object::Number(_), Object::Number(_)) => {
-                    Object::Number(left.as_numeric()? + right.as_numeric()?)
-                }
-                (Object::Text(_), Object::Text(_)) => {
-                    Object::Text(format!("{}{}", left.as_text()?, right.as_text()?))
-                }
-                (l_object, r_object) => {
-                    return Err(RuntimeError::PlusTypeMismatch(
-                        l_object.kind_to_string(),
-                        r_object.kind_to_string(),
-                        b.left.span.into(),
-                        b.right.span.into(),
-                    ))
-                }
-            },
-            Equal => match (&left, &right) {
-                (Object::Number(_), Object::Number(_)) => {
-                    Object::Boolean(left.as_numeric()? == right.as_numeric()?)
-                }
-                (Object::Text(_), Object::Text(_)) => {
-                    Object::Boolean(left.as_text()? == right.as_text()?)
-                }
-                (l_object, r_object) => {
-                    return Err(RuntimeError::EqualTypeMismatch(
-                        l_object.kind_to_string(),
-                        r_object.kind_to_string(),
-                        b.left.span.into(),
-                        b.right.span.into(),
-                    ))
-                }
-            },
-            Less => match (&left, &right) {
-                (Object::Number(_), Object::Number(_)) => {
-                    Object::Boolean(left.as_numeric()? < right.as_numeric()?)
-                }
-                (Object::Text(_), Object::Text(_)) => {
-                    Object::Boolean(left.as_text()? < right.as_text()?)
-                }
-                (l_object, r_object) => {
-                    return Err(RuntimeError::LessTypeMismatch(
-                        l_object.kind_to_string(),
-                        r_object.kind_to_string(),
-                        b.left.span.into(),
-                        b.right.span.into(),
-                    ))
+    fn visit_index(&mut self, i: &Index) -> Result<Object, RuntimeError> {
+        let index = self.eval_index(&i.index)?;
+        // Avoid copying the whole array when indexing into a variable directly
+        if let Expr::VariableUsage(name) = &i.array.expr {
+            return self
+                .environment
+                .get_index(name, index, i.array.span, i.index.span);
+        }
+        match self.eval_expr(&i.array)? {
+            Object::Array(elements) => usize::try_from(index)
+                .ok()
+                .and_then(|i| elements.get(i))
+                .cloned()
+                .ok_or_else(|| {
+                    RuntimeError::IndexOutOfBounds(index, elements.len(), i.index.span.into())
+                }),
+            other => Err(RuntimeError::IndexNonArray(
+                other.kind_to_string(),
+                i.array.span.into(),
+            )),
+        }
+    }
+
+    fn visit_interpolation(&mut self, i: &Interpolation) -> Result<Object, RuntimeError> {
+        let mut text = String::new();
+        for segment in &i.segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => text.push_str(literal),
+                InterpolationSegment::Expression(expression) => {
+                    let value = self.eval_expr(expression)?;
+                    text.push_str(&value.to_string());
                 }
-            },
-            _ => {
-                return Err(RuntimeError::UnexpectedBinaryOperator(
-                    format!("{:?}", b.operator.token),
-                    b.operator.span.into(),
-                ))
             }
-        };
-        Ok(result)
+        }
+        Ok(Object::Text(text))
     }
 
-    fn visit_grouping(&mut self, g: &Grouping) -> Result<Object, RuntimeError> {
-        // Ignore the grouping; evaluate inner expression
-        self.eval_expr(&g.expression.expr)
+    /// Internal helper: evaluates an array index expression into a number
+    fn eval_index(&mut self, index: &Expression) -> Result<i64, RuntimeError> {
+        let value = self.eval_expr(index)?;
+        match value.as_numeric(index.span) {
+            Ok(num) => Ok(num),
+            Err(_) => Err(RuntimeError::IndexNonNumeric(
+                value.kind_to_string(),
+                index.span.into(),
+            )),
+        }
     }
 
     fn visit_literal(&mut self, l: &Literal) -> Result<Object, RuntimeError> {
         let result = match &l.value.token {
             Number(n) => Object::Number(*n),
+            RealNumber(n) => Object::Real(*n),
             Text(t) => Object::Text(t.clone()),
             False => Object::Boolean(false),
             True => Object::Boolean(true),
@@ -149,11 +343,12 @@ impl Interpreter {
     }
 
     fn visit_logical(&mut self, l: &Logical) -> Result<Object, RuntimeError> {
-        let right = self.eval_expr(&l.right.expr)?;
-        let left = self.eval_expr(&l.left.expr)?;
+        // Short-circuiting: the right operand is only evaluated if the left one does not determine the result
+        let left = self.eval_expr(&l.left)?.as_bool(l.left.span)?;
         let tokentype = l.operator.tokentype();
         let result = match tokentype {
-            And => Object::Boolean(left.as_bool()? && right.as_bool()?),
+            And => Object::Boolean(left && self.eval_expr(&l.right)?.as_bool(l.right.span)?),
+            Or => Object::Boolean(left || self.eval_expr(&l.right)?.as_bool(l.right.span)?),
             _ => {
                 return Err(RuntimeError::UnexpectedLogicalOperator(
                     format!("{:?}", l.operator.token),
@@ -164,70 +359,73 @@ impl Interpreter {
         Ok(result)
     }
 
-    fn visit_unary(&mut self, u: &Unary) -> Result<Object, RuntimeError> {
-        let right = self.eval_expr(&u.right.expr)?;
-        let result = match u.operator.tokentype() {
-            Minus => Object::Number(-right.as_numeric()?),
-            Bang => Object::Boolean(!right.as_bool()?),
-            _ => {
-                return Err(RuntimeError::UnexpectedUnaryOperator(
-                    format!("{:?}", u.operator.token),
-                    u.operator.span.into(),
-                ))
-            }
-        };
-        Ok(result)
+    fn visit_unary(&mut self, u: &Unary, span: StartEndSpan) -> Result<Object, RuntimeError> {
+        let right = self.eval_expr(&u.right)?;
+        operators::unary(&u.operator, &right, u.right.span, span)
     }
 
-    fn visit_variable_usage(&self, name: &str) -> Result<Object, RuntimeError> {
-        self.environment.get(name)
+    fn visit_variable_usage(&self, name: &str, span: StartEndSpan) -> Result<Object, RuntimeError> {
+        self.environment.get(name, span)
     }
 
     /// Evaluates a variable assignment. Has side effects: stores the variable in the current interpreter's `environment`.
     fn visit_assign(&mut self, a: &Assign) -> Result<Object, RuntimeError> {
-        let value = self.eval_expr(&a.value.expr)?;
-        self.environment.assign(&a.name, value, a.token.span)
+        self.check_for_variable(&a.name, a.token.span)?;
+        let value = self.eval_expr(&a.value)?;
+        self.assign(&a.name, value, a.token.span)
+    }
+
+    /// Evaluates an assignment to an array element. Has side effects: stores the element in the current interpreter's `environment`.
+    fn visit_assign_index(&mut self, a: &AssignIndex) -> Result<Object, RuntimeError> {
+        self.check_for_variable(&a.name, a.token.span)?;
+        let index = self.eval_index(&a.index)?;
+        let value = self.eval_expr(&a.value)?;
+        let old = self.environment.assign_index(
+            &a.name,
+            index,
+            value.clone(),
+            a.index.span,
+            a.value.span,
+        )?;
+        self.hook.variable_written(VariableWrite {
+            name: &a.name,
+            index: Some(index),
+            old: Some(&old),
+            new: &value,
+            span: a.token.span,
+        });
+        Ok(value)
     }
 
     /// Evaluates a variable declaration i.e. the initial definition of a variable. Has side effects: stores the variable in the current interpreter's `environment`.
     fn eval_variable_declaration(&mut self, v: &Variable) -> Result<Object, RuntimeError> {
+        self.check_for_variable(&v.name, v.span)?;
         if let Some(initializer) = &v.initializer {
-            let value = self.eval_expr(&initializer.expr)?;
+            let value = self.eval_expr(initializer)?;
             // TODO: more robust type checking, going via tostring and format is ugly
-            if value.kind_to_string() != format!("{:?}", v.kind) {
+            if value.kind_to_string() != v.kind.to_string() {
                 return Err(RuntimeError::VariableAssignTypeMismatch(
-                    format!("{:?}", v.kind),
+                    v.kind.to_string(),
                     value.kind_to_string(),
                     v.span.into(),
                     initializer.span.into(),
                 ));
             }
-            self.environment.define(&v.name, value.clone(), v.span)?;
+            self.define(&v.name, &value, v.span)?;
             Ok(value)
         } else {
-            // Language spec: "If not explicitly initialized, variables are assigned an appropriate default value."
-            let default_value = match v.kind {
-                VarType::Boolean => Object::Boolean(false),
-                VarType::Number => Object::Number(0),
-                VarType::Text => Object::Text("".to_owned()),
-            };
-            self.environment
-                .define(&v.name, default_value.clone(), v.span)?;
+            let default_value = Object::default_value(&v.kind);
+            self.define(&v.name, &default_value, v.span)?;
             Ok(default_value)
         }
     }
-}
-
-impl Visitor<Object, RuntimeError> for Interpreter {
-    fn visit_expression(&mut self, expression: &Expression) -> Result<Object, RuntimeError> {
-        self.eval_expr(&expression.expr)
-    }
 
-    fn visit_statement(&mut self, statement: &Statement) -> Result<Object, RuntimeError> {
+    /// Internal helper: executes the statement, between the calls to the [Hook]
+    fn execute(&mut self, statement: &Statement) -> Result<Object, RuntimeError> {
         let expr = match &statement.stmt {
             Stmt::Assert(e) => {
                 let result = self.visit_expression(e)?;
-                match result.as_bool() {
+                match result.as_bool(e.span) {
                     Ok(bool) => {
                         if bool {
                             return Ok(Object::Nothing);
@@ -239,34 +437,14 @@ impl Visitor<Object, RuntimeError> for Interpreter {
             }
             Stmt::Expression(expr) | Stmt::Print(expr) => expr,
             Stmt::Read(name) => {
-                // TODO: better input handling
-                // TODO: less hacky
+                self.check_for_variable(name, statement.span)?;
                 let mut buffer = String::new();
-                let stdin = io::stdin();
-                stdin
+                self.console
                     .read_line(&mut buffer)
-                    .map_err(|_| RuntimeError::ReadLineFailed)?;
-                let old = self.environment.get(name)?;
-                let new = match old {
-                    Object::Number(_) => Object::Number(
-                        buffer
-                            .trim()
-                            .parse()
-                            .map_err(|_| RuntimeError::ReadParseIntFailed)?,
-                    ),
-                    Object::Text(_) => Object::Text(buffer),
-                    Object::Boolean(_) => Object::Boolean(
-                        buffer
-                            .trim()
-                            .parse()
-                            .map_err(|_| RuntimeError::ReadParseBoolFailed)?,
-                    ),
-                    Object::Nothing => {
-                        return Err(RuntimeError::ReadNothing);
-                    }
-                };
-                let object = self.environment.assign(name, new, statement.span)?;
-                return Ok(object);
+                    .map_err(|_| RuntimeError::ReadLineFailed(statement.span.into()))?;
+                let old = self.environment.get(name, statement.span)?;
+                let new = old.parse_input(buffer, statement.span)?;
+                return self.assign(name, new, statement.span);
             }
             Stmt::VariableDefinition(v) => return self.eval_variable_declaration(v),
             Stmt::Forloop(f) => {
@@ -274,52 +452,119 @@ impl Visitor<Object, RuntimeError> for Interpreter {
                 // NOTE: "The for control variable behaves like a constant inside the loop: it cannot be assigned another value (before exiting the for statement)"
                 // This means we evaluate the start and end only once, based on the initial start..end declaration
                 let start = self.visit_expression(&f.left)?;
-                let start = match start.as_numeric() {
-                    Ok(num) => num,
-                    Err(_) => {
-                        return Err(RuntimeError::ForStartNonNumeric(
-                            start.to_string(),
-                            f.left.span.into(),
-                        ))
-                    }
-                };
-                let end = self.visit_expression(&f.right)?;
-                let end = match end.as_numeric() {
-                    Ok(num) => num,
-                    Err(_) => {
-                        return Err(RuntimeError::ForEndNonNumeric(
-                            end.to_string(),
-                            f.right.span.into(),
-                        ))
+                let values = match &f.right {
+                    Some(right) => {
+                        let start = match start.as_numeric(f.left.span) {
+                            Ok(num) => num,
+                            Err(_) => {
+                                return Err(RuntimeError::ForStartNonNumeric(
+                                    start.to_string(),
+                                    f.left.span.into(),
+                                ))
+                            }
+                        };
+                        let end = self.visit_expression(right)?;
+                        let end = match end.as_numeric(right.span) {
+                            Ok(num) => num,
+                            Err(_) => {
+                                return Err(RuntimeError::ForEndNonNumeric(
+                                    end.to_string(),
+                                    right.span.into(),
+                                ))
+                            }
+                        };
+                        if start > end {
+                            return Err(RuntimeError::ForEndLarger(
+                                f.left.span.into(),
+                                right.span.into(),
+                            ));
+                        }
+                        LoopValues::Range { next: start, end }
                     }
+                    // Iterating over the elements of an array
+                    None => match start {
+                        Object::Array(elements) => LoopValues::Elements { elements, next: 0 },
+                        other => {
+                            return Err(RuntimeError::ForIterateNonArray(
+                                other.kind_to_string(),
+                                f.left.span.into(),
+                            ))
+                        }
+                    },
                 };
-                if start > end {
-                    return Err(RuntimeError::ForEndLarger(
-                        f.left.span.into(),
-                        f.right.span.into(),
-                    ));
-                }
-                for i in start..=end {
-                    match self.environment.assign(&name, Object::Number(i), f.span) {
-                        Ok(_) => (),
-                        Err(_) => return Err(RuntimeError::ForBadAssignment(name, f.span.into())),
-                    };
-                    for statement in &f.body {
-                        self.visit_statement(statement)?;
-                    }
-                }
+                // Nested loops must not reuse the control variable of an enclosing loop
+                self.check_for_variable(&name, f.header)?;
+                self.for_variables.push((name, f.header));
+                self.loop_values.push(values);
+                self.positions.push(0);
+                self.iterate(f)?;
                 return Ok(Object::Nothing);
             }
         };
-        let result = self.eval_expr(&expr.expr)?;
+        let result = self.eval_expr(expr)?;
         if let Stmt::Print(_expr) = &statement.stmt {
             // NOTE: the course project spec is slightly unclear on whether a print statement should contain an implicit newline or not
-            print!("{}", result);
-            io::stdout()
-                .flush()
-                .map_err(|_| RuntimeError::PrintCouldNotFlush)?;
+            self.console
+                .print(&result.to_string())
+                .map_err(|_| RuntimeError::PrintCouldNotFlush(statement.span.into()))?;
         };
 
         Ok(result)
     }
 }
+
+impl<H: Hook> Visitor<Object, RuntimeError> for Interpreter<H> {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<Object, RuntimeError> {
+        self.eval_expr(expression)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) -> Result<Object, RuntimeError> {
+        let state = State {
+            environment: &self.environment,
+            loops: &self.for_variables,
+        };
+        self.hook.before_statement(statement, state)?;
+        let result = self.execute(statement)?;
+        self.hook.after_statement(statement, &result);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use crate::lexing::Lexer;
+    use crate::parsing::*;
+    use crate::runtime::RuntimeError;
+    use crate::span::StartEndSpan;
+    use crate::visitors::Interpreter;
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let tokens = Lexer::new(source).scan().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn for_variable_assign_runtime_guard() {
+        // Parsing the loop body on its own bypasses the static check in the parser
+        let mut statements = parse("var x : int;");
+        let body = parse("x := 2;");
+        let range = parse("1; 3;");
+        let (start, end) = match (&range[0].stmt, &range[1].stmt) {
+            (Stmt::Expression(start), Stmt::Expression(end)) => (start.clone(), end.clone()),
+            _ => unreachable!(),
+        };
+        let span = StartEndSpan::new(0, 0);
+        statements.push(Statement::new(
+            Stmt::Forloop(Forloop::new("x", start, Some(end), body, span, span)),
+            span,
+        ));
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.eval(&statements);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::ForVariableModified(_, _, _))
+        ));
+    }
+}
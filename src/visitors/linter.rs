@@ -0,0 +1,444 @@
+use std::convert::Infallible;
+
+use crate::lints::{Level, Lint, LintConfig, Rule};
+use crate::parsing::*;
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken;
+
+use super::Visitor;
+
+#[derive(Debug)]
+/// The usage of a declared variable, tracked for the unused variable lints
+struct VariableUsage {
+    name: String,
+    /// The span of the declaration statement
+    span: StartEndSpan,
+    initialized: bool,
+    reads: usize,
+    writes: usize,
+}
+
+#[derive(Debug, Default)]
+/// [Linter] is a [Visitor] for finding likely mistakes in programs that are otherwise valid. See [crate::lints] for the rules.
+pub struct Linter {
+    config: LintConfig,
+    /// The declared variables, in declaration order
+    variables: Vec<VariableUsage>,
+    lints: Vec<Lint>,
+}
+
+impl Linter {
+    /// Creates a new [Linter] with the given configuration
+    #[must_use]
+    pub fn new(config: LintConfig) -> Self {
+        Self {
+            config,
+            variables: Vec::new(),
+            lints: Vec::new(),
+        }
+    }
+
+    /// The primary function of the [Linter]: returns the lints found in the program, in source order.
+    /// Lints of allowed rules are not returned.
+    pub fn lint(&mut self, statements: &[Statement]) -> Vec<Lint> {
+        self.variables.clear();
+        self.lints.clear();
+        self.visit_block(statements);
+        self.check_variables();
+        let mut lints: Vec<Lint> = self
+            .lints
+            .drain(..)
+            .filter(|lint| lint.level != Level::Allow)
+            .collect();
+        lints.sort_by_key(|lint| (lint.span.start, lint.span.end));
+        lints
+    }
+
+    fn report(&mut self, rule: Rule, message: &str, label: &str, span: StartEndSpan) {
+        self.report_with_help(rule, message, label, span, None);
+    }
+
+    fn report_with_help(
+        &mut self,
+        rule: Rule,
+        message: &str,
+        label: &str,
+        span: StartEndSpan,
+        help: Option<String>,
+    ) {
+        self.lints.push(Lint {
+            rule,
+            level: self.config.level(rule),
+            message: message.to_owned(),
+            label: label.to_owned(),
+            span,
+            help,
+        });
+    }
+
+    fn variable(&mut self, name: &str) -> Option<&mut VariableUsage> {
+        self.variables
+            .iter_mut()
+            .rev()
+            .find(|variable| variable.name == name)
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(variable) = self.variable(name) {
+            variable.reads += 1;
+        }
+    }
+
+    fn write(&mut self, name: &str) {
+        if let Some(variable) = self.variable(name) {
+            variable.writes += 1;
+        }
+    }
+
+    /// Visits the statements of a block, i.e. the whole program or the body of a for loop
+    fn visit_block(&mut self, statements: &[Statement]) {
+        let mut statements = statements.iter();
+        while let Some(statement) = statements.next() {
+            let _ = self.visit_statement(statement);
+            if let Stmt::Assert(condition) = &statement.stmt {
+                if is_false(condition) {
+                    let rest: Vec<&Statement> = statements.collect();
+                    if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+                        self.report_with_help(
+                            Rule::UnreachableCode,
+                            "Unreachable code",
+                            "This code is never executed",
+                            StartEndSpan::new(first.span.start, last.span.end),
+                            Some(
+                                "The preceding `assert false;` always stops the program".to_owned(),
+                            ),
+                        );
+                    }
+                    for statement in rest {
+                        let _ = self.visit_statement(statement);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Reports the declared variables that are never read
+    fn check_variables(&mut self) {
+        let variables = std::mem::take(&mut self.variables);
+        for variable in variables.iter().filter(|variable| variable.reads == 0) {
+            if variable.initialized || variable.writes > 0 {
+                self.report_with_help(
+                    Rule::VariableNeverRead,
+                    &format!("Variable `{}` is assigned, but never read", variable.name),
+                    "The value of this variable is never used",
+                    variable.span,
+                    Some("Remove the variable, or use its value".to_owned()),
+                );
+            } else {
+                self.report_with_help(
+                    Rule::UnusedVariable,
+                    &format!("Unused variable `{}`", variable.name),
+                    "Declared, but never used",
+                    variable.span,
+                    Some("Remove the declaration".to_owned()),
+                );
+            }
+        }
+    }
+
+    fn check_unused_result(&mut self, expression: &Expression) {
+        let help = match &expression.expr {
+            Expr::Assign(_) | Expr::AssignIndex(_) => return,
+            Expr::Binary(b)
+                if b.operator.token == RawToken::Equal
+                    && matches!(b.left.expr, Expr::VariableUsage(_)) =>
+            {
+                Some("Use the assignment operator := instead of = for assigning a value".to_owned())
+            }
+            _ => None,
+        };
+        self.report_with_help(
+            Rule::UnusedResult,
+            "Unused expression result",
+            "The value of this expression is discarded",
+            expression.span,
+            help,
+        );
+    }
+
+    fn check_assert(&mut self, condition: &Expression) {
+        if is_constant(condition) && !is_false(condition) {
+            self.report(
+                Rule::ConstantAssert,
+                "Constant condition in `assert`",
+                "This condition does not depend on the program state",
+                condition.span,
+            );
+        }
+    }
+}
+
+impl Visitor<(), Infallible> for Linter {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), Infallible> {
+        match &expression.expr {
+            Expr::Assign(a) => {
+                if let Expr::VariableUsage(name) = &ungrouped(&a.value).expr {
+                    if *name == a.name {
+                        self.report(
+                            Rule::SelfAssignment,
+                            &format!("Variable `{name}` is assigned to itself"),
+                            "This assignment has no effect",
+                            expression.span,
+                        );
+                    }
+                }
+                self.visit_expression(&a.value)?;
+                self.write(&a.name);
+            }
+            Expr::AssignIndex(a) => {
+                self.visit_expression(&a.index)?;
+                self.visit_expression(&a.value)?;
+                self.write(&a.name);
+            }
+            Expr::Binary(b) => {
+                if is_comparison(&b.operator.token) {
+                    if let (Expr::VariableUsage(left), Expr::VariableUsage(right)) =
+                        (&ungrouped(&b.left).expr, &ungrouped(&b.right).expr)
+                    {
+                        if left == right {
+                            self.report(
+                                Rule::SelfComparison,
+                                &format!("Variable `{left}` is compared to itself"),
+                                "The result of this comparison is always the same",
+                                expression.span,
+                            );
+                        }
+                    }
+                }
+                self.visit_expression(&b.left)?;
+                self.visit_expression(&b.right)?;
+            }
+            Expr::Call(c) => {
+                for argument in &c.arguments {
+                    self.visit_expression(argument)?;
+                }
+            }
+            Expr::Grouping(g) => self.visit_expression(&g.expression)?,
+            Expr::Index(i) => {
+                self.visit_expression(&i.array)?;
+                self.visit_expression(&i.index)?;
+            }
+            Expr::Interpolation(i) => {
+                for segment in &i.segments {
+                    if let InterpolationSegment::Expression(expression) = segment {
+                        self.visit_expression(expression)?;
+                    }
+                }
+            }
+            Expr::Literal(_) => (),
+            Expr::Logical(l) => {
+                self.visit_expression(&l.left)?;
+                self.visit_expression(&l.right)?;
+            }
+            Expr::Unary(u) => self.visit_expression(&u.right)?,
+            Expr::VariableUsage(name) => self.read(name),
+        }
+        Ok(())
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) -> Result<(), Infallible> {
+        match &statement.stmt {
+            Stmt::Assert(condition) => {
+                self.check_assert(condition);
+                self.visit_expression(condition)?;
+            }
+            Stmt::Expression(expression) => {
+                self.check_unused_result(expression);
+                self.visit_expression(expression)?;
+            }
+            Stmt::Forloop(f) => {
+                self.visit_expression(&f.left)?;
+                if let Some(right) = &f.right {
+                    self.visit_expression(right)?;
+                }
+                self.write(&f.variable);
+                if f.body.is_empty() {
+                    self.report(
+                        Rule::EmptyForBody,
+                        "Empty `for` loop body",
+                        "This loop does nothing",
+                        f.span,
+                    );
+                }
+                self.visit_block(&f.body);
+            }
+            Stmt::Print(expression) => self.visit_expression(expression)?,
+            Stmt::Read(name) => self.write(name),
+            Stmt::VariableDefinition(variable) => {
+                if let Some(initializer) = &variable.initializer {
+                    self.visit_expression(initializer)?;
+                }
+                self.variables.push(VariableUsage {
+                    name: variable.name.clone(),
+                    span: variable.span,
+                    initialized: variable.initializer.is_some(),
+                    reads: 0,
+                    writes: 0,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the expression inside any groupings
+fn ungrouped(expression: &Expression) -> &Expression {
+    match &expression.expr {
+        Expr::Grouping(g) => ungrouped(&g.expression),
+        _ => expression,
+    }
+}
+
+fn is_comparison(operator: &RawToken) -> bool {
+    matches!(
+        operator,
+        RawToken::Equal
+            | RawToken::NotEqual
+            | RawToken::Less
+            | RawToken::LessEqual
+            | RawToken::Greater
+            | RawToken::GreaterEqual
+    )
+}
+
+/// Returns true for the literal `false`, possibly in groupings
+fn is_false(expression: &Expression) -> bool {
+    matches!(&ungrouped(expression).expr, Expr::Literal(l) if l.value.token == RawToken::False)
+}
+
+/// Returns true if the value of the expression does not depend on the program state.
+/// The builtin functions are pure, so calls with constant arguments are constant too.
+fn is_constant(expression: &Expression) -> bool {
+    match &expression.expr {
+        Expr::Literal(_) => true,
+        Expr::Binary(b) => is_constant(&b.left) && is_constant(&b.right),
+        Expr::Call(c) => c.arguments.iter().all(is_constant),
+        Expr::Grouping(g) => is_constant(&g.expression),
+        Expr::Index(i) => is_constant(&i.array) && is_constant(&i.index),
+        Expr::Interpolation(i) => i.segments.iter().all(|segment| match segment {
+            InterpolationSegment::Literal(_) => true,
+            InterpolationSegment::Expression(expression) => is_constant(expression),
+        }),
+        Expr::Logical(l) => is_constant(&l.left) && is_constant(&l.right),
+        Expr::Unary(u) => is_constant(&u.right),
+        Expr::Assign(_) | Expr::AssignIndex(_) | Expr::VariableUsage(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::lexing::Lexer;
+
+    fn lint(source: &str) -> Vec<Lint> {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        Linter::default().lint(&statements)
+    }
+
+    fn rules(source: &str) -> Vec<Rule> {
+        lint(source).iter().map(|lint| lint.rule).collect()
+    }
+
+    #[test]
+    fn clean_program() {
+        let source = "var x : int := 1;\nx := x + 1;\nprint x;";
+        assert_eq!(rules(source), []);
+    }
+
+    #[test]
+    fn unused_variable() {
+        let lints = lint("var x : int;");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Rule::UnusedVariable);
+        assert_eq!(lints[0].span, StartEndSpan::new(0, 11));
+    }
+
+    #[test]
+    fn variable_never_read() {
+        assert_eq!(
+            rules("var x : int := 1;\nx := 2;"),
+            [Rule::VariableNeverRead]
+        );
+        assert_eq!(rules("var x : int;\nread x;"), [Rule::VariableNeverRead]);
+    }
+
+    #[test]
+    fn unused_result() {
+        let lints = lint("var x : int := 1;\nx = 2;\nprint x;");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Rule::UnusedResult);
+        assert_eq!(lints[0].span, StartEndSpan::new(18, 23));
+        assert!(lints[0].help.is_some());
+    }
+
+    #[test]
+    fn self_assignment() {
+        assert_eq!(
+            rules("var x : int := 1;\nx := (x);\nprint x;"),
+            [Rule::SelfAssignment]
+        );
+    }
+
+    #[test]
+    fn constant_assert() {
+        assert_eq!(rules("assert 1 + 1 = 2;"), [Rule::ConstantAssert]);
+        assert_eq!(rules("assert length(\"abc\") = 3;"), [Rule::ConstantAssert]);
+        assert_eq!(rules("var x : int := 1;\nassert x = 1;"), []);
+    }
+
+    #[test]
+    fn empty_for_body() {
+        assert_eq!(
+            rules("var i : int;\nfor i in 1..3 do\nend for;"),
+            [Rule::VariableNeverRead, Rule::EmptyForBody]
+        );
+    }
+
+    #[test]
+    fn self_comparison() {
+        assert_eq!(
+            rules("var x : int := 1;\nprint x <> x;"),
+            [Rule::SelfComparison]
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let lints = lint("print 1;\nassert false;\nprint 2;\nprint 3;");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Rule::UnreachableCode);
+        assert_eq!(lints[0].span, StartEndSpan::new(23, 39));
+    }
+
+    #[test]
+    fn unreachable_code_in_loop() {
+        let source = "var i : int;\nfor i in 1..3 do\nassert false;\nprint i;\nend for;";
+        assert_eq!(rules(source), [Rule::UnreachableCode]);
+    }
+
+    #[test]
+    fn allowed_rules_are_not_reported() {
+        let mut config = LintConfig::default();
+        config.set("unused_variable", Level::Allow).unwrap();
+        config.set("constant_assert", Level::Deny).unwrap();
+        let tokens = Lexer::new("var x : int;\nassert true;").scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let lints = Linter::new(config).lint(&statements);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Rule::ConstantAssert);
+        assert_eq!(lints[0].level, Level::Deny);
+    }
+}
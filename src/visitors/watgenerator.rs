@@ -0,0 +1,670 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::parsing::*;
+use crate::runtime::RuntimeError;
+use crate::span::StartEndSpan;
+use crate::tokens::RawToken;
+
+use super::codegen::{
+    runtime_errors, unexpected_binary_operator, unexpected_literal, unexpected_logical_operator,
+    unexpected_unary_operator, SourceLines,
+};
+use super::Visitor;
+
+const INDENT: &str = "  ";
+
+/// The runtime library of the generated modules: values, operators, builtins, error reporting and the host imports
+const RUNTIME: &str = include_str!("watgenerator/runtime.wat");
+
+/// The address of the static data of the program in linear memory. The runtime keeps its own texts below it
+const DATA_START: usize = 64;
+
+#[derive(Debug)]
+/// [WatGenerator] is a [Visitor] for compiling the program into a WebAssembly module in the text format.
+///
+/// As in the [CGenerator](super::CGenerator), values stay dynamically typed and all runtime checks of the
+/// [Interpreter](super::Interpreter) are performed with the same error codes, messages and source locations.
+/// Expressions are compiled into stack instructions that leave a pointer to their value on the stack.
+///
+/// The module imports its input and output from the host, see the top of the runtime for the functions of
+/// the `minipl` import module, and exports its `memory` and the `main` function running the program.
+pub struct WatGenerator {
+    lines: SourceLines,
+    /// Texts of the program and the runtime errors, placed in linear memory from [DATA_START]
+    data: Vec<u8>,
+    /// Addresses of the texts already in the data, so that each text is stored only once
+    texts: HashMap<String, usize>,
+    /// Lines and columns of the source locations referred to by the code, indexed by location id
+    locations: Vec<(usize, usize)>,
+    location_ids: HashMap<(usize, usize), usize>,
+    /// Indices of the variables, each stored in a 16-byte record in linear memory
+    variables: HashMap<String, usize>,
+    /// Declarations of the local variables of the `main` function, used by for loops
+    locals: Vec<String>,
+    loops: usize,
+}
+
+impl WatGenerator {
+    /// Creates a new [WatGenerator] for the given source code
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self {
+            lines: SourceLines::new(source),
+            data: Vec::new(),
+            texts: HashMap::new(),
+            locations: Vec::new(),
+            location_ids: HashMap::new(),
+            variables: HashMap::new(),
+            locals: Vec::new(),
+            loops: 0,
+        }
+    }
+
+    /// The primary function of the [WatGenerator]: returns the WebAssembly text of the whole module
+    ///
+    /// # Errors
+    /// Returns the same [RuntimeError] as the [Interpreter](super::Interpreter) for an expression the parser never constructs,
+    /// e.g. a binary expression with a logical operator in a syntax tree built by hand
+    pub fn generate(&mut self, statements: &[Statement]) -> Result<String, RuntimeError> {
+        self.data.clear();
+        self.texts.clear();
+        self.locations.clear();
+        self.location_ids.clear();
+        self.variables.clear();
+        self.locals.clear();
+        self.loops = 0;
+        let mut body = String::new();
+        for statement in statements {
+            body.push_str(&self.visit_statement(statement)?);
+        }
+
+        // Static data: the texts, then the tables of the runtime errors and the locations, then the variables
+        let errors = runtime_errors();
+        let mut tables = Vec::new();
+        for (code, message) in &errors {
+            for field in [
+                self.text(code),
+                code.len(),
+                self.text(message),
+                message.len(),
+            ] {
+                tables.extend(address(field).to_le_bytes());
+            }
+        }
+        for (line, column) in &self.locations {
+            tables.extend(address(*line).to_le_bytes());
+            tables.extend(address(*column).to_le_bytes());
+        }
+        let error_table = align(DATA_START + self.data.len(), 8);
+        let location_table = error_table + 16 * errors.len();
+        let variables = align(error_table + tables.len(), 8);
+        let heap = variables + 16 * self.variables.len();
+
+        let mut module = String::from(
+            ";; Generated by minipl. Assemble with e.g. `wat2wasm program.wat`, or build with `--target wasm`\n",
+        );
+        module.push_str("(module\n");
+        module.push_str(RUNTIME);
+        module.push('\n');
+        let _ = writeln!(
+            module,
+            "{INDENT};; Runtime errors, as indices into the error table"
+        );
+        for (index, (code, _)) in errors.iter().enumerate() {
+            let _ = writeln!(module, "{INDENT}(global ${code} i32 (i32.const {index}))");
+        }
+        let _ = writeln!(
+            module,
+            "{INDENT}(global $error_table i32 (i32.const {error_table}))"
+        );
+        let _ = writeln!(
+            module,
+            "{INDENT}(global $location_table i32 (i32.const {location_table}))"
+        );
+        if !self.variables.is_empty() {
+            let mut names: Vec<(&String, &usize)> = self.variables.iter().collect();
+            names.sort_by_key(|(_, index)| **index);
+            let names: Vec<&str> = names.iter().map(|(name, _)| name.as_str()).collect();
+            let _ = writeln!(module, "{INDENT};; Variables: {}", names.join(", "));
+            for index in 0..names.len() {
+                let _ = writeln!(
+                    module,
+                    "{INDENT}(global $var{index} i32 (i32.const {}))",
+                    variables + 16 * index
+                );
+            }
+        }
+        let _ = writeln!(
+            module,
+            "{INDENT}(global $heap (mut i32) (i32.const {heap}))"
+        );
+        let _ = writeln!(
+            module,
+            "{INDENT}(data (i32.const {DATA_START}) {})",
+            wat_string(&self.data)
+        );
+        let _ = writeln!(
+            module,
+            "{INDENT}(data (i32.const {error_table}) {})\n",
+            wat_string(&tables)
+        );
+        let _ = writeln!(module, "{INDENT}(func (export \"main\")");
+        for local in &self.locals {
+            let _ = writeln!(module, "{INDENT}{INDENT}{local}");
+        }
+        module.push_str(&indent(&body, 2));
+        let _ = writeln!(module, "{INDENT})\n)");
+        Ok(module)
+    }
+
+    /// Internal helper: returns the address of the text in linear memory, adding it to the data if needed
+    fn text(&mut self, text: &str) -> usize {
+        if let Some(address) = self.texts.get(text) {
+            return *address;
+        }
+        let address = DATA_START + self.data.len();
+        self.data.extend(text.as_bytes());
+        self.texts.insert(text.to_owned(), address);
+        address
+    }
+
+    /// Internal helper: returns the instruction pushing the address of the variable with the given name
+    fn variable(&mut self, name: &str) -> String {
+        let next = self.variables.len();
+        let index = *self.variables.entry(name.to_owned()).or_insert(next);
+        format!("global.get $var{index}\n")
+    }
+
+    /// Internal helper: returns the instruction pushing the id of the source location of the start of the span
+    fn location(&mut self, span: StartEndSpan) -> String {
+        let location = self.lines.line_column(span.start);
+        let next = self.locations.len();
+        let id = *self.location_ids.entry(location).or_insert(next);
+        if id == next {
+            self.locations.push(location);
+        }
+        format!("i32.const {id}\n")
+    }
+
+    /// Internal helper: returns the instructions reporting the runtime error. The stack is unreachable afterwards
+    fn fail(&mut self, code: &str, span: StartEndSpan) -> String {
+        format!(
+            "global.get ${code}\n{}call $fail\nunreachable\n",
+            self.location(span)
+        )
+    }
+
+    /// Internal helper: declares a new local variable of the `main` function, and returns its name
+    fn local(&mut self, name: &str, kind: &str) -> String {
+        let local = format!("${name}{}", self.loops);
+        self.locals.push(format!("(local {local} {kind})"));
+        local
+    }
+
+    fn compile_binary(&mut self, b: &Binary, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let mut code = self.visit_expression(&b.left)?;
+        code += &self.visit_expression(&b.right)?;
+        let operator = match b.operator.tokentype() {
+            RawToken::Plus => 0,
+            RawToken::Minus => 1,
+            RawToken::Star => 2,
+            RawToken::Slash => 3,
+            RawToken::Equal => 4,
+            RawToken::NotEqual => 5,
+            RawToken::Less => 6,
+            RawToken::LessEqual => 7,
+            RawToken::Greater => 8,
+            RawToken::GreaterEqual => 9,
+            // NOTE: the parser only constructs binary expressions of the operators above
+            _ => return Err(unexpected_binary_operator(&b.operator)),
+        };
+        let _ = writeln!(code, "i32.const {operator}");
+        code += &self.location(b.left.span);
+        code += &self.location(b.right.span);
+        code += &self.location(span);
+        Ok(code + "call $binary\n")
+    }
+
+    fn compile_call(&mut self, c: &Call, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let mut code = String::new();
+        for argument in &c.arguments {
+            code += &self.visit_expression(argument)?;
+        }
+        let count = match c.name.as_str() {
+            "length" | "to_int" | "to_lower" | "to_real" | "to_string" | "to_upper" => Some(1),
+            "char_at" | "find" => Some(2),
+            "substring" => Some(3),
+            _ => None,
+        };
+        let error = match count {
+            Some(count) if count == c.arguments.len() => None,
+            Some(_) => Some(self.fail("E0314", span)),
+            None => {
+                // The call expression starts with the name of the function
+                let name_span = StartEndSpan::new(span.start, span.start + c.name.chars().count());
+                Some(self.fail("E0313", name_span))
+            }
+        };
+        if let Some(error) = error {
+            // The arguments are evaluated before the call fails
+            for _ in &c.arguments {
+                code += "drop\n";
+            }
+            return Ok(code + &error);
+        }
+        for argument in &c.arguments {
+            code += &self.location(argument.span);
+        }
+        code += &self.location(span);
+        let _ = writeln!(code, "call ${}", c.name);
+        Ok(code)
+    }
+
+    fn compile_index(&mut self, i: &Index) -> Result<String, RuntimeError> {
+        // The index is evaluated before the array
+        let mut code = self.visit_expression(&i.index)?;
+        code += &self.location(i.index.span);
+        code += "call $as_index\n";
+        code += &self.visit_expression(&i.array)?;
+        code += &self.location(i.array.span);
+        code += &self.location(i.index.span);
+        Ok(code + "call $index\n")
+    }
+
+    fn compile_interpolation(&mut self, i: &Interpolation) -> Result<String, RuntimeError> {
+        let mut code = self.compile_text("");
+        for segment in &i.segments {
+            match segment {
+                InterpolationSegment::Literal(literal) => code += &self.compile_text(literal),
+                InterpolationSegment::Expression(expression) => {
+                    code += &self.visit_expression(expression)?;
+                    code += "call $to_text\n";
+                }
+            }
+            code += "call $concat\n";
+        }
+        Ok(code)
+    }
+
+    fn compile_text(&mut self, text: &str) -> String {
+        let address = if text.is_empty() { 0 } else { self.text(text) };
+        format!(
+            "i32.const {address}\ni32.const {}\ncall $text\n",
+            text.len()
+        )
+    }
+
+    fn compile_literal(&mut self, l: &Literal) -> Result<String, RuntimeError> {
+        let code = match &l.value.token {
+            RawToken::Number(n) => format!("i64.const {n}\ncall $number\n"),
+            RawToken::RealNumber(n) => format!("f64.const {}\ncall $real\n", wat_real(*n)),
+            RawToken::Text(t) => self.compile_text(t),
+            RawToken::False => "i32.const 0\ncall $boolean\n".to_owned(),
+            RawToken::True => "i32.const 1\ncall $boolean\n".to_owned(),
+            // NOTE: the parser only constructs literals of the tokens above
+            _ => return Err(unexpected_literal(&l.value)),
+        };
+        Ok(code)
+    }
+
+    fn compile_logical(&mut self, l: &Logical) -> Result<String, RuntimeError> {
+        // Short-circuiting: the right operand is only evaluated if the left one does not determine the result
+        let mut code = self.visit_expression(&l.left)?;
+        code += &self.location(l.left.span);
+        code += "call $as_boolean\n";
+        let short_circuit = match l.operator.tokentype() {
+            RawToken::And => {
+                code += "i32.eqz\n";
+                0
+            }
+            RawToken::Or => 1,
+            // NOTE: the parser only constructs logical expressions of the operators above
+            _ => return Err(unexpected_logical_operator(&l.operator)),
+        };
+        code += "if (result i32)\n";
+        let _ = writeln!(code, "i32.const {short_circuit}");
+        code += "else\n";
+        code += &self.visit_expression(&l.right)?;
+        code += &self.location(l.right.span);
+        code += "call $as_boolean\n";
+        code += "end\n";
+        Ok(code + "call $boolean\n")
+    }
+
+    fn compile_unary(&mut self, u: &Unary, span: StartEndSpan) -> Result<String, RuntimeError> {
+        let mut code = self.visit_expression(&u.right)?;
+        code += &self.location(u.right.span);
+        match u.operator.tokentype() {
+            RawToken::Minus => {
+                code += &self.location(span);
+                Ok(code + "call $negate\n")
+            }
+            RawToken::Bang => Ok(code + "call $not\n"),
+            // NOTE: the parser only constructs unary expressions of the operators above
+            _ => Err(unexpected_unary_operator(&u.operator)),
+        }
+    }
+
+    fn compile_assign(&mut self, a: &Assign) -> Result<String, RuntimeError> {
+        let variable = self.variable(&a.name);
+        let mut code = format!("{variable}call $check_loop\n{variable}");
+        code += &self.visit_expression(&a.value)?;
+        code += &self.location(a.token.span);
+        Ok(code + "call $assign\n")
+    }
+
+    fn compile_assign_index(&mut self, a: &AssignIndex) -> Result<String, RuntimeError> {
+        let variable = self.variable(&a.name);
+        let mut code = format!("{variable}call $check_loop\n{variable}");
+        code += &self.visit_expression(&a.index)?;
+        let index_location = self.location(a.index.span);
+        code += &index_location;
+        code += "call $as_index\n";
+        code += &self.visit_expression(&a.value)?;
+        code += &index_location;
+        Ok(code + "call $assign_index\n")
+    }
+
+    fn compile_variable_declaration(&mut self, v: &Variable) -> Result<String, RuntimeError> {
+        let variable = self.variable(&v.name);
+        let mut code = format!("{variable}call $check_loop\n{variable}");
+        match &v.initializer {
+            Some(initializer) => {
+                code += &self.visit_expression(initializer)?;
+                // The declared type is given as its default value
+                code += &default_value(&v.kind);
+                code += &self.location(v.span);
+                Ok(code + "call $define\n")
+            }
+            None => {
+                code += &default_value(&v.kind);
+                code += &self.location(v.span);
+                Ok(code + "call $define_default\n")
+            }
+        }
+    }
+
+    fn compile_forloop(&mut self, f: &Forloop) -> Result<String, RuntimeError> {
+        self.loops += 1;
+        let variable = self.variable(&f.variable);
+        let (exit, next) = (
+            format!("$exit{}", self.loops),
+            format!("$next{}", self.loops),
+        );
+        let mut code = self.visit_expression(&f.left)?;
+        let start_location = self.location(f.left.span);
+        code += &start_location;
+        match &f.right {
+            Some(right) => {
+                let (first, last, counter) = (
+                    self.local("first", "i64"),
+                    self.local("last", "i64"),
+                    self.local("counter", "i64"),
+                );
+                let _ = writeln!(code, "call $for_start\nlocal.set {first}");
+                code += &self.visit_expression(right)?;
+                code += &self.location(right.span);
+                let _ = writeln!(code, "call $for_end\nlocal.set {last}");
+                let _ = writeln!(code, "local.get {first}\nlocal.get {last}\ni64.gt_s\nif");
+                code += &self.fail("E0325", f.left.span);
+                code += "end\n";
+                code += &variable;
+                code += &self.location(f.header);
+                let _ = writeln!(
+                    code,
+                    "call $loop_begin\nlocal.get {first}\nlocal.set {counter}"
+                );
+                let _ = writeln!(code, "block {exit}\nloop {next}");
+                code += &variable;
+                let _ = writeln!(code, "local.get {counter}\ncall $number");
+                code += &self.location(f.span);
+                code += "call $loop_assign\n";
+                code += &self.compile_body(&f.body)?;
+                // The counter stops at the end without incrementing past it, so that ranges ending at the largest number do not overflow
+                let _ = writeln!(
+                    code,
+                    "local.get {counter}\nlocal.get {last}\ni64.eq\nbr_if {exit}"
+                );
+                let _ = writeln!(
+                    code,
+                    "local.get {counter}\ni64.const 1\ni64.add\nlocal.set {counter}"
+                );
+            }
+            // Iterating over the elements of an array
+            None => {
+                let (elements, counter) =
+                    (self.local("elements", "i32"), self.local("index", "i32"));
+                let _ = writeln!(code, "call $for_array\nlocal.set {elements}");
+                code += &variable;
+                code += &self.location(f.header);
+                let _ = writeln!(code, "call $loop_begin\ni32.const 0\nlocal.set {counter}");
+                let _ = writeln!(code, "block {exit}\nloop {next}");
+                let _ = writeln!(
+                    code,
+                    "local.get {counter}\nlocal.get {elements}\ncall $array_length\ni32.ge_u\nbr_if {exit}"
+                );
+                code += &variable;
+                let _ = writeln!(
+                    code,
+                    "local.get {elements}\nlocal.get {counter}\ncall $element"
+                );
+                code += &self.location(f.span);
+                code += "call $loop_assign\n";
+                code += &self.compile_body(&f.body)?;
+                let _ = writeln!(
+                    code,
+                    "local.get {counter}\ni32.const 1\ni32.add\nlocal.set {counter}"
+                );
+            }
+        }
+        let _ = writeln!(code, "br {next}\nend\nend");
+        Ok(code + &variable + "call $loop_end\n")
+    }
+
+    fn compile_body(&mut self, body: &[Statement]) -> Result<String, RuntimeError> {
+        body.iter()
+            .map(|statement| self.visit_statement(statement))
+            .collect()
+    }
+}
+
+impl Visitor<String, RuntimeError> for WatGenerator {
+    /// Returns the instructions evaluating the expression, leaving a pointer to its value on the stack
+    fn visit_expression(&mut self, expression: &Expression) -> Result<String, RuntimeError> {
+        let span = expression.span;
+        let code = match &expression.expr {
+            Expr::Assign(a) => self.compile_assign(a)?,
+            Expr::AssignIndex(a) => self.compile_assign_index(a)?,
+            Expr::Binary(b) => self.compile_binary(b, span)?,
+            Expr::Call(c) => self.compile_call(c, span)?,
+            Expr::Grouping(g) => self.visit_expression(&g.expression)?,
+            Expr::Index(i) => self.compile_index(i)?,
+            Expr::Interpolation(i) => self.compile_interpolation(i)?,
+            Expr::Literal(l) => self.compile_literal(l)?,
+            Expr::Logical(l) => self.compile_logical(l)?,
+            Expr::Unary(u) => self.compile_unary(u, span)?,
+            Expr::VariableUsage(name) => {
+                let variable = self.variable(name);
+                variable + &self.location(span) + "call $get\n"
+            }
+        };
+        Ok(code)
+    }
+
+    /// Returns the instructions of the statement, preceded by a comment with its source location
+    fn visit_statement(&mut self, statement: &Statement) -> Result<String, RuntimeError> {
+        let (line, column) = self.lines.line_column(statement.span.start);
+        let mut code = format!(";; {line}:{column}\n");
+        match &statement.stmt {
+            Stmt::Assert(e) => {
+                code += &self.visit_expression(e)?;
+                code += &self.location(e.span);
+                code += "call $assert\n";
+            }
+            Stmt::Expression(e) => {
+                code += &self.visit_expression(e)?;
+                code += "drop\n";
+            }
+            Stmt::Forloop(f) => code += &self.compile_forloop(f)?,
+            Stmt::Print(e) => {
+                code += &self.visit_expression(e)?;
+                code += "call $print\n";
+            }
+            Stmt::Read(name) => {
+                code += &self.variable(name);
+                code += &self.location(statement.span);
+                code += "call $read\n";
+            }
+            Stmt::VariableDefinition(v) => code += &self.compile_variable_declaration(v)?,
+        }
+        Ok(code)
+    }
+}
+
+/// Returns the instructions pushing the default value of a variable of the given type
+fn default_value(kind: &VarType) -> String {
+    match kind {
+        VarType::Boolean => "i32.const 0\ncall $boolean\n".to_owned(),
+        VarType::Number => "i64.const 0\ncall $number\n".to_owned(),
+        VarType::Real => "f64.const 0.0\ncall $real\n".to_owned(),
+        VarType::Text => "i32.const 0\ni32.const 0\ncall $text\n".to_owned(),
+        VarType::Array(kind, size) => {
+            format!("i32.const {size}\n{}call $array_of\n", default_value(kind))
+        }
+    }
+}
+
+/// Indents the instructions by the given nesting level, and the contents of blocks one level deeper
+fn indent(code: &str, level: usize) -> String {
+    let mut indented = String::new();
+    let mut level = level;
+    for line in code.lines() {
+        if line.starts_with("end") || line.starts_with("else") {
+            level -= 1;
+        }
+        let _ = writeln!(indented, "{}{line}", INDENT.repeat(level));
+        if ["block", "loop", "if", "else"]
+            .iter()
+            .any(|header| line.starts_with(header))
+        {
+            level += 1;
+        }
+    }
+    indented
+}
+
+/// Internal helper: converts an address or a length of the static data into an i32 of the module
+fn address(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+fn align(address: usize, alignment: usize) -> usize {
+    address.div_ceil(alignment) * alignment
+}
+
+/// Returns the real number as a WebAssembly float literal of exactly the same value
+fn wat_real(real: f64) -> String {
+    if real.is_nan() {
+        "nan".to_owned()
+    } else if real.is_infinite() {
+        format!("{}inf", if real < 0.0 { "-" } else { "" })
+    } else {
+        // NOTE: the Debug format is the shortest representation that round-trips, and is valid WebAssembly text
+        format!("{real:?}")
+    }
+}
+
+/// Returns the bytes as a WebAssembly string literal. Bytes outside printable ASCII are escaped in hexadecimal
+fn wat_string(bytes: &[u8]) -> String {
+    let mut literal = String::from("\"");
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(*byte as char);
+            }
+            b' '..=b'~' => literal.push(*byte as char),
+            _ => {
+                let _ = write!(literal, "\\{byte:02x}");
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::lexing::Lexer;
+    use crate::tokens::Token;
+
+    fn generate(source: &str) -> String {
+        let tokens = Lexer::new(source).scan().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        WatGenerator::new(source).generate(&statements).unwrap()
+    }
+
+    #[test]
+    fn unexpected_operators_are_errors() {
+        // Building the expression by hand bypasses the parser, which never puts a binary operator in a unary expression
+        let one = Expression::new(
+            Expr::Literal(Literal::new(Token::new(
+                RawToken::Number(1),
+                StartEndSpan::new(1, 2),
+            ))),
+            StartEndSpan::new(1, 2),
+        );
+        let star = Token::new(RawToken::Star, StartEndSpan::new(0, 1));
+        let unary = Expression::new(Expr::Unary(Unary::new(star, one)), StartEndSpan::new(0, 2));
+        let statements = [Statement::new(Stmt::Print(unary), StartEndSpan::new(0, 2))];
+        let result = WatGenerator::new("*1").generate(&statements);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::UnexpectedUnaryOperator(_, _))
+        ));
+    }
+
+    #[test]
+    fn wat_string_escapes() {
+        assert_eq!(wat_string(b"a\"b\\c\n"), "\"a\\\"b\\\\c\\0a\"");
+        assert_eq!(wat_string("ä".as_bytes()), "\"\\c3\\a4\"");
+    }
+
+    #[test]
+    fn wat_real_round_trips() {
+        assert_eq!(wat_real(1.0), "1.0");
+        assert_eq!(wat_real(1e100), "1e100");
+        assert_eq!(wat_real(f64::NEG_INFINITY), "-inf");
+        assert_eq!(wat_real(f64::NAN), "nan");
+    }
+
+    #[test]
+    fn locations_are_numbered_once() {
+        let program = generate("print 1 / 0;\nprint 1 / 0;");
+        assert!(program.contains("(data (i32.const 64) \"E0301"));
+        // The whole expression starts at its left operand, so both share a location
+        assert!(program.contains("i32.const 0\n    i32.const 1\n    i32.const 0\n    call $binary"));
+        assert!(program.contains("i32.const 2\n    i32.const 3\n    i32.const 2\n    call $binary"));
+        assert!(!program.contains("i32.const 4\n    call $binary"));
+    }
+
+    #[test]
+    fn variables_are_numbered_in_order_of_appearance() {
+        let program = generate("var b : int; var a : int := b;");
+        assert!(program.contains(";; Variables: b, a\n  (global $var0 i32"));
+        assert!(program.contains("global.get $var1\n    call $check_loop"));
+    }
+
+    #[test]
+    fn modules_are_valid() {
+        let program = generate(
+            "var a : array[3] of int; for i in a do print i; end for;\n\
+             var x : int; for x in 1..3 do print \"{x}\" + \"ä\"; end for;\n\
+             assert(true | 1 / 0 = 0); print substring(\"abc\", 0, 2);",
+        );
+        wat::parse_str(program).unwrap();
+    }
+}
@@ -0,0 +1,909 @@
+  ;; Runtime of Mini-PL programs compiled to WebAssembly. Mirrors the semantics of the interpreter:
+  ;; values are dynamically typed, and every runtime error of the interpreter is reported with the
+  ;; same error code, message and source location.
+  ;;
+  ;; Values are pointers to 16-byte boxes in linear memory: the kind at offset 0, and the payload
+  ;; at offset 8. Texts are UTF-8 bytes, with the pointer at offset 8 and the length at offset 12.
+  ;; Arrays point to a block of the length followed by the element values. Memory is never freed.
+  ;;
+  ;; Texts, input and error reports are passed to the host as pointers into the exported memory.
+
+  ;; Writes the text to the standard output
+  (import "minipl" "print" (func $host_print (param $pointer i32) (param $length i32)))
+  ;; Reads the next line of the standard input, including the line break, and returns its length in bytes.
+  ;; Returns -1 if reading failed or the line is not valid UTF-8
+  (import "minipl" "read_line" (func $host_read_line (result i32)))
+  ;; Copies the line read by `read_line` into memory
+  (import "minipl" "read_bytes" (func $host_read_bytes (param $pointer i32)))
+  ;; Writes the shortest representation of the real number that round-trips into memory, at most 32 bytes,
+  ;; and returns its length. Always with a fractional part or an exponent, e.g. `1.0`, `1e16`, `NaN` or `-inf`
+  (import "minipl" "format_real" (func $host_format_real (param $real f64) (param $pointer i32) (result i32)))
+  ;; Parses the text as a real number, e.g. `1.5`, `1e3` or `inf`, and stores it at `$result`. Returns 0 if parsing failed
+  (import "minipl" "parse_real" (func $host_parse_real (param $pointer i32) (param $length i32) (param $result i32) (result i32)))
+  ;; Reports a runtime error with the given code, message, line and column. The program traps afterwards
+  (import "minipl" "fail" (func $host_fail (param $code i32) (param $code_length i32) (param $message i32) (param $message_length i32) (param $line i32) (param $column i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Constant texts used by the runtime
+  (data (i32.const 8) "truefalse, []SS")
+  (global $TRUE i32 (i32.const 8))
+  (global $FALSE i32 (i32.const 12))
+  (global $SEPARATOR i32 (i32.const 17))
+  (global $OPEN i32 (i32.const 19))
+  (global $CLOSE i32 (i32.const 20))
+  (global $SHARP_S i32 (i32.const 21))
+
+  ;; Kinds of values
+  (global $NUMBER i32 (i32.const 0))
+  (global $REAL i32 (i32.const 1))
+  (global $TEXT i32 (i32.const 2))
+  (global $BOOLEAN i32 (i32.const 3))
+  (global $ARRAY i32 (i32.const 4))
+
+  ;; Memory
+
+  (func $alloc (param $size i32) (result i32)
+    (local $address i32)
+    (local $pages i32)
+    (local.set $address (global.get $heap))
+    (global.set $heap
+      (i32.and (i32.add (i32.add (local.get $address) (local.get $size)) (i32.const 7)) (i32.const -8)))
+    (local.set $pages
+      (i32.sub (i32.shr_u (i32.add (global.get $heap) (i32.const 65535)) (i32.const 16)) (memory.size)))
+    (if (i32.gt_s (local.get $pages) (i32.const 0))
+      (then
+        (if (i32.eq (memory.grow (local.get $pages)) (i32.const -1))
+          (then unreachable))))
+    (local.get $address))
+
+  ;; Values
+
+  (func $box (param $kind i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $alloc (i32.const 16)))
+    (i32.store (local.get $value) (local.get $kind))
+    (local.get $value))
+
+  (func $number (param $number i64) (result i32)
+    (local $value i32)
+    (local.set $value (call $box (global.get $NUMBER)))
+    (i64.store offset=8 (local.get $value) (local.get $number))
+    (local.get $value))
+
+  (func $real (param $real f64) (result i32)
+    (local $value i32)
+    (local.set $value (call $box (global.get $REAL)))
+    (f64.store offset=8 (local.get $value) (local.get $real))
+    (local.get $value))
+
+  ;; A text referring to the given bytes. The bytes are not copied, as texts are immutable
+  (func $text (param $pointer i32) (param $length i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $box (global.get $TEXT)))
+    (i32.store offset=8 (local.get $value) (local.get $pointer))
+    (i32.store offset=12 (local.get $value) (local.get $length))
+    (local.get $value))
+
+  (func $boolean (param $boolean i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $box (global.get $BOOLEAN)))
+    (i32.store offset=8 (local.get $value) (i32.ne (local.get $boolean) (i32.const 0)))
+    (local.get $value))
+
+  (func $array (param $elements i32) (result i32)
+    (local $value i32)
+    (local.set $value (call $box (global.get $ARRAY)))
+    (i32.store offset=8 (local.get $value) (local.get $elements))
+    (local.get $value))
+
+  ;; An array of the given length, with all elements set to the given value
+  (func $array_of (param $length i32) (param $element i32) (result i32)
+    (local $elements i32)
+    (local $i i32)
+    (local.set $elements (call $alloc (i32.add (i32.const 4) (i32.shl (local.get $length) (i32.const 2)))))
+    (i32.store (local.get $elements) (local.get $length))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (i32.store offset=4 (i32.add (local.get $elements) (i32.shl (local.get $i) (i32.const 2))) (local.get $element))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $array (local.get $elements)))
+
+  ;; The element of the array at the given index. The index must be within bounds
+  (func $element (param $array i32) (param $index i32) (result i32)
+    (i32.load offset=4
+      (i32.add (i32.load offset=8 (local.get $array)) (i32.shl (local.get $index) (i32.const 2)))))
+
+  (func $array_length (param $array i32) (result i32)
+    (i32.load (i32.load offset=8 (local.get $array))))
+
+  ;; Arrays have value semantics: they are copied when stored into a variable. The elements are
+  ;; immutable values of the basic types, so they are shared
+  (func $copy (param $value i32) (result i32)
+    (local $length i32)
+    (local $elements i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $ARRAY))
+      (then (return (local.get $value))))
+    (local.set $length (call $array_length (local.get $value)))
+    (local.set $elements (call $alloc (i32.add (i32.const 4) (i32.shl (local.get $length) (i32.const 2)))))
+    (memory.copy
+      (local.get $elements)
+      (i32.load offset=8 (local.get $value))
+      (i32.add (i32.const 4) (i32.shl (local.get $length) (i32.const 2))))
+    (call $array (local.get $elements)))
+
+  ;; Whether the values have the same type, e.g. `Array[3] of Number`. Used for type checking assignments
+  (func $same_kind (param $a i32) (param $b i32) (result i32)
+    (if (i32.ne (i32.load (local.get $a)) (i32.load (local.get $b)))
+      (then (return (i32.const 0))))
+    (if (i32.ne (i32.load (local.get $a)) (global.get $ARRAY))
+      (then (return (i32.const 1))))
+    (if (i32.ne (call $array_length (local.get $a)) (call $array_length (local.get $b)))
+      (then (return (i32.const 0))))
+    (if (i32.eqz (call $array_length (local.get $a)))
+      (then (return (i32.const 1))))
+    (i32.eq
+      (i32.load (call $element (local.get $a) (i32.const 0)))
+      (i32.load (call $element (local.get $b) (i32.const 0)))))
+
+  ;; Errors
+
+  ;; Reports a runtime error and traps. The error and the location are indices into the tables of the program
+  (func $fail (param $error i32) (param $location i32)
+    (local $entry i32)
+    (local $at i32)
+    (local.set $entry (i32.add (global.get $error_table) (i32.shl (local.get $error) (i32.const 4))))
+    (local.set $at (i32.add (global.get $location_table) (i32.shl (local.get $location) (i32.const 3))))
+    (call $host_fail
+      (i32.load (local.get $entry))
+      (i32.load offset=4 (local.get $entry))
+      (i32.load offset=8 (local.get $entry))
+      (i32.load offset=12 (local.get $entry))
+      (i32.load (local.get $at))
+      (i32.load offset=4 (local.get $at)))
+    unreachable)
+
+  ;; Texts
+
+  (func $text_pointer (param $text i32) (result i32)
+    (i32.load offset=8 (local.get $text)))
+
+  (func $text_length (param $text i32) (result i32)
+    (i32.load offset=12 (local.get $text)))
+
+  (func $concat (param $a i32) (param $b i32) (result i32)
+    (local $pointer i32)
+    (local.set $pointer (call $alloc (i32.add (call $text_length (local.get $a)) (call $text_length (local.get $b)))))
+    (memory.copy (local.get $pointer) (call $text_pointer (local.get $a)) (call $text_length (local.get $a)))
+    (memory.copy
+      (i32.add (local.get $pointer) (call $text_length (local.get $a)))
+      (call $text_pointer (local.get $b))
+      (call $text_length (local.get $b)))
+    (call $text
+      (local.get $pointer)
+      (i32.add (call $text_length (local.get $a)) (call $text_length (local.get $b)))))
+
+  ;; Whether the bytes at the two pointers are equal
+  (func $equal_bytes (param $a i32) (param $b i32) (param $length i32) (result i32)
+    (local $i i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (if (i32.ne
+              (i32.load8_u (i32.add (local.get $a) (local.get $i)))
+              (i32.load8_u (i32.add (local.get $b) (local.get $i))))
+          (then (return (i32.const 0))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.const 1))
+
+  ;; Three-way comparison of texts, by their bytes
+  (func $compare_texts (param $a i32) (param $b i32) (result i32)
+    (local $i i32)
+    (local $length i32)
+    (local $x i32)
+    (local $y i32)
+    (local.set $length (call $text_length (local.get $a)))
+    (if (i32.lt_u (call $text_length (local.get $b)) (local.get $length))
+      (then (local.set $length (call $text_length (local.get $b)))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (local.set $x (i32.load8_u (i32.add (call $text_pointer (local.get $a)) (local.get $i))))
+        (local.set $y (i32.load8_u (i32.add (call $text_pointer (local.get $b)) (local.get $i))))
+        (if (i32.ne (local.get $x) (local.get $y))
+          (then (return (select (i32.const -1) (i32.const 1) (i32.lt_u (local.get $x) (local.get $y))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.sub
+      (i32.gt_u (call $text_length (local.get $a)) (call $text_length (local.get $b)))
+      (i32.lt_u (call $text_length (local.get $a)) (call $text_length (local.get $b)))))
+
+  (func $is_space (param $byte i32) (result i32)
+    (i32.or
+      (i32.eq (local.get $byte) (i32.const 32))
+      (i32.and (i32.ge_u (local.get $byte) (i32.const 9)) (i32.le_u (local.get $byte) (i32.const 13)))))
+
+  ;; The bytes without leading and trailing whitespace, as a pointer and a length
+  (func $trim (param $pointer i32) (param $length i32) (result i32 i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $length)))
+        (br_if $done (i32.eqz (call $is_space (i32.load8_u (local.get $pointer)))))
+        (local.set $pointer (i32.add (local.get $pointer) (i32.const 1)))
+        (local.set $length (i32.sub (local.get $length) (i32.const 1)))
+        (br $next)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $length)))
+        (br_if $done
+          (i32.eqz (call $is_space (i32.load8_u (i32.sub (i32.add (local.get $pointer) (local.get $length)) (i32.const 1))))))
+        (local.set $length (i32.sub (local.get $length) (i32.const 1)))
+        (br $next)))
+    (local.get $pointer)
+    (local.get $length))
+
+  ;; Parses a number in the syntax of the interpreter: an optional sign and decimal digits.
+  ;; Returns the number, and whether parsing succeeded
+  (func $parse_number (param $pointer i32) (param $length i32) (result i64 i32)
+    (local $i i32)
+    (local $byte i32)
+    (local $negative i32)
+    (local $magnitude i64)
+    (local $limit i64)
+    (local $digit i64)
+    (if (i32.gt_s (local.get $length) (i32.const 0))
+      (then
+        (local.set $byte (i32.load8_u (local.get $pointer)))
+        (if (i32.or (i32.eq (local.get $byte) (i32.const 43)) (i32.eq (local.get $byte) (i32.const 45)))
+          (then
+            (local.set $negative (i32.eq (local.get $byte) (i32.const 45)))
+            (local.set $i (i32.const 1))))))
+    (if (i32.ge_s (local.get $i) (local.get $length))
+      (then (return (i64.const 0) (i32.const 0))))
+    (local.set $limit
+      (select (i64.const 0x8000000000000000) (i64.const 0x7fffffffffffffff) (local.get $negative)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_s (local.get $i) (local.get $length)))
+        (local.set $byte (i32.load8_u (i32.add (local.get $pointer) (local.get $i))))
+        (if (i32.or (i32.lt_u (local.get $byte) (i32.const 48)) (i32.gt_u (local.get $byte) (i32.const 57)))
+          (then (return (i64.const 0) (i32.const 0))))
+        (local.set $digit (i64.extend_i32_u (i32.sub (local.get $byte) (i32.const 48))))
+        (if (i64.gt_u (local.get $magnitude) (i64.div_u (i64.sub (local.get $limit) (local.get $digit)) (i64.const 10)))
+          (then (return (i64.const 0) (i32.const 0))))
+        (local.set $magnitude (i64.add (i64.mul (local.get $magnitude) (i64.const 10)) (local.get $digit)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (select (i64.sub (i64.const 0) (local.get $magnitude)) (local.get $magnitude) (local.get $negative))
+    (i32.const 1))
+
+  (func $utf8_length (param $pointer i32) (param $length i32) (result i32)
+    (local $i i32)
+    (local $count i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (if (i32.ne
+              (i32.and (i32.load8_u (i32.add (local.get $pointer) (local.get $i))) (i32.const 0xC0))
+              (i32.const 0x80))
+          (then (local.set $count (i32.add (local.get $count) (i32.const 1)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $count))
+
+  ;; The byte offset of the character with the given index, or the length of the text
+  (func $utf8_offset (param $pointer i32) (param $length i32) (param $index i32) (result i32)
+    (local $i i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (if (i32.ne
+              (i32.and (i32.load8_u (i32.add (local.get $pointer) (local.get $i))) (i32.const 0xC0))
+              (i32.const 0x80))
+          (then
+            (if (i32.eqz (local.get $index))
+              (then (return (local.get $i))))
+            (local.set $index (i32.sub (local.get $index) (i32.const 1)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $length))
+
+  ;; Printing
+
+  (func $format_number (param $number i64) (result i32)
+    (local $buffer i32)
+    (local $position i32)
+    (local $magnitude i64)
+    (local.set $buffer (call $alloc (i32.const 20)))
+    (local.set $position (i32.add (local.get $buffer) (i32.const 20)))
+    ;; The magnitude is unsigned, so that it also holds the smallest number
+    (local.set $magnitude
+      (select
+        (i64.sub (i64.const 0) (local.get $number))
+        (local.get $number)
+        (i64.lt_s (local.get $number) (i64.const 0))))
+    (loop $next
+      (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+      (i32.store8 (local.get $position)
+        (i32.add (i32.const 48) (i32.wrap_i64 (i64.rem_u (local.get $magnitude) (i64.const 10)))))
+      (local.set $magnitude (i64.div_u (local.get $magnitude) (i64.const 10)))
+      (br_if $next (i64.ne (local.get $magnitude) (i64.const 0))))
+    (if (i64.lt_s (local.get $number) (i64.const 0))
+      (then
+        (local.set $position (i32.sub (local.get $position) (i32.const 1)))
+        (i32.store8 (local.get $position) (i32.const 45))))
+    (call $text
+      (local.get $position)
+      (i32.sub (i32.add (local.get $buffer) (i32.const 20)) (local.get $position))))
+
+  ;; The printed representation of the value
+  (func $to_text (param $value i32) (result i32)
+    (local $kind i32)
+    (local $buffer i32)
+    (local $result i32)
+    (local $i i32)
+    (local.set $kind (i32.load (local.get $value)))
+    (if (i32.eq (local.get $kind) (global.get $NUMBER))
+      (then (return (call $format_number (i64.load offset=8 (local.get $value))))))
+    (if (i32.eq (local.get $kind) (global.get $REAL))
+      (then
+        (local.set $buffer (call $alloc (i32.const 32)))
+        (return
+          (call $text
+            (local.get $buffer)
+            (call $host_format_real (f64.load offset=8 (local.get $value)) (local.get $buffer))))))
+    (if (i32.eq (local.get $kind) (global.get $TEXT))
+      (then (return (local.get $value))))
+    (if (i32.eq (local.get $kind) (global.get $BOOLEAN))
+      (then
+        (return
+          (if (result i32) (i32.load offset=8 (local.get $value))
+            (then (call $text (global.get $TRUE) (i32.const 4)))
+            (else (call $text (global.get $FALSE) (i32.const 5)))))))
+    (local.set $result (call $text (global.get $OPEN) (i32.const 1)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (call $array_length (local.get $value))))
+        (if (i32.gt_u (local.get $i) (i32.const 0))
+          (then
+            (local.set $result
+              (call $concat (local.get $result) (call $text (global.get $SEPARATOR) (i32.const 2))))))
+        (local.set $result
+          (call $concat (local.get $result) (call $to_text (call $element (local.get $value) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $concat (local.get $result) (call $text (global.get $CLOSE) (i32.const 1))))
+
+  (func $print (param $value i32)
+    (local $text i32)
+    (local.set $text (call $to_text (local.get $value)))
+    (call $host_print (call $text_pointer (local.get $text)) (call $text_length (local.get $text))))
+
+  ;; Conversions
+
+  (func $as_boolean (param $value i32) (param $location i32) (result i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $BOOLEAN))
+      (then (call $fail (global.get $E0303) (local.get $location))))
+    (i32.load offset=8 (local.get $value)))
+
+  (func $as_index (param $value i32) (param $location i32) (result i64)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0312) (local.get $location))))
+    (i64.load offset=8 (local.get $value)))
+
+  ;; Operators
+
+  ;; The binary operators: 0 `+`, 1 `-`, 2 `*`, 3 `/`, 4 `=`, 5 `<>`, 6 `<`, 7 `<=`, 8 `>`, 9 `>=`.
+  ;; The locations are those of the operands and the whole expression
+  (func $binary (param $left i32) (param $right i32) (param $operator i32)
+                (param $left_location i32) (param $right_location i32) (param $location i32) (result i32)
+    (local $left_kind i32)
+    (local $right_kind i32)
+    (local $a i64)
+    (local $b i64)
+    (local $result i64)
+    (local $x f64)
+    (local $y f64)
+    (local $order i32)
+    (local.set $left_kind (i32.load (local.get $left)))
+    (local.set $right_kind (i32.load (local.get $right)))
+    ;; Number and Real values are never converted implicitly
+    (if (i32.or
+          (i32.and (i32.eq (local.get $left_kind) (global.get $NUMBER)) (i32.eq (local.get $right_kind) (global.get $REAL)))
+          (i32.and (i32.eq (local.get $left_kind) (global.get $REAL)) (i32.eq (local.get $right_kind) (global.get $NUMBER))))
+      (then (call $fail (global.get $E0307) (local.get $left_location))))
+
+    ;; `-`, `*` and `/`
+    (if (i32.and (i32.ge_u (local.get $operator) (i32.const 1)) (i32.le_u (local.get $operator) (i32.const 3)))
+      (then
+        (if (i32.eq (local.get $left_kind) (global.get $REAL))
+          (then
+            (if (i32.ne (local.get $right_kind) (global.get $REAL))
+              (then (call $fail (global.get $E0302) (local.get $right_location))))
+            (local.set $x (f64.load offset=8 (local.get $left)))
+            (local.set $y (f64.load offset=8 (local.get $right)))
+            (if (i32.eq (local.get $operator) (i32.const 1))
+              (then (return (call $real (f64.sub (local.get $x) (local.get $y))))))
+            (if (i32.eq (local.get $operator) (i32.const 2))
+              (then (return (call $real (f64.mul (local.get $x) (local.get $y))))))
+            (return (call $real (f64.div (local.get $x) (local.get $y))))))
+        (if (i32.ne (local.get $left_kind) (global.get $NUMBER))
+          (then (call $fail (global.get $E0301) (local.get $left_location))))
+        (if (i32.ne (local.get $right_kind) (global.get $NUMBER))
+          (then (call $fail (global.get $E0301) (local.get $right_location))))
+        (local.set $a (i64.load offset=8 (local.get $left)))
+        (local.set $b (i64.load offset=8 (local.get $right)))
+        (if (i32.eq (local.get $operator) (i32.const 1))
+          (then
+            (local.set $result (i64.sub (local.get $a) (local.get $b)))
+            ;; Overflow if the operands have different signs, and the result has a different sign than the left operand
+            (if (i64.lt_s
+                  (i64.and
+                    (i64.xor (local.get $a) (local.get $b))
+                    (i64.xor (local.get $a) (local.get $result)))
+                  (i64.const 0))
+              (then (call $fail (global.get $E0343) (local.get $location))))
+            (return (call $number (local.get $result)))))
+        (if (i32.eq (local.get $operator) (i32.const 3))
+          (then
+            (if (i64.eqz (local.get $b))
+              (then (call $fail (global.get $E0342) (local.get $left_location))))
+            (if (i32.and
+                  (i64.eq (local.get $a) (i64.const 0x8000000000000000))
+                  (i64.eq (local.get $b) (i64.const -1)))
+              (then (call $fail (global.get $E0343) (local.get $location))))
+            (return (call $number (i64.div_s (local.get $a) (local.get $b))))))
+        (if (i32.or
+              (i32.and (i64.eq (local.get $a) (i64.const -1)) (i64.eq (local.get $b) (i64.const 0x8000000000000000)))
+              (i32.and (i64.eq (local.get $b) (i64.const -1)) (i64.eq (local.get $a) (i64.const 0x8000000000000000))))
+          (then (call $fail (global.get $E0343) (local.get $location))))
+        (local.set $result (i64.mul (local.get $a) (local.get $b)))
+        (if (i64.ne (local.get $a) (i64.const 0))
+          (then
+            (if (i64.ne (i64.div_s (local.get $result) (local.get $a)) (local.get $b))
+              (then (call $fail (global.get $E0343) (local.get $location))))))
+        (return (call $number (local.get $result)))))
+
+    ;; `+`
+    (if (i32.eqz (local.get $operator))
+      (then
+        (if (i32.ne (local.get $left_kind) (local.get $right_kind))
+          (then (call $fail (global.get $E0330) (local.get $left_location))))
+        (if (i32.eq (local.get $left_kind) (global.get $NUMBER))
+          (then
+            (local.set $a (i64.load offset=8 (local.get $left)))
+            (local.set $b (i64.load offset=8 (local.get $right)))
+            (local.set $result (i64.add (local.get $a) (local.get $b)))
+            ;; Overflow if the result has a different sign than both operands
+            (if (i64.lt_s
+                  (i64.and
+                    (i64.xor (local.get $a) (local.get $result))
+                    (i64.xor (local.get $b) (local.get $result)))
+                  (i64.const 0))
+              (then (call $fail (global.get $E0343) (local.get $location))))
+            (return (call $number (local.get $result)))))
+        (if (i32.eq (local.get $left_kind) (global.get $REAL))
+          (then
+            (return
+              (call $real (f64.add (f64.load offset=8 (local.get $left)) (f64.load offset=8 (local.get $right)))))))
+        (if (i32.eq (local.get $left_kind) (global.get $TEXT))
+          (then (return (call $concat (local.get $left) (local.get $right)))))
+        (call $fail (global.get $E0330) (local.get $left_location))))
+
+    ;; Comparisons, of two values of the same type: Number, Real or Text
+    (if (i32.or
+          (i32.ne (local.get $left_kind) (local.get $right_kind))
+          (i32.ge_u (local.get $left_kind) (global.get $BOOLEAN)))
+      (then
+        (if (i32.eq (local.get $operator) (i32.const 4))
+          (then (call $fail (global.get $E0305) (local.get $left_location))))
+        (if (i32.eq (local.get $operator) (i32.const 5))
+          (then (call $fail (global.get $E0308) (local.get $left_location))))
+        (if (i32.eq (local.get $operator) (i32.const 6))
+          (then (call $fail (global.get $E0306) (local.get $left_location))))
+        (call $fail (global.get $E0309) (local.get $left_location))))
+    ;; Comparisons involving a NaN are false, except `<>`
+    (if (i32.eq (local.get $left_kind) (global.get $REAL))
+      (then
+        (local.set $x (f64.load offset=8 (local.get $left)))
+        (local.set $y (f64.load offset=8 (local.get $right)))
+        (if (i32.eq (local.get $operator) (i32.const 4))
+          (then (return (call $boolean (f64.eq (local.get $x) (local.get $y))))))
+        (if (i32.eq (local.get $operator) (i32.const 5))
+          (then (return (call $boolean (f64.ne (local.get $x) (local.get $y))))))
+        (if (i32.eq (local.get $operator) (i32.const 6))
+          (then (return (call $boolean (f64.lt (local.get $x) (local.get $y))))))
+        (if (i32.eq (local.get $operator) (i32.const 7))
+          (then (return (call $boolean (f64.le (local.get $x) (local.get $y))))))
+        (if (i32.eq (local.get $operator) (i32.const 8))
+          (then (return (call $boolean (f64.gt (local.get $x) (local.get $y))))))
+        (return (call $boolean (f64.ge (local.get $x) (local.get $y))))))
+    (if (i32.eq (local.get $left_kind) (global.get $NUMBER))
+      (then
+        (local.set $a (i64.load offset=8 (local.get $left)))
+        (local.set $b (i64.load offset=8 (local.get $right)))
+        (local.set $order (i32.sub (i64.gt_s (local.get $a) (local.get $b)) (i64.lt_s (local.get $a) (local.get $b)))))
+      (else (local.set $order (call $compare_texts (local.get $left) (local.get $right)))))
+    (if (i32.eq (local.get $operator) (i32.const 4))
+      (then (return (call $boolean (i32.eqz (local.get $order))))))
+    (if (i32.eq (local.get $operator) (i32.const 5))
+      (then (return (call $boolean (i32.ne (local.get $order) (i32.const 0))))))
+    (if (i32.eq (local.get $operator) (i32.const 6))
+      (then (return (call $boolean (i32.lt_s (local.get $order) (i32.const 0))))))
+    (if (i32.eq (local.get $operator) (i32.const 7))
+      (then (return (call $boolean (i32.le_s (local.get $order) (i32.const 0))))))
+    (if (i32.eq (local.get $operator) (i32.const 8))
+      (then (return (call $boolean (i32.gt_s (local.get $order) (i32.const 0))))))
+    (call $boolean (i32.ge_s (local.get $order) (i32.const 0))))
+
+  (func $negate (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (if (i32.eq (i32.load (local.get $value)) (global.get $REAL))
+      (then (return (call $real (f64.neg (f64.load offset=8 (local.get $value)))))))
+    (if (i32.ne (i32.load (local.get $value)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0301) (local.get $value_location))))
+    (if (i64.eq (i64.load offset=8 (local.get $value)) (i64.const 0x8000000000000000))
+      (then (call $fail (global.get $E0343) (local.get $location))))
+    (call $number (i64.sub (i64.const 0) (i64.load offset=8 (local.get $value)))))
+
+  (func $not (param $value i32) (param $value_location i32) (result i32)
+    (call $boolean (i32.eqz (call $as_boolean (local.get $value) (local.get $value_location)))))
+
+  ;; Arrays
+
+  (func $index (param $index i64) (param $array i32) (param $array_location i32) (param $index_location i32) (result i32)
+    (if (i32.ne (i32.load (local.get $array)) (global.get $ARRAY))
+      (then (call $fail (global.get $E0311) (local.get $array_location))))
+    (if (i32.or
+          (i64.lt_s (local.get $index) (i64.const 0))
+          (i64.ge_s (local.get $index) (i64.extend_i32_u (call $array_length (local.get $array)))))
+      (then (call $fail (global.get $E0310) (local.get $index_location))))
+    (call $element (local.get $array) (i32.wrap_i64 (local.get $index))))
+
+  ;; Variables. Each variable takes 16 bytes: whether it is declared, whether it is the control variable
+  ;; of an executing for loop, the location of the header of that loop, and the value
+
+  ;; The control variable of an executing for loop cannot be modified
+  (func $check_loop (param $variable i32)
+    (if (i32.load offset=4 (local.get $variable))
+      (then (call $fail (global.get $E0326) (i32.load offset=8 (local.get $variable))))))
+
+  (func $get (param $variable i32) (param $location i32) (result i32)
+    (if (i32.eqz (i32.load (local.get $variable)))
+      (then (call $fail (global.get $E0340) (local.get $location))))
+    (i32.load offset=12 (local.get $variable)))
+
+  ;; Declares the variable. The type of the declaration is given as its default value
+  (func $define (param $variable i32) (param $value i32) (param $default i32) (param $location i32)
+    (if (i32.eqz (call $same_kind (local.get $value) (local.get $default)))
+      (then (call $fail (global.get $E0341) (local.get $location))))
+    (call $define_default (local.get $variable) (call $copy (local.get $value)) (local.get $location)))
+
+  (func $define_default (param $variable i32) (param $value i32) (param $location i32)
+    (if (i32.load (local.get $variable))
+      (then (call $fail (global.get $E0338) (local.get $location))))
+    (i32.store (local.get $variable) (i32.const 1))
+    (i32.store offset=12 (local.get $variable) (local.get $value)))
+
+  (func $assign (param $variable i32) (param $value i32) (param $location i32) (result i32)
+    (if (i32.eqz (i32.load (local.get $variable)))
+      (then (call $fail (global.get $E0339) (local.get $location))))
+    (if (i32.eqz (call $same_kind (local.get $value) (i32.load offset=12 (local.get $variable))))
+      (then (call $fail (global.get $E0341) (local.get $location))))
+    (i32.store offset=12 (local.get $variable) (call $copy (local.get $value)))
+    (local.get $value))
+
+  (func $assign_index (param $variable i32) (param $index i64) (param $value i32) (param $index_location i32) (result i32)
+    (local $array i32)
+    (local $slot i32)
+    (if (i32.eqz (i32.load (local.get $variable)))
+      (then (call $fail (global.get $E0339) (local.get $index_location))))
+    (local.set $array (i32.load offset=12 (local.get $variable)))
+    (if (i32.ne (i32.load (local.get $array)) (global.get $ARRAY))
+      (then (call $fail (global.get $E0311) (local.get $index_location))))
+    (if (i32.or
+          (i64.lt_s (local.get $index) (i64.const 0))
+          (i64.ge_s (local.get $index) (i64.extend_i32_u (call $array_length (local.get $array)))))
+      (then (call $fail (global.get $E0310) (local.get $index_location))))
+    (if (i32.eqz
+          (call $same_kind (local.get $value) (call $element (local.get $array) (i32.wrap_i64 (local.get $index)))))
+      (then (call $fail (global.get $E0341) (local.get $index_location))))
+    ;; The array of the variable is not shared, so it is modified in place
+    (local.set $slot
+      (i32.add
+        (i32.load offset=8 (local.get $array))
+        (i32.shl (i32.wrap_i64 (local.get $index)) (i32.const 2))))
+    (i32.store offset=4 (local.get $slot) (local.get $value))
+    (local.get $value))
+
+  ;; For loops
+
+  (func $for_start (param $value i32) (param $location i32) (result i64)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0329) (local.get $location))))
+    (i64.load offset=8 (local.get $value)))
+
+  (func $for_end (param $value i32) (param $location i32) (result i64)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0327) (local.get $location))))
+    (i64.load offset=8 (local.get $value)))
+
+  ;; The array to iterate over. It is copied, so that the loop body cannot change the elements
+  (func $for_array (param $value i32) (param $location i32) (result i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $ARRAY))
+      (then (call $fail (global.get $E0328) (local.get $location))))
+    (call $copy (local.get $value)))
+
+  (func $loop_begin (param $variable i32) (param $header i32)
+    (call $check_loop (local.get $variable))
+    (i32.store offset=4 (local.get $variable) (i32.const 1))
+    (i32.store offset=8 (local.get $variable) (local.get $header)))
+
+  (func $loop_assign (param $variable i32) (param $value i32) (param $location i32)
+    (if (i32.eqz (i32.load (local.get $variable)))
+      (then (call $fail (global.get $E0324) (local.get $location))))
+    (if (i32.eqz (call $same_kind (local.get $value) (i32.load offset=12 (local.get $variable))))
+      (then (call $fail (global.get $E0324) (local.get $location))))
+    (i32.store offset=12 (local.get $variable) (local.get $value)))
+
+  (func $loop_end (param $variable i32)
+    (i32.store offset=4 (local.get $variable) (i32.const 0)))
+
+  ;; Statements
+
+  (func $assert (param $value i32) (param $location i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $BOOLEAN))
+      (then (call $fail (global.get $E0322) (local.get $location))))
+    (if (i32.eqz (i32.load offset=8 (local.get $value)))
+      (then (call $fail (global.get $E0323) (local.get $location)))))
+
+  (func $read (param $variable i32) (param $location i32)
+    (local $length i32)
+    (local $buffer i32)
+    (local $kind i32)
+    (local $pointer i32)
+    (local $trimmed i32)
+    (local $ok i32)
+    (local $number i64)
+    (local $value i32)
+    (call $check_loop (local.get $variable))
+    (local.set $length (call $host_read_line))
+    (if (i32.lt_s (local.get $length) (i32.const 0))
+      (then (call $fail (global.get $E0332) (local.get $location))))
+    (local.set $buffer (call $alloc (local.get $length)))
+    (call $host_read_bytes (local.get $buffer))
+    (local.set $kind (i32.load (call $get (local.get $variable) (local.get $location))))
+    (call $trim (local.get $buffer) (local.get $length))
+    (local.set $trimmed)
+    (local.set $pointer)
+    (if (i32.eq (local.get $kind) (global.get $NUMBER))
+      (then
+        (call $parse_number (local.get $pointer) (local.get $trimmed))
+        (local.set $ok)
+        (local.set $number)
+        (if (i32.eqz (local.get $ok))
+          (then (call $fail (global.get $E0336) (local.get $location))))
+        (local.set $value (call $number (local.get $number)))))
+    (if (i32.eq (local.get $kind) (global.get $REAL))
+      (then
+        (local.set $value (call $box (global.get $REAL)))
+        (if (i32.eqz
+              (call $host_parse_real
+                (local.get $pointer)
+                (local.get $trimmed)
+                (i32.add (local.get $value) (i32.const 8))))
+          (then (call $fail (global.get $E0337) (local.get $location))))))
+    ;; Texts keep the whole line
+    (if (i32.eq (local.get $kind) (global.get $TEXT))
+      (then (local.set $value (call $text (local.get $buffer) (local.get $length)))))
+    (if (i32.eq (local.get $kind) (global.get $BOOLEAN))
+      (then
+        (if (i32.and
+              (i32.eq (local.get $trimmed) (i32.const 4))
+              (call $equal_bytes (local.get $pointer) (global.get $TRUE) (i32.const 4)))
+          (then (local.set $value (call $boolean (i32.const 1))))
+          (else
+            (if (i32.and
+                  (i32.eq (local.get $trimmed) (i32.const 5))
+                  (call $equal_bytes (local.get $pointer) (global.get $FALSE) (i32.const 5)))
+              (then (local.set $value (call $boolean (i32.const 0))))
+              (else (call $fail (global.get $E0335) (local.get $location))))))))
+    (if (i32.eq (local.get $kind) (global.get $ARRAY))
+      (then (call $fail (global.get $E0333) (local.get $location))))
+    (drop (call $assign (local.get $variable) (local.get $value) (local.get $location))))
+
+  ;; Builtin functions. The locations are those of the arguments, and the whole call
+
+  (func $length (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (if (i32.eq (i32.load (local.get $value)) (global.get $ARRAY))
+      (then (return (call $number (i64.extend_i32_u (call $array_length (local.get $value)))))))
+    (if (i32.ne (i32.load (local.get $value)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $value_location))))
+    (call $number
+      (i64.extend_i32_u
+        (call $utf8_length (call $text_pointer (local.get $value)) (call $text_length (local.get $value))))))
+
+  (func $to_int (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (local $pointer i32)
+    (local $length i32)
+    (local $ok i32)
+    (local $number i64)
+    ;; Truncates towards zero, saturating at the bounds. NaN becomes zero
+    (if (i32.eq (i32.load (local.get $value)) (global.get $REAL))
+      (then (return (call $number (i64.trunc_sat_f64_s (f64.load offset=8 (local.get $value)))))))
+    (if (i32.ne (i32.load (local.get $value)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $value_location))))
+    (call $trim (call $text_pointer (local.get $value)) (call $text_length (local.get $value)))
+    (local.set $length)
+    (local.set $pointer)
+    (call $parse_number (local.get $pointer) (local.get $length))
+    (local.set $ok)
+    (local.set $number)
+    (if (i32.eqz (local.get $ok))
+      (then (call $fail (global.get $E0316) (local.get $value_location))))
+    (call $number (local.get $number)))
+
+  (func $to_real (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0315) (local.get $value_location))))
+    (call $real (f64.convert_i64_s (i64.load offset=8 (local.get $value)))))
+
+  (func $to_string (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (if (i32.or
+          (i32.eq (i32.load (local.get $value)) (global.get $TEXT))
+          (i32.eq (i32.load (local.get $value)) (global.get $ARRAY)))
+      (then (call $fail (global.get $E0315) (local.get $value_location))))
+    (call $to_text (local.get $value)))
+
+  ;; Case conversion of ASCII and Latin-1 letters. Other characters are kept as is
+  (func $convert_case (param $value i32) (param $value_location i32) (param $upper i32) (result i32)
+    (local $source i32)
+    (local $length i32)
+    (local $buffer i32)
+    (local $i i32)
+    (local $out i32)
+    (local $byte i32)
+    (local $next i32)
+    (if (i32.ne (i32.load (local.get $value)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $value_location))))
+    (local.set $source (call $text_pointer (local.get $value)))
+    (local.set $length (call $text_length (local.get $value)))
+    ;; `ß` becomes `SS` of the same length, so the result is never longer than the text
+    (local.set $buffer (call $alloc (local.get $length)))
+    (block $done
+      (loop $continue
+        (br_if $done (i32.ge_u (local.get $i) (local.get $length)))
+        (local.set $byte (i32.load8_u (i32.add (local.get $source) (local.get $i))))
+        (if (i32.and
+              (i32.eq (local.get $byte) (i32.const 0xC3))
+              (i32.lt_u (i32.add (local.get $i) (i32.const 1)) (local.get $length)))
+          (then
+            ;; U+00C0 to U+00FF are encoded as 0xC3 0x80 to 0xC3 0xBF
+            (local.set $next (i32.load8_u (i32.add (i32.add (local.get $source) (local.get $i)) (i32.const 1))))
+            (if (i32.and (local.get $upper) (i32.eq (local.get $next) (i32.const 0x9F)))
+              (then
+                (memory.copy (i32.add (local.get $buffer) (local.get $out)) (global.get $SHARP_S) (i32.const 2)))
+              (else
+                (if (i32.and
+                      (local.get $upper)
+                      (i32.and
+                        (i32.and (i32.ge_u (local.get $next) (i32.const 0xA0)) (i32.le_u (local.get $next) (i32.const 0xBE)))
+                        (i32.ne (local.get $next) (i32.const 0xB7))))
+                  (then (local.set $next (i32.sub (local.get $next) (i32.const 0x20)))))
+                (if (i32.and
+                      (i32.eqz (local.get $upper))
+                      (i32.and
+                        (i32.and (i32.ge_u (local.get $next) (i32.const 0x80)) (i32.le_u (local.get $next) (i32.const 0x9E)))
+                        (i32.ne (local.get $next) (i32.const 0x97))))
+                  (then (local.set $next (i32.add (local.get $next) (i32.const 0x20)))))
+                (i32.store8 (i32.add (local.get $buffer) (local.get $out)) (local.get $byte))
+                (i32.store8 (i32.add (i32.add (local.get $buffer) (local.get $out)) (i32.const 1)) (local.get $next))))
+            (local.set $i (i32.add (local.get $i) (i32.const 2)))
+            (local.set $out (i32.add (local.get $out) (i32.const 2)))
+            (br $continue)))
+        (if (i32.and
+              (local.get $upper)
+              (i32.and (i32.ge_u (local.get $byte) (i32.const 97)) (i32.le_u (local.get $byte) (i32.const 122))))
+          (then (local.set $byte (i32.sub (local.get $byte) (i32.const 32)))))
+        (if (i32.and
+              (i32.eqz (local.get $upper))
+              (i32.and (i32.ge_u (local.get $byte) (i32.const 65)) (i32.le_u (local.get $byte) (i32.const 90))))
+          (then (local.set $byte (i32.add (local.get $byte) (i32.const 32)))))
+        (i32.store8 (i32.add (local.get $buffer) (local.get $out)) (local.get $byte))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (local.set $out (i32.add (local.get $out) (i32.const 1)))
+        (br $continue)))
+    (call $text (local.get $buffer) (local.get $out)))
+
+  (func $to_upper (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (call $convert_case (local.get $value) (local.get $value_location) (i32.const 1)))
+
+  (func $to_lower (param $value i32) (param $value_location i32) (param $location i32) (result i32)
+    (call $convert_case (local.get $value) (local.get $value_location) (i32.const 0)))
+
+  (func $substring (param $text i32) (param $start i32) (param $end i32)
+                   (param $text_location i32) (param $start_location i32) (param $end_location i32)
+                   (param $location i32) (result i32)
+    (local $from i64)
+    (local $to i64)
+    (local $pointer i32)
+    (local $length i32)
+    (local $offset i32)
+    (if (i32.ne (i32.load (local.get $text)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $text_location))))
+    (if (i32.ne (i32.load (local.get $start)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0315) (local.get $start_location))))
+    (if (i32.ne (i32.load (local.get $end)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0315) (local.get $end_location))))
+    (local.set $pointer (call $text_pointer (local.get $text)))
+    (local.set $length (call $text_length (local.get $text)))
+    (local.set $from (i64.load offset=8 (local.get $start)))
+    (local.set $to (i64.load offset=8 (local.get $end)))
+    (if (i32.or
+          (i32.or (i64.lt_s (local.get $from) (i64.const 0)) (i64.gt_s (local.get $from) (local.get $to)))
+          (i64.gt_s
+            (local.get $to)
+            (i64.extend_i32_u (call $utf8_length (local.get $pointer) (local.get $length)))))
+      (then (call $fail (global.get $E0317) (local.get $location))))
+    (local.set $offset (call $utf8_offset (local.get $pointer) (local.get $length) (i32.wrap_i64 (local.get $from))))
+    (call $text
+      (i32.add (local.get $pointer) (local.get $offset))
+      (i32.sub
+        (call $utf8_offset (local.get $pointer) (local.get $length) (i32.wrap_i64 (local.get $to)))
+        (local.get $offset))))
+
+  (func $char_at (param $text i32) (param $index i32) (param $text_location i32) (param $index_location i32)
+                 (param $location i32) (result i32)
+    (local $at i64)
+    (local $pointer i32)
+    (local $length i32)
+    (local $offset i32)
+    (if (i32.ne (i32.load (local.get $text)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $text_location))))
+    (if (i32.ne (i32.load (local.get $index)) (global.get $NUMBER))
+      (then (call $fail (global.get $E0315) (local.get $index_location))))
+    (local.set $pointer (call $text_pointer (local.get $text)))
+    (local.set $length (call $text_length (local.get $text)))
+    (local.set $at (i64.load offset=8 (local.get $index)))
+    (if (i32.or
+          (i64.lt_s (local.get $at) (i64.const 0))
+          (i64.ge_s
+            (local.get $at)
+            (i64.extend_i32_u (call $utf8_length (local.get $pointer) (local.get $length)))))
+      (then (call $fail (global.get $E0310) (local.get $index_location))))
+    (local.set $offset (call $utf8_offset (local.get $pointer) (local.get $length) (i32.wrap_i64 (local.get $at))))
+    (call $text
+      (i32.add (local.get $pointer) (local.get $offset))
+      (i32.sub
+        (call $utf8_offset
+          (local.get $pointer)
+          (local.get $length)
+          (i32.add (i32.wrap_i64 (local.get $at)) (i32.const 1)))
+        (local.get $offset))))
+
+  (func $find (param $text i32) (param $pattern i32) (param $text_location i32) (param $pattern_location i32)
+              (param $location i32) (result i32)
+    (local $i i32)
+    (if (i32.ne (i32.load (local.get $text)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $text_location))))
+    (if (i32.ne (i32.load (local.get $pattern)) (global.get $TEXT))
+      (then (call $fail (global.get $E0315) (local.get $pattern_location))))
+    (block $done
+      (loop $next
+        (br_if $done
+          (i32.gt_u
+            (i32.add (local.get $i) (call $text_length (local.get $pattern)))
+            (call $text_length (local.get $text))))
+        (if (call $equal_bytes
+              (i32.add (call $text_pointer (local.get $text)) (local.get $i))
+              (call $text_pointer (local.get $pattern))
+              (call $text_length (local.get $pattern)))
+          (then
+            (return
+              (call $number
+                (i64.extend_i32_u (call $utf8_length (call $text_pointer (local.get $text)) (local.get $i)))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $number (i64.const -1)))
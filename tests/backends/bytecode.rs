@@ -0,0 +1,151 @@
+//! Tests of the bytecode backend: the listings printed by `minipl disasm`, and the programs executed on the virtual machine
+//! by `minipl run --bytecode`, which must behave exactly like the interpreter
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+use super::{input, run, sources, MINIPL};
+
+/// Runs the source file with the interpreter and on the virtual machine, reporting errors as JSON
+fn run_both(source: &Path, stdin: &str) -> (Output, Output) {
+    let run_with = |arguments: &[&str]| {
+        run(
+            Command::new(MINIPL)
+                .args(["run", "--allow", "warnings", "--message-format", "json"])
+                .args(arguments)
+                .arg(source),
+            stdin,
+        )
+    };
+    (run_with(&[]), run_with(&["--bytecode"]))
+}
+
+#[test]
+fn valid_programs_match_interpreter() {
+    for source in sources("tests/sources/valid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let (interpreted, executed) = run_both(&source, input(name));
+        assert!(interpreted.status.success(), "{name} failed to run");
+        assert!(
+            executed.status.success(),
+            "{name} failed on the virtual machine"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&executed.stdout),
+            String::from_utf8_lossy(&interpreted.stdout),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn runtime_errors_match_interpreter() {
+    let mut compared = 0;
+    for source in sources("tests/sources/invalid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let (interpreted, executed) = run_both(&source, "");
+        assert_eq!(
+            executed.status.success(),
+            interpreted.status.success(),
+            "{name}"
+        );
+        // The output includes the JSON report of the error, with the spans of its labels
+        let stdout = String::from_utf8(interpreted.stdout).unwrap();
+        assert_eq!(String::from_utf8_lossy(&executed.stdout), stdout, "{name}");
+        let report = stdout
+            .lines()
+            .last()
+            .and_then(|line| serde_json::from_str::<Value>(line).ok());
+        if let Some(report) = report {
+            // Errors in lexing and parsing are reported before the program runs
+            if report["code"].as_str().unwrap().starts_with("E03") {
+                compared += 1;
+            }
+        }
+    }
+    assert!(compared > 20, "Only {compared} runtime errors compared");
+}
+
+#[test]
+fn listings_of_loops_logical_operators_and_arrays() {
+    let listings = [
+        (
+            "for_zero_to_ten_print",
+            include_str!("listings/for_zero_to_ten_print.txt"),
+        ),
+        ("logical_or", include_str!("listings/logical_or.txt")),
+        ("array", include_str!("listings/array.txt")),
+    ];
+    for (name, expected) in listings {
+        let output = run(
+            Command::new(MINIPL)
+                .args(["disasm", "--interleave"])
+                .arg(format!("tests/sources/valid/{name}.minipl")),
+            "",
+        );
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            expected,
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn listings_are_consistent() {
+    for source in sources("tests/sources/valid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let output = run(
+            Command::new(MINIPL)
+                .args(["disasm", "--interleave"])
+                .arg(&source),
+            "",
+        );
+        assert!(output.status.success(), "{name} failed to disassemble");
+        let listing = String::from_utf8(output.stdout).unwrap();
+        assert!(!listing.contains("<invalid"), "{name}:\n{listing}");
+
+        // Every jump refers to a label of the listing
+        let labels: HashSet<&str> = listing
+            .lines()
+            .filter_map(|line| line.strip_suffix(':'))
+            .collect();
+        for line in listing.lines() {
+            if let Some((_, jump)) = line.split_once("-> ") {
+                let label = jump.split_whitespace().next().unwrap();
+                assert!(labels.contains(label), "{name}: {line}");
+            }
+        }
+    }
+}
+
+#[test]
+fn interleaved_source_lines() {
+    let output = run(
+        Command::new(MINIPL)
+            .args(["disasm", "--interleave"])
+            .arg("tests/sources/valid/sample2.minipl"),
+        "",
+    );
+    let listing = String::from_utf8(output.stdout).unwrap();
+    // The loop header is shown again before the jump back at the end of the loop
+    let header = ";    5 | for x in 0..nTimes-1 do";
+    assert_eq!(listing.matches(header).count(), 2, "{listing}");
+}
+
+#[test]
+fn lexing_errors_are_reported_as_json() {
+    let output = run(
+        Command::new(MINIPL)
+            .args(["disasm", "--message-format", "json"])
+            .arg("tests/sources/invalid/parseint_error.minipl"),
+        "",
+    );
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let report: Value = serde_json::from_str(stdout.trim_end()).unwrap();
+    assert_eq!(report["code"], "E0102", "{stdout}");
+}
@@ -0,0 +1,197 @@
+//! Differential tests of the C backend: compiled programs must behave exactly like the interpreter.
+//! The tests are skipped when no C compiler is available.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{expected_error, input, run, sources, MINIPL};
+
+fn compiler_available() -> bool {
+    let available = Command::new("cc")
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !available {
+        eprintln!("No C compiler `cc` found, skipping the C backend tests");
+    }
+    available
+}
+
+/// Compiles the source file into a native executable via C, and returns the path of the executable.
+/// Returns `None` if the source file does not build, e.g. due to a parsing error
+fn compile(source: &Path) -> Option<PathBuf> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("backends_c");
+    fs::create_dir_all(&directory).unwrap();
+    let name = source.file_stem().unwrap().to_str().unwrap();
+    let (c_file, executable) = (directory.join(format!("{name}.c")), directory.join(name));
+    let build = run(
+        Command::new(MINIPL)
+            .arg("build")
+            .arg(source)
+            .arg("--output")
+            .arg(&c_file),
+        "",
+    );
+    if !build.status.success() {
+        return None;
+    }
+    let cc = run(
+        Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-o"])
+            .arg(&executable)
+            .arg(&c_file)
+            .arg("-lm"),
+        "",
+    );
+    assert!(
+        cc.status.success(),
+        "Compiling {} failed:\n{}",
+        c_file.display(),
+        String::from_utf8_lossy(&cc.stderr)
+    );
+    Some(executable)
+}
+
+#[test]
+fn valid_programs_match_interpreter() {
+    if !compiler_available() {
+        return;
+    }
+    for source in sources("tests/sources/valid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let stdin = input(name);
+        let interpreted = run(
+            Command::new(MINIPL)
+                .args(["run", "--allow", "warnings"])
+                .arg(&source),
+            stdin,
+        );
+        assert!(interpreted.status.success(), "{name} failed to run");
+        let executable = compile(&source).unwrap();
+        let compiled = run(&mut Command::new(executable), stdin);
+        assert!(compiled.status.success(), "compiled {name} failed");
+
+        // The interpreter prints a newline after the program output
+        let expected = interpreted.stdout.strip_suffix(b"\n").unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&compiled.stdout),
+            String::from_utf8_lossy(expected),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn runtime_errors_match_interpreter() {
+    if !compiler_available() {
+        return;
+    }
+    let mut compared = 0;
+    for source in sources("tests/sources/invalid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let executable = match compile(&source) {
+            Some(executable) => executable,
+            None => continue,
+        };
+        let interpreted = run(
+            Command::new(MINIPL)
+                .args(["run", "--allow", "warnings", "--message-format", "json"])
+                .arg(&source),
+            "",
+        );
+        let compiled = run(&mut Command::new(executable), "");
+        assert_eq!(
+            compiled.status.success(),
+            interpreted.status.success(),
+            "{name}"
+        );
+        if interpreted.status.success() {
+            continue;
+        }
+
+        let (output, expected) = expected_error(&source, interpreted.stdout);
+        assert_eq!(
+            String::from_utf8_lossy(&compiled.stderr),
+            expected,
+            "{name}"
+        );
+        assert_eq!(String::from_utf8_lossy(&compiled.stdout), output, "{name}");
+        compared += 1;
+    }
+    assert!(compared > 20, "Only {compared} runtime errors compared");
+}
+
+#[test]
+fn reals_print_like_interpreter() {
+    if !compiler_available() {
+        return;
+    }
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("backends_c");
+    fs::create_dir_all(&directory).unwrap();
+    let source = directory.join("reals.minipl");
+    let reals = [
+        "0.0",
+        "1.0",
+        "0.1",
+        "0.3",
+        "1.5",
+        "123.456",
+        "0.0001",
+        "0.00001",
+        "0.000015",
+        "1e15",
+        "1e16",
+        "1.5e16",
+        "12345678901234567.0",
+        "1e300",
+        "1e-300",
+        "5e-324",
+        "1.7976931348623157e308",
+        "3.141592653589793",
+        "2.718281828459045",
+    ];
+    let mut program = String::new();
+    for real in reals {
+        program.push_str(&format!(
+            "print {real}; print \" \"; print -{real}; print \"\\n\";\n"
+        ));
+    }
+    program.push_str(
+        "print 1.0 / 0.0; print \" \"; print -1.0 / 0.0; print \" \"; print 0.0 / 0.0;\n",
+    );
+    program
+        .push_str("print \" \"; print 0.1 + 0.2; print \" \"; print to_real(9007199254740993);\n");
+    fs::write(&source, program).unwrap();
+
+    let interpreted = run(
+        Command::new(MINIPL)
+            .args(["run", "--allow", "warnings"])
+            .arg(&source),
+        "",
+    );
+    let compiled = run(&mut Command::new(compile(&source).unwrap()), "");
+    assert_eq!(
+        String::from_utf8_lossy(&compiled.stdout),
+        String::from_utf8_lossy(interpreted.stdout.strip_suffix(b"\n").unwrap())
+    );
+}
+
+#[test]
+fn lexing_errors_abort_the_build() {
+    let c_file = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lexing_error.c");
+    let _ = fs::remove_file(&c_file);
+    let build = run(
+        Command::new(MINIPL)
+            .args(["build", "--message-format", "json"])
+            .arg("tests/sources/invalid/parseint_error.minipl")
+            .arg("--output")
+            .arg(&c_file),
+        "",
+    );
+    assert!(!build.status.success());
+    let stdout = String::from_utf8(build.stdout).unwrap();
+    assert!(stdout.starts_with("{\"code\":\"E0102\""), "{stdout}");
+    assert!(!c_file.exists());
+}
@@ -0,0 +1,77 @@
+//! Tests of the compiler backends, comparing the compiled programs with the interpreter
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+mod bytecode;
+mod c;
+mod wasm;
+
+const MINIPL: &str = env!("CARGO_BIN_EXE_minipl");
+
+/// The standard input given to the programs that read it
+fn input(name: &str) -> &'static str {
+    match name {
+        "echo_boolean" => "true\n",
+        "echo_int" => "42\n",
+        "echo_string" => "hello\n",
+        "sample2" => "3\n",
+        "sample3" => "5\n",
+        _ => "",
+    }
+}
+
+fn run(command: &mut Command, stdin: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn sources(directory: &str) -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "minipl")
+        })
+        .collect();
+    sources.sort();
+    sources
+}
+
+/// Returns the output and the error report expected from a compiled program that fails at runtime,
+/// given the output of the interpreter with `--message-format json`
+fn expected_error(source: &Path, interpreted: Vec<u8>) -> (String, String) {
+    // The JSON report is the last line of the output
+    let stdout = String::from_utf8(interpreted).unwrap();
+    let (output, report) = stdout
+        .trim_end()
+        .rsplit_once('\n')
+        .unwrap_or(("", stdout.trim_end()));
+    let report: Value = serde_json::from_str(report).unwrap();
+    let start = &report["labels"][0]["range"]["start"];
+    let expected = format!(
+        "Error: [{}] {}\n  --> {}:{}:{}\n",
+        report["code"].as_str().unwrap(),
+        report["message"].as_str().unwrap(),
+        source.display(),
+        start["line"].as_u64().unwrap() + 1,
+        start["character"].as_u64().unwrap() + 1,
+    );
+    (output.to_owned(), expected)
+}
@@ -0,0 +1,271 @@
+//! Differential tests of the WebAssembly backend: compiled modules must behave exactly like the interpreter.
+//! The modules are run in process by a small host implementing the `minipl` imports on top of wasmi.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use wasmi::{Caller, Engine, Extern, Linker, Memory, Module, Store};
+
+use super::{expected_error, input, run, sources, MINIPL};
+
+/// The state of the host: the standard input and the captured output of the module
+struct Host {
+    path: String,
+    stdin: Vec<u8>,
+    /// The line read by the last `read_line`
+    line: Vec<u8>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// The result of running a module: whether it succeeded, and its output
+struct Outcome {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+fn memory(caller: &Caller<'_, Host>) -> Memory {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .unwrap()
+}
+
+fn read(caller: &Caller<'_, Host>, pointer: i32, length: i32) -> Vec<u8> {
+    let mut bytes = vec![0; length as usize];
+    memory(caller)
+        .read(caller, pointer as usize, &mut bytes)
+        .unwrap();
+    bytes
+}
+
+fn write(caller: &mut Caller<'_, Host>, pointer: i32, bytes: &[u8]) {
+    memory(caller)
+        .write(caller, pointer as usize, bytes)
+        .unwrap();
+}
+
+/// Runs the `main` function of the module, with the given path of the source file used in error reports
+fn execute(wasm: &[u8], path: &str, stdin: &str) -> Outcome {
+    let engine = Engine::default();
+    let module = Module::new(&engine, wasm).unwrap();
+    let host = Host {
+        path: path.to_owned(),
+        stdin: stdin.as_bytes().to_vec(),
+        line: Vec::new(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    let mut store = Store::new(&engine, host);
+    let mut linker = <Linker<Host>>::new(&engine);
+    linker
+        .func_wrap(
+            "minipl",
+            "print",
+            |mut caller: Caller<'_, Host>, pointer: i32, length: i32| {
+                let bytes = read(&caller, pointer, length);
+                caller.data_mut().stdout.extend(bytes);
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap("minipl", "read_line", |mut caller: Caller<'_, Host>| {
+            let host = caller.data_mut();
+            let end = host
+                .stdin
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map_or(host.stdin.len(), |newline| newline + 1);
+            host.line = host.stdin.drain(..end).collect();
+            match std::str::from_utf8(&host.line) {
+                Ok(_) => host.line.len() as i32,
+                Err(_) => -1,
+            }
+        })
+        .unwrap();
+    linker
+        .func_wrap(
+            "minipl",
+            "read_bytes",
+            |mut caller: Caller<'_, Host>, pointer: i32| {
+                let line = caller.data().line.clone();
+                write(&mut caller, pointer, &line);
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "minipl",
+            "format_real",
+            |mut caller: Caller<'_, Host>, real: f64, pointer: i32| {
+                let text = format!("{real:?}");
+                write(&mut caller, pointer, text.as_bytes());
+                text.len() as i32
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "minipl",
+            "parse_real",
+            |mut caller: Caller<'_, Host>, pointer: i32, length: i32, result: i32| {
+                let bytes = read(&caller, pointer, length);
+                match String::from_utf8(bytes).unwrap().parse::<f64>() {
+                    Ok(real) => {
+                        write(&mut caller, result, &real.to_le_bytes());
+                        1
+                    }
+                    Err(_) => 0,
+                }
+            },
+        )
+        .unwrap();
+    linker
+        .func_wrap(
+            "minipl",
+            "fail",
+            |mut caller: Caller<'_, Host>,
+             code: i32,
+             code_length: i32,
+             message: i32,
+             message_length: i32,
+             line: i32,
+             column: i32| {
+                let code = String::from_utf8(read(&caller, code, code_length)).unwrap();
+                let message = String::from_utf8(read(&caller, message, message_length)).unwrap();
+                let host = caller.data_mut();
+                let report = format!(
+                    "Error: [{code}] {message}\n  --> {}:{line}:{column}\n",
+                    host.path
+                );
+                host.stderr.extend(report.as_bytes());
+            },
+        )
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .ensure_no_start(&mut store)
+        .unwrap();
+    let main = instance.get_typed_func::<(), ()>(&store, "main").unwrap();
+    let success = main.call(&mut store, ()).is_ok();
+    let host = store.into_data();
+    Outcome {
+        success,
+        stdout: String::from_utf8(host.stdout).unwrap(),
+        stderr: String::from_utf8(host.stderr).unwrap(),
+    }
+}
+
+/// Compiles the source file into a binary WebAssembly module.
+/// Returns `None` if the source file does not build, e.g. due to a parsing error
+fn compile(source: &Path) -> Option<Vec<u8>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("backends_wasm");
+    fs::create_dir_all(&directory).unwrap();
+    let name = source.file_stem().unwrap().to_str().unwrap();
+    let wasm_file = directory.join(format!("{name}.wasm"));
+    let build = run(
+        Command::new(MINIPL)
+            .arg("build")
+            .arg(source)
+            .args(["--target", "wasm", "--output"])
+            .arg(&wasm_file),
+        "",
+    );
+    if !build.status.success() {
+        return None;
+    }
+    Some(fs::read(wasm_file).unwrap())
+}
+
+#[test]
+fn valid_programs_match_interpreter() {
+    for source in sources("tests/sources/valid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let stdin = input(name);
+        let interpreted = run(
+            Command::new(MINIPL)
+                .args(["run", "--allow", "warnings"])
+                .arg(&source),
+            stdin,
+        );
+        assert!(interpreted.status.success(), "{name} failed to run");
+        let compiled = execute(
+            &compile(&source).unwrap(),
+            &source.display().to_string(),
+            stdin,
+        );
+        assert!(
+            compiled.success,
+            "compiled {name} failed: {}",
+            compiled.stderr
+        );
+
+        // The interpreter prints a newline after the program output
+        let expected = interpreted.stdout.strip_suffix(b"\n").unwrap();
+        assert_eq!(compiled.stdout, String::from_utf8_lossy(expected), "{name}");
+    }
+}
+
+#[test]
+fn runtime_errors_match_interpreter() {
+    let mut compared = 0;
+    for source in sources("tests/sources/invalid") {
+        let name = source.file_stem().unwrap().to_str().unwrap();
+        let wasm = match compile(&source) {
+            Some(wasm) => wasm,
+            None => continue,
+        };
+        let interpreted = run(
+            Command::new(MINIPL)
+                .args(["run", "--allow", "warnings", "--message-format", "json"])
+                .arg(&source),
+            "",
+        );
+        let compiled = execute(&wasm, &source.display().to_string(), "");
+        assert_eq!(compiled.success, interpreted.status.success(), "{name}");
+        if interpreted.status.success() {
+            continue;
+        }
+        let (output, expected) = expected_error(&source, interpreted.stdout);
+        assert_eq!(compiled.stderr, expected, "{name}");
+        assert_eq!(compiled.stdout, output, "{name}");
+        compared += 1;
+    }
+    assert!(compared > 20, "Only {compared} runtime errors compared");
+}
+
+#[test]
+fn reading_input_matches_interpreter() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("backends_wasm");
+    fs::create_dir_all(&directory).unwrap();
+    let source = directory.join("reading.minipl");
+    fs::write(
+        &source,
+        "var n : int; var r : real; var b : bool; var s : string;\n\
+         read n; read r; read b; read s;\n\
+         print n - 1; print \" \"; print r / 2.0; print \" \"; print !b; print \" [\" + s + \"]\";\n\
+         print \" \" + to_upper(s) + \" \" + to_lower(s); print \" \"; print find(s, \"ß\");\n",
+    )
+    .unwrap();
+    let stdin = "  -9223372036854775807 \n1e3\n false\nStraße Ä\n";
+
+    let interpreted = run(
+        Command::new(MINIPL)
+            .args(["run", "--allow", "warnings"])
+            .arg(&source),
+        stdin,
+    );
+    let compiled = execute(
+        &compile(&source).unwrap(),
+        &source.display().to_string(),
+        stdin,
+    );
+    assert!(compiled.success, "{}", compiled.stderr);
+    assert_eq!(
+        compiled.stdout,
+        String::from_utf8_lossy(interpreted.stdout.strip_suffix(b"\n").unwrap())
+    );
+}
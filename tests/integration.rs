@@ -1,2 +1,4 @@
+mod backends;
 mod invalid;
+mod tools;
 mod valid;
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use minipl::lexing::{Lexer, RecoverableLexingError};
+use minipl::tokens::RawToken;
+use minipl::StartEndSpan;
+use serde_json::Value;
+
+/// Returns the recoverable lexing errors of the source, along with their spans
+fn lexing_errors(source: &str) -> Vec<(RecoverableLexingError, StartEndSpan)> {
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    tokens
+        .into_iter()
+        .filter_map(|token| match token.token {
+            RawToken::Error(error) => Some((error, token.span)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parseint_error() {
+    let source = include_str!("../sources/invalid/parseint_error.minipl");
+    let errors = lexing_errors(source);
+    assert_eq!(
+        errors,
+        vec![(
+            RecoverableLexingError::NumberOutOfRange,
+            StartEndSpan::new(58, 77)
+        )]
+    );
+}
+
+#[test]
+fn unterminated_comment() {
+    let source = include_str!("../sources/invalid/unterminated_comment.minipl");
+    let errors = lexing_errors(source);
+    assert_eq!(
+        errors,
+        vec![(
+            RecoverableLexingError::UnterminatedComment(1),
+            StartEndSpan::new(0, 2)
+        )]
+    );
+}
+
+#[test]
+fn errors_after_huge_literal_are_reported() {
+    let source = "var x : int := 99999999999999999999;\nprint x % 2;\nprint \"\\q\";";
+    let errors: Vec<&str> = lexing_errors(source)
+        .iter()
+        .map(|(error, _)| error.code())
+        .collect();
+    assert_eq!(errors, vec!["E0102", "E0110", "E0104"]);
+}
+
+/// Runs the program with `minipl run --message-format json`, returning the exit status and the standard output
+fn run(name: &str, source: &[u8]) -> (bool, String) {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.minipl"));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_minipl"))
+        .args(["run", "--message-format", "json"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn programs_with_lexing_errors_do_not_run() {
+    // The parser skips the unknown character, but the program must not run without it
+    let (success, stdout) = run("lexing_error_after_print", b"print \"hello\"; @");
+    assert!(!success);
+    assert!(!stdout.contains("hello"), "{stdout}");
+    assert!(stdout.starts_with("{\"code\":\"E0110\""), "{stdout}");
+}
+
+#[test]
+fn invalid_utf8_source_is_reported() {
+    let (success, stdout) = run("invalid_utf8", b"print \"Gr\xfc\xdfe\";");
+    assert!(!success);
+    assert!(stdout.starts_with("{\"code\":\"E0112\""), "{stdout}");
+}
+
+#[test]
+fn lexing_errors_after_a_parse_error_are_reported() {
+    let (success, stdout) = run(
+        "lexing_errors_after_parse_error",
+        b"print 99999999999999999999;\nprint 1;\nprint $;",
+    );
+    assert!(!success);
+    let codes: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["code"].clone())
+        .collect();
+    // The parse errors at the skipped tokens are not reported
+    assert_eq!(codes, vec!["E0102", "E0110"], "{stdout}");
+}
+
+#[test]
+fn check_fails_on_lexing_errors() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("check_lexing_error.minipl");
+    fs::write(&path, "print 1; $ print 2;").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_minipl"))
+        .arg("check")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs `minipl check` with the given lint flags on a program whose only lint is `variable_never_read`, returning whether it succeeded
+fn check(name: &str, flags: &[&str]) -> bool {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.minipl"));
+    fs::write(&path, "var x : int := 1;\nprint 2;").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_minipl"))
+        .arg("check")
+        .args(flags)
+        .arg(&path)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+#[test]
+fn later_flags_override_earlier_ones() {
+    assert!(check(
+        "allow_after_deny",
+        &["-D", "warnings", "-A", "variable_never_read"]
+    ));
+    assert!(!check(
+        "deny_after_allow",
+        &["-A", "variable_never_read", "-D", "warnings"]
+    ));
+    assert!(!check(
+        "deny_after_warn",
+        &["--warn", "warnings", "--deny", "variable_never_read"]
+    ));
+}
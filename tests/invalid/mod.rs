@@ -1,2 +1,4 @@
+mod lex;
+mod lint;
 mod parse;
 mod runtime;
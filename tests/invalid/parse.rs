@@ -1,11 +1,12 @@
 use minipl::lexing::Lexer;
 use minipl::parsing::{ParseError, Parser};
+use minipl::StartEndSpan;
 
 /*
 #[test]
 fn template() {
     let source = include_str!("../sources/invalid/template.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -16,7 +17,7 @@ fn template() {
 #[test]
 fn missing_paren() {
     let source = include_str!("../sources/invalid/missing_paren.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -26,7 +27,7 @@ fn missing_paren() {
 #[test]
 fn expected_expression() {
     let source = include_str!("../sources/invalid/expected_expression.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -36,7 +37,7 @@ fn expected_expression() {
 #[test]
 fn expected_identifier() {
     let source = include_str!("../sources/invalid/expected_identifier.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -46,7 +47,7 @@ fn expected_identifier() {
 #[test]
 fn expected_type_annotation() {
     let source = include_str!("../sources/invalid/expected_type_annotation.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -59,7 +60,7 @@ fn expected_type_annotation() {
 #[test]
 fn expected_assign() {
     let source = include_str!("../sources/invalid/expected_assign.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -72,7 +73,7 @@ fn expected_assign() {
 #[test]
 fn assign_to_nonvariable() {
     let source = include_str!("../sources/invalid/assign_to_nonvariable.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -82,7 +83,7 @@ fn assign_to_nonvariable() {
 #[test]
 fn read_to_nonvariable() {
     let source = include_str!("../sources/invalid/read_to_nonvariable.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -92,7 +93,7 @@ fn read_to_nonvariable() {
 #[test]
 fn expected_assign_found_equal() {
     let source = include_str!("../sources/invalid/expected_assign_found_equal.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -105,7 +106,7 @@ fn expected_assign_found_equal() {
 #[test]
 fn missing_semicolon() {
     let source = include_str!("../sources/invalid/missing_semicolon.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -115,7 +116,7 @@ fn missing_semicolon() {
 #[test]
 fn for_missing_variable() {
     let source = include_str!("../sources/invalid/for_missing_variable.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -125,7 +126,7 @@ fn for_missing_variable() {
 #[test]
 fn for_missing_range() {
     let source = include_str!("../sources/invalid/for_missing_range.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     dbg!(&tokens);
     let mut parser = Parser::new(tokens);
@@ -136,7 +137,7 @@ fn for_missing_range() {
 #[test]
 fn for_missing_in() {
     let source = include_str!("../sources/invalid/for_missing_in.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -146,7 +147,7 @@ fn for_missing_in() {
 #[test]
 fn for_missing_do() {
     let source = include_str!("../sources/invalid/for_missing_do.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
@@ -156,9 +157,135 @@ fn for_missing_do() {
 #[test]
 fn end_missing_for() {
     let source = include_str!("../sources/invalid/end_missing_for.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let result = parser.parse();
     assert!(matches!(result, Err(ParseError::EndMissingFor(_, _))));
 }
+
+#[test]
+fn for_variable_assign() {
+    let source = include_str!("../sources/invalid/for_variable_assign.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(
+        result,
+        Err(ParseError::ForVariableModified(_, _, _))
+    ));
+}
+
+#[test]
+fn for_variable_read() {
+    let source = include_str!("../sources/invalid/for_variable_read.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(
+        result,
+        Err(ParseError::ForVariableModified(_, _, _))
+    ));
+}
+
+#[test]
+fn for_variable_redeclaration() {
+    let source = include_str!("../sources/invalid/for_variable_redeclaration.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(
+        result,
+        Err(ParseError::ForVariableModified(_, _, _))
+    ));
+}
+
+#[test]
+fn for_variable_nested() {
+    let source = include_str!("../sources/invalid/for_variable_nested.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(
+        result,
+        Err(ParseError::ForVariableModified(_, _, _))
+    ));
+}
+
+#[test]
+fn expected_array_size() {
+    let source = include_str!("../sources/invalid/expected_array_size.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(result, Err(ParseError::ExpectedArraySize(_, _))));
+}
+
+#[test]
+fn array_size_too_large() {
+    let source = include_str!("../sources/invalid/array_size_too_large.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(matches!(
+        result,
+        Err(ParseError::ArraySizeTooLarge(100_000_000_000_000, _, _))
+    ));
+}
+
+#[test]
+fn interpolation_expected_expression() {
+    let source = include_str!("../sources/invalid/interpolation_expected_expression.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    // The error points at the closing brace within the string
+    assert!(matches!(result, Err(ParseError::ExpectedExpression(_, span)) if span.offset() == 12));
+}
+
+#[test]
+fn interpolation_expected_closing_brace() {
+    let source = include_str!("../sources/invalid/interpolation_expected_closing_brace.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    // The error points at the unexpected token within the string
+    assert!(
+        matches!(result, Err(ParseError::ExpectedClosingBrace(_, span)) if span.offset() == 11)
+    );
+}
+
+#[test]
+fn misspelled_keyword() {
+    let source = include_str!("../sources/invalid/misspelled_keyword.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let error = parser.parse().unwrap_err();
+    assert!(
+        matches!(&error, ParseError::MisspelledKeyword(found, keyword, _, _) if found == "pritn" && keyword == "print")
+    );
+    let suggestion = error.suggestion().unwrap();
+    assert_eq!(suggestion.replacement, "print");
+    assert_eq!(suggestion.span, StartEndSpan::new(18, 23));
+}
+
+#[test]
+fn misspelled_keyword_call() {
+    let source = include_str!("../sources/invalid/misspelled_keyword_call.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse();
+    assert!(
+        matches!(result, Err(ParseError::MisspelledKeyword(_, keyword, _, _)) if keyword == "assert")
+    );
+}
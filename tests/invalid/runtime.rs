@@ -2,12 +2,13 @@ use minipl::lexing::Lexer;
 use minipl::parsing::Parser;
 use minipl::runtime::RuntimeError::*;
 use minipl::visitors::Interpreter;
+use minipl::StartEndSpan;
 
 /*
 #[test]
 fn template() {
     let source = include_str!("../sources/invalid/template.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -20,31 +21,31 @@ fn template() {
 #[test]
 fn as_numeric_failed() {
     let source = include_str!("../sources/invalid/as_numeric_failed.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
     let mut interpreter = Interpreter::new();
     let result = interpreter.eval(&parsed);
-    assert!(matches!(result, Err(AsNumericFailed(_))));
+    assert!(matches!(result, Err(AsNumericFailed(_, span)) if span.offset() == 47));
 }
 
 #[test]
 fn as_boolean_failed() {
     let source = include_str!("../sources/invalid/as_boolean_failed.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
     let mut interpreter = Interpreter::new();
     let result = interpreter.eval(&parsed);
-    assert!(matches!(result, Err(AsBooleanFailed(_))));
+    assert!(matches!(result, Err(AsBooleanFailed(_, span)) if span.offset() == 53));
 }
 
 #[test]
 fn equal_type_mismatch() {
     let source = include_str!("../sources/invalid/equal_type_mismatch.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -56,7 +57,7 @@ fn equal_type_mismatch() {
 #[test]
 fn less_type_mismatch() {
     let source = include_str!("../sources/invalid/less_type_mismatch.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -68,7 +69,7 @@ fn less_type_mismatch() {
 #[test]
 fn assert_expr_not_truthy() {
     let source = include_str!("../sources/invalid/assert_expr_not_truthy.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -80,7 +81,7 @@ fn assert_expr_not_truthy() {
 #[test]
 fn assertion_failed() {
     let source = include_str!("../sources/invalid/assertion_failed.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -92,7 +93,7 @@ fn assertion_failed() {
 #[test]
 fn for_end_larger() {
     let source = include_str!("../sources/invalid/for_end_larger.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -104,7 +105,7 @@ fn for_end_larger() {
 #[test]
 fn for_end_nonnumeric() {
     let source = include_str!("../sources/invalid/for_end_nonnumeric.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -116,7 +117,7 @@ fn for_end_nonnumeric() {
 #[test]
 fn for_start_nonnumeric() {
     let source = include_str!("../sources/invalid/for_start_nonnumeric.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -128,7 +129,7 @@ fn for_start_nonnumeric() {
 #[test]
 fn plus_type_mismatch() {
     let source = include_str!("../sources/invalid/plus_type_mismatch.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -140,7 +141,7 @@ fn plus_type_mismatch() {
 #[test]
 fn re_declaration() {
     let source = include_str!("../sources/invalid/re_declaration.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -152,7 +153,7 @@ fn re_declaration() {
 #[test]
 fn variable_assign_to_undeclared() {
     let source = include_str!("../sources/invalid/variable_assign_to_undeclared.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -164,19 +165,48 @@ fn variable_assign_to_undeclared() {
 #[test]
 fn variable_get_failed() {
     let source = include_str!("../sources/invalid/variable_get_failed.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
     let mut interpreter = Interpreter::new();
     let result = interpreter.eval(&parsed);
-    assert!(matches!(result, Err(VariableGetFailed(_))));
+    assert!(matches!(result, Err(VariableGetFailed(_, span, _, _)) if span.offset() == 6));
+}
+
+#[test]
+fn variable_get_failed_expression() {
+    let source = include_str!("../sources/invalid/variable_get_failed_expression.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(
+        matches!(result, Err(VariableGetFailed(_, span, _, _)) if span.offset() == 28 && span.len() == 1)
+    );
+}
+
+#[test]
+fn variable_get_failed_suggestion() {
+    let source = include_str!("../sources/invalid/variable_get_failed_suggestion.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.eval(&parsed).unwrap_err();
+    assert!(matches!(&error, VariableGetFailed(_, _, help, _) if help == "Did you mean `count`?"));
+    let suggestion = error.suggestion().unwrap();
+    assert_eq!(suggestion.replacement, "count");
+    assert_eq!(suggestion.span, StartEndSpan::new(28, 32));
 }
 
 #[test]
 fn variable_assign_type_mismatch() {
     let source = include_str!("../sources/invalid/variable_assign_type_mismatch.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -191,7 +221,46 @@ fn variable_assign_type_mismatch() {
 #[test]
 fn variable_assign_type_mismatch2() {
     let source = include_str!("../sources/invalid/variable_assign_type_mismatch2.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(
+        result,
+        Err(VariableAssignTypeMismatch(_, _, _, _))
+    ));
+}
+
+#[test]
+fn index_out_of_bounds() {
+    let source = include_str!("../sources/invalid/index_out_of_bounds.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(IndexOutOfBounds(3, 3, _))));
+}
+
+#[test]
+fn index_assign_out_of_bounds() {
+    let source = include_str!("../sources/invalid/index_assign_out_of_bounds.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(IndexOutOfBounds(-1, 3, _))));
+}
+
+#[test]
+fn index_assign_type_mismatch() {
+    let source = include_str!("../sources/invalid/index_assign_type_mismatch.minipl");
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -202,3 +271,136 @@ fn variable_assign_type_mismatch2() {
         Err(VariableAssignTypeMismatch(_, _, _, _))
     ));
 }
+
+#[test]
+fn index_non_array() {
+    let source = include_str!("../sources/invalid/index_non_array.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(IndexNonArray(_, _))));
+}
+
+#[test]
+fn for_iterate_non_array() {
+    let source = include_str!("../sources/invalid/for_iterate_non_array.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(ForIterateNonArray(_, _))));
+}
+
+#[test]
+fn unknown_builtin() {
+    let source = include_str!("../sources/invalid/unknown_builtin.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    let error = result.unwrap_err();
+    assert!(matches!(error, UnknownBuiltin(_, _, _, _)));
+    let suggestion = error.suggestion().unwrap();
+    assert_eq!(suggestion.replacement, "length");
+    assert_eq!(suggestion.span, StartEndSpan::new(15, 21));
+}
+
+#[test]
+fn comparison_type_mismatch() {
+    let source = include_str!("../sources/invalid/comparison_type_mismatch.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(ComparisonTypeMismatch(_, _, _, _))));
+}
+
+#[test]
+fn not_equal_type_mismatch() {
+    let source = include_str!("../sources/invalid/not_equal_type_mismatch.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(NotEqualTypeMismatch(_, _, _, _))));
+}
+
+#[test]
+fn number_real_mismatch() {
+    let source = include_str!("../sources/invalid/number_real_mismatch.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(NumberRealMismatch(_, _, _, _))));
+}
+
+#[test]
+fn parse_int_failed() {
+    let source = include_str!("../sources/invalid/parse_int_failed.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(ParseIntFailed(_, _))));
+}
+
+#[test]
+fn substring_out_of_bounds() {
+    let source = include_str!("../sources/invalid/substring_out_of_bounds.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    assert!(matches!(result, Err(SubstringOutOfBounds(2, 4, 3, _))));
+}
+
+#[test]
+fn division_by_zero() {
+    let source = include_str!("../sources/invalid/division_by_zero.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    if let Err(DivisionByZero(left, right)) = result {
+        assert_eq!((left.offset(), left.len()), (24, 2));
+        assert_eq!((right.offset(), right.len()), (29, 1));
+    } else {
+        panic!("expected DivisionByZero, got {result:?}");
+    }
+}
+
+#[test]
+fn integer_overflow() {
+    let source = include_str!("../sources/invalid/integer_overflow.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval(&parsed);
+    if let Err(IntegerOverflow(span)) = result {
+        assert_eq!((span.offset(), span.len()), (44, 8));
+    } else {
+        panic!("expected IntegerOverflow, got {result:?}");
+    }
+}
@@ -0,0 +1,43 @@
+use std::fs;
+
+use super::minipl;
+
+#[test]
+fn summary_lcov_and_html() {
+    let directory = std::env::temp_dir();
+    let lcov = directory.join(format!("minipl-coverage-{}.lcov", std::process::id()));
+    let output = minipl(
+        &[
+            "run",
+            "--coverage",
+            lcov.to_str().unwrap(),
+            "tests/sources/valid/sample2.minipl",
+        ],
+        "3\n",
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("How many times?0 : Hello, World!\n"));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("\nCoverage:\n"));
+    let row = stderr
+        .lines()
+        .find(|line| line.trim_start().starts_with("Statements"))
+        .unwrap();
+    let columns: Vec<&str> = row.split_whitespace().collect();
+    assert_eq!(columns, ["Statements", "8", "8", "100.0%"]);
+    assert!(!stderr.contains("Lines never executed"));
+
+    let tracefile = fs::read_to_string(&lcov).unwrap();
+    fs::remove_file(&lcov).unwrap();
+    assert!(tracefile.starts_with("TN:\nSF:tests/sources/valid/sample2.minipl\n"));
+    // The statements in the loop body are executed once per iteration
+    assert!(tracefile.contains("\nDA:5,1\nDA:6,3\nDA:7,3\nDA:12,1\n"));
+    assert!(tracefile.ends_with("LF:8\nLH:8\nend_of_record\n"));
+
+    let html = lcov.with_extension("html");
+    let report = fs::read_to_string(&html).unwrap();
+    fs::remove_file(&html).unwrap();
+    assert!(report.contains("<tr class=\"hit\"><td class=\"line\">6</td><td class=\"count\">3</td><td class=\"code\">print x;</td></tr>"));
+}
@@ -0,0 +1,266 @@
+use serde_json::{json, Value};
+
+use super::minipl;
+
+const SAMPLE: &str = "tests/sources/valid/sample2.minipl";
+
+/// Frames the body of a message with its header
+fn framed(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+/// Runs `minipl dap` with the scripted requests, returning the messages it sent
+fn session(requests: &[Value]) -> Vec<Value> {
+    let mut input = String::new();
+    for (seq, request) in requests.iter().enumerate() {
+        let mut request = request.clone();
+        request["seq"] = json!(seq + 1);
+        request["type"] = json!("request");
+        input.push_str(&framed(&request.to_string()));
+    }
+    let output = minipl(&["dap"], &input);
+    assert!(output.status.success());
+    messages(&output.stdout)
+}
+
+/// Splits the output of `minipl dap` into the messages
+fn messages(stdout: &[u8]) -> Vec<Value> {
+    let mut stdout = String::from_utf8(stdout.to_vec()).unwrap();
+    let mut messages = Vec::new();
+    while !stdout.is_empty() {
+        let (header, rest) = stdout.split_once("\r\n\r\n").unwrap();
+        let length: usize = header
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        messages.push(serde_json::from_str(&rest[..length]).unwrap());
+        stdout = rest[length..].to_owned();
+    }
+    messages
+}
+
+/// The bodies of the responses to the command, in order
+fn responses<'a>(messages: &'a [Value], command: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["type"] == "response" && message["command"] == command)
+        .collect()
+}
+
+/// The bodies of the events, in order
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|message| message["type"] == "event" && message["event"] == event)
+        .map(|message| &message["body"])
+        .collect()
+}
+
+/// Requests launching the program with the input, and configuring the breakpoints at the lines
+fn launch(program: &str, input: &str, stop_on_entry: bool, lines: &[u64]) -> Vec<Value> {
+    let breakpoints: Vec<Value> = lines.iter().map(|line| json!({ "line": line })).collect();
+    vec![
+        json!({ "command": "initialize", "arguments": { "adapterID": "minipl" } }),
+        json!({ "command": "launch", "arguments": { "program": program, "input": input, "stopOnEntry": stop_on_entry } }),
+        json!({ "command": "setBreakpoints", "arguments": { "source": { "path": program }, "breakpoints": breakpoints } }),
+        json!({ "command": "configurationDone" }),
+    ]
+}
+
+#[test]
+fn breakpoint_in_loop() {
+    let mut requests = launch(SAMPLE, "2\n", false, &[6, 9]);
+    requests.extend([
+        json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        json!({ "command": "scopes", "arguments": { "frameId": 0 } }),
+        json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "evaluate", "arguments": { "expression": "x * 10", "frameId": 0 } }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let messages = session(&requests);
+    assert!(messages.iter().all(|message| message["success"] != false));
+
+    // No statement starts on the comment line
+    let breakpoints = &responses(&messages, "setBreakpoints")[0]["body"]["breakpoints"];
+    assert_eq!(breakpoints[0]["verified"], true);
+    assert_eq!(breakpoints[1]["verified"], false);
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 2);
+    assert!(stopped
+        .iter()
+        .all(|body| body["reason"] == "breakpoint" && body["hitBreakpointIds"] == json!([1])));
+
+    let frames = &responses(&messages, "stackTrace")[0]["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "for x = 0");
+    assert_eq!(
+        (
+            &frames[0]["line"],
+            &frames[0]["column"],
+            &frames[0]["endColumn"]
+        ),
+        (&json!(6), &json!(1), &json!(8))
+    );
+    assert_eq!(frames[1]["name"], "main");
+    assert_eq!(
+        (&frames[1]["line"], &frames[1]["column"]),
+        (&json!(5), &json!(1))
+    );
+    assert_eq!(frames[0]["source"]["path"], SAMPLE);
+
+    let variables = &responses(&messages, "variables")[0]["body"]["variables"];
+    assert_eq!(
+        variables,
+        &json!([
+            { "name": "nTimes", "value": "2", "type": "Number", "variablesReference": 0 },
+            { "name": "x", "value": "0", "type": "Number", "variablesReference": 0 },
+        ])
+    );
+    let evaluated = &responses(&messages, "evaluate")[0]["body"];
+    assert_eq!(
+        (&evaluated["result"], &evaluated["type"]),
+        (&json!("10"), &json!("Number"))
+    );
+
+    let output: String = events(&messages, "output")
+        .iter()
+        .map(|body| body["output"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        output,
+        "How many times?0 : Hello, World!\n1 : Hello, World!\n"
+    );
+    assert_eq!(events(&messages, "exited")[0]["exitCode"], 0);
+    assert_eq!(events(&messages, "terminated").len(), 1);
+}
+
+#[test]
+fn stepping_through_loops() {
+    let program = "tests/sources/valid/for_nested.minipl";
+    let mut requests = launch(program, "", true, &[]);
+    for command in ["next", "next", "stepIn", "stepIn"] {
+        requests.push(json!({ "command": command, "arguments": { "threadId": 1 } }));
+    }
+    requests.push(json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }));
+    requests.push(json!({ "command": "stepOut", "arguments": { "threadId": 1 } }));
+    requests.push(json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }));
+    requests.push(json!({ "command": "disconnect" }));
+    let messages = session(&requests);
+
+    let reasons: Vec<&Value> = events(&messages, "stopped")
+        .iter()
+        .map(|body| &body["reason"])
+        .collect();
+    assert_eq!(reasons, ["entry", "step", "step", "step", "step", "step"]);
+
+    // Paused inside both loops, at the first statement of the inner loop
+    let traces = responses(&messages, "stackTrace");
+    let frames = &traces[0]["body"]["stackFrames"];
+    let names: Vec<&Value> = frames
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| &f["name"])
+        .collect();
+    assert_eq!(names, ["for y = 0", "for x = 0", "main"]);
+    let lines: Vec<&Value> = frames
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| &f["line"])
+        .collect();
+    assert_eq!(lines, [5, 4, 3]);
+
+    // Stepping out of the inner loop pauses at the statement after it
+    let frames = &traces[1]["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "for x = 0");
+    assert_eq!(frames[0]["line"], 8);
+    assert_eq!(traces[1]["body"]["totalFrames"], 2);
+
+    // The program is stopped without running to the end
+    assert!(events(&messages, "terminated").is_empty());
+}
+
+#[test]
+fn arrays_can_be_expanded() {
+    let program = "tests/sources/valid/array.minipl";
+    let mut requests = launch(program, "", false, &[11]);
+    requests.extend([
+        json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+        json!({ "command": "variables", "arguments": { "variablesReference": 2 } }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let messages = session(&requests);
+    let variables = responses(&messages, "variables");
+    let xs = &variables[0]["body"]["variables"][3];
+    assert_eq!(xs["name"], "xs");
+    assert_eq!(xs["type"], "Array[5] of Number");
+    assert_eq!(xs["variablesReference"], 2);
+    let elements = &variables[1]["body"]["variables"];
+    assert_eq!(
+        elements[4],
+        json!({ "name": "[4]", "value": "16", "type": "Number", "variablesReference": 0 })
+    );
+}
+
+#[test]
+fn errors_are_reported() {
+    let messages = session(&launch(
+        "tests/sources/invalid/assertion_failed.minipl",
+        "",
+        false,
+        &[],
+    ));
+    let output = events(&messages, "output");
+    assert_eq!(output[0]["category"], "stderr");
+    assert!(output[0]["output"]
+        .as_str()
+        .unwrap()
+        .starts_with("Error: [E03"));
+    assert_eq!(events(&messages, "exited")[0]["exitCode"], 1);
+
+    let messages = session(&[
+        json!({ "command": "initialize", "arguments": {} }),
+        json!({ "command": "launch", "arguments": { "program": "tests/sources/invalid/missing_semicolon.minipl" } }),
+        json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+    ]);
+    let launched = responses(&messages, "launch")[0];
+    assert_eq!(launched["success"], false);
+    assert!(launched["message"].as_str().unwrap().contains("at line"));
+    assert_eq!(
+        responses(&messages, "stackTrace")[0]["message"],
+        "The program is not paused"
+    );
+}
+
+#[test]
+fn invalid_json_is_skipped() {
+    let request = json!({ "seq": 1, "type": "request", "command": "initialize", "arguments": {} });
+    let input = framed("{\"seq\": 1, \"type\":") + &framed(&request.to_string());
+    let output = minipl(&["dap"], &input);
+    assert!(output.status.success());
+    let messages = messages(&output.stdout);
+    let output = events(&messages, "output");
+    assert_eq!(output[0]["category"], "console");
+    assert!(output[0]["output"]
+        .as_str()
+        .unwrap()
+        .starts_with("Ignored a message that is not valid JSON"));
+    assert_eq!(responses(&messages, "initialize")[0]["success"], true);
+}
+
+#[test]
+fn huge_content_length_is_an_error() {
+    // The body is not allocated up front, so the server reports the message ending early rather than aborting
+    let output = minipl(&["dap"], "Content-Length: 100000000000000\r\n\r\n{}");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("the input ended after 2 of the 100000000000000 bytes"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,52 @@
+use super::minipl;
+
+const SAMPLE: &str = "tests/sources/valid/sample2.minipl";
+
+#[test]
+fn breakpoint_in_loop() {
+    // The input of the program is read between the commands
+    let output = minipl(&["debug", SAMPLE], "b 6\nc\n2\nc\np x\nc\nc\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("Breakpoint at line 6, column 1").count(), 2);
+    assert!(stdout.contains("(minipl) x = 1 (Number)"));
+    assert!(stdout.ends_with("Program finished\n"), "{stdout}");
+}
+
+#[test]
+fn quit_stops_the_program() {
+    let output = minipl(&["debug", SAMPLE], "n\nvars\nq\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("(minipl) nTimes = 0 (Number)\n"));
+    // The print statement is shown, but not executed
+    assert_eq!(stdout.matches("How many times?").count(), 1);
+    assert!(stdout.ends_with("Program stopped\n"), "{stdout}");
+}
+
+#[test]
+fn runtime_errors_are_reported() {
+    let output = minipl(
+        &["debug", "tests/sources/invalid/assertion_failed.minipl"],
+        "c\n",
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("E03"));
+}
+
+#[test]
+fn lexing_errors_are_reported() {
+    let output = minipl(
+        &[
+            "--message-format",
+            "json",
+            "debug",
+            "tests/sources/invalid/parseint_error.minipl",
+        ],
+        "c\n",
+    );
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The program is not debugged
+    assert!(stdout.starts_with("{\"code\":\"E0102\""), "{stdout}");
+}
@@ -0,0 +1,32 @@
+//! Tests of the tools observing the execution of programs
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+mod coverage;
+mod dap;
+mod debug;
+mod profile;
+mod replay;
+mod snapshot;
+mod trace;
+
+const MINIPL: &str = env!("CARGO_BIN_EXE_minipl");
+
+/// Runs `minipl` with the arguments, writing the standard input
+fn minipl(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(MINIPL)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
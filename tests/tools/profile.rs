@@ -0,0 +1,48 @@
+use std::fs;
+
+use super::minipl;
+
+#[test]
+fn hot_spots_and_collapsed_stacks() {
+    let path = std::env::temp_dir().join(format!("minipl-profile-{}.folded", std::process::id()));
+    let output = minipl(
+        &[
+            "run",
+            "--profile",
+            "--profile-output",
+            path.to_str().unwrap(),
+            "tests/sources/valid/sample2.minipl",
+        ],
+        "3\n",
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("How many times?0 : Hello, World!\n"));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Hot spots by self time"));
+    // The statements in the loop body are executed once per iteration
+    let row = stderr
+        .lines()
+        .find(|line| line.ends_with("print x"))
+        .unwrap();
+    assert!(row.trim_start().starts_with("6:1"));
+    assert_eq!(row.split_whitespace().nth(1), Some("3"));
+    let row = stderr
+        .lines()
+        .skip_while(|line| !line.starts_with("for loops"))
+        .find(|line| line.ends_with("for x in 0..nTimes-1 do"))
+        .unwrap();
+    let columns: Vec<&str> = row.split_whitespace().take(3).collect();
+    assert_eq!(columns, ["5:1", "1", "3"]);
+
+    let stacks = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(stacks.lines().count(), 8);
+    for line in stacks.lines() {
+        let (stack, time) = line.rsplit_once(' ').unwrap();
+        assert!(stack.starts_with("main;"));
+        time.parse::<u128>().unwrap();
+    }
+    assert!(stacks.contains("main;5:1 for x in 0..nTimes-1 do;6:1 print x "));
+}
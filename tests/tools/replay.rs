@@ -0,0 +1,57 @@
+use std::fs;
+
+use super::minipl;
+
+#[test]
+fn record_and_replay() {
+    let session = std::env::temp_dir().join(format!("minipl-session-{}.json", std::process::id()));
+    let session = session.to_str().unwrap();
+    let recorded = minipl(
+        &[
+            "run",
+            "--record",
+            session,
+            "tests/sources/valid/sample2.minipl",
+        ],
+        "2\n",
+    );
+    assert!(recorded.status.success());
+    let stderr = String::from_utf8(recorded.stderr).unwrap();
+    assert!(stderr.contains("Recorded 6 events of the run to "));
+
+    // The recorded input is replayed, whatever the standard input is
+    let replayed = minipl(
+        &[
+            "run",
+            "--replay",
+            session,
+            "tests/sources/valid/sample2.minipl",
+        ],
+        "",
+    );
+    assert!(replayed.status.success());
+    assert_eq!(replayed.stdout, recorded.stdout);
+    let stderr = String::from_utf8(replayed.stderr).unwrap();
+    assert!(stderr.contains(": the output matches the recording"));
+
+    // A different program diverges at its first different output
+    let replayed = minipl(
+        &[
+            "run",
+            "--replay",
+            session,
+            "--message-format",
+            "json",
+            "tests/sources/valid/sample1.minipl",
+        ],
+        "",
+    );
+    fs::remove_file(session).unwrap();
+    assert!(!replayed.status.success());
+    let stdout = String::from_utf8(replayed.stdout).unwrap();
+    let diagnostic: serde_json::Value = stdout
+        .lines()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .unwrap();
+    assert_eq!(diagnostic["code"], "E0502");
+}
@@ -0,0 +1,72 @@
+use std::fs;
+
+use super::minipl;
+
+#[test]
+fn pause_and_resume_across_processes() {
+    let directory = std::env::temp_dir();
+    let first = directory.join(format!("minipl-snapshot-{}-1.json", std::process::id()));
+    let second = directory.join(format!("minipl-snapshot-{}-2.json", std::process::id()));
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+    let paused = minipl(
+        &[
+            "run",
+            "--snapshot",
+            first,
+            "--pause-on-read",
+            "tests/sources/valid/sample2.minipl",
+        ],
+        "",
+    );
+    assert!(paused.status.success());
+    assert_eq!(paused.stdout, b"How many times?");
+    let stderr = String::from_utf8(paused.stderr).unwrap();
+    assert!(stderr.contains("Paused awaiting input, before the statement at line 3, column 1"));
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(first).unwrap()).unwrap();
+    assert_eq!(snapshot["reason"], "read");
+    assert_eq!(snapshot["output"], "How many times?");
+
+    // The input is read by the resumed process, which pauses again inside the loop
+    let resumed = minipl(
+        &["resume", first, "--snapshot", second, "--pause-at", "7"],
+        "3\n",
+    );
+    fs::remove_file(first).unwrap();
+    assert!(resumed.status.success());
+    assert_eq!(resumed.stdout, b"0");
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(second).unwrap()).unwrap();
+    assert_eq!(snapshot["positions"], serde_json::json!([4, 1]));
+    assert_eq!(snapshot["loops"][0]["variable"], "x");
+    assert_eq!(snapshot["variables"]["nTimes"]["Number"], 3);
+
+    let finished = minipl(&["resume", second], "");
+    fs::remove_file(second).unwrap();
+    assert!(finished.status.success());
+    let stdout = String::from_utf8(finished.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        " : Hello, World!\n1 : Hello, World!\n2 : Hello, World!\n\n"
+    );
+}
+
+#[test]
+fn invalid_snapshots() {
+    let path = std::env::temp_dir().join(format!(
+        "minipl-snapshot-{}-invalid.json",
+        std::process::id()
+    ));
+    fs::write(&path, "{\"source\": \"print 1;\"}").unwrap();
+    let output = minipl(
+        &["--message-format", "json", "resume", path.to_str().unwrap()],
+        "",
+    );
+    fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().unwrap()).unwrap();
+    assert_eq!(diagnostic["code"], "E0601");
+}
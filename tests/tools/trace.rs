@@ -0,0 +1,73 @@
+use std::fs;
+
+use serde_json::Value;
+
+use super::minipl;
+
+const SAMPLE: &str = "tests/sources/valid/sample2.minipl";
+
+#[test]
+fn text_trace_on_stderr() {
+    let output = minipl(&["run", "--trace", SAMPLE], "2\n");
+    assert!(output.status.success());
+    // The output of the program is not mixed with the trace
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        "How many times?0 : Hello, World!\n1 : Hello, World!\n\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr
+        .contains("3:1     statement  read nTimes\n3:1       assign     nTimes: 0 → 2 (Number)\n"));
+    assert!(stderr.contains("5:13      expression nTimes-1 => 1 (Number)\n"));
+    assert!(
+        stderr.contains("6:1       statement  print x\n6:7         expression x => 1 (Number)\n")
+    );
+    assert!(stderr.contains("12:9      expression x = nTimes-1 => true (Boolean)\n"));
+}
+
+#[test]
+fn json_trace_to_file() {
+    let path = std::env::temp_dir().join(format!("minipl-trace-{}.jsonl", std::process::id()));
+    let output = minipl(
+        &[
+            "run",
+            "--trace=json",
+            "--trace-output",
+            path.to_str().unwrap(),
+            "tests/sources/valid/var_reassign.minipl",
+        ],
+        "",
+    );
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let trace = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let events: Vec<Value> = trace
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(events.iter().any(|event| event["event"] == "define"));
+    let assign = events
+        .iter()
+        .find(|event| event["event"] == "assign")
+        .unwrap();
+    assert!(assign["old"] != assign["new"]);
+    assert!(assign["line"].as_u64().unwrap() > 1);
+}
+
+#[test]
+fn runtime_errors_end_the_trace() {
+    let output = minipl(
+        &[
+            "run",
+            "--trace",
+            "tests/sources/invalid/division_by_zero.minipl",
+        ],
+        "",
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let statement = stderr.find("statement").unwrap();
+    assert!(statement < stderr.find("E03").unwrap());
+}
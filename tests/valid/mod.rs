@@ -1,11 +1,14 @@
 use minipl::lexing::Lexer;
+use minipl::optimizer::Optimizer;
+use minipl::parsing::expression::{Expr, Expression};
+use minipl::parsing::statement::Stmt;
 use minipl::parsing::Parser;
 use minipl::visitors::Interpreter;
 
 #[test]
 fn empty() {
     let source = include_str!("../sources/valid/empty.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -16,7 +19,7 @@ fn empty() {
 #[test]
 fn newline() {
     let source = include_str!("../sources/valid/newline.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -27,7 +30,7 @@ fn newline() {
 #[test]
 fn comment() {
     let source = include_str!("../sources/valid/comment.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse();
@@ -38,7 +41,7 @@ fn comment() {
 #[test]
 fn multiline_comment_singleline() {
     let source = include_str!("../sources/valid/multiline_comment_singleline.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse();
@@ -49,7 +52,7 @@ fn multiline_comment_singleline() {
 #[test]
 fn multiline_comment() {
     let source = include_str!("../sources/valid/multiline_comment.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse();
@@ -60,7 +63,7 @@ fn multiline_comment() {
 #[test]
 fn multiline_comment_nested() {
     let source = include_str!("../sources/valid/multiline_comment_nested.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse();
@@ -71,7 +74,7 @@ fn multiline_comment_nested() {
 #[test]
 fn helloworld() {
     let source = include_str!("../sources/valid/helloworld.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -82,7 +85,7 @@ fn helloworld() {
 #[test]
 fn hello_less_world() {
     let source = include_str!("../sources/valid/hello_less_world.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -93,7 +96,7 @@ fn hello_less_world() {
 #[test]
 fn hello_plus_world() {
     let source = include_str!("../sources/valid/hello_plus_world.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -104,7 +107,7 @@ fn hello_plus_world() {
 #[test]
 fn one_plus_two_times_three() {
     let source = include_str!("../sources/valid/one_plus_two_times_three.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -112,10 +115,29 @@ fn one_plus_two_times_three() {
     interpreter.eval(&parsed).unwrap();
 }
 
+#[test]
+fn one_plus_two_times_three_optimized() {
+    let source = include_str!("../sources/valid/one_plus_two_times_three.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let optimized = Optimizer::default().optimize(&parsed);
+    assert!(matches!(
+        &optimized[0].stmt,
+        Stmt::Print(Expression {
+            expr: Expr::Literal(_),
+            ..
+        })
+    ));
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&optimized).unwrap();
+}
+
 #[test]
 fn var_with_assign() {
     let source = include_str!("../sources/valid/var_with_assign.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -126,7 +148,7 @@ fn var_with_assign() {
 #[test]
 fn var_without_assign() {
     let source = include_str!("../sources/valid/var_without_assign.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -137,7 +159,7 @@ fn var_without_assign() {
 #[test]
 fn var_bool() {
     let source = include_str!("../sources/valid/var_bool.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -148,7 +170,7 @@ fn var_bool() {
 #[test]
 fn var_string() {
     let source = include_str!("../sources/valid/var_string.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -159,7 +181,7 @@ fn var_string() {
 #[test]
 fn logical_and() {
     let source = include_str!("../sources/valid/logical_and.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -170,7 +192,7 @@ fn logical_and() {
 #[test]
 fn for_zero_to_ten_print() {
     let source = include_str!("../sources/valid/for_zero_to_ten_print.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -181,7 +203,7 @@ fn for_zero_to_ten_print() {
 #[test]
 fn assert_true() {
     let source = include_str!("../sources/valid/for_zero_to_ten_print.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();
@@ -192,7 +214,95 @@ fn assert_true() {
 #[test]
 fn assert_truthy_comparison() {
     let source = include_str!("../sources/valid/assert_truthy_comparison.minipl");
-    let mut lexer = Lexer::new(source);
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn array() {
+    let source = include_str!("../sources/valid/array.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn comparison_operators() {
+    let source = include_str!("../sources/valid/comparison_operators.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn logical_or() {
+    let source = include_str!("../sources/valid/logical_or.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn evaluation_order() {
+    let source = include_str!("../sources/valid/evaluation_order.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn real() {
+    let source = include_str!("../sources/valid/real.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn string_builtins() {
+    let source = include_str!("../sources/valid/string_builtins.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn interpolation() {
+    let source = include_str!("../sources/valid/interpolation.minipl");
+    let lexer = Lexer::new(source);
+    let tokens = lexer.scan().unwrap();
+    let mut parser = Parser::new(tokens);
+    let parsed = parser.parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(&parsed).unwrap();
+}
+
+#[test]
+fn escapes() {
+    let source = include_str!("../sources/valid/escapes.minipl");
+    let lexer = Lexer::new(source);
     let tokens = lexer.scan().unwrap();
     let mut parser = Parser::new(tokens);
     let parsed = parser.parse().unwrap();